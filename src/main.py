@@ -3,12 +3,53 @@ import time
 import random
 import logging
 import os
+import sys
+import copy
+import csv
+from collections import OrderedDict, deque
+import math
+import re
+import threading
+import _thread
+import ast
+import asyncio
+import base64
+import concurrent.futures
+import contextvars
+import functools
+import heapq
+import hmac
+import html
+import io
+import json
+import queue
+import shutil
+import signal
+import socket
+import sqlite3
+import struct
+import subprocess
+import tempfile
+import urllib.parse
+import urllib.request
+import wave
+from http.server import BaseHTTPRequestHandler, ThreadingHTTPServer
+from dataclasses import dataclass, field
+from datetime import datetime, timezone
 import click
+import yaml
 import numpy as np
-from kubernetes import client, config
-from kubernetes.client.rest import ApiException
 
-from typing import Dict, List, Tuple, Optional
+try:
+    from kubernetes import client, config
+    from kubernetes.client.rest import ApiException
+except ImportError:  # Only needed to monitor a live cluster
+    client = config = None
+
+    class ApiException(Exception):
+        """Stand-in for the kubernetes client exception when it isn't installed."""
+
+from typing import Any, AsyncIterator, Callable, Dict, List, NamedTuple, Tuple, Optional
 
 # --- 1. Simplicity: Provide clear, maintainable solutions ---
 # --- 2. Focus: Stick strictly to defined tasks ---
@@ -33,6 +74,177 @@ USE_KUBE_CONFIG = (
     os.environ.get("USE_KUBE_CONFIG", "true").lower() == "true"
 )  # Use ~/.kube/config (default: true)
 K8S_NAMESPACE = os.environ.get("K8S_NAMESPACE", "default")  # Default namespace
+SEQUENCER_BPM = float(
+    os.environ.get("SEQUENCER_BPM", 120)
+)  # Sequencer tempo in beats per minute
+HEARTBEAT_INTERVAL = float(
+    os.environ.get("HEARTBEAT_INTERVAL", 1.0)
+)  # Seconds between heartbeat ticks
+ENV_PREFIX = "SONIFY_"  # Prefix of environment variables that override configuration settings
+ENV_REFERENCE = re.compile(r"\$(\$?)\{([A-Za-z_][A-Za-z0-9_]*)\}")  # ${NAME} ($${NAME} escapes)
+HEARTBEAT_FREQUENCY = 1760  # Heartbeat tick pitch in Hz (A6)
+HEARTBEAT_DURATION = 0.03  # Heartbeat tick length in seconds
+HEARTBEAT_VOLUME = 0.15  # Heartbeat tick amplitude (0.0 - 1.0)
+VOLUME_STEP = 0.1  # Master volume change per key press
+PAUSE_CHECK_INTERVAL = 0.2  # Seconds between checks while polling is paused
+STALE_COLOR = "#808080"  # Console color of a metric whose fetch timed out
+OUTPUT_DRAIN_TIMEOUT = 5  # Seconds a queued output may take to finish its backlog on shutdown
+MAX_GAIN = 2.0  # Per-metric gain at the top of a MIDI controller knob
+MAX_POLL_INTERVAL = 60.0  # Polling interval in seconds at the top of a MIDI controller knob
+OSC_PREFIX = "/sonify"  # Address prefix of the OSC messages the monitor accepts
+OSC_TEST_FREQUENCY = 440  # Pitch in Hz of an OSC test tone without a frequency argument
+OSC_TEST_DURATION = 0.5  # Length in seconds of an OSC test tone
+DASHBOARD_HISTORY = 60  # Samples per metric kept for the GUI history charts
+DASHBOARD_REFRESH_MS = 250  # Milliseconds between GUI redraws
+WATERFALL_HISTORY = 500  # Ticks per metric kept for the terminal waterfall
+WATERFALL_LABEL_WIDTH = 16  # Characters of each metric name shown left of its waterfall row
+GAUGE_STYLES = ("none", "block", "braille")  # Values accepted for monitoring.gauge
+GAUGE_WIDTH = 8  # Characters in the gauge after each output line's value
+SEVERITY_COLORS = {
+    "ok": "#22C55E",
+    "warning": "#F59E0B",
+    "critical": "#EF4444",
+}  # Tray icon color for the worst severity of a tick, in increasing order of severity
+SAMPLE_RATE = 44100  # CD quality sample rate
+TONE_CACHE_SIZE = 256  # Rendered note buffers kept for reuse
+FADE_DURATION = 0.005  # Seconds of fade-in/out at the edges of every note buffer
+RECORDING_FORMATS = {
+    ".wav": None, ".ogg": ("OGG", "VORBIS"), ".flac": ("FLAC", "PCM_16"),
+}  # soundfile format and subtype of each recording file extension, None for plain WAV
+PIPE_BLOCK = 0.05  # Seconds of audio written to the pipe at a time, silent or not
+RECORDING_BLOCK = 65536  # Samples encoded at a time when compressing a recording
+ICECAST_FORMATS = {
+    "ogg": ("libvorbis", "audio/ogg"), "mp3": ("libmp3lame", "audio/mpeg"),
+}  # ffmpeg encoder and content type of each outputs.icecast.format
+ICECAST_BLOCK = 0.1  # Seconds of audio sent to the encoder at a time, silent or not
+ICECAST_CHUNK = 4096  # Most bytes of the encoded stream relayed to the server at a time
+ICECAST_TIMEOUT = 10  # Seconds to connect to the Icecast server and have the source accepted
+PREEMPT_FADE = 0.05  # Seconds a note is faded over when a higher priority note preempts it
+CRITICAL_PRIORITY = 10  # Added to a metric's sound map priority while its value is critical
+LIMITER_THRESHOLD = 0.8  # Output level above which the master limiter compresses
+LOUDNESS_FLOOR_DB = -12.0  # A-weighting level every note is evened out to by loudness compensation
+AUDIO_FALLBACKS = ("bell", "flash")  # Values accepted in audio.fallback
+AUDIO_BACKENDS = (
+    "simpleaudio", "sounddevice", "webaudio", "midi", "pipe",
+)  # Values accepted for audio.backend
+VOICES = ("sine", "fm", "additive", "pluck", "noise")  # Values accepted for a sound map voice type
+ROW_VOICES = ("sine", "pluck", "fm", "additive")  # Voice types given in turn to a metric's rows
+ANNOTATION_PREFIX = "sonify.k8s.io/"  # Prefix of the workload annotations that adjust the sound
+ANNOTATION_PRIORITIES = {"low": -1, "normal": 0, "high": 2}  # sonify.k8s.io/priority values
+PROFILE_GROUP = "sonify.k8s.io"  # API group of the SonificationProfile custom resource
+PROFILE_VERSION = "v1alpha1"  # API version of the SonificationProfile custom resource
+PROFILE_PLURAL = "sonificationprofiles"  # Resource name of SonificationProfile in the API
+VOLATILITY_EFFECTS = ("vibrato", "tremolo")  # Values accepted for a sound map volatility
+VOLATILITY_WINDOW = 12  # Samples the rolling standard deviation of a metric is taken over
+VOLATILITY_FULL_SCALE = 0.1  # Standard deviation, as a share of the range, that sounds most nervous
+VIBRATO_RATE = 5.5  # Vibrato speed in Hz
+VIBRATO_MAX_DEPTH = 0.03  # Largest vibrato pitch swing, as a share of the frequency
+TREMOLO_DEPTH = 0.6  # Share of the amplitude a tremolo dips by
+TREMOLO_RATES = (2.0, 12.0)  # Tremolo speed in Hz for the calmest and the most volatile values
+NOISE_COLORS = ("white", "pink", "crackle")  # Values accepted for a noise voice
+TEMPO_SYNCS = ("none", "midi", "link")  # Values accepted for tempo.sync
+MIDI_CLOCK_PPQN = 24  # MIDI clock pulses per quarter note
+MIDI_CLOCK_TIMEOUT = 2.0  # Seconds without clock pulses before falling back to the fixed tempo
+OVERRUN_POLICIES = ("drop-oldest", "drop-newest", "shorten")  # Values accepted for audio.overrun_policy
+MIDI_VIRTUAL_PORT = "sonify-k8s"  # Name of the virtual MIDI output port
+MIDI_DRUM_CHANNEL = 9  # General MIDI percussion channel (channel 10, counting from 1)
+MIDI_CC_STEPS = 16  # Control change messages per note when a voice ramps a MIDI CC
+MIDI_VOLUME_CONTROL = 7  # Channel volume controller, ramped down to fade out on shutdown
+MIDI_DEFAULT_VOLUME = 100  # General MIDI channel volume, restored after fading out
+OFFENDER_WARNING_WINDOW = 600  # Seconds a pod's Warning events count towards top_offenders
+GPU_RESOURCE = "nvidia.com/gpu"  # Extended resource counted by gpu_usage
+FALCO_PRIORITIES = (
+    "debug", "informational", "notice", "warning", "error", "critical", "alert", "emergency",
+)  # Falco rule priorities, least severe first; security_alerts plays one above its index
+FALCO_MAX_BODY = 1 << 20  # Largest webhook request body accepted, in bytes
+TRIVY_GROUP = "aquasecurity.github.io"  # API group of trivy-operator's VulnerabilityReport
+TRIVY_VERSION = "v1alpha1"  # API version of trivy-operator's VulnerabilityReport
+TRIVY_PLURAL = "vulnerabilityreports"  # Resource name of VulnerabilityReport in the API
+DEPRECATED_APIS = {
+    "extensions/v1beta1": {"Deployment": "1.16", "DaemonSet": "1.16", "Ingress": "1.22"},
+    "apps/v1beta1": {"Deployment": "1.16", "StatefulSet": "1.16"},
+    "apps/v1beta2": {"Deployment": "1.16", "StatefulSet": "1.16", "DaemonSet": "1.16"},
+    "networking.k8s.io/v1beta1": {"Ingress": "1.22"},
+    "batch/v1beta1": {"CronJob": "1.25"},
+    "policy/v1beta1": {"PodDisruptionBudget": "1.25"},
+    "autoscaling/v2beta1": {"HorizontalPodAutoscaler": "1.25"},
+    "autoscaling/v2beta2": {"HorizontalPodAutoscaler": "1.26"},
+}  # Removed API versions and, per kind, the Kubernetes release that removed it
+DEPRECATION_RESOURCES = (
+    ("apps", "v1", "deployments", "Deployment"),
+    ("apps", "v1", "statefulsets", "StatefulSet"),
+    ("apps", "v1", "daemonsets", "DaemonSet"),
+    ("batch", "v1", "cronjobs", "CronJob"),
+    ("networking.k8s.io", "v1", "ingresses", "Ingress"),
+    ("autoscaling", "v2", "horizontalpodautoscalers", "HorizontalPodAutoscaler"),
+    ("policy", "v1", "poddisruptionbudgets", "PodDisruptionBudget"),
+)  # Resources whose last applied apiVersion deprecated_apis inspects, and their kinds
+LAST_APPLIED_ANNOTATION = "kubectl.kubernetes.io/last-applied-configuration"  # kubectl apply's
+DEPRECATION_REMINDER_POLLS = 16  # Polls between deprecated_api reminders for a single offender
+OPENCOST_WINDOW_PATTERN = re.compile(r"^\d+[mhd]$")  # opencost.window, e.g. 1h or 30m
+EVICTION_WINDOW = 600  # Seconds an Evicted event counts towards memory_evictions or disk_evictions
+EVICTION_RESOURCES = {
+    "memory": ("memory",), "disk": ("ephemeral-storage", "nodefs", "imagefs", "disk"),
+}  # Words in an eviction message naming the resource the node ran short of
+ADMISSION_WINDOW = 600  # Seconds a rejected create counts towards admission_failures
+ADMISSION_PATTERNS = {
+    "denied": re.compile(r'admission webhook "([^"]+)" denied the request'),
+    "failed": re.compile(r'failed calling webhook "([^"]+)"'),
+}  # How an event message names a webhook that denied a request or couldn't be called
+POD_SECURITY_LABEL = "pod-security.kubernetes.io/"  # Prefix of Pod Security admission's labels
+POD_SECURITY_MODES = ("enforce", "warn", "audit")  # Pod Security admission namespace label modes
+POD_SECURITY_LEVELS = ("privileged", "baseline", "restricted")  # Standards, least strict first
+POD_SECURITY_PATTERN = re.compile(r'violates PodSecurity "([a-z]+)')  # In a rejected create's event
+BASELINE_CAPABILITIES = frozenset({
+    "AUDIT_WRITE", "CHOWN", "DAC_OVERRIDE", "FOWNER", "FSETID", "KILL", "MKNOD",
+    "NET_BIND_SERVICE", "SETFCAP", "SETGID", "SETPCAP", "SETUID", "SYS_CHROOT",
+})  # Capabilities the baseline Pod Security Standard allows adding
+PROVISIONING_REASONS = ("ProvisioningFailed", "FailedBinding")  # PVC events of a failed volume
+MEMORY_SUFFIXES = {
+    "Ki": 2**10, "Mi": 2**20, "Gi": 2**30, "Ti": 2**40, "Pi": 2**50,
+    "k": 10**3, "M": 10**6, "G": 10**9, "T": 10**12, "P": 10**15, "m": 10**-3,
+}  # Multipliers of Kubernetes memory quantity suffixes
+SHUTDOWN_FADE = 1.0  # Seconds playing audio takes to fade out when the monitor stops
+RATE_SUFFIX = ".rate"  # Suffix of a metric's per-second rate of change, e.g. replicas.rate
+EXPRESSION_FUNCTIONS: Dict[str, Callable] = {
+    "min": min, "max": max, "abs": abs, "sqrt": math.sqrt, "log": math.log,
+}  # Functions a derived metric's expression may call
+HEALTH_COMPONENTS = (
+    "readiness", "node_pressure", "restarts", "errors",
+)  # Parts of the cluster_health score, each weighted by sound_map.cluster_health.weights
+CLUSTER_EVENTS = (
+    "pod_restart", "deploy", "node_join", "node_lost", "scale_up", "scale_down",
+    "memory_eviction", "disk_eviction", "eviction_threshold", "rollout_succeeded",
+    "rollout_failed", "deprecated_api", "admission_failure", "pod_security_violation",
+    "provisioning_failed",
+)  # Discrete events detected between polls
+AUTOSCALER_REASONS = {
+    "ScaledUpGroup": "scale_ups", "ScaleDown": "scale_downs", "ScaleDownEmpty": "scale_downs",
+}  # cluster-autoscaler Event reasons, counted into node_pressure's extra data
+NOTE_NAME_PATTERN = re.compile(r"^([A-Ga-g])([#b]?)(-?\d+)$")  # Scientific pitch notation, e.g. F#5
+NOTE_OFFSETS = {"C": 0, "D": 2, "E": 4, "F": 5, "G": 7, "A": 9, "B": 11}  # Semitones above C
+HEX_COLOR_PATTERN = re.compile(r"^#[0-9A-Fa-f]{6}$")  # Colors that can be blended or drawn
+NOTE_NAMES = ("C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B")  # By semitone
+MAPPINGS = ("discrete", "continuous")  # Values accepted for a sound map mapping
+SCALES = {
+    "chromatic": list(range(12)),
+    "major": [0, 2, 4, 5, 7, 9, 11],
+    "minor": [0, 2, 3, 5, 7, 8, 10],
+    "pentatonic": [0, 2, 4, 7, 9],
+}  # Degrees of a 12-step tuning in one octave of each scale a sound map's steps can use
+TUNING_SYSTEMS = ("equal", "just", "scala")  # Values accepted for tuning.system
+JUST_INTONATION = (
+    16 / 15, 9 / 8, 6 / 5, 5 / 4, 4 / 3, 45 / 32, 3 / 2, 8 / 5, 5 / 3, 9 / 5, 15 / 8, 2.0,
+)  # 5-limit chromatic ratios above the root, ending with the octave
+WEB_PLAYER_PATH = os.path.join(
+    os.path.dirname(os.path.abspath(__file__)), "web", "index.html"
+)  # Page served by the webaudio backend
+ARPEGGIO_MIN_STEP = float(
+    os.environ.get("ARPEGGIO_MIN_STEP", 0.08)
+)  # Shortest arpeggio step in seconds
+FUSE_DIMENSIONS = ("pitch", "brightness", "pan")  # Keys accepted in a sound map fuse
+MIDI_PAN_CONTROL = 10  # MIDI control change that pans a channel
+SOUR_INTERVAL = 2 ** (1 / 12)  # Detuning of a sour note: a semitone above its pitch
 
 # --- Logging Setup ---
 logging.basicConfig(level=LOG_LEVEL, format="%(asctime)s - %(levelname)s - %(message)s")
@@ -40,10 +252,13 @@ logger = logging.getLogger(__name__)
 
 # --- 3. Language-Specific Best Practices ---
 # Use a dictionary to map metrics to their sonification configurations.
+# "thresholds" is a (warning, critical) pair; when warning > critical, lower values are worse.
 SOUND_MAP: Dict[str, Dict[str, Tuple[int, str]]] = {
     "cpu_usage": {
         "metric_name": "CPU Usage",
         "unit": "%",
+        "range": (0, 100),
+        "thresholds": (70, 90),
         "notes": [
             (262, "C4"),
             (294, "D4"),
@@ -68,6 +283,8 @@ SOUND_MAP: Dict[str, Dict[str, Tuple[int, str]]] = {
     "memory_usage": {
         "metric_name": "Memory Usage",
         "unit": "%",
+        "range": (0, 100),
+        "thresholds": (75, 90),
         "notes": [
             (277, "C#4"),
             (311, "D#4"),
@@ -92,8 +309,11 @@ SOUND_MAP: Dict[str, Dict[str, Tuple[int, str]]] = {
     "pod_status": {
         "metric_name": "Pod Status",
         "unit": "",
+        "range": (0, 3),
+        "thresholds": (1, 0),
         "notes": [(220, "A3"), (262, "C4"), (330, "E4"), (392, "G4")],
         "colors": ["#86EF7D", "#22C55E", "#16A34A", "#065F46"],
+        "priority": 2,
         "status_map": {
             "Running": 3,
             "Pending": 1,
@@ -105,6 +325,8 @@ SOUND_MAP: Dict[str, Dict[str, Tuple[int, str]]] = {
     "http_latency": {
         "metric_name": "HTTP Latency",
         "unit": "ms",
+        "range": (0, 500),
+        "thresholds": (200, 400),
         "notes": [
             (294, "D4"),
             (330, "E4"),
@@ -129,6 +351,8 @@ SOUND_MAP: Dict[str, Dict[str, Tuple[int, str]]] = {
     "errors_per_second": {
         "metric_name": "Errors/Second",
         "unit": "err/s",
+        "range": (0, 10),
+        "thresholds": (1, 5),
         "notes": [
             (131, "C3"),
             (147, "D3"),
@@ -153,6 +377,8 @@ SOUND_MAP: Dict[str, Dict[str, Tuple[int, str]]] = {
     "replicas": {
         "metric_name": "Replica Count",
         "unit": "Count",
+        "range": (0, 5),
+        "thresholds": None,
         "notes": [
             (262, "C4"),
             (277, "C#4"),
@@ -177,522 +403,10550 @@ SOUND_MAP: Dict[str, Dict[str, Tuple[int, str]]] = {
     "node_pressure": {
         "metric_name": "Node Pressure",
         "unit": "",
+        "range": (0, 3),
+        "thresholds": (1, 3),
         "notes": [(262, "C4"), (294, "D4"), (330, "E4"), (349, "F4")],
         "colors": ["#FFFFFF", "#F0F4C3", "#D4E157", "#A4A71D"],
+        "priority": 2,
         "status_map": {"False": 0, "True": 3},
     },
+    # Each node's CPU requests against its allocatable CPU, played in node order as a melody
+    "node_melody": {
+        "metric_name": "Node Melody",
+        "unit": "%",
+        "range": (0, 100),
+        "thresholds": (70, 90),
+        "notes": [
+            (262, "C4"),
+            (294, "D4"),
+            (330, "E4"),
+            (392, "G4"),
+            (440, "A4"),
+            (523, "C5"),
+            (587, "D5"),
+            (659, "E5"),
+        ],
+        "colors": [
+            "#E0F2FE",
+            "#BAE6FD",
+            "#7DD3FC",
+            "#38BDF8",
+            "#FBBF24",
+            "#F59E0B",
+            "#EA580C",
+            "#DC2626",
+        ],
+        "sequence": True,
+    },
+    # Synthetic: a weighted score of the healthy share of each component, see cluster_health()
+    "cluster_health": {
+        "metric_name": "Cluster Health",
+        "unit": "",
+        "range": (0, 100),
+        "thresholds": (70, 40),
+        "notes": [
+            (220, "A3"),
+            (262, "C4"),
+            (294, "D4"),
+            (330, "E4"),
+            (392, "G4"),
+            (440, "A4"),
+            (523, "C5"),
+            (587, "D5"),
+        ],
+        "colors": [
+            "#B91C1C",
+            "#DC2626",
+            "#EA580C",
+            "#F59E0B",
+            "#EAB308",
+            "#84CC16",
+            "#22C55E",
+            "#15803D",
+        ],
+        "weights": {"readiness": 0.4, "node_pressure": 0.2, "restarts": 0.2, "errors": 0.2},
+        "max_restart_rate": 1.0,
+    },
+    # Utilization of a namespace's GPUs, or their share of allocatable GPUs without
+    # Prometheus; it glides so idle GPUs are heard as clearly as busy ones
+    "gpu_usage": {
+        "metric_name": "GPU Usage",
+        "unit": "%",
+        "range": (0, 100),
+        "thresholds": None,
+        "mapping": "continuous",
+        "notes": [
+            (220, "A3"),
+            (262, "C4"),
+            (294, "D4"),
+            (330, "E4"),
+            (392, "G4"),
+            (440, "A4"),
+            (523, "C5"),
+            (587, "D5"),
+        ],
+        "colors": [
+            "#9CA3AF",
+            "#BBF7D0",
+            "#86EFAC",
+            "#4ADE80",
+            "#22C55E",
+            "#16A34A",
+            "#15803D",
+            "#166534",
+        ],
+    },
+    # A namespace's spend rate from OpenCost, in your currency per hour; within budget
+    # (below the warning threshold) it is a soft, long, dull pad
+    "cost_per_hour": {
+        "metric_name": "Cost per Hour",
+        "unit": "/h",
+        "range": (0, 20),
+        "thresholds": (10, 15),
+        "notes": [
+            (131, "C3"),
+            (147, "D3"),
+            (165, "E3"),
+            (196, "G3"),
+            (220, "A3"),
+            (262, "C4"),
+            (330, "E4"),
+            (392, "G4"),
+        ],
+        "colors": [
+            "#D1FAE5",
+            "#A7F3D0",
+            "#6EE7B7",
+            "#FDE68A",
+            "#FCD34D",
+            "#F59E0B",
+            "#EA580C",
+            "#DC2626",
+        ],
+        "voice": {"type": "additive"},
+        "duration_range": (1.2, 0.4),
+        "velocity_range": (0.2, 1.0),
+    },
+    # Workloads last applied with an API version removed from Kubernetes; each poll
+    # has a reminder motif that comes round more often the more of them there are
+    "deprecated_apis": {
+        "metric_name": "Deprecated APIs",
+        "unit": "",
+        "range": (0, 10),
+        "thresholds": (1, 5),
+        "notes": [
+            (262, "C4"),
+            (294, "D4"),
+            (330, "E4"),
+            (349, "F4"),
+            (392, "G4"),
+            (440, "A4"),
+            (494, "B4"),
+            (523, "C5"),
+        ],
+        "colors": [
+            "#E5E7EB",
+            "#FEF9C3",
+            "#FEF08A",
+            "#FDE047",
+            "#FACC15",
+            "#EAB308",
+            "#CA8A04",
+            "#A16207",
+        ],
+        "voice": {"type": "pluck"},
+    },
+    # Critical CVEs in the images running in a namespace, from trivy-operator; a slow
+    # drone that sinks lower and lasts longer as unpatched criticals accumulate
+    "critical_cves": {
+        "metric_name": "Critical CVEs",
+        "unit": "",
+        "range": (0, 20),
+        "thresholds": (1, 5),
+        "notes": [
+            (147, "D3"),
+            (131, "C3"),
+            (110, "A2"),
+            (98, "G2"),
+            (82, "E2"),
+            (73, "D2"),
+            (65, "C2"),
+            (55, "A1"),
+        ],
+        "colors": [
+            "#CBD5E1",
+            "#94A3B8",
+            "#64748B",
+            "#7C3AED",
+            "#6D28D9",
+            "#5B21B6",
+            "#4C1D95",
+            "#2E1065",
+        ],
+        "voice": {"type": "fm", "ratio": 0.5, "index": 1.5},
+        "duration_range": (1.5, 3.0),
+        "velocity_range": (0.3, 0.9),
+    },
+    # The worst Falco alert of each tick, in a clangorous FM voice no other metric
+    # uses; 0 (no alerts) is almost silent
+    "security_alerts": {
+        "metric_name": "Security Alerts",
+        "unit": "",
+        "range": (0, len(FALCO_PRIORITIES)),
+        "thresholds": (4, 6),
+        "notes": [
+            (262, "C4"),
+            (370, "F#4"),
+            (523, "C5"),
+            (740, "F#5"),
+            (831, "G#5"),
+            (932, "A#5"),
+            (1047, "C6"),
+            (1175, "D6"),
+            (1480, "F#6"),
+        ],
+        "colors": [
+            "#A3A3A3",
+            "#C4B5FD",
+            "#A78BFA",
+            "#F0ABFC",
+            "#E879F9",
+            "#F43F5E",
+            "#E11D48",
+            "#BE123C",
+            "#881337",
+        ],
+        "priority": 5,
+        "status_map": {"none": 0, **{p: i + 1 for i, p in enumerate(FALCO_PRIORITIES)}},
+        "voice": {"type": "fm", "ratio": 3.5, "index": 6.0},
+        "velocity_range": (0.05, 1.0),
+    },
+    # Pods evicted by the kubelet in the last EVICTION_WINDOW, memory low and disk high
+    "memory_evictions": {
+        "metric_name": "Memory Evictions",
+        "unit": "pods",
+        "range": (0, 10),
+        "thresholds": (1, 5),
+        "notes": [
+            (131, "C3"),
+            (147, "D3"),
+            (165, "E3"),
+            (196, "G3"),
+            (220, "A3"),
+            (262, "C4"),
+            (294, "D4"),
+            (330, "E4"),
+        ],
+        "colors": [
+            "#EDE9FE",
+            "#DDD6FE",
+            "#C4B5FD",
+            "#A78BFA",
+            "#8B5CF6",
+            "#7C3AED",
+            "#6D28D9",
+            "#5B21B6",
+        ],
+        "priority": 2,
+    },
+    "disk_evictions": {
+        "metric_name": "Disk Evictions",
+        "unit": "pods",
+        "range": (0, 10),
+        "thresholds": (1, 5),
+        "notes": [
+            (523, "C5"),
+            (587, "D5"),
+            (659, "E5"),
+            (784, "G5"),
+            (880, "A5"),
+            (1047, "C6"),
+            (1175, "D6"),
+            (1319, "E6"),
+        ],
+        "colors": [
+            "#FEF3C7",
+            "#FDE68A",
+            "#FCD34D",
+            "#D6A15A",
+            "#B7791F",
+            "#975A16",
+            "#7B4A12",
+            "#5F370E",
+        ],
+        "priority": 2,
+    },
+    # Creates rejected by admission webhooks in the last ADMISSION_WINDOW, whether
+    # denied or failing because the webhook can't be called
+    "admission_failures": {
+        "metric_name": "Admission Failures",
+        "unit": "",
+        "range": (0, 20),
+        "thresholds": (1, 5),
+        "notes": [
+            (220, "A3"),
+            (247, "B3"),
+            (262, "C4"),
+            (311, "D#4"),
+            (330, "E4"),
+            (370, "F#4"),
+            (415, "G#4"),
+            (440, "A4"),
+        ],
+        "colors": [
+            "#F1F5F9",
+            "#FFE4E6",
+            "#FECDD3",
+            "#FDA4AF",
+            "#FB7185",
+            "#F43F5E",
+            "#E11D48",
+            "#9F1239",
+        ],
+        "priority": 2,
+    },
+    # PersistentVolumeClaims stuck Pending because their volume failed to provision
+    "pending_volumes": {
+        "metric_name": "Pending Volumes",
+        "unit": "pvcs",
+        "range": (0, 8),
+        "thresholds": (1, 3),
+        "notes": [
+            (196, "G3"),
+            (220, "A3"),
+            (233, "A#3"),
+            (262, "C4"),
+            (294, "D4"),
+            (311, "D#4"),
+            (349, "F4"),
+            (392, "G4"),
+        ],
+        "colors": [
+            "#F5F5F4",
+            "#E7E5E4",
+            "#D6D3D1",
+            "#FDBA74",
+            "#FB923C",
+            "#F97316",
+            "#C2410C",
+            "#7C2D12",
+        ],
+        "priority": 2,
+    },
+    # Minutes the oldest of those claims has been stuck; it glides, so a failure left
+    # alone is heard climbing
+    "pending_volume_age": {
+        "metric_name": "Pending Volume Age",
+        "unit": "min",
+        "range": (0, 60),
+        "thresholds": (5, 30),
+        "mapping": "continuous",
+        "notes": [
+            (131, "C3"),
+            (165, "E3"),
+            (196, "G3"),
+            (262, "C4"),
+            (330, "E4"),
+            (392, "G4"),
+            (523, "C5"),
+            (659, "E5"),
+        ],
+        "colors": [
+            "#F5F5F4",
+            "#E7E5E4",
+            "#D6D3D1",
+            "#FDBA74",
+            "#FB923C",
+            "#F97316",
+            "#C2410C",
+            "#7C2D12",
+        ],
+    },
+    # Pods Pod Security admission rejected in the last ADMISSION_WINDOW, plus running pods
+    # that violate the strictest level the namespace is labelled with
+    "pod_security": {
+        "metric_name": "Pod Security Violations",
+        "unit": "pods",
+        "range": (0, 10),
+        "thresholds": (1, 5),
+        "notes": [
+            (247, "B3"),
+            (277, "C#4"),
+            (311, "D#4"),
+            (349, "F4"),
+            (392, "G4"),
+            (440, "A4"),
+            (494, "B4"),
+            (554, "C#5"),
+        ],
+        "colors": [
+            "#F0FDFA",
+            "#CCFBF1",
+            "#99F6E4",
+            "#FDE68A",
+            "#FBBF24",
+            "#F97316",
+            "#EA580C",
+            "#B91C1C",
+        ],
+        "voice": {"type": "fm", "ratio": 1.41, "index": 3.0},
+    },
+    # The worst pods by restarts plus recent warnings, played worst first as a sequence
+    "top_offenders": {
+        "metric_name": "Top Offenders",
+        "unit": "",
+        "range": (0, 10),
+        "thresholds": (3, 6),
+        "notes": [
+            (196, "G3"),
+            (233, "Bb3"),
+            (262, "C4"),
+            (311, "Eb4"),
+            (349, "F4"),
+            (392, "G4"),
+            (466, "Bb4"),
+            (523, "C5"),
+        ],
+        "colors": [
+            "#A3A3A3",
+            "#FDE68A",
+            "#FCD34D",
+            "#FBBF24",
+            "#F59E0B",
+            "#F97316",
+            "#EA580C",
+            "#DC2626",
+        ],
+        "count": 3,
+        "sequence": True,
+    },
+    # Mean time the API server took to answer the monitor's own requests since the last
+    # sample; it glides, so the control plane slowing down is heard as a rising tone
+    "apiserver_latency": {
+        "metric_name": "API Server Latency",
+        "unit": "ms",
+        "range": (0, 1500),
+        "thresholds": (300, 1000),
+        "mapping": "continuous",
+        "notes": [
+            (196, "G3"),
+            (233, "A#3"),
+            (262, "C4"),
+            (311, "D#4"),
+            (349, "F4"),
+            (392, "G4"),
+            (466, "A#4"),
+            (523, "C5"),
+        ],
+        "colors": [
+            "#ECFDF5",
+            "#D1FAE5",
+            "#A7F3D0",
+            "#FDE68A",
+            "#FCD34D",
+            "#FB923C",
+            "#F97316",
+            "#C2410C",
+        ],
+    },
+    # Schedulable nodes' allocatable CPU or memory, whichever is scarcer, not yet requested;
+    # lower values are worse and it sinks as the cluster fills up
+    "scheduling_headroom": {
+        "metric_name": "Scheduling Headroom",
+        "unit": "%",
+        "range": (0, 100),
+        "thresholds": (20, 10),
+        "notes": [
+            (131, "C3"),
+            (147, "D3"),
+            (165, "E3"),
+            (196, "G3"),
+            (220, "A3"),
+            (262, "C4"),
+            (294, "D4"),
+            (330, "E4"),
+        ],
+        "colors": [
+            "#B91C1C",
+            "#EA580C",
+            "#F59E0B",
+            "#FCD34D",
+            "#BEF264",
+            "#86EFAC",
+            "#4ADE80",
+            "#22C55E",
+        ],
+        "priority": 2,
+    },
+    # Pods created plus pods deleted per minute; a namespace that keeps replacing its
+    # pods sounds high even while every pod is Running
+    "pod_churn": {
+        "metric_name": "Pod Churn",
+        "unit": "pods/min",
+        "range": (0, 30),
+        "thresholds": (5, 15),
+        "notes": [
+            (330, "E4"),
+            (370, "F#4"),
+            (415, "G#4"),
+            (440, "A4"),
+            (494, "B4"),
+            (554, "C#5"),
+            (622, "D#5"),
+            (659, "E5"),
+        ],
+        "colors": [
+            "#ECFEFF",
+            "#CFFAFE",
+            "#A5F3FC",
+            "#67E8F9",
+            "#FDE047",
+            "#FB923C",
+            "#F4511E",
+            "#C62828",
+        ],
+        "voice": {"type": "pluck"},
+    },
+    # Each Deployment's ready replicas against its desired replicas, in name order, one
+    # row and one voice per Deployment; the value is the least ready one
+    "deployment_readiness": {
+        "metric_name": "Deployment Readiness",
+        "unit": "%",
+        "range": (0, 100),
+        "thresholds": (75, 50),
+        "notes": [
+            (196, "G3"),
+            (220, "A3"),
+            (262, "C4"),
+            (294, "D4"),
+            (330, "E4"),
+            (392, "G4"),
+            (440, "A4"),
+            (523, "C5"),
+        ],
+        "colors": [
+            "#B71C1C",
+            "#D32F2F",
+            "#F4511E",
+            "#FB8C00",
+            "#FDD835",
+            "#C0CA33",
+            "#7CB342",
+            "#43A047",
+        ],
+        "sequence": True,
+        "rows": True,
+    },
 }
 
 
-# --- Audio Utility Functions ---
-def play_note(frequency: int, duration: float = 0.5) -> None:
-    """
-    Plays a musical note using simpleaudio and numpy for reliable sound generation.
-
-    Args:
-        frequency: The frequency of the note in Hz.
-        duration: The duration of the note in seconds.
-    """
-    if os.environ.get("TEST_MODE", "False").lower() == "true":
-        logger.info(
-            f"Playing note at {frequency} Hz for {duration} seconds (TEST_MODE)"
-        )
-        return
-
-    try:
-        import simpleaudio as sa
-        import numpy as np
-
-        # Calculate sample values for pure tone
-        sample_rate = 44100  # CD quality sample rate
-        t = np.linspace(0, duration, int(sample_rate * duration), False)
-
-        # Generate a pure tone with envelope for cleaner sound
-        # Apply an ADSR (Attack, Decay, Sustain, Release) envelope
-        attack = 0.05  # Short attack
-        decay = 0.05  # Short decay
-        release = 0.1  # Short release
-        sustain_level = 0.8  # Sustain at 80% amplitude
-
-        # Create a time array for the envelope
-        envelope = np.ones(len(t))
-        attack_samples = int(attack * sample_rate)
-        decay_samples = int(decay * sample_rate)
-        release_samples = int(release * sample_rate)
-
-        # Apply attack
-        if attack_samples > 0:
-            envelope[:attack_samples] = np.linspace(0, 1, attack_samples)
-
-        # Apply decay to sustain level
-        if decay_samples > 0:
-            start_idx = attack_samples
-            end_idx = start_idx + decay_samples
-            if end_idx > start_idx:  # Ensure we have decay time
-                envelope[start_idx:end_idx] = np.linspace(
-                    1, sustain_level, end_idx - start_idx
-                )
-
-        # Apply release
-        if release_samples > 0:
-            start_idx = len(envelope) - release_samples
-            if start_idx < len(envelope):  # Ensure we have release time
-                envelope[start_idx:] = np.linspace(
-                    sustain_level if decay_samples > 0 else 1,
-                    0,
-                    len(envelope) - start_idx,
-                )
-
-        # Generate the tone with the envelope
-        tone = np.sin(2 * np.pi * frequency * t) * envelope
-
-        # Normalize to 16-bit range and convert to int16
-        audio = (tone * 32767).astype(np.int16)
-
-        # Play the sound
-        play_obj = sa.play_buffer(audio, 1, 2, sample_rate)
+# Short motifs for discrete occurrences, addressable by name from the "earcons" settings.
+# "notes" are note names or frequencies, each played for "step" seconds.
+EARCONS: Dict[str, Dict] = {
+    "fanfare": {"notes": ["C5", "E5", "G5", "C6"], "step": 0.12},
+    "failure": {"notes": ["A4", "F4", "D4"], "step": 0.12},
+    "alarm": {"notes": ["A5", "E5", "A5", "E5"], "step": 0.1},
+    "all-clear": {"notes": ["E5", "A5"], "step": 0.12},
+    "restart": {"notes": ["G4", "D4"], "step": 0.15, "voice": {"type": "pluck"}},
+    "deploy": {"notes": ["C5", "G5"], "step": 0.15, "voice": {"type": "pluck"}},
+    "node-join": {"notes": ["C4", "G4", "C5"], "step": 0.1, "voice": {"type": "pluck"}},
+    "node-lost": {"notes": ["C5", "G4", "C4"], "step": 0.1, "voice": {"type": "pluck"}},
+    "scale-up": {"notes": ["C4", "E4", "G4", "C5", "E5"], "step": 0.07, "volume": 0.7},
+    "scale-down": {"notes": ["E5", "C5", "G4", "E4", "C4"], "step": 0.07, "volume": 0.7},
+    "memory-eviction": {"notes": ["E3", "C3"], "step": 0.15, "voice": {"type": "pluck"}},
+    "disk-eviction": {"notes": ["E6", "C6"], "step": 0.15, "voice": {"type": "pluck"}},
+    "eviction-threshold": {"notes": ["C4", "B3", "C4", "B3"], "step": 0.08, "volume": 0.6},
+    "capability-missing": {"notes": ["E4", "C4"], "step": 0.25, "volume": 0.6},
+    "admission-rejected": {"notes": ["A4", "A4", "D#4"], "step": 0.1, "voice": {"type": "fm"}},
+    "pod-security": {"notes": ["B4", "F5", "B4"], "step": 0.09, "volume": 0.7},
+    "provisioning-failed": {"notes": ["D4", "C#4", "C4"], "step": 0.14, "voice": {"type": "pluck"}},
+    "deprecation-reminder": {"notes": ["G4", "E4", "G4", "C4"], "step": 0.12, "volume": 0.5},
+    "heartbeat": {
+        "notes": [HEARTBEAT_FREQUENCY],
+        "step": HEARTBEAT_DURATION,
+        "volume": HEARTBEAT_VOLUME,
+    },
+    "heartbeat-warning": {
+        "notes": [HEARTBEAT_FREQUENCY * 3 / 4],
+        "step": HEARTBEAT_DURATION,
+        "volume": HEARTBEAT_VOLUME,
+        "voice": {"type": "fm", "ratio": 1.5, "index": 1.0},
+    },
+    "heartbeat-critical": {
+        "notes": [HEARTBEAT_FREQUENCY / 2, HEARTBEAT_FREQUENCY / 2],
+        "step": HEARTBEAT_DURATION,
+        "volume": HEARTBEAT_VOLUME,
+        "voice": {"type": "fm", "ratio": 1.41, "index": 3.0},
+    },
+}
+HEARTBEAT_EARCONS = {
+    "ok": "heartbeat", "warning": "heartbeat-warning", "critical": "heartbeat-critical",
+}  # Earcon the heartbeat ticks with for the cluster's current severity
 
-        # Wait for the sound to finish
-        play_obj.wait_done()
 
-    except Exception as e:
-        logger.error(f"Error playing note: {e}")
-        # Fallback to simple sleep if sound fails
-        time.sleep(duration)
+# --- Configuration File ---
+# Defaults for every setting that can be given in config.yaml
+DEFAULT_CONFIG: Dict[str, Dict] = {
+    "kubernetes": {
+        "namespace": K8S_NAMESPACE,
+        "use_kubeconfig": USE_KUBE_CONFIG,
+        "api_url": K8S_API_URL,
+        "token": "",
+        "token_file": "",
+        "ca_cert": "",
+        "insecure_skip_verify": False,
+        "proxy": "",
+        "kubeconfig": "",
+        "context": "",
+        "impersonate_user": "",
+        "impersonate_groups": [],
+        "label_selector": "",
+        "namespace_include": [],
+        "namespace_exclude": [],
+        "namespace_refresh": 60,
+        "annotation_refresh": 0,
+        "profile_refresh": 0,
+    },
+    "monitoring": {
+        "poll_interval": POLL_INTERVAL,
+        "verbose": False,
+        "use_color": False,
+        "gauge": "none",
+        "fetch_timeout": 10,
+        "state_file": "",
+        "sample_queue": 0,
+        "on_change_only": False,
+        "change_delta": 0.0,
+    },
+    "audio": {
+        "use_midi": False,
+        "midi_port": "",
+        "midi_virtual": False,
+        "note_duration": 0.5,
+        "enabled": True,
+        "backend": "simpleaudio",
+        "device": "",
+        "host_api": "",
+        "latency": "low",
+        "fade": FADE_DURATION,
+        "crossfade": 0.0,
+        "schedule_grid": 0.0,
+        "limiter_threshold": LIMITER_THRESHOLD,
+        "loudness_compensation": False,
+        "queue_size": 0,
+        "overrun_policy": "drop-oldest",
+        "preempt": True,
+        "fallback": [],
+        "web_host": "127.0.0.1",
+        "web_port": 8765,
+        "pipe_path": "-",
+        "record_path": "",
+        "drum_map": {"pod_restart": 38, "deploy": 49, "node_join": 45},
+    },
+    "metrics": {
+        # node_melody, cluster_health and top_offenders are opt-in, since they fetch
+        # what the others already do, the evictions since they need to read events,
+        # gpu_usage since most clusters have no GPUs, cost_per_hour since it needs OpenCost
+        # and security_alerts and critical_cves since they need Falco and trivy-operator
+        "enabled": [
+            name for name in SOUND_MAP
+            if name not in (
+                "node_melody", "cluster_health", "top_offenders", "memory_evictions",
+                "disk_evictions", "gpu_usage", "cost_per_hour", "security_alerts",
+                "critical_cves", "deprecated_apis", "apiserver_latency",
+                "admission_failures", "pod_security", "pending_volumes", "pending_volume_age",
+                "scheduling_headroom", "pod_churn", "deployment_readiness",
+            )
+        ],
+        "lead": "",
+        "accompaniment_volume": 0.5,
+    },
+    "tempo": {
+        "sync": "none",
+        "midi_port": "",
+    },
+    "rollouts": {
+        "enabled": True,
+        "success": ["C5", "E5", "G5", "C6"],
+        "failure": ["A4", "F4", "D4"],
+        "step": 0.12,
+    },
+    "earcons": {
+        "library": {},
+        "events": {
+            "node_join": "node-join",
+            "node_lost": "node-lost",
+            "scale_up": "scale-up",
+            "scale_down": "scale-down",
+            "memory_eviction": "memory-eviction",
+            "disk_eviction": "disk-eviction",
+            "eviction_threshold": "eviction-threshold",
+            "rollout_succeeded": "fanfare",
+            "rollout_failed": "failure",
+            "deprecated_api": "deprecation-reminder",
+            "admission_failure": "admission-rejected",
+            "pod_security_violation": "pod-security",
+            "provisioning_failed": "provisioning-failed",
+        },
+        "alarms": {},
+        "missing_permission": "capability-missing",
+    },
+    "alarms": {
+        "escalate_after": 0,
+        "max_escalation": 3,
+        "reminder_volume": 0.0,
+        "duck_volume": 0.5,
+        "duck_release": 10,
+    },
+    "controller": {
+        "enabled": False,
+        "midi_port": "",
+        "controls": {7: "volume"},
+    },
+    "tuning": {
+        "system": "equal",
+        "scala_file": "",
+        "root": "C4",
+        "a4": 440.0,
+    },
+    "osc": {
+        "enabled": False,
+        "host": "127.0.0.1",
+        "port": 9000,
+    },
+    "statsd": {
+        "enabled": False,
+        "host": "127.0.0.1",
+        "port": 8125,
+        "mapping": {},
+    },
+    "falco": {
+        "enabled": False,
+        "host": "127.0.0.1",
+        "port": 2802,
+        "token": "",
+        "token_file": "",
+        "min_priority": "notice",
+    },
+    "prometheus": {
+        "url": "",
+        "token": "",
+        "token_file": "",
+        "gpu_query": 'avg(DCGM_FI_DEV_GPU_UTIL{namespace="$namespace"})',
+    },
+    "opencost": {
+        "url": "",
+        "window": "1h",
+    },
+    "outputs": {
+        "queue_size": 0,
+        "influxdb": {
+            "enabled": False,
+            "url": "http://localhost:8086",
+            "org": "",
+            "bucket": "sonify-k8s",
+            "token": "",
+            "token_file": "",
+            "measurement": "sonify_k8s",
+        },
+        "graphite": {
+            "enabled": False,
+            "host": "localhost",
+            "port": 2003,
+            "prefix": "sonify_k8s",
+        },
+        "grafana": {
+            "enabled": False,
+            "url": "http://localhost:3000",
+            "token": "",
+            "token_file": "",
+            "dashboard_uid": "",
+            "tags": ["sonify-k8s"],
+        },
+        "desktop": {
+            "enabled": False,
+        },
+        "haptic": {
+            "enabled": False,
+            "controller": 0,
+            "warning": 0.3,
+            "critical": 1.0,
+            "duration": 0.5,
+        },
+        "history": {
+            "enabled": False,
+            "path": "sonify-k8s-history.db",
+        },
+        "icecast": {
+            "enabled": False,
+            "url": "http://localhost:8000/sonify-k8s.ogg",
+            "user": "source",
+            "password": "",
+            "password_file": "",
+            "format": "ogg",
+            "bitrate": 96,
+            "name": "Sonify K8s",
+            "description": "Live sonification of the cluster",
+            "ffmpeg": "ffmpeg",
+        },
+    },
+}
+
+
+# Comments written above each setting by `generate-config`
+CONFIG_COMMENTS: Dict[str, str] = {
+    "kubernetes": "Kubernetes Configuration",
+    "kubernetes.namespace": "Namespace to monitor",
+    "kubernetes.use_kubeconfig": "Use kubeconfig file from ~/.kube/config (true/false)\n"
+    "If false, will attempt to use in-cluster configuration",
+    "kubernetes.api_url": "API URL, used when use_kubeconfig is false outside a cluster",
+    "kubernetes.token": "Bearer token for api_url",
+    "kubernetes.token_file": "File holding the bearer token for api_url, e.g. a mounted Secret",
+    "kubernetes.ca_cert": "CA certificate file for the API server (empty for the default)",
+    "kubernetes.insecure_skip_verify": "Don't verify the API server's certificate (testing only)",
+    "kubernetes.proxy": 'HTTPS proxy to reach the API server through, e.g. "http://proxy:3128"',
+    "kubernetes.kubeconfig": "Path to the kubeconfig file (empty for $KUBECONFIG or ~/.kube/config)",
+    "kubernetes.context": "kubeconfig context to use (empty for the current context)",
+    "kubernetes.impersonate_user": "User or service account to act as, e.g.\n"
+    '"system:serviceaccount:web:viewer" (empty to act as yourself)',
+    "kubernetes.impersonate_groups": "Group to act as with impersonate_user (at most one)",
+    "kubernetes.label_selector": "Only count pods and deployments matching this label selector,\n"
+    'e.g. "app=web"',
+    "kubernetes.namespace_include": "Regexes of namespaces to discover and monitor alongside\n"
+    'namespace, e.g. ["^team-", "^prod$"] (empty to monitor only namespace)',
+    "kubernetes.namespace_exclude": 'Regexes of discovered namespaces to skip, e.g. ["^kube-"]',
+    "kubernetes.namespace_refresh": "Seconds between namespace discovery refreshes",
+    "kubernetes.annotation_refresh": "Seconds between reads of the sonify.k8s.io/ annotations of\n"
+    "deployments and pods, which adjust how their namespace sounds (0 to ignore them)",
+    "kubernetes.profile_refresh": "Seconds between reconciles of the SonificationProfile custom\n"
+    "resources, which configure the namespaces they are in (0 to ignore them)",
+    "monitoring": "Monitoring Configuration",
+    "monitoring.poll_interval": "Polling interval in seconds",
+    "monitoring.verbose": "Enable verbose logging",
+    "monitoring.use_color": "Enable ANSI color output",
+    "monitoring.gauge": "Show where each value lies in its range with a bar after it, for\n"
+    "monochrome terminals: none, block or braille",
+    "monitoring.fetch_timeout": "Seconds to wait for each metric before reporting it as stale\n"
+    "and moving on (0 to wait indefinitely)",
+    "monitoring.state_file": "Keep active and acknowledged alarms and the last samples in this file,\n"
+    "so a restart doesn't replay alarms that already fired (empty to disable)",
+    "monitoring.sample_queue": "Polls that may wait to be played while the next one is fetched\n"
+    "(0 fetches, plays and exports each poll before the next one starts)",
+    "monitoring.on_change_only": "Only sound a metric when its note changes, or it raises or\n"
+    "clears an alarm, instead of every poll",
+    "monitoring.change_delta": "With on_change_only, also sound a metric when its value moves by\n"
+    "more than this since it last sounded (0 for note changes only)",
+    "audio": "Audio Configuration",
+    "audio.use_midi": "Use MIDI for sound output (requires MIDI device); same as backend: midi",
+    "audio.midi_port": "MIDI output port to send notes to (empty for the default)",
+    "audio.midi_virtual": f'Create a virtual MIDI output port named "{MIDI_VIRTUAL_PORT}" that DAWs\n'
+    "can record from (macOS and Linux), instead of using midi_port",
+    "audio.note_duration": "Note duration in seconds",
+    "audio.enabled": "Enable audio playback (set to false for silent mode)",
+    "audio.backend": "Where notes are played: simpleaudio (local sound card),\n"
+    "sounddevice (low-latency PortAudio stream, supports JACK),\n"
+    "webaudio (a browser page at http://web_host:web_port), midi, or\n"
+    "pipe (raw 16-bit mono PCM at 44.1 kHz written to pipe_path)",
+    "audio.device": "sounddevice output device name or index (empty for the default)",
+    "audio.host_api": 'sounddevice host API, e.g. "JACK Audio Connection Kit" (empty for the default)',
+    "audio.latency": 'sounddevice output latency: "low", "high" or seconds',
+    "audio.fade": "Seconds of fade-in/out at the edges of every note, to avoid clicks",
+    "audio.crossfade": "Seconds consecutive notes overlap by (sounddevice backend only, 0 to disable)",
+    "audio.schedule_grid": "Seconds between the beats notes are placed on, sample-accurately,\n"
+    "so polling jitter doesn't shift them (sounddevice backend only, 0 to disable;\n"
+    "0.125 is a sixteenth note at 120 BPM)",
+    "audio.limiter_threshold": "Output level (0-1) above which the master limiter softly\n"
+    "compresses stacked notes so they never clip (1.0 disables it)",
+    "audio.loudness_compensation": "Scale each note by an approximate equal-loudness (A-weighting)\n"
+    "correction so low notes sound as loud as high ones (lowers the overall volume)",
+    "audio.queue_size": "Notes that may wait to be played (0 plays each note before polling continues)",
+    "audio.overrun_policy": "What happens when the queue is full: drop-oldest, drop-newest,\n"
+    "or shorten (speed up the backlog, dropping the oldest note if still full)",
+    "audio.preempt": "With a queue, cut a playing note short when a higher priority note\n"
+    "arrives, e.g. a critical alarm over an ambient metric",
+    "audio.fallback": "When notes can't be played, e.g. over SSH, mark alarms in the terminal\n"
+    "instead: bell (ring the terminal bell) and/or flash (print critical lines inverted)",
+    "audio.web_host": "Address the webaudio page is served on",
+    "audio.web_port": "Port the webaudio page is served on",
+    "audio.pipe_path": 'File or named pipe the pipe backend writes to ("-" for stdout)',
+    "audio.record_path": "Record the session to this .wav, .ogg or .flac file, tagged with the\n"
+    "cluster, namespace and time range (empty to disable; .ogg and .flac need soundfile)",
+    "audio.drum_map": "General MIDI percussion note played on channel 10 for each cluster event\n"
+    "when the MIDI backend is active (38: snare, 49: crash cymbal, 45: low tom)",
+    "metrics": "Metrics Configuration",
+    "metrics.enabled": "Define which metrics to monitor; opt-in metrics that can be added:\n"
+    "  cluster_health: a 0-100 score of pod readiness, node pressure, restarts and errors\n"
+    "  <metric>.rate (e.g. replicas.rate): a metric's change per second\n"
+    "  top_offenders: the pods with the most restarts and warnings\n"
+    "  node_melody: one note per node, a sour one if the node is under pressure\n"
+    "  memory_evictions, disk_evictions: the pods the kubelet evicted\n"
+    "  gpu_usage: the utilization of a namespace's GPUs\n"
+    "  cost_per_hour: a namespace's spend rate from OpenCost\n"
+    "  security_alerts: Falco's alerts\n"
+    "  critical_cves: trivy-operator's count of critical CVEs\n"
+    "  deprecated_apis: workloads applied with API versions removed from Kubernetes\n"
+    "  apiserver_latency: how long the API server takes to answer the monitor\n"
+    "  admission_failures: the creates admission webhooks rejected\n"
+    "  pod_security: pods Pod Security admission rejected or would warn about\n"
+    "  pending_volumes, pending_volume_age: claims whose volume failed to provision,\n"
+    "    and how long the oldest has waited\n"
+    "  scheduling_headroom: share of allocatable CPU or memory not yet requested\n"
+    "  pod_churn: the pods created and deleted per minute\n"
+    "  deployment_readiness: one row and voice per Deployment",
+    "metrics.lead": "Metric played as the lead voice, e.g. cluster_health; the others are\n"
+    "played quieter, as accompaniment (empty for none)",
+    "metrics.accompaniment_volume": "Volume of the other metrics while a lead is set (0.0 - 1.0)",
+    "tempo": "Tempo Sync for the sequencer",
+    "tempo.sync": "none (use --bpm), midi (follow an external MIDI clock) or\n"
+    "link (join an Ableton Link session; requires the LinkPython package)",
+    "tempo.midi_port": "MIDI input port to read the clock from (empty for the default)",
+    "rollouts": "Deployment Rollout Fanfares",
+    "rollouts.enabled": "Play a motif when a Deployment rollout completes or fails",
+    "rollouts.success": "Notes of the rising fanfare played when a rollout completes",
+    "rollouts.failure": "Notes of the descending jingle played when a rollout fails",
+    "rollouts.step": "Seconds per note of the motifs",
+    "earcons": "Earcons: short named motifs for discrete occurrences",
+    "earcons.library": "Earcons to add to (or replace in) the built-in catalog, e.g.\n"
+    'page: {notes: ["A5", "E5", "A5"], step: 0.1, voice: {type: pluck}}',
+    "earcons.events": "Earcon played for each cluster event: pod_restart, deploy, node_join,\n"
+    "node_lost, scale_up, scale_down (by the cluster-autoscaler), memory_eviction,\n"
+    "disk_eviction, eviction_threshold (a node starting to evict), rollout_succeeded,\n"
+    "rollout_failed, deprecated_api, admission_failure, pod_security_violation or\n"
+    "provisioning_failed",
+    "earcons.alarms": "Earcon played when an alarm is fired or cleared, e.g. fired: alarm",
+    "earcons.missing_permission": "Earcon played once per resource and namespace when a metric\n"
+    "is disabled because the service account may not read it (empty for none)",
+    "alarms": "Alarm Escalation: make critical alarms harder to miss until acknowledged",
+    "alarms.escalate_after": "Seconds an alarm may go unacknowledged before it is repeated louder\n"
+    "and faster, and again after each further interval (0 to never escalate)",
+    "alarms.max_escalation": "How many times an alarm escalates",
+    "alarms.reminder_volume": "Volume of an acknowledged alarm's subdued reminder, relative to\n"
+    "its normal volume (0 silences acknowledged alarms)",
+    "alarms.duck_volume": "Volume the metrics that aren't in alarm drop to when an alarm earcon\n"
+    "plays, so it cuts through the mix (1.0 to never duck)",
+    "alarms.duck_release": "Seconds the ducked metrics take to return to their full volume",
+    "controller": "MIDI Controller Input",
+    "controller.enabled": "Adjust playback live from the knobs and faders of a MIDI controller",
+    "controller.midi_port": "MIDI input port of the controller (empty for the default)",
+    "controller.controls": "What each control change number adjusts: volume, gain.<metric>,\n"
+    "threshold.<metric> or poll_interval, e.g. 16: gain.errors_per_second",
+    "tuning": "Tuning used for sound map entries that list scale degrees",
+    "tuning.system": "equal (12-tone equal temperament), just (5-limit just intonation)\n"
+    "or scala (the scale in scala_file)",
+    "tuning.scala_file": "Path to a Scala (.scl) tuning file",
+    "tuning.root": "Scale degree 0, as a frequency in Hz or a note name",
+    "tuning.a4": "Frequency in Hz of A4, used to convert note names to frequencies",
+    "osc": "OSC Remote Control",
+    "osc.enabled": "Accept OSC messages from control surfaces such as TouchOSC or QLab",
+    "osc.host": "Address to listen on (0.0.0.0 to accept messages from other hosts)",
+    "osc.port": "UDP port to listen on",
+    "statsd": "statsd Ingestion",
+    "statsd.enabled": "Listen for statsd metrics over UDP",
+    "statsd.host": "Address to listen on (0.0.0.0 to accept metrics from other hosts)",
+    "statsd.port": "UDP port to listen on",
+    "statsd.mapping": "Map statsd metric names to sound map metrics, e.g.\n"
+    '"checkout.errors": errors_per_second\n'
+    "Counters are sonified as a per-second rate, timers as their mean,\n"
+    "gauges as their latest value",
+    "falco": "Falco Alerts, sonified as security_alerts",
+    "falco.enabled": "Receive Falco alerts on a webhook, from Falco's http_output or\n"
+    "falcosidekick's webhook output (add security_alerts to metrics.enabled)",
+    "falco.host": "Address to listen on (0.0.0.0 to accept alerts from other hosts)",
+    "falco.port": "TCP port to listen on",
+    "falco.token": "Shared secret alerts must carry in an \"Authorization: Bearer\" header\n"
+    "(or set token_file; empty to accept any alert)",
+    "falco.token_file": "File holding the shared secret",
+    "falco.min_priority": "Ignore alerts below this priority: debug, informational, notice,\n"
+    "warning, error, critical, alert or emergency",
+    "prometheus": "Prometheus, for the metrics it can measure better than the Kubernetes API",
+    "prometheus.url": "Base URL of the Prometheus server, e.g. http://prometheus:9090\n"
+    "(empty to use the Kubernetes API only)",
+    "prometheus.token": "Bearer token for Prometheus (or set token_file)",
+    "prometheus.token_file": "File holding the Prometheus bearer token",
+    "prometheus.gpu_query": "PromQL for gpu_usage, the utilization of a namespace's GPUs\n"
+    "in percent; $namespace is replaced by the namespace (default: the DCGM exporter's)",
+    "opencost": "OpenCost, for cost_per_hour",
+    "opencost.url": "Base URL of the OpenCost API, e.g. http://opencost.opencost:9003",
+    "opencost.window": "How far back the spend rate is averaged over, e.g. 1h, 30m or 1d",
+    "outputs": "Outputs\n"
+    "influxdb: mirror every sample and its note/severity to InfluxDB v2\n"
+    "graphite: mirror every sample and its severity (0 ok, 1 warning, 2 critical) to Graphite\n"
+    "grafana: create an annotation whenever an alarm fires or clears\n"
+    "desktop: raise a desktop notification whenever a critical alarm fires\n"
+    "haptic: rumble a game controller while a metric is warning or critical\n"
+    "history: store every sample in a SQLite file, for the export command\n"
+    "icecast: stream the live audio to an Icecast mountpoint (ogg or mp3, needs ffmpeg)\n"
+    "Instead of a token, set token_file to a file holding it, such as a mounted\n"
+    "Kubernetes Secret; any string can also reference ${ENV_VAR}",
+    "outputs.queue_size": "Samples each output may fall behind by, written from its own thread\n"
+    "(0 writes to every output as each sample is played)",
+    "sound_map": "Sound Mapping\n"
+    "Each metric maps its range onto notes ([frequency, name]) and colors of equal length.\n"
+    "thresholds is a [warning, critical] pair; when warning > critical, lower values are worse.\n"
+    "voice picks the instrument, e.g. {type: noise, noise: crackle} plays static that gets\n"
+    "denser and louder as the value rises (noise is white, pink or crackle), and\n"
+    "{type: fm, ratio: 2.0, index: 3.0} a two-operator FM tone (ratio is the\n"
+    "modulator/carrier frequency ratio, index the modulation depth), and\n"
+    "{type: additive, harmonics: 8, brightness: errors_per_second} a harmonic tone\n"
+    "that gets brighter as the brightness metric (default: the metric itself) rises.\n"
+    "{type: pluck, decay: 0.996} is a plucked string that rings out on its own; lower\n"
+    "decay values damp it faster.\n"
+    "An entry for a new name with an expression, e.g. headroom: {expression: 100 - cpu_usage,\n"
+    "range: [0, 100], notes: [...], colors: [...]}, derives a metric from the others each tick.",
+}
+
+
+def merge_config(base: Dict, override: Dict) -> Dict:
+    """
+    Recursively merges one configuration dictionary over another.
+
+    Args:
+        base: The configuration to merge into (not modified).
+        override: The values that take precedence.
+
+    Returns:
+        A new merged configuration.
+    """
+    merged = copy.deepcopy(base)
+    for key, value in override.items():
+        if isinstance(value, dict) and isinstance(merged.get(key), dict):
+            merged[key] = merge_config(merged[key], value)
+        else:
+            merged[key] = copy.deepcopy(value)
+    return merged
+
+
+def env_overrides(environ: Optional[Dict[str, str]] = None) -> Dict:
+    """
+    Collects configuration overrides from SONIFY_-prefixed environment variables.
+
+    Double underscores separate the keys of the setting, e.g.
+    SONIFY_AUDIO__NOTE_DURATION=0.25 sets audio.note_duration. Values of settings
+    whose default is a string are taken as-is, so SONIFY_KUBERNETES__NAMESPACE=0123
+    stays "0123"; other values are parsed as YAML, so SONIFY_METRICS__ENABLED="[cpu_usage,
+    replicas]" is a list. The section and setting must exist in DEFAULT_CONFIG (any key
+    of sound_map is allowed); keys below them are free-form, e.g.
+    SONIFY_EARCONS__EVENTS__POD_RESTART=restart, and are never parsed as a boolean or null.
+
+    Args:
+        environ: The environment to read. If None, os.environ.
+
+    Returns:
+        The overrides, nested like a configuration file.
+    """
+    overrides: Dict = {}
+    for name, raw in sorted((os.environ if environ is None else environ).items()):
+        if not name.startswith(ENV_PREFIX):
+            continue
+        keys = [
+            int(key) if key.isdigit() else key
+            for key in name[len(ENV_PREFIX):].lower().split("__")
+        ]
+        known = DEFAULT_CONFIG.get(keys[0])
+        if not (keys[0] == "sound_map" or isinstance(known, dict)) or len(keys) < 2 or (
+            keys[0] != "sound_map" and keys[1] not in known
+        ):
+            logger.warning(f"Ignoring {name}: not a configuration setting")
+            continue
+        default = DEFAULT_CONFIG
+        for key in keys:
+            default = default.get(key) if isinstance(default, dict) else None
+        if isinstance(default, str):
+            value = raw
+        else:
+            try:
+                value = yaml.safe_load(raw) if raw else ""
+            except yaml.YAMLError:
+                value = raw
+            # YAML reads no, off and null as a boolean and None, never what a free-form key means
+            if default is None and (value is None or isinstance(value, bool)):
+                value = raw
+        node = overrides
+        for key in keys[:-1]:
+            node = node.setdefault(key, {})
+        node[keys[-1]] = value
+    return overrides
+
+
+def interpolate_env(value, path: str = "", environ: Optional[Dict[str, str]] = None):
+    """
+    Replaces ${NAME} references in the strings of a configuration with environment variables.
+    $${NAME} is left as a literal ${NAME}.
+
+    Args:
+        value: A configuration, or any value in one.
+        path: Where value is in the configuration, for messages.
+        environ: The environment to read. If None, os.environ.
+
+    Returns:
+        A copy of value with every reference replaced.
+
+    Raises:
+        ValueError: If a referenced variable is not set.
+    """
+    environ = os.environ if environ is None else environ
+    if isinstance(value, dict):
+        return {
+            key: interpolate_env(item, f"{path}.{key}" if path else str(key), environ)
+            for key, item in value.items()
+        }
+    if isinstance(value, list):
+        return [interpolate_env(item, f"{path}[{i}]", environ) for i, item in enumerate(value)]
+    if not isinstance(value, str):
+        return value
+
+    def substitute(match) -> str:
+        escaped, name = match.groups()
+        if escaped:
+            return match.group(0)[1:]
+        if name not in environ:
+            raise ValueError(f"{path}: environment variable {name} is not set")
+        return environ[name]
+
+    return ENV_REFERENCE.sub(substitute, value)
+
+
+def read_secret(value: str, path: str) -> str:
+    """
+    Gets a credential that is either given inline or kept in a file.
+
+    Args:
+        value: The inline credential.
+        path: The file holding the credential, such as a mounted Kubernetes Secret.
+            If empty, value is used.
+
+    Returns:
+        The credential, without the file's surrounding whitespace.
+
+    Raises:
+        OSError: If the file cannot be read.
+    """
+    if not path:
+        return value
+    with open(os.path.expanduser(path), "r", encoding="utf-8") as f:
+        return f.read().strip()
+
+
+def check_token_source(section: Dict, name: str, used: bool = True) -> List[str]:
+    """
+    Checks that a section of a configuration sets at most one of token and token_file,
+    and that its token_file can be read.
+
+    Args:
+        section: The section, such as cfg["prometheus"].
+        name: Where the section is in the configuration, for messages.
+        used: Whether the section is in use; the token_file of one that isn't is not read.
+
+    Returns:
+        A list of problem descriptions.
+    """
+    if section.get("token") and section.get("token_file"):
+        return [f"{name}: set token or token_file, not both"]
+    if used and section.get("token_file"):
+        try:
+            read_secret("", section["token_file"])
+        except OSError as e:
+            return [f"{name}.token_file: {e}"]
+    return []
+
+
+def normalize_controls(data: Dict) -> None:
+    """
+    Converts the controller.controls keys of a configuration to integers in place, since
+    TOML and JSON keys are always strings but CC numbers are integers.
+
+    Args:
+        data: A configuration, or a profile overlay.
+    """
+    controls = (data.get("controller") or {}).get("controls")
+    if isinstance(controls, dict):
+        data["controller"]["controls"] = {
+            int(control) if str(control).isdigit() else control: target
+            for control, target in controls.items()
+        }
+
+
+def read_config_file(path: str, including: Tuple[str, ...] = ()) -> Dict:
+    """
+    Reads a configuration file without merging it over DEFAULT_CONFIG.
+
+    The files listed in its "include" setting, relative to its directory, are read
+    first and merged in order, then the file's own settings are merged over them.
+    Mappings are merged key by key; anything else, such as a list, is replaced.
+
+    Args:
+        path: The path of the configuration file: TOML for a .toml extension, JSON for
+            .json, otherwise YAML.
+        including: The files that include this one, to detect include cycles.
+
+    Returns:
+        The settings in the file and its includes, including the "profiles" section.
+
+    Raises:
+        OSError: If the file or an include cannot be read.
+        yaml.YAMLError: If the file is not valid YAML.
+        ValueError: If the file is not valid TOML or JSON, does not contain a mapping,
+            has a bad include or include cycle, or has a profile that is not a mapping.
+    """
+    extension = os.path.splitext(path)[1].lower()
+    if extension == ".toml":
+        try:
+            import tomllib
+        except ImportError:  # Python < 3.11
+            import tomli as tomllib
+        with open(path, "rb") as f:
+            data = tomllib.load(f)
+    else:
+        with open(path, "r", encoding="utf-8") as f:
+            data = (json.load(f) if extension == ".json" else yaml.safe_load(f)) or {}
+    if not isinstance(data, dict):
+        raise ValueError(f"{path}: expected a mapping at the top level")
+    normalize_controls(data)
+
+    includes = data.pop("include", None) or []
+    if isinstance(includes, str):
+        includes = [includes]
+    if not isinstance(includes, list) or not all(isinstance(i, str) for i in includes):
+        raise ValueError(f"{path}: include: expected a path or a list of paths")
+    including = including + (os.path.realpath(path),)
+    merged: Dict = {}
+    for include in includes:
+        include_path = os.path.join(os.path.dirname(path), os.path.expanduser(include))
+        if os.path.realpath(include_path) in including:
+            raise ValueError(f"{path}: include: include cycle through {include}")
+        merged = merge_config(merged, read_config_file(include_path, including))
+    data = merge_config(merged, data)
+
+    profiles = data.get("profiles") or {}
+    if not isinstance(profiles, dict):
+        raise ValueError(f"{path}: profiles: expected a mapping of profile names")
+    for name, overlay in profiles.items():
+        if not isinstance(overlay, dict):
+            raise ValueError(f"{path}: profiles.{name}: expected a mapping")
+        normalize_controls(overlay)
+    return data
+
+
+def load_config(path: Optional[str] = None, profile: Optional[str] = None) -> Dict:
+    """
+    Loads a configuration file over DEFAULT_CONFIG, overlays a profile from its
+    "profiles" section, then applies env_overrides().
+
+    Args:
+        path: The path of the configuration file, see read_config_file(). If None,
+            only defaults are used.
+        profile: The name of the profile to overlay. If None, only the base settings.
+
+    Returns:
+        The merged configuration.
+
+    Raises:
+        OSError: If the file cannot be read.
+        yaml.YAMLError: If the file is not valid YAML.
+        ValueError: If the file is not valid TOML or JSON, does not contain a mapping,
+            does not define the profile, or references an unset environment variable.
+    """
+    data = read_config_file(path) if path is not None else {}
+    profiles = data.pop("profiles", None) or {}
+    if profile is not None:
+        if profile not in profiles:
+            defined = ", ".join(profiles) or "none"
+            raise ValueError(f"unknown profile '{profile}' (defined: {defined})")
+        data = merge_config(data, profiles[profile])
+    return merge_config(merge_config(DEFAULT_CONFIG, interpolate_env(data)), env_overrides())
+
+
+def sound_map_to_config(sound_map: Dict) -> Dict:
+    """
+    Converts a sound map into plain YAML-serializable data.
+
+    Args:
+        sound_map: A sound map such as SOUND_MAP.
+
+    Returns:
+        The sound map with tuples converted to lists.
+    """
+
+    def plain(value):
+        if isinstance(value, (list, tuple)):
+            return [plain(v) for v in value]
+        if isinstance(value, dict):
+            return {k: plain(v) for k, v in value.items()}
+        return value
+
+    return plain(sound_map)
+
+
+def note_frequency(name: str, a4: float = 440.0) -> float:
+    """
+    Converts a note name such as "C4", "F#5" or "Bb3" to a frequency in equal temperament.
+
+    Args:
+        name: The note name.
+        a4: The frequency of A4 in Hz.
+
+    Returns:
+        The frequency in Hz, rounded to 2 decimal places.
+
+    Raises:
+        ValueError: If the name is not a note name.
+    """
+    match = NOTE_NAME_PATTERN.match(name.strip())
+    if not match:
+        raise ValueError(f"not a note name: '{name}'")
+    letter, accidental, octave = match.groups()
+    semitone = NOTE_OFFSETS[letter.upper()] + {"#": 1, "b": -1, "": 0}[accidental]
+    midi_note = 12 * (int(octave) + 1) + semitone
+    return round(a4 * 2 ** ((midi_note - 69) / 12), 2)
+
+
+def frequency_name(frequency: float, a4: float = 440.0) -> str:
+    """
+    Names the equal-tempered note nearest to a frequency, with its offset in cents.
+
+    Args:
+        frequency: The frequency in Hz.
+        a4: The frequency of A4 in Hz.
+
+    Returns:
+        The name, e.g. "A4" or "C#5+12c".
+    """
+    semitones = 69 + 12 * math.log2(frequency / a4)
+    midi_note = int(round(semitones))
+    cents = int(round((semitones - midi_note) * 100))
+    name = f"{NOTE_NAMES[midi_note % 12]}{midi_note // 12 - 1}"
+    return f"{name}{cents:+d}c" if cents else name
+
+
+def resolve_note(note, a4: float = 440.0) -> Tuple[float, str]:
+    """
+    Converts a sound map note to a (frequency, name) pair.
+
+    Args:
+        note: A (frequency, name) pair, a (note name, name) pair or a note name.
+        a4: The frequency of A4 in Hz.
+
+    Returns:
+        The (frequency, name) pair.
+    """
+    if isinstance(note, str):
+        return note_frequency(note, a4), note.strip()
+    frequency, label = note
+    if isinstance(frequency, str):
+        frequency = note_frequency(frequency, a4)
+    return frequency, label
+
+
+def load_scala(path: str) -> List[float]:
+    """
+    Reads a Scala (.scl) tuning file.
+
+    Args:
+        path: The file to read.
+
+    Returns:
+        The frequency ratio of each scale step above 1/1, ending with the period.
+
+    Raises:
+        ValueError: If the file is not a valid Scala file.
+    """
+    with open(path, "r", encoding="latin-1") as f:
+        lines = [line.strip() for line in f if not line.startswith("!")]
+    if len(lines) < 2:
+        raise ValueError(f"{path}: missing description or note count")
+    try:
+        count = int(lines[1].split()[0])
+        ratios = []
+        for line in lines[2:2 + count]:
+            pitch = line.split()[0]
+            if "." in pitch:
+                ratios.append(2 ** (float(pitch) / 1200))
+            elif "/" in pitch:
+                numerator, denominator = pitch.split("/")
+                ratios.append(int(numerator) / int(denominator))
+            else:
+                ratios.append(float(int(pitch)))
+    except (IndexError, ValueError, ZeroDivisionError) as e:
+        raise ValueError(f"{path}: {e}")
+    if len(ratios) != count or count < 1:
+        raise ValueError(f"{path}: expected {count} pitches, found {len(ratios)}")
+    return ratios
+
+
+def scale_ratios(tuning: Optional[Dict]) -> List[float]:
+    """
+    Gets the ratios of a tuning, in the form returned by load_scala().
+
+    Args:
+        tuning: The "tuning" section of a configuration.
+
+    Returns:
+        The frequency ratio of each scale step above the root, ending with the period.
+    """
+    system = (tuning or {}).get("system", "equal")
+    if system == "just":
+        return list(JUST_INTONATION)
+    if system == "scala":
+        return load_scala(tuning["scala_file"])
+    return [2 ** (step / 12) for step in range(1, 13)]
+
+
+def scale_degrees(steps: int, scale: str = "chromatic") -> List[int]:
+    """
+    Lists the degrees of the first notes of a scale, continuing into higher octaves.
+
+    Args:
+        steps: How many degrees to list.
+        scale: One of SCALES.
+
+    Returns:
+        The degrees, from the root up.
+    """
+    pattern = SCALES[scale]
+    return [pattern[i % len(pattern)] + 12 * (i // len(pattern)) for i in range(steps)]
+
+
+def color_gradient(colors: List[str], count: int) -> List[str]:
+    """
+    Spreads a list of hex colors over a different number of steps, blending
+    neighbouring colors in between.
+
+    Args:
+        colors: The colors, e.g. ["#22C55E", "#EF4444"], from the first step to the last.
+        count: How many colors to return.
+
+    Returns:
+        The colors.
+    """
+    if len(colors) == count:
+        return list(colors)
+    if len(colors) == 1 or count == 1:
+        return [colors[0]] * count
+    rgb = [[int(c.lstrip("#")[i:i + 2], 16) for i in (0, 2, 4)] for c in colors]
+    blended = []
+    for step in range(count):
+        position = step * (len(colors) - 1) / (count - 1)
+        lower = min(int(position), len(colors) - 2)
+        weight = position - lower
+        blended.append("#" + "".join(
+            f"{round(a + (b - a) * weight):02X}" for a, b in zip(rgb[lower], rgb[lower + 1])
+        ))
+    return blended
+
+
+def degree_frequency(degree: int, root: float, ratios: List[float]) -> float:
+    """
+    Gets the frequency of a scale degree; degrees past the end of the scale wrap into
+    the next period, and negative degrees into the previous one.
+
+    Args:
+        degree: The scale degree, 0 being the root.
+        root: The frequency of degree 0 in Hz.
+        ratios: The scale, as returned by scale_ratios().
+
+    Returns:
+        The frequency in Hz.
+    """
+    period, step = divmod(degree, len(ratios))
+    ratio = ratios[step - 1] if step else 1.0
+    return root * ratio * ratios[-1] ** period
+
+
+def apply_rollouts(rollouts: Dict) -> None:
+    """
+    Sets the "fanfare" and "failure" earcons, played for rollouts by default, from a
+    configuration file.
+
+    Args:
+        rollouts: The "rollouts" section of a configuration.
+    """
+    EARCONS["fanfare"] = {"notes": list(rollouts["success"]), "step": rollouts["step"]}
+    EARCONS["failure"] = {"notes": list(rollouts["failure"]), "step": rollouts["step"]}
+
+
+def apply_earcons(library: Optional[Dict]) -> None:
+    """
+    Adds earcons from a configuration file to EARCONS, replacing built-ins of the same name.
+
+    Args:
+        library: The "earcons.library" section of a configuration, keyed by earcon name.
+    """
+    for name, earcon in (library or {}).items():
+        EARCONS[name] = merge_config({"step": 0.12}, earcon)
+
+
+def apply_sound_map(overrides: Optional[Dict], tuning: Optional[Dict] = None) -> None:
+    """
+    Merges sound map entries from a configuration file into SOUND_MAP. Note names are
+    converted to frequencies, and entries that list "degrees" instead of "notes" get
+    their notes from the tuning. Either way the entry keeps the tuning's "a4".
+
+    Args:
+        overrides: The "sound_map" section of a configuration, keyed by metric name.
+        tuning: The "tuning" section of a configuration.
+    """
+    tuning = merge_config(DEFAULT_CONFIG["tuning"], tuning or {})
+    a4 = tuning["a4"]
+    ratios = None
+    # Rates and fused metrics are based on another metric's entry, so they are merged after it
+    for metric_name, entry in sorted(
+        (overrides or {}).items(),
+        key=lambda item: item[0].endswith(RATE_SUFFIX) or "fuse" in item[1],
+    ):
+        if metric_name in SOUND_MAP:
+            base = SOUND_MAP[metric_name]
+        elif metric_name.endswith(RATE_SUFFIX):
+            base_name = metric_name[: -len(RATE_SUFFIX)]
+            base = rate_metric_entry(base_name, SOUND_MAP[base_name])
+        elif "fuse" in entry:
+            base = fused_metric_entry(metric_name, entry["fuse"], SOUND_MAP[entry["fuse"]["pitch"]])
+        else:
+            # New entries are derived metrics, named after their key unless they say otherwise
+            base = {"metric_name": metric_name, "unit": ""}
+        merged = merge_config(base, entry)
+        degrees = entry.get("degrees")
+        if "steps" in entry and "notes" not in entry and "degrees" not in entry:
+            degrees = scale_degrees(entry["steps"], entry.get("scale", "chromatic"))
+        if "notes" in entry or degrees is not None:
+            # Continuous notes are named in the tuning the entry's notes are in
+            merged["a4"] = a4
+        if "notes" in entry:
+            merged["notes"] = [resolve_note(note, a4) for note in entry["notes"]]
+        elif degrees is not None:
+            if ratios is None:
+                ratios = scale_ratios(tuning)
+            root = tuning["root"]
+            if isinstance(root, str):
+                root = note_frequency(root, a4)
+            merged["notes"] = [
+                (round(degree_frequency(degree, root, ratios), 2), f"deg {degree}")
+                for degree in degrees
+            ]
+        if "steps" in entry:
+            # Any number of colors is blended into one per step
+            merged["colors"] = color_gradient(merged["colors"], len(merged["notes"]))
+        for key in ("range", "thresholds"):
+            if isinstance(merged.get(key), list):
+                merged[key] = tuple(merged[key])
+        SOUND_MAP[metric_name] = merged
+
+
+def rate_metric_entry(metric_name: str, entry: Dict) -> Dict:
+    """
+    Creates the sound map entry of a metric's per-second rate of change. Its range
+    spans the metric's whole range changing within a minute either way, so a steady
+    value plays the middle note; it has no thresholds unless configured.
+
+    Args:
+        metric_name: The name of the metric whose rate it is.
+        entry: The metric's sound map entry.
+
+    Returns:
+        The entry for metric_name + RATE_SUFFIX.
+    """
+    low, high = entry["range"]
+    span = (high - low) / 60
+    return {
+        "metric_name": f"{entry.get('metric_name', metric_name)} Rate",
+        "unit": f"{entry.get('unit') or ''}/s",
+        "range": (-span, span),
+        "notes": list(entry["notes"]),
+        "colors": list(entry["colors"]),
+        "rate_of": metric_name,
+    }
+
+
+def fused_metric_entry(metric_name: str, fuse: Dict, pitch: Dict) -> Dict:
+    """
+    Creates the sound map entry of a metric that plays several metrics as one voice:
+    its pitch, range and thresholds are those of the "pitch" metric, the "brightness"
+    metric brightens an additive voice, and "pan" ("namespace" or a metric) places it
+    in the stereo field.
+
+    Args:
+        metric_name: The name of the fused metric.
+        fuse: The metric of each of FUSE_DIMENSIONS.
+        pitch: The sound map entry of the pitch metric.
+
+    Returns:
+        The entry, derived from the pitch metric with an expression.
+    """
+    entry = {
+        "metric_name": metric_name,
+        "unit": pitch.get("unit", ""),
+        "range": pitch.get("range"),
+        "notes": list(pitch.get("notes") or []),
+        "colors": list(pitch.get("colors") or []),
+        "expression": fuse["pitch"],
+    }
+    if "thresholds" in pitch:
+        entry["thresholds"] = pitch["thresholds"]
+    if fuse.get("brightness"):
+        entry["voice"] = {"type": "additive", "harmonics": 8, "brightness": fuse["brightness"]}
+    return entry
+
+
+def register_rate_metrics(metrics: List[str]) -> None:
+    """
+    Adds a SOUND_MAP entry for each rate in a list of metrics that hasn't got one yet.
+
+    Args:
+        metrics: Metric names, of which those ending in RATE_SUFFIX are rates.
+    """
+    for name in metrics:
+        base_name = name[: -len(RATE_SUFFIX)]
+        if name.endswith(RATE_SUFFIX) and name not in SOUND_MAP and base_name in SOUND_MAP:
+            SOUND_MAP[name] = rate_metric_entry(base_name, SOUND_MAP[base_name])
+
+
+def render_default_config(full: bool = False) -> str:
+    """
+    Renders DEFAULT_CONFIG as a commented YAML document.
+
+    Args:
+        full: Whether to include the built-in sound map.
+
+    Returns:
+        The YAML document.
+    """
+
+    def comment(key: str, indent: str = "") -> str:
+        if key not in CONFIG_COMMENTS:
+            return ""
+        return "".join(f"{indent}# {line}\n" for line in CONFIG_COMMENTS[key].split("\n"))
+
+    out = [
+        "# Sonify K8s Configuration File\n",
+        "# This file contains customizable settings for the Sonify K8s application\n",
+    ]
+    sections = dict(DEFAULT_CONFIG)
+    if full:
+        sections["sound_map"] = sound_map_to_config(SOUND_MAP)
+
+    for section, values in sections.items():
+        out.append("\n" + comment(section) + f"{section}:\n")
+        entries = []
+        for key, value in values.items():
+            # Keep the sound map compact with inline note pairs and color lists
+            dumped = yaml.safe_dump(
+                {key: value},
+                default_flow_style=None if section == "sound_map" else False,
+                sort_keys=False,
+            )
+            entries.append(
+                comment(f"{section}.{key}", "  ")
+                + "".join(f"  {line}\n" for line in dumped.splitlines())
+            )
+        out.append("\n".join(entries))
+    return "".join(out)
+
+
+def validate_voice(path: str, voice: Optional[Dict]) -> List[str]:
+    """
+    Checks a sound map voice for problems.
+
+    Args:
+        path: Where the voice is in the configuration, for messages.
+        voice: The voice, or None for the default sine voice.
+
+    Returns:
+        A list of problem descriptions.
+    """
+    if voice is None:
+        return []
+    if not isinstance(voice, dict):
+        return [f"{path}: must be a mapping"]
+    problems = []
+    if voice.get("type", "sine") not in VOICES:
+        problems.append(f"{path}.type: must be one of {', '.join(VOICES)}")
+    if voice.get("type") == "noise" and voice.get("noise", "white") not in NOISE_COLORS:
+        problems.append(f"{path}.noise: must be one of {', '.join(NOISE_COLORS)}")
+    for key in ("ratio", "index"):
+        value = voice.get(key, 1.0)
+        if not isinstance(value, (int, float)) or isinstance(value, bool) or value < 0:
+            problems.append(f"{path}.{key}: must be a number of at least 0")
+    harmonics = voice.get("harmonics", 8)
+    if not isinstance(harmonics, int) or not 1 <= harmonics <= 64:
+        problems.append(f"{path}.harmonics: must be an integer between 1 and 64")
+    decay = voice.get("decay", 0.996)
+    if not isinstance(decay, (int, float)) or isinstance(decay, bool) or not 0 < decay <= 1:
+        problems.append(f"{path}.decay: must be a number between 0 and 1")
+    if "brightness" in voice and voice["brightness"] not in SOUND_MAP:
+        problems.append(f"{path}.brightness: unknown metric '{voice['brightness']}'")
+    for key, highest in (("midi_channel", 16), ("midi_program", 128)):
+        value = voice.get(key, 1)
+        if not isinstance(value, int) or isinstance(value, bool) or not 1 <= value <= highest:
+            problems.append(f"{path}.{key}: must be an integer between 1 and {highest}")
+    cc = voice.get("midi_cc", 0)
+    if not isinstance(cc, int) or isinstance(cc, bool) or not 0 <= cc <= 127:
+        problems.append(f"{path}.midi_cc: must be an integer between 0 and 127")
+    if "cc_metric" in voice and voice["cc_metric"] not in SOUND_MAP:
+        problems.append(f"{path}.cc_metric: unknown metric '{voice['cc_metric']}'")
+    return problems
+
+
+def validate_earcon(path: str, earcon) -> List[str]:
+    """
+    Checks an earcon from a configuration for problems.
+
+    Args:
+        path: Where the earcon is in the configuration, for messages.
+        earcon: The earcon.
+
+    Returns:
+        A list of problem descriptions.
+    """
+    if not isinstance(earcon, dict):
+        return [f"{path}: must be a mapping"]
+    problems = []
+    notes = earcon.get("notes")
+    if not isinstance(notes, list) or not notes or not all(
+        (isinstance(n, str) and NOTE_NAME_PATTERN.match(n.strip()))
+        or (isinstance(n, (int, float)) and not isinstance(n, bool) and n > 0)
+        for n in notes
+    ):
+        problems.append(f"{path}.notes: must be a list of note names or frequencies")
+    for key in ("step", "volume"):
+        value = earcon.get(key, 1.0)
+        if not isinstance(value, (int, float)) or isinstance(value, bool) or value <= 0:
+            problems.append(f"{path}.{key}: must be a number greater than 0")
+    problems.extend(validate_voice(f"{path}.voice", earcon.get("voice")))
+    return problems
+
+
+def is_number(value) -> bool:
+    """Check whether a configuration value is an int or float, but not a bool."""
+    return isinstance(value, (int, float)) and not isinstance(value, bool)
+
+
+def validate_sound_map_entry(metric_name: str, entry: Dict) -> List[str]:
+    """
+    Checks that a sound map entry, merged over the built-in one, is playable.
+
+    Args:
+        metric_name: The name of the metric, for messages.
+        entry: The merged entry.
+
+    Returns:
+        A list of problem descriptions.
+    """
+    path = f"sound_map.{metric_name}"
+    problems = []
+    value_range = entry.get("range")
+    low = high = None
+    if (
+        not isinstance(value_range, (list, tuple))
+        or len(value_range) != 2
+        or not all(is_number(v) for v in value_range)
+    ):
+        problems.append(f"{path}.range: must be a [min, max] pair of numbers")
+    elif value_range[0] >= value_range[1]:
+        problems.append(
+            f"{path}.range: min ({value_range[0]}) must be less than max ({value_range[1]})"
+        )
+    else:
+        low, high = value_range
+
+    notes = entry.get("degrees") if "degrees" in entry else entry.get("notes")
+    colors = entry.get("colors")
+    steps = entry.get("steps")
+    if steps is not None and (not isinstance(steps, int) or isinstance(steps, bool) or steps < 1):
+        problems.append(f"{path}.steps: must be an integer of at least 1")
+    if entry.get("scale", "chromatic") not in SCALES:
+        problems.append(f"{path}.scale: must be one of {', '.join(SCALES)}")
+    if steps is None and (not isinstance(notes, (list, tuple)) or not notes):
+        problems.append(f"{path}.notes: must list at least one note")
+    if not isinstance(colors, (list, tuple)) or not colors:
+        problems.append(f"{path}.colors: must list at least one color")
+    elif steps is not None:
+        if not all(isinstance(c, str) and HEX_COLOR_PATTERN.match(c) for c in colors):
+            problems.append(f"{path}.colors: must be #RRGGBB colors to blend over the steps")
+    elif isinstance(notes, (list, tuple)) and notes and len(notes) != len(colors):
+        problems.append(
+            f"{path}.colors: must have one color per note"
+            f" ({len(notes)} notes, {len(colors)} colors)"
+        )
+
+    weights = entry.get("weights")
+    if weights is not None:
+        if not isinstance(weights, dict) or not all(
+            name in HEALTH_COMPONENTS and is_number(w) and w >= 0 for name, w in weights.items()
+        ):
+            problems.append(
+                f"{path}.weights: must map {', '.join(HEALTH_COMPONENTS)} to numbers of at least 0"
+            )
+        elif not sum(weights.values()):
+            problems.append(f"{path}.weights: at least one weight must be greater than 0")
+    priority = entry.get("priority", 0)
+    if not isinstance(priority, int) or isinstance(priority, bool) or priority < 0:
+        problems.append(f"{path}.priority: must be an integer of at least 0")
+    count = entry.get("count", 1)
+    if not isinstance(count, int) or isinstance(count, bool) or count < 1:
+        problems.append(f"{path}.count: must be an integer greater than 0")
+    if "max_restart_rate" in entry and not (
+        is_number(entry["max_restart_rate"]) and entry["max_restart_rate"] > 0
+    ):
+        problems.append(f"{path}.max_restart_rate: must be a number greater than 0")
+
+    thresholds = entry.get("thresholds")
+    if thresholds is None:
+        return problems
+    if (
+        not isinstance(thresholds, (list, tuple))
+        or len(thresholds) != 2
+        or not all(is_number(t) for t in thresholds)
+    ):
+        problems.append(f"{path}.thresholds: must be a [warning, critical] pair of numbers or null")
+    elif thresholds[0] == thresholds[1]:
+        problems.append(f"{path}.thresholds: warning and critical must differ")
+    elif low is not None and not all(low <= t <= high for t in thresholds):
+        problems.append(f"{path}.thresholds: must be within the range {low}-{high}")
+    return problems
+
+
+def validate_config(cfg: Dict) -> List[str]:
+    """
+    Checks a configuration for problems.
+
+    Args:
+        cfg: The configuration to check.
+
+    Returns:
+        A list of problem descriptions, empty if the configuration is valid.
+    """
+    problems = []
+    for section in cfg:
+        if section not in DEFAULT_CONFIG and section != "sound_map":
+            problems.append(f"{section}: unknown section")
+
+    sound_map = cfg.get("sound_map") or {}
+    known_metrics = set(SOUND_MAP) | {
+        name
+        for name, entry in sound_map.items()
+        if isinstance(entry, dict) and ("expression" in entry or "fuse" in entry)
+    }
+    known_metrics |= {name + RATE_SUFFIX for name in known_metrics}
+    for metric_name, entry in sound_map.items():
+        if metric_name not in known_metrics:
+            problems.append(
+                f"sound_map.{metric_name}: unknown metric (add an expression to derive it)"
+            )
+        elif not isinstance(entry, dict):
+            problems.append(f"sound_map.{metric_name}: must be a mapping")
+        else:
+            for i, note in enumerate(entry.get("notes") or []):
+                if not isinstance(note, (str, list, tuple)) or (
+                    not isinstance(note, str) and len(note) != 2
+                ):
+                    problems.append(
+                        f"sound_map.{metric_name}.notes[{i}]: "
+                        "must be a [frequency, name] pair or a note name"
+                    )
+                    continue
+                name = note if isinstance(note, str) else note[0]
+                if isinstance(name, str) and not NOTE_NAME_PATTERN.match(name.strip()):
+                    problems.append(f"sound_map.{metric_name}.notes[{i}]: not a note name: '{name}'")
+            problems.extend(validate_voice(f"sound_map.{metric_name}.voice", entry.get("voice")))
+            for key in ("muted", "solo", "rows"):
+                if key in entry and not isinstance(entry[key], bool):
+                    problems.append(f"sound_map.{metric_name}.{key}: must be true or false")
+            if entry.get("mapping", "discrete") not in MAPPINGS:
+                problems.append(
+                    f"sound_map.{metric_name}.mapping: must be one of {', '.join(MAPPINGS)}"
+                )
+            frequency_range = entry.get("frequency_range")
+            if frequency_range is not None and not (
+                isinstance(frequency_range, (list, tuple))
+                and len(frequency_range) == 2
+                and all(is_number(f) and f > 0 for f in frequency_range)
+                and frequency_range[0] < frequency_range[1]
+            ):
+                problems.append(
+                    f"sound_map.{metric_name}.frequency_range: must be a [low, high] pair of "
+                    "frequencies in Hz, low first"
+                )
+            for key, limit, unit in (
+                ("duration_range", math.inf, "seconds"),
+                ("velocity_range", 1.0, "volumes up to 1.0"),
+            ):
+                pair = entry.get(key)
+                if pair is not None and not (
+                    isinstance(pair, (list, tuple))
+                    and len(pair) == 2
+                    and all(is_number(v) and 0 < v <= limit for v in pair)
+                ):
+                    problems.append(
+                        f"sound_map.{metric_name}.{key}: must be a [best, worst] pair of {unit}"
+                    )
+            if entry.get("volatility") not in (None, "") + VOLATILITY_EFFECTS:
+                problems.append(
+                    f"sound_map.{metric_name}.volatility: must be one of "
+                    f"{', '.join(VOLATILITY_EFFECTS)}"
+                )
+            degrees = entry.get("degrees")
+            if degrees is not None and (
+                not isinstance(degrees, list)
+                or not degrees
+                or not all(isinstance(d, int) and not isinstance(d, bool) for d in degrees)
+            ):
+                problems.append(f"sound_map.{metric_name}.degrees: must be a list of integers")
+            fuse = entry.get("fuse")
+            if fuse is not None and not isinstance(fuse, dict):
+                problems.append(f"sound_map.{metric_name}.fuse: must be a mapping")
+            elif fuse is not None:
+                if "pitch" not in fuse:
+                    problems.append(f"sound_map.{metric_name}.fuse.pitch: is required")
+                for dimension, other in fuse.items():
+                    if dimension not in FUSE_DIMENSIONS:
+                        problems.append(
+                            f"sound_map.{metric_name}.fuse.{dimension}: must be one of "
+                            f"{', '.join(FUSE_DIMENSIONS)}"
+                        )
+                    elif not (dimension == "pan" and other == "namespace") and (
+                        other == metric_name or other not in known_metrics
+                    ):
+                        problems.append(
+                            f"sound_map.{metric_name}.fuse.{dimension}: unknown metric '{other}'"
+                        )
+            if "expression" in entry:
+                try:
+                    used = expression_metrics(parse_expression(str(entry["expression"])))
+                except ValueError as e:
+                    problems.append(f"sound_map.{metric_name}.expression: {e}")
+                else:
+                    for name in used:
+                        if name == metric_name or name not in known_metrics:
+                            problems.append(
+                                f"sound_map.{metric_name}.expression: unknown metric '{name}'"
+                            )
+            if metric_name in SOUND_MAP:
+                base_entry = SOUND_MAP[metric_name]
+            elif metric_name.endswith(RATE_SUFFIX):
+                base_name = metric_name[: -len(RATE_SUFFIX)]
+                base_entry = rate_metric_entry(
+                    base_name,
+                    merge_config(SOUND_MAP.get(base_name, {}), sound_map.get(base_name) or {}),
+                )
+            elif isinstance(fuse, dict) and fuse.get("pitch") in known_metrics:
+                pitch_name = fuse["pitch"]
+                base_entry = fused_metric_entry(
+                    metric_name,
+                    fuse,
+                    merge_config(SOUND_MAP.get(pitch_name, {}), sound_map.get(pitch_name) or {}),
+                )
+            else:
+                base_entry = {}
+            problems.extend(
+                validate_sound_map_entry(metric_name, merge_config(base_entry, entry))
+            )
+
+    kubernetes = cfg["kubernetes"]
+    problems.extend(check_token_source(kubernetes, "kubernetes"))
+    if kubernetes.get("ca_cert") and not os.path.isfile(kubernetes["ca_cert"]):
+        problems.append(f"kubernetes.ca_cert: no such file: {kubernetes['ca_cert']}")
+    if kubernetes.get("ca_cert") and kubernetes.get("insecure_skip_verify"):
+        problems.append("kubernetes: set ca_cert or insecure_skip_verify, not both")
+    if kubernetes.get("proxy") and not urllib.parse.urlparse(kubernetes["proxy"]).netloc:
+        problems.append(f"kubernetes.proxy: not a URL: '{kubernetes['proxy']}'")
+    groups = kubernetes.get("impersonate_groups")
+    if not isinstance(groups, list) or not all(isinstance(g, str) and g for g in groups):
+        problems.append("kubernetes.impersonate_groups: must be a list of group names")
+    elif groups and not kubernetes.get("impersonate_user"):
+        problems.append("kubernetes.impersonate_groups: requires impersonate_user")
+    elif len(groups) > 1:
+        problems.append("kubernetes.impersonate_groups: only one group can be impersonated")
+
+    for key in ("namespace_include", "namespace_exclude"):
+        for pattern in cfg["kubernetes"].get(key) or []:
+            try:
+                re.compile(pattern)
+            except (re.error, TypeError) as e:
+                problems.append(f"kubernetes.{key}: invalid pattern '{pattern}': {e}")
+    for key in ("annotation_refresh", "profile_refresh"):
+        seconds = cfg["kubernetes"].get(key)
+        if not is_number(seconds) or seconds < 0:
+            problems.append(f"kubernetes.{key}: must be a number of seconds of at least 0")
+    refresh = cfg["kubernetes"].get("namespace_refresh")
+    if not isinstance(refresh, (int, float)) or isinstance(refresh, bool) or refresh <= 0:
+        problems.append("kubernetes.namespace_refresh: must be a number greater than 0")
+
+    poll_interval = cfg["monitoring"].get("poll_interval")
+    if not isinstance(poll_interval, (int, float)) or poll_interval <= 0:
+        problems.append("monitoring.poll_interval: must be a number greater than 0")
+    fetch_timeout = cfg["monitoring"].get("fetch_timeout")
+    if not isinstance(fetch_timeout, (int, float)) or isinstance(fetch_timeout, bool) or (
+        fetch_timeout < 0
+    ):
+        problems.append("monitoring.fetch_timeout: must be a number of seconds of at least 0")
+    if not isinstance(cfg["monitoring"].get("state_file"), str):
+        problems.append("monitoring.state_file: must be a path, or empty to disable")
+    sample_queue = cfg["monitoring"].get("sample_queue")
+    if not isinstance(sample_queue, int) or isinstance(sample_queue, bool) or sample_queue < 0:
+        problems.append("monitoring.sample_queue: must be an integer of at least 0")
+    if not isinstance(cfg["monitoring"].get("on_change_only"), bool):
+        problems.append("monitoring.on_change_only: must be true or false")
+    change_delta = cfg["monitoring"].get("change_delta")
+    if not isinstance(change_delta, (int, float)) or isinstance(change_delta, bool) or (
+        change_delta < 0
+    ):
+        problems.append("monitoring.change_delta: must be a number of at least 0")
+
+    if cfg["monitoring"].get("gauge") not in GAUGE_STYLES:
+        problems.append(f"monitoring.gauge: must be one of {', '.join(GAUGE_STYLES)}")
+
+    note_duration = cfg["audio"].get("note_duration")
+    if not isinstance(note_duration, (int, float)) or note_duration <= 0:
+        problems.append("audio.note_duration: must be a number greater than 0")
+    if cfg["audio"].get("backend") not in AUDIO_BACKENDS:
+        problems.append(f"audio.backend: must be one of {', '.join(AUDIO_BACKENDS)}")
+    latency = cfg["audio"].get("latency")
+    if latency not in ("low", "high") and (
+        not isinstance(latency, (int, float)) or isinstance(latency, bool) or latency <= 0
+    ):
+        problems.append('audio.latency: must be "low", "high" or a number of seconds')
+    for key in ("fade", "crossfade", "schedule_grid"):
+        value = cfg["audio"].get(key)
+        if not isinstance(value, (int, float)) or isinstance(value, bool) or value < 0:
+            problems.append(f"audio.{key}: must be a number of seconds of at least 0")
+    threshold = cfg["audio"].get("limiter_threshold")
+    if not isinstance(threshold, (int, float)) or not 0 < threshold <= 1:
+        problems.append("audio.limiter_threshold: must be a number between 0 and 1")
+    queue_size = cfg["audio"].get("queue_size")
+    if not isinstance(queue_size, int) or queue_size < 0:
+        problems.append("audio.queue_size: must be an integer of at least 0")
+    if not isinstance(cfg["audio"].get("preempt"), bool):
+        problems.append("audio.preempt: must be true or false")
+    if cfg["audio"].get("overrun_policy") not in OVERRUN_POLICIES:
+        problems.append(f"audio.overrun_policy: must be one of {', '.join(OVERRUN_POLICIES)}")
+    fallback = cfg["audio"].get("fallback")
+    if not isinstance(fallback, list) or any(f not in AUDIO_FALLBACKS for f in fallback):
+        problems.append(f"audio.fallback: must be a list of {', '.join(AUDIO_FALLBACKS)}")
+    if cfg["audio"].get("backend") == "pipe" and not cfg["audio"].get("pipe_path"):
+        problems.append("audio.pipe_path: required by the pipe backend")
+    record_path = cfg["audio"].get("record_path")
+    if record_path and os.path.splitext(record_path)[1].lower() not in RECORDING_FORMATS:
+        problems.append(
+            f"audio.record_path: must end in one of {', '.join(RECORDING_FORMATS)}"
+        )
+    web_port = cfg["audio"].get("web_port")
+    if not isinstance(web_port, int) or not 0 < web_port < 65536:
+        problems.append("audio.web_port: must be an integer between 1 and 65535")
+    for event_type, drum in (cfg["audio"].get("drum_map") or {}).items():
+        if event_type not in CLUSTER_EVENTS:
+            problems.append(
+                f"audio.drum_map.{event_type}: must be one of {', '.join(CLUSTER_EVENTS)}"
+            )
+        elif not isinstance(drum, int) or isinstance(drum, bool) or not 0 <= drum <= 127:
+            problems.append(f"audio.drum_map.{event_type}: must be a MIDI note between 0 and 127")
+
+    for metric_name in cfg["metrics"].get("enabled") or []:
+        if metric_name not in known_metrics:
+            problems.append(f"metrics.enabled: unknown metric '{metric_name}'")
+    lead = cfg["metrics"].get("lead")
+    if "cost_per_hour" in (cfg["metrics"].get("enabled") or []) and not (
+        (cfg.get("opencost") or {}).get("url")
+    ):
+        problems.append("metrics.enabled: cost_per_hour requires opencost.url")
+    if "security_alerts" in (cfg["metrics"].get("enabled") or []) and not (
+        (cfg.get("falco") or {}).get("enabled")
+    ):
+        problems.append("metrics.enabled: security_alerts requires falco.enabled")
+    if lead and lead not in (cfg["metrics"].get("enabled") or []):
+        problems.append(f"metrics.lead: '{lead}' must be one of metrics.enabled")
+    accompaniment = cfg["metrics"].get("accompaniment_volume")
+    if not is_number(accompaniment) or not 0 <= accompaniment <= 1:
+        problems.append("metrics.accompaniment_volume: must be a number from 0.0 to 1.0")
+
+    for key in ("success", "failure"):
+        notes = cfg["rollouts"].get(key)
+        if not isinstance(notes, list) or not notes or not all(
+            isinstance(n, str) and NOTE_NAME_PATTERN.match(n.strip()) for n in notes
+        ):
+            problems.append(f'rollouts.{key}: must be a list of note names, e.g. ["C5", "E5"]')
+    step = cfg["rollouts"].get("step")
+    if not isinstance(step, (int, float)) or isinstance(step, bool) or step <= 0:
+        problems.append("rollouts.step: must be a number of seconds greater than 0")
+
+    earcons = cfg["earcons"]
+    library = earcons.get("library") or {}
+    for name, earcon in library.items():
+        problems.extend(validate_earcon(f"earcons.library.{name}", earcon))
+    for key, occurrences in (("events", CLUSTER_EVENTS), ("alarms", ("fired", "cleared"))):
+        for occurrence, name in (earcons.get(key) or {}).items():
+            if occurrence not in occurrences:
+                problems.append(
+                    f"earcons.{key}.{occurrence}: must be one of {', '.join(occurrences)}"
+                )
+            elif name not in EARCONS and name not in library:
+                problems.append(f"earcons.{key}.{occurrence}: unknown earcon '{name}'")
+    missing_permission = earcons.get("missing_permission")
+    if missing_permission and missing_permission not in EARCONS and (
+        missing_permission not in library
+    ):
+        problems.append(f"earcons.missing_permission: unknown earcon '{missing_permission}'")
+
+    alarms = cfg["alarms"]
+    escalate_after = alarms.get("escalate_after")
+    if not isinstance(escalate_after, (int, float)) or isinstance(escalate_after, bool) or (
+        escalate_after < 0
+    ):
+        problems.append("alarms.escalate_after: must be a number of seconds of at least 0")
+    max_escalation = alarms.get("max_escalation")
+    if not isinstance(max_escalation, int) or isinstance(max_escalation, bool) or (
+        max_escalation < 1
+    ):
+        problems.append("alarms.max_escalation: must be an integer of at least 1")
+    reminder_volume = alarms.get("reminder_volume")
+    if not isinstance(reminder_volume, (int, float)) or isinstance(reminder_volume, bool) or not (
+        0 <= reminder_volume <= 1
+    ):
+        problems.append("alarms.reminder_volume: must be a number from 0.0 to 1.0")
+    duck_volume = alarms.get("duck_volume")
+    if not isinstance(duck_volume, (int, float)) or isinstance(duck_volume, bool) or not (
+        0 <= duck_volume <= 1
+    ):
+        problems.append("alarms.duck_volume: must be a number from 0.0 to 1.0")
+    duck_release = alarms.get("duck_release")
+    if not isinstance(duck_release, (int, float)) or isinstance(duck_release, bool) or (
+        duck_release <= 0
+    ):
+        problems.append("alarms.duck_release: must be a number of seconds greater than 0")
+
+    if cfg["tempo"].get("sync") not in TEMPO_SYNCS:
+        problems.append(f"tempo.sync: must be one of {', '.join(TEMPO_SYNCS)}")
+
+    for control, target in (cfg["controller"].get("controls") or {}).items():
+        kind, _, metric_name = str(target).partition(".")
+        if not isinstance(control, int) or isinstance(control, bool) or not 0 <= control <= 127:
+            problems.append(f"controller.controls.{control}: must be a CC number between 0 and 127")
+        elif target not in ("volume", "poll_interval") and (
+            kind not in ("gain", "threshold") or metric_name not in SOUND_MAP
+        ):
+            problems.append(
+                f"controller.controls.{control}: must be volume, poll_interval,"
+                " gain.<metric> or threshold.<metric>"
+            )
+
+    tuning = cfg["tuning"]
+    if tuning.get("system") not in TUNING_SYSTEMS:
+        problems.append(f"tuning.system: must be one of {', '.join(TUNING_SYSTEMS)}")
+    elif tuning["system"] == "scala":
+        try:
+            load_scala(tuning.get("scala_file") or "")
+        except (OSError, ValueError) as e:
+            problems.append(f"tuning.scala_file: {e}")
+    root = tuning.get("root")
+    if isinstance(root, str):
+        if not NOTE_NAME_PATTERN.match(root.strip()):
+            problems.append(f"tuning.root: not a note name: '{root}'")
+    elif not isinstance(root, (int, float)) or isinstance(root, bool) or root <= 0:
+        problems.append("tuning.root: must be a number greater than 0 or a note name")
+    a4 = tuning.get("a4")
+    if not isinstance(a4, (int, float)) or isinstance(a4, bool) or a4 <= 0:
+        problems.append("tuning.a4: must be a number greater than 0")
+
+    port = cfg["osc"].get("port")
+    if not isinstance(port, int) or not 0 < port < 65536:
+        problems.append("osc.port: must be an integer between 1 and 65535")
+
+    prometheus = cfg.get("prometheus") or {}
+    url = prometheus.get("url")
+    if url and urllib.parse.urlparse(str(url)).scheme not in ("http", "https"):
+        problems.append(f"prometheus.url: must be an http:// or https:// URL, not {url!r}")
+    if not isinstance(prometheus.get("gpu_query"), str) or not prometheus["gpu_query"].strip():
+        problems.append("prometheus.gpu_query: must be a PromQL query")
+    problems.extend(check_token_source(prometheus, "prometheus", used=bool(url)))
+    opencost = cfg.get("opencost") or {}
+    url = opencost.get("url")
+    if url and urllib.parse.urlparse(str(url)).scheme not in ("http", "https"):
+        problems.append(f"opencost.url: must be an http:// or https:// URL, not {url!r}")
+    if not OPENCOST_WINDOW_PATTERN.match(str(opencost.get("window"))):
+        problems.append("opencost.window: must be a duration such as 1h, 30m or 1d")
+    port = cfg["statsd"].get("port")
+    if not isinstance(port, int) or not 0 < port < 65536:
+        problems.append("statsd.port: must be an integer between 1 and 65535")
+    for statsd_name, metric_name in (cfg["statsd"].get("mapping") or {}).items():
+        if metric_name not in SOUND_MAP:
+            problems.append(f"statsd.mapping.{statsd_name}: unknown metric '{metric_name}'")
+    falco = cfg.get("falco") or {}
+    port = falco.get("port")
+    if not isinstance(port, int) or not 0 < port < 65536:
+        problems.append("falco.port: must be an integer between 1 and 65535")
+    if falco.get("min_priority") not in FALCO_PRIORITIES:
+        problems.append(f"falco.min_priority: must be one of {', '.join(FALCO_PRIORITIES)}")
+    problems.extend(check_token_source(falco, "falco", used=bool(falco.get("enabled"))))
+
+    queue_size = cfg["outputs"].get("queue_size")
+    if not isinstance(queue_size, int) or isinstance(queue_size, bool) or queue_size < 0:
+        problems.append("outputs.queue_size: must be an integer of at least 0")
+    influxdb = cfg["outputs"]["influxdb"]
+    if influxdb.get("enabled"):
+        for key in ("url", "org", "bucket"):
+            if not influxdb.get(key):
+                problems.append(f"outputs.influxdb.{key}: required when enabled")
+    for name in ("influxdb", "grafana"):
+        output = cfg["outputs"][name]
+        problems.extend(
+            check_token_source(output, f"outputs.{name}", used=bool(output.get("enabled")))
+        )
+
+    graphite = cfg["outputs"]["graphite"]
+    if graphite.get("enabled"):
+        if not graphite.get("host"):
+            problems.append("outputs.graphite.host: required when enabled")
+        port = graphite.get("port")
+        if not isinstance(port, int) or not 0 < port < 65536:
+            problems.append("outputs.graphite.port: must be an integer between 1 and 65535")
+    haptic = cfg["outputs"]["haptic"]
+    controller = haptic.get("controller")
+    if not isinstance(controller, int) or isinstance(controller, bool) or controller < 0:
+        problems.append("outputs.haptic.controller: must be an integer of at least 0")
+    for severity in ("warning", "critical"):
+        intensity = haptic.get(severity)
+        if not isinstance(intensity, (int, float)) or isinstance(intensity, bool) or not (
+            0 <= intensity <= 1
+        ):
+            problems.append(f"outputs.haptic.{severity}: must be a number from 0.0 to 1.0")
+    duration = haptic.get("duration")
+    if not isinstance(duration, (int, float)) or isinstance(duration, bool) or duration <= 0:
+        problems.append("outputs.haptic.duration: must be a number of seconds greater than 0")
+    icecast = cfg["outputs"]["icecast"]
+    if icecast.get("enabled"):
+        url = urllib.parse.urlparse(icecast.get("url") or "")
+        if url.scheme != "http" or not url.hostname:
+            problems.append("outputs.icecast.url: must be the mountpoint's http:// URL")
+        if icecast.get("format") not in ICECAST_FORMATS:
+            problems.append(f"outputs.icecast.format: must be one of {', '.join(ICECAST_FORMATS)}")
+        bitrate = icecast.get("bitrate")
+        if not isinstance(bitrate, int) or isinstance(bitrate, bool) or bitrate <= 0:
+            problems.append("outputs.icecast.bitrate: must be a number of kbit/s greater than 0")
+        if icecast.get("password") and icecast.get("password_file"):
+            problems.append("outputs.icecast: set password or password_file, not both")
+        elif icecast.get("password_file"):
+            try:
+                read_secret("", icecast["password_file"])
+            except OSError as e:
+                problems.append(f"outputs.icecast.password_file: {e}")
+    history = cfg["outputs"]["history"]
+    if history.get("enabled") and not history.get("path"):
+        problems.append("outputs.history.path: required when enabled")
+    grafana = cfg["outputs"]["grafana"]
+    if grafana.get("enabled") and not grafana.get("url"):
+        problems.append("outputs.grafana.url: required when enabled")
+
+    return problems
+
+
+# --- Audio Sinks ---
+class ToneCache:
+    """
+    Keeps recently rendered sample buffers so repeated notes are synthesized once.
+
+    The note set is small and durations are fixed, so steady-state monitoring
+    plays the same handful of buffers over and over.
+    """
+
+    def __init__(self, max_entries: int = TONE_CACHE_SIZE):
+        """
+        Initialize an empty cache.
+
+        Args:
+            max_entries: How many buffers to keep; the least recently used is evicted.
+        """
+        self.max_entries = max_entries
+        self.buffers: "OrderedDict[Tuple, np.ndarray]" = OrderedDict()
+        self.lock = threading.Lock()
+        self.hits = 0
+        self.misses = 0
+
+    def get(self, key: Tuple, render: Callable[[], np.ndarray]) -> np.ndarray:
+        """
+        Returns the cached buffer for a key, rendering it on a miss.
+
+        Args:
+            key: Everything the buffer depends on, e.g. (waveform, envelope, frequency, duration).
+            render: Renders the buffer.
+
+        Returns:
+            The read-only buffer.
+        """
+        with self.lock:
+            if key in self.buffers:
+                self.buffers.move_to_end(key)
+                self.hits += 1
+                return self.buffers[key]
+
+        samples = render()
+        samples.setflags(write=False)
+        with self.lock:
+            self.misses += 1
+            self.buffers[key] = samples
+            while len(self.buffers) > self.max_entries:
+                self.buffers.popitem(last=False)
+        return samples
+
+    def clear(self) -> None:
+        """Drop every cached buffer."""
+        with self.lock:
+            self.buffers.clear()
+
+
+tone_cache = ToneCache()
+
+
+def render_tone(
+    frequency: float,
+    duration: float,
+    volume: float = 1.0,
+    sample_rate: int = SAMPLE_RATE,
+    fade: float = FADE_DURATION,
+    voice: Optional[Dict] = None,
+) -> np.ndarray:
+    """
+    Renders a note with the given voice, reusing cached buffers except for noise.
+
+    Args:
+        frequency: The frequency of the note in Hz.
+        duration: The duration of the note in seconds.
+        volume: The amplitude of the note (0.0 - 1.0).
+        sample_rate: The sample rate in Hz.
+        fade: Seconds of fade-in/out at the buffer edges.
+        voice: The instrument, e.g. {"type": "noise", "noise": "pink", "level": 0.5}.
+            If None, a pure tone with an ADSR envelope.
+
+    Returns:
+        The samples, between -1.0 and 1.0.
+    """
+    voice = voice or {"type": "sine"}
+
+    def render() -> np.ndarray:
+        return apply_fades(
+            apply_modulation(
+                synthesize_voice(voice, frequency, duration, sample_rate), voice, sample_rate
+            ),
+            fade,
+            sample_rate,
+        )
+
+    if voice.get("type") == "noise":
+        # Random every time, or every error note would replay the same static
+        return render() * volume
+    samples = tone_cache.get(
+        (tuple(sorted(voice.items())), frequency, duration, sample_rate, fade), render
+    )
+    return samples * volume
+
+
+def synthesize_voice(
+    voice: Dict, frequency: float, duration: float, sample_rate: int = SAMPLE_RATE
+) -> np.ndarray:
+    """
+    Synthesizes a full-volume note with one of the VOICES.
+
+    Args:
+        voice: The instrument and its settings.
+        frequency: The frequency of the note in Hz.
+        duration: The duration of the note in seconds.
+        sample_rate: The sample rate in Hz.
+
+    Returns:
+        The samples, between -1.0 and 1.0.
+    """
+    if voice.get("type") == "fm":
+        return synthesize_fm(
+            frequency, voice.get("ratio", 2.0), voice.get("index", 2.0), duration, sample_rate
+        )
+    if voice.get("type") == "additive":
+        return synthesize_additive(
+            frequency,
+            voice.get("harmonics", 8),
+            voice.get("brightness_level", voice.get("level", 0.5)),
+            duration,
+            sample_rate,
+        )
+    if voice.get("type") == "pluck":
+        return synthesize_pluck(frequency, voice.get("decay", 0.996), duration, sample_rate)
+    if voice.get("type") == "noise":
+        return synthesize_noise(
+            voice.get("noise", "white"), voice.get("level", 1.0), duration, sample_rate
+        )
+    return synthesize_tone(frequency, duration, sample_rate)
+
+
+def synthesize_noise(
+    color: str, level: float, duration: float, sample_rate: int = SAMPLE_RATE
+) -> np.ndarray:
+    """
+    Synthesizes static whose density and loudness grow with the metric level.
+
+    Args:
+        color: "white" (hiss), "pink" (softer rumble) or "crackle" (sparse clicks).
+        level: Where the metric sits in its range, from 0.0 to 1.0.
+        duration: The duration in seconds.
+        sample_rate: The sample rate in Hz.
+
+    Returns:
+        The samples, between -1.0 and 1.0.
+    """
+    n = int(sample_rate * duration)
+    rng = np.random.default_rng()
+    if color == "crackle":
+        # From an occasional click to a dense crackle, 20 to 400 clicks per second
+        clicks = rng.random(n) < (20 + 380 * level) / sample_rate
+        samples = np.where(clicks, rng.uniform(-1, 1, n), 0.0)
+    elif color == "pink":
+        # Voss-McCartney: sum white noise rows held for 1, 2, 4, ... samples
+        octaves = 8
+        samples = np.zeros(n)
+        for octave in range(octaves):
+            step = 2**octave
+            samples = samples + np.repeat(rng.uniform(-1, 1, n // step + 1), step)[:n]
+        samples = samples / octaves
+    else:
+        samples = rng.uniform(-1, 1, n)
+    return samples * (0.3 + 0.7 * level)
+
+
+def apply_fades(samples: np.ndarray, fade: float, sample_rate: int = SAMPLE_RATE) -> np.ndarray:
+    """
+    Ramps the edges of a buffer from and to silence so it starts and stops without a click.
+
+    Args:
+        samples: The buffer, modified in place.
+        fade: Seconds of fade at each edge, at most half the buffer.
+        sample_rate: The sample rate in Hz.
+
+    Returns:
+        The buffer.
+    """
+    fade_samples = min(int(fade * sample_rate), len(samples) // 2)
+    if fade_samples > 0:
+        samples[:fade_samples] = samples[:fade_samples] * np.linspace(0, 1, fade_samples)
+        samples[-fade_samples:] = samples[-fade_samples:] * np.linspace(1, 0, fade_samples)
+    return samples
+
+
+def apply_modulation(
+    samples: np.ndarray, voice: Dict, sample_rate: int = SAMPLE_RATE
+) -> np.ndarray:
+    """
+    Adds the vibrato or tremolo a voice asks for, see metric_voice().
+
+    Args:
+        samples: The synthesized note.
+        voice: The instrument, with "vibrato" or "tremolo" set to a level from 0.0 to 1.0.
+        sample_rate: The sample rate in Hz.
+
+    Returns:
+        The modulated samples.
+    """
+    t = np.arange(len(samples)) / sample_rate
+    vibrato = voice.get("vibrato", 0.0)
+    if vibrato > 0 and len(samples) > 1:
+        # Read the note at a speed swinging around 1, which swings the pitch with it
+        depth = vibrato * VIBRATO_MAX_DEPTH
+        angle = 2 * np.pi * VIBRATO_RATE
+        warped = t + depth * (1 - np.cos(angle * t)) / angle
+        samples = np.interp(np.minimum(warped, t[-1]), t, samples)
+    tremolo = voice.get("tremolo", 0.0)
+    if tremolo > 0:
+        low, high = TREMOLO_RATES
+        rate = low + (high - low) * tremolo
+        samples = samples * (1 - TREMOLO_DEPTH * (0.5 - 0.5 * np.cos(2 * np.pi * rate * t)))
+    return samples
+
+
+def crossfade(tail: np.ndarray, head: np.ndarray) -> np.ndarray:
+    """
+    Mixes the end of one note into the start of the next with opposing linear ramps.
+
+    Args:
+        tail: The last samples of the previous note.
+        head: The next note.
+
+    Returns:
+        The next note with the tail mixed into its start.
+    """
+    overlap = min(len(tail), len(head))
+    mixed = head.copy()
+    if overlap > 0:
+        mixed[:overlap] = tail[:overlap] * np.linspace(1, 0, overlap) + head[
+            :overlap
+        ] * np.linspace(0, 1, overlap)
+    return mixed
+
+
+def synthesize_tone(
+    frequency: float, duration: float, sample_rate: int = SAMPLE_RATE
+) -> np.ndarray:
+    """
+    Synthesizes a full-volume pure tone with an ADSR envelope.
+
+    Args:
+        frequency: The frequency of the note in Hz.
+        duration: The duration of the note in seconds.
+        sample_rate: The sample rate in Hz.
+
+    Returns:
+        The samples, between -1.0 and 1.0.
+    """
+    # Calculate sample values for pure tone
+    t = np.linspace(0, duration, int(sample_rate * duration), False)
+
+    # Generate the tone with the envelope
+    return np.sin(2 * np.pi * frequency * t) * adsr_envelope(len(t), sample_rate)
+
+
+def synthesize_fm(
+    frequency: float,
+    ratio: float,
+    index: float,
+    duration: float,
+    sample_rate: int = SAMPLE_RATE,
+) -> np.ndarray:
+    """
+    Synthesizes a two-operator FM tone with an ADSR envelope.
+
+    Args:
+        frequency: The carrier frequency in Hz.
+        ratio: The modulator frequency as a multiple of the carrier, e.g. 2.0.
+        index: The modulation index; higher values sound brighter and harsher.
+        duration: The duration of the note in seconds.
+        sample_rate: The sample rate in Hz.
+
+    Returns:
+        The samples, between -1.0 and 1.0.
+    """
+    t = np.linspace(0, duration, int(sample_rate * duration), False)
+    modulator = np.sin(2 * np.pi * frequency * ratio * t)
+    carrier = np.sin(2 * np.pi * frequency * t + index * modulator)
+    return carrier * adsr_envelope(len(t), sample_rate)
+
+
+def synthesize_additive(
+    frequency: float,
+    harmonics: int,
+    brightness: float,
+    duration: float,
+    sample_rate: int = SAMPLE_RATE,
+) -> np.ndarray:
+    """
+    Synthesizes a sum of harmonics whose upper partials grow with brightness.
+
+    Args:
+        frequency: The fundamental frequency in Hz.
+        harmonics: How many harmonics to sum, including the fundamental.
+        brightness: From 0.0 (nearly a sine) to 1.0 (buzzy, all harmonics equal).
+        duration: The duration of the note in seconds.
+        sample_rate: The sample rate in Hz.
+
+    Returns:
+        The samples, between -1.0 and 1.0.
+    """
+    t = np.linspace(0, duration, int(sample_rate * duration), False)
+    # Harmonic k is weighted 1/k^rolloff: steep for dull tones, flat for bright ones
+    rolloff = 3.0 - 3.0 * brightness
+    tone = np.zeros(len(t))
+    total = 0.0
+    for k in range(1, harmonics + 1):
+        if frequency * k >= sample_rate / 2:
+            break  # Above Nyquist it would alias
+        weight = 1 / k**rolloff
+        tone = tone + weight * np.sin(2 * np.pi * frequency * k * t)
+        total += weight
+    return tone / total * adsr_envelope(len(t), sample_rate)
+
+
+def synthesize_pluck(
+    frequency: float, decay: float, duration: float, sample_rate: int = SAMPLE_RATE
+) -> np.ndarray:
+    """
+    Synthesizes a plucked string with the Karplus-Strong algorithm.
+
+    A burst of noise circulates through a delay line one period long; averaging
+    neighbouring samples on every pass low-passes it into a naturally decaying tone.
+
+    Args:
+        frequency: The frequency of the string in Hz.
+        decay: How much of its energy the string keeps per pass (0.0 - 1.0).
+        duration: The duration of the note in seconds.
+        sample_rate: The sample rate in Hz.
+
+    Returns:
+        The samples, between -1.0 and 1.0.
+    """
+    n = int(sample_rate * duration)
+    period = max(int(round(sample_rate / frequency)), 1)
+    rng = np.random.default_rng()
+    samples = np.zeros(n + period + 1)
+    samples[: period + 1] = rng.uniform(-1, 1, period + 1)
+    # y[i] = decay * (y[i - period] + y[i - period - 1]) / 2, one period at a time
+    for start in range(period + 1, len(samples), period):
+        end = min(start + period, len(samples))
+        previous = samples[start - period : end - period]
+        before_previous = samples[start - period - 1 : end - period - 1]
+        samples[start:end] = decay * 0.5 * (previous + before_previous)
+    return samples[:n]
+
+
+def adsr_envelope(length: int, sample_rate: int = SAMPLE_RATE) -> np.ndarray:
+    """
+    Builds the ADSR envelope shared by the pitched voices.
+
+    Args:
+        length: The number of samples.
+        sample_rate: The sample rate in Hz.
+
+    Returns:
+        The envelope, between 0.0 and 1.0.
+    """
+    # Apply an ADSR (Attack, Decay, Sustain, Release) envelope
+    attack = 0.05  # Short attack
+    decay = 0.05  # Short decay
+    release = 0.1  # Short release
+    sustain_level = 0.8  # Sustain at 80% amplitude
+
+    # Create the envelope at full level
+    envelope = np.ones(length)
+    attack_samples = int(attack * sample_rate)
+    decay_samples = int(decay * sample_rate)
+    release_samples = int(release * sample_rate)
+
+    # Shrink the envelope proportionally for notes shorter than it
+    envelope_samples = attack_samples + decay_samples + release_samples
+    if envelope_samples > length:
+        scale = length / envelope_samples
+        attack_samples = int(attack_samples * scale)
+        decay_samples = int(decay_samples * scale)
+        release_samples = int(release_samples * scale)
+
+    # Apply attack
+    if attack_samples > 0:
+        envelope[:attack_samples] = np.linspace(0, 1, attack_samples)
+
+    # Apply decay to sustain level
+    if decay_samples > 0:
+        start_idx = attack_samples
+        end_idx = start_idx + decay_samples
+        if end_idx > start_idx:  # Ensure we have decay time
+            envelope[start_idx:end_idx] = np.linspace(
+                1, sustain_level, end_idx - start_idx
+            )
+
+    # Apply release
+    if release_samples > 0:
+        start_idx = len(envelope) - release_samples
+        if start_idx < len(envelope):  # Ensure we have release time
+            envelope[start_idx:] = np.linspace(
+                sustain_level if decay_samples > 0 else 1,
+                0,
+                len(envelope) - start_idx,
+            )
+
+    return envelope
+
+
+def soft_limit(samples: np.ndarray, threshold: float = LIMITER_THRESHOLD) -> np.ndarray:
+    """
+    Soft-knee limiter for the master bus.
+
+    Samples below the threshold pass unchanged; louder ones are compressed
+    smoothly towards full scale, so summed notes never clip.
+
+    Args:
+        samples: The buffer to limit.
+        threshold: The level (0-1) where compression starts; 1.0 disables the limiter.
+
+    Returns:
+        The limited samples, between -1.0 and 1.0.
+    """
+    if threshold >= 1:
+        return np.clip(samples, -1.0, 1.0)
+    headroom = 1 - threshold
+    magnitude = np.abs(samples)
+    limited = threshold + headroom * np.tanh((magnitude - threshold) / headroom)
+    return np.where(magnitude > threshold, np.sign(samples) * limited, samples)
+
+
+# The priority of the notes being played, see QueuedSink; higher plays first
+note_priority: contextvars.ContextVar = contextvars.ContextVar("note_priority", default=0)
+# Set while the audio sink fails to play notes, e.g. without a sound card over SSH
+audio_failed = threading.Event()
+
+
+class AudioSink:
+    """Somewhere notes can be played."""
+
+    can_fade = False  # Whether fade_out() can cut short a note that is playing
+
+    def play(
+        self, frequency: float, duration: float, volume: float = 1.0, voice: Optional[Dict] = None
+    ) -> None:
+        """
+        Play a note, returning once it has finished.
+
+        Args:
+            frequency: The frequency of the note in Hz.
+            duration: The duration of the note in seconds.
+            volume: The amplitude of the note (0.0 - 1.0).
+            voice: The instrument, see render_tone(). If None, a pure tone.
+        """
+        raise NotImplementedError
+
+    def play_event(self, event_type: str, volume: float = 1.0) -> None:
+        """
+        Mark a discrete cluster event. Sinks without a percussion layer ignore it.
+
+        Args:
+            event_type: One of CLUSTER_EVENTS.
+            volume: The amplitude of the hit (0.0 - 1.0).
+        """
+
+    def fade_out(self, duration: float) -> None:
+        """
+        Fade whatever is still playing to silence, returning once it is silent.
+        Sinks that can't change a note once it started let it finish.
+
+        Args:
+            duration: How long the fade takes in seconds.
+        """
+
+    def close(self) -> None:
+        """Release any resources held by the sink."""
+
+
+class SimpleaudioSink(AudioSink):
+    """Plays notes on the local sound card with simpleaudio."""
+
+    can_fade = True
+
+    def __init__(
+        self, fade: float = FADE_DURATION, limiter_threshold: float = LIMITER_THRESHOLD
+    ):
+        """
+        Initialize the sink.
+
+        Args:
+            fade: Seconds of fade-in/out at the edges of every note.
+            limiter_threshold: The level where the master limiter starts compressing.
+        """
+        self.fade = fade
+        self.limiter_threshold = limiter_threshold
+        self.playing: set = set()  # simpleaudio PlayObjects of the notes still sounding
+        self.lock = threading.Lock()
+
+    def play(
+        self, frequency: float, duration: float, volume: float = 1.0, voice: Optional[Dict] = None
+    ) -> None:
+        import simpleaudio as sa
+
+        tone = render_tone(frequency, duration, volume, fade=self.fade, voice=voice)
+        tone = soft_limit(tone, self.limiter_threshold)
+
+        # Normalize to 16-bit range and convert to int16
+        audio = (tone * 32767).astype(np.int16)
+
+        # Play the sound and wait for it to finish, or for fade_out() to stop it
+        play_obj = sa.play_buffer(audio, 1, 2, SAMPLE_RATE)
+        with self.lock:
+            self.playing.add(play_obj)
+        try:
+            play_obj.wait_done()
+        finally:
+            with self.lock:
+                self.playing.discard(play_obj)
+
+    def fade_out(self, duration: float) -> None:
+        # simpleaudio can't change a buffer once it plays, so the notes are stopped instead
+        with self.lock:
+            playing, self.playing = self.playing, set()
+        for play_obj in playing:
+            play_obj.stop()
+
+
+class NoteScheduler:
+    """
+    Places notes at exact sample offsets on a musical grid and mixes them into an output stream.
+
+    Each note starts on the first grid line after both the current playback position
+    and the end of the previously scheduled note, so notes from one tick stay back to
+    back and ticks land on the beat however much the poll loop jitters.
+    """
+
+    def __init__(self, grid: float, sample_rate: int = SAMPLE_RATE):
+        """
+        Initialize an empty timeline.
+
+        Args:
+            grid: Seconds between grid lines.
+            sample_rate: The sample rate in Hz.
+        """
+        self.sample_rate = sample_rate
+        self.grid_samples = max(int(round(grid * sample_rate)), 1)
+        self.position = 0  # Samples rendered so far
+        self.cursor = 0  # End of the last scheduled note
+        self.notes: List[Tuple[int, np.ndarray]] = []
+        self.condition = threading.Condition()
+
+    def schedule(self, samples: np.ndarray) -> int:
+        """
+        Adds a note to the timeline.
+
+        Args:
+            samples: The rendered note.
+
+        Returns:
+            The sample offset the note starts at.
+        """
+        with self.condition:
+            earliest = max(self.position, self.cursor)
+            start = -(-earliest // self.grid_samples) * self.grid_samples
+            self.notes.append((start, samples))
+            self.cursor = start + len(samples)
+            return start
+
+    def render(self, frames: int) -> np.ndarray:
+        """
+        Mixes the next block of output.
+
+        Args:
+            frames: The number of samples to render.
+
+        Returns:
+            The mixed samples.
+        """
+        with self.condition:
+            block_start = self.position
+            block_end = block_start + frames
+            output = np.zeros(frames)
+            remaining = []
+            for start, samples in self.notes:
+                end = start + len(samples)
+                if start < block_end and end > block_start:
+                    lo = max(start, block_start) - block_start
+                    hi = min(end, block_end) - block_start
+                    offset = block_start - start
+                    output[lo:hi] = output[lo:hi] + samples[lo + offset : hi + offset]
+                if end > block_end:
+                    remaining.append((start, samples))
+            self.notes = remaining
+            self.position = block_end
+            self.condition.notify_all()
+            return output
+
+    def fade_out(self, duration: float) -> int:
+        """
+        Fades the scheduled notes to silence from the playback position and drops
+        everything that would have played after the fade.
+
+        Args:
+            duration: How long the fade takes in seconds.
+
+        Returns:
+            The sample offset the output is silent from.
+        """
+        with self.condition:
+            length = max(int(duration * self.sample_rate), 1)
+            end = self.position + length
+            faded = []
+            for start, samples in self.notes:
+                if start >= end:
+                    continue
+                offsets = np.arange(start, min(start + len(samples), end))
+                ramp = np.clip((end - offsets) / length, 0.0, 1.0)
+                faded.append((start, samples[: len(offsets)] * ramp))
+            self.notes = faded
+            self.cursor = min(self.cursor, end)
+            return end
+
+    def wait_until(self, offset: int, timeout: Optional[float] = None) -> bool:
+        """
+        Blocks until playback reaches a sample offset.
+
+        Args:
+            offset: The sample offset.
+            timeout: The most seconds to wait.
+
+        Returns:
+            True if playback reached the offset.
+        """
+        with self.condition:
+            return self.condition.wait_for(lambda: self.position >= offset, timeout)
+
+
+class SounddeviceSink(AudioSink):
+    """
+    Writes notes to a PortAudio output stream that stays open between notes.
+
+    Skipping the per-note buffer setup keeps latency low, and selecting the
+    JACK host API lets the output be routed into mixers and DAWs.
+    """
+
+    can_fade = True
+
+    def __init__(
+        self,
+        device: Optional[str] = None,
+        host_api: Optional[str] = None,
+        latency="low",
+        fade: float = FADE_DURATION,
+        crossfade: float = 0.0,
+        limiter_threshold: float = LIMITER_THRESHOLD,
+        schedule_grid: float = 0.0,
+    ):
+        """
+        Open the output stream.
+
+        Args:
+            device: The output device name or index. If None, the default device
+                of the host API.
+            host_api: The host API name, e.g. "JACK Audio Connection Kit".
+                If None, PortAudio's default.
+            latency: "low", "high" or the latency in seconds.
+            fade: Seconds of fade-in/out at the edges of every note.
+            crossfade: Seconds consecutive notes overlap by. The end of each note is
+                held back and mixed into the next one if it follows within that time.
+            limiter_threshold: The level where the master limiter starts compressing.
+            schedule_grid: Seconds between the grid lines a NoteScheduler places notes on.
+                If 0, notes are written to the stream as they arrive.
+        """
+        import sounddevice as sd
+
+        self.fade = fade
+        self.crossfade_samples = int(crossfade * SAMPLE_RATE)
+        self.limiter_threshold = limiter_threshold
+        self.tail: Optional[np.ndarray] = None
+        self.tail_timer: Optional[threading.Timer] = None
+        self.lock = threading.Lock()
+
+        if isinstance(device, str) and device.isdigit():
+            device = int(device)
+        if host_api and device is None:
+            device = self.host_api_output_device(sd, host_api)
+
+        self.scheduler = NoteScheduler(schedule_grid) if schedule_grid > 0 else None
+        self.stream = sd.OutputStream(
+            samplerate=SAMPLE_RATE,
+            channels=1,
+            dtype="float32",
+            device=device,
+            latency=latency,
+            callback=self._callback if self.scheduler else None,
+        )
+        self.stream.start()
+
+    def _callback(self, outdata, frames, time_info, status) -> None:
+        outdata[:, 0] = soft_limit(self.scheduler.render(frames), self.limiter_threshold)
+
+    @staticmethod
+    def host_api_output_device(sd, host_api: str) -> int:
+        """
+        Finds the default output device of a host API.
+
+        Args:
+            sd: The sounddevice module.
+            host_api: The host API name, matched case-insensitively.
+
+        Returns:
+            The index of the device.
+        """
+        for api in sd.query_hostapis():
+            if host_api.lower() in api["name"].lower():
+                return api["default_output_device"]
+        raise ValueError(f"Unknown audio host API: {host_api}")
+
+    def play(
+        self, frequency: float, duration: float, volume: float = 1.0, voice: Optional[Dict] = None
+    ) -> None:
+        samples = render_tone(frequency, duration, volume, fade=self.fade, voice=voice)
+        if self.scheduler:
+            # Return once the note starts, so the next one can be queued right behind it
+            start = self.scheduler.schedule(samples)
+            wait = (start - self.scheduler.position) / SAMPLE_RATE
+            self.scheduler.wait_until(start, timeout=wait + 1)
+            return
+
+        with self.lock:
+            if self.tail_timer is not None:
+                self.tail_timer.cancel()
+            if self.tail is not None:
+                samples = crossfade(self.tail, samples)
+                self.tail = None
+            samples = soft_limit(samples, self.limiter_threshold)
+
+            hold = min(self.crossfade_samples, len(samples) // 2)
+            if hold > 0:
+                self.tail = samples[-hold:]
+                samples = samples[:-hold]
+
+            # Blocks until the samples are queued, which keeps notes back to back
+            self.stream.write(samples.astype(np.float32))
+
+            if self.tail is not None:
+                # Play the held-back end on its own if no note follows in time
+                self.tail_timer = threading.Timer(hold / SAMPLE_RATE, self.flush_tail)
+                self.tail_timer.daemon = True
+                self.tail_timer.start()
+
+    def flush_tail(self) -> None:
+        """Write the held-back end of the last note."""
+        with self.lock:
+            if self.tail is not None:
+                self.stream.write(self.tail.astype(np.float32))
+                self.tail = None
+
+    def fade_out(self, duration: float) -> None:
+        if self.scheduler:
+            end = self.scheduler.fade_out(duration)
+            self.scheduler.wait_until(end, timeout=duration + 1)
+            return
+        # Written samples can't be reached any more; only the held-back end can be faded
+        with self.lock:
+            if self.tail_timer is not None:
+                self.tail_timer.cancel()
+            if self.tail is not None:
+                ramp = np.linspace(1.0, 0.0, len(self.tail))
+                self.stream.write((self.tail * ramp).astype(np.float32))
+                self.tail = None
+
+    def close(self) -> None:
+        if self.tail_timer is not None:
+            self.tail_timer.cancel()
+        self.flush_tail()
+        self.stream.stop()
+        self.stream.close()
+
+
+class WebAudioSink(AudioSink):
+    """
+    Serves a browser page that plays notes with WebAudio.
+
+    Notes are pushed to every open page as server-sent events; the page
+    synthesizes them itself, so no audio device is needed on this host.
+    """
+
+    def __init__(self, host: str = "127.0.0.1", port: int = 8765):
+        """
+        Start serving the page.
+
+        Args:
+            host: The address to listen on.
+            port: The port to listen on.
+        """
+        self.clients: List[queue.Queue] = []
+        self.lock = threading.Lock()
+        self.server = ThreadingHTTPServer((host, port), self._make_handler())
+        self.server.daemon_threads = True
+        self.thread = threading.Thread(target=self.server.serve_forever, daemon=True)
+        self.thread.start()
+        logger.info(f"Serving the WebAudio player on http://{host}:{self.server.server_port}/")
+
+    def _make_handler(self):
+        sink = self
+
+        class Handler(BaseHTTPRequestHandler):
+            def do_GET(self):
+                if self.path == "/events":
+                    self.stream_events()
+                elif self.path in ("/", "/index.html"):
+                    with open(WEB_PLAYER_PATH, "rb") as f:
+                        page = f.read()
+                    self.send_response(200)
+                    self.send_header("Content-Type", "text/html; charset=utf-8")
+                    self.send_header("Content-Length", str(len(page)))
+                    self.end_headers()
+                    self.wfile.write(page)
+                else:
+                    self.send_error(404)
+
+            def stream_events(self):
+                self.send_response(200)
+                self.send_header("Content-Type", "text/event-stream")
+                self.send_header("Cache-Control", "no-cache")
+                self.end_headers()
+                notes = sink.subscribe()
+                try:
+                    while True:
+                        try:
+                            message = f"data: {notes.get(timeout=15)}\n\n"
+                        except queue.Empty:
+                            message = ": keepalive\n\n"
+                        self.wfile.write(message.encode("utf-8"))
+                        self.wfile.flush()
+                except OSError:
+                    pass  # The page was closed
+                finally:
+                    sink.unsubscribe(notes)
+
+            def log_message(self, format, *args):
+                logger.debug(f"WebAudio player: {format % args}")
+
+        return Handler
+
+    def subscribe(self) -> queue.Queue:
+        """Register a page to receive notes."""
+        notes: queue.Queue = queue.Queue()
+        with self.lock:
+            self.clients.append(notes)
+        return notes
+
+    def unsubscribe(self, notes: queue.Queue) -> None:
+        """Stop sending notes to a page."""
+        with self.lock:
+            if notes in self.clients:
+                self.clients.remove(notes)
+
+    def play(
+        self, frequency: float, duration: float, volume: float = 1.0, voice: Optional[Dict] = None
+    ) -> None:
+        message = json.dumps(
+            {"frequency": frequency, "duration": duration, "volume": volume, "voice": voice}
+        )
+        with self.lock:
+            for notes in self.clients:
+                notes.put(message)
+        # Keep the same pacing as a local sound card
+        time.sleep(duration)
+
+    def close(self) -> None:
+        self.server.shutdown()
+        self.server.server_close()
+
+
+class PipeSink(AudioSink):
+    """
+    Writes the rendered notes as a continuous raw PCM stream (16-bit little-endian
+    mono at SAMPLE_RATE), for aplay, ffmpeg or a streaming encoder to play.
+
+    A thread feeds the stream in real time, with silence between notes, so its
+    reader never runs dry while waiting for the next note.
+    """
+
+    can_fade = True
+
+    def __init__(
+        self,
+        stream,
+        fade: float = FADE_DURATION,
+        limiter_threshold: float = LIMITER_THRESHOLD,
+    ):
+        """
+        Initialize the sink and start the thread feeding the stream.
+
+        Args:
+            stream: A binary file to write to, e.g. sys.stdout.buffer or a named pipe.
+            fade: Seconds of fade-in/out at the edges of every note.
+            limiter_threshold: The level where the master limiter starts compressing.
+        """
+        self.stream = stream
+        self.fade = fade
+        self.limiter_threshold = limiter_threshold
+        # Notes start as soon as they are played, one after the other
+        self.scheduler = NoteScheduler(1 / SAMPLE_RATE)
+        self.feeding = True
+        self.closed = threading.Event()
+        self.thread = threading.Thread(target=self._feed, daemon=True)
+        self.thread.start()
+
+    def _feed(self) -> None:
+        frames = int(PIPE_BLOCK * SAMPLE_RATE)
+        next_block = time.monotonic()
+        while not self.closed.is_set():
+            block = soft_limit(self.scheduler.render(frames), self.limiter_threshold)
+            try:
+                self.stream.write((block * 32767).astype("<i2").tobytes())
+                self.stream.flush()
+            except (OSError, ValueError) as e:
+                logger.error(f"Audio pipe closed by its reader: {e}")
+                self.feeding = False
+                return
+            next_block += PIPE_BLOCK
+            self.closed.wait(max(0.0, next_block - time.monotonic()))
+
+    def play(
+        self, frequency: float, duration: float, volume: float = 1.0, voice: Optional[Dict] = None
+    ) -> None:
+        if not self.feeding:
+            raise OSError("The audio pipe was closed by its reader")
+        tone = render_tone(frequency, duration, volume, fade=self.fade, voice=voice)
+        start = self.scheduler.schedule(tone)
+        # Return once the note has been heard, like a sound card sink
+        self.scheduler.wait_until(start + len(tone), timeout=duration + 1)
+
+    def fade_out(self, duration: float) -> None:
+        end = self.scheduler.fade_out(duration)
+        if self.feeding:
+            self.scheduler.wait_until(end, timeout=duration + 1)
+
+    def close(self) -> None:
+        self.closed.set()
+        self.thread.join(timeout=1)
+        try:
+            self.stream.flush()
+            if self.stream is not sys.__stdout__.buffer:
+                self.stream.close()
+        except (OSError, ValueError):
+            pass
+
+
+class RecordingSink(AudioSink):
+    """
+    Plays notes through another sink while recording them, silences between notes
+    included, as a WAV file, or an OGG Vorbis or FLAC file once the session ends.
+
+    Compressed recordings are written as WAV first and encoded on close, so their
+    tags can hold the session's time range.
+    """
+
+    def __init__(
+        self,
+        sink: AudioSink,
+        path: str,
+        tags: Optional[Dict[str, str]] = None,
+        fade: float = FADE_DURATION,
+        limiter_threshold: float = LIMITER_THRESHOLD,
+        clock: Optional[Callable[[], float]] = None,
+    ):
+        """
+        Start recording.
+
+        Args:
+            sink: Where the notes are played.
+            path: The file to record to; its extension picks one of RECORDING_FORMATS.
+            tags: Embedded in compressed recordings, e.g. {"cluster": "prod",
+                "namespace": "web"}, along with the time range.
+            fade: Seconds of fade-in/out at the edges of every recorded note.
+            limiter_threshold: The level where the master limiter starts compressing.
+            clock: Where the recording's timing comes from. If None, time.time.
+        """
+        self.sink = sink
+        self.path = path
+        self.tags = tags or {}
+        self.fade = fade
+        self.limiter_threshold = limiter_threshold
+        self.clock = clock or time.time
+        self.format = RECORDING_FORMATS[os.path.splitext(path)[1].lower()]
+        if self.format is None:
+            self.wav_path = path
+        else:
+            # Next to the recording, so keeping it on failure is a rename, not a copy
+            handle, self.wav_path = tempfile.mkstemp(
+                suffix=".wav", dir=os.path.dirname(os.path.abspath(path))
+            )
+            os.close(handle)
+        self.wav = wave.open(self.wav_path, "wb")
+        self.wav.setnchannels(1)
+        self.wav.setsampwidth(2)
+        self.wav.setframerate(SAMPLE_RATE)
+        self.started = self.clock()
+        self.written = 0  # Samples recorded so far
+        self.lock = threading.Lock()
+
+    @property
+    def can_fade(self) -> bool:
+        """Whether the sink being recorded can cut its notes short."""
+        return self.sink.can_fade
+
+    def play(
+        self, frequency: float, duration: float, volume: float = 1.0, voice: Optional[Dict] = None
+    ) -> None:
+        tone = soft_limit(
+            render_tone(frequency, duration, volume, fade=self.fade, voice=voice),
+            self.limiter_threshold,
+        )
+        with self.lock:
+            if self.wav is not None:
+                gap = int((self.clock() - self.started) * SAMPLE_RATE) - self.written
+                samples = np.concatenate([np.zeros(max(gap, 0)), tone])
+                self.wav.writeframes((samples * 32767).astype(np.int16).tobytes())
+                self.written += len(samples)
+        self.sink.play(frequency, duration, volume, voice)
+
+    def play_event(self, event_type: str, volume: float = 1.0) -> None:
+        self.sink.play_event(event_type, volume)
+
+    def fade_out(self, duration: float) -> None:
+        self.sink.fade_out(duration)
+
+    def close(self) -> None:
+        self.sink.close()
+        with self.lock:
+            if self.wav is None:
+                return
+            self.wav.close()
+            self.wav = None
+        if self.format is None:
+            logger.info(f"Recorded the session to {self.path}")
+            return
+        start, end = (
+            datetime.fromtimestamp(t, timezone.utc).isoformat(timespec="seconds")
+            for t in (self.started, self.started + self.written / SAMPLE_RATE)
+        )
+        tags = dict(self.tags, start=start, end=end)
+        try:
+            import soundfile
+
+            encode_recording(self.wav_path, self.path, self.format, tags, soundfile)
+        except Exception as e:
+            kept = os.path.splitext(self.path)[0] + ".wav"
+            os.replace(self.wav_path, kept)
+            logger.error(f"Failed to encode {self.path}, kept the recording as {kept}: {e}")
+            return
+        os.remove(self.wav_path)
+        logger.info(f"Recorded the session to {self.path}")
+
+
+def encode_recording(
+    wav_path: str, path: str, file_format: Tuple[str, str], tags: Dict[str, str], soundfile
+) -> None:
+    """
+    Compresses a WAV recording with libsndfile, embedding tags.
+
+    Args:
+        wav_path: The 16-bit mono recording.
+        path: The file to write.
+        file_format: The soundfile format and subtype, e.g. ("OGG", "VORBIS").
+        tags: The cluster, namespace, start and end of the session, as available.
+        soundfile: The soundfile module.
+    """
+    with wave.open(wav_path, "rb") as wav, soundfile.SoundFile(
+        path, "w", wav.getframerate(), 1, subtype=file_format[1], format=file_format[0]
+    ) as out:
+        # Vorbis comments and FLAC tags are written with the header, before any audio
+        where = "/".join(tags[key] for key in ("cluster", "namespace") if tags.get(key))
+        out.title = f"Sonify K8s: {where}" if where else "Sonify K8s"
+        out.album = tags.get("cluster", "")
+        out.date = tags["start"]
+        out.software = "sonify-k8s"
+        out.comment = ", ".join(f"{key}={value}" for key, value in tags.items())
+        while True:
+            frames = wav.readframes(RECORDING_BLOCK)
+            if not frames:
+                break
+            out.write(np.frombuffer(frames, dtype=np.int16))
+
+
+def icecast_command(icecast: Dict) -> List[str]:
+    """
+    Builds the ffmpeg command that encodes raw PCM from stdin into a stream on stdout.
+
+    Args:
+        icecast: The outputs.icecast settings.
+
+    Returns:
+        The command line.
+    """
+    encoder, _ = ICECAST_FORMATS[icecast["format"]]
+    return [
+        icecast["ffmpeg"], "-hide_banner", "-loglevel", "error",
+        "-f", "s16le", "-ar", str(SAMPLE_RATE), "-ac", "1", "-i", "-",
+        "-c:a", encoder, "-b:a", f"{icecast['bitrate']}k",
+        "-f", icecast["format"], "-",
+    ]
+
+
+def icecast_request(icecast: Dict, password: str) -> Tuple[Tuple[str, int], bytes]:
+    """
+    Builds the HTTP request that opens the mountpoint as a source.
+
+    The source password is sent from this process rather than handed to ffmpeg,
+    whose command line any local user can read.
+
+    Args:
+        icecast: The outputs.icecast settings.
+        password: The source password of the mountpoint.
+
+    Returns:
+        The server's host and port, and the request headers.
+
+    Raises:
+        ValueError: If the URL is not an http:// URL. The request is sent without TLS,
+            so an https:// server would get the password in plain text.
+    """
+    url = urllib.parse.urlparse(icecast["url"])
+    if url.scheme != "http" or not url.hostname:
+        raise ValueError(f"not an http:// mountpoint URL: {icecast['url']}")
+    address = (url.hostname, url.port or 8000)
+    credentials = base64.b64encode(f"{icecast['user']}:{password}".encode()).decode()
+    headers = [
+        f"PUT {url.path or '/'} HTTP/1.1",
+        f"Host: {address[0]}:{address[1]}",
+        f"Authorization: Basic {credentials}",
+        f"Content-Type: {ICECAST_FORMATS[icecast['format']][1]}",
+        f"Ice-Name: {icecast['name']}",
+        f"Ice-Description: {icecast['description']}",
+        "Ice-Public: 0",
+        "User-Agent: sonify-k8s",
+        "Expect: 100-continue",
+    ]
+    # A line break in the stream name or description would end the header early
+    headers = [" ".join(header.splitlines()) for header in headers]
+    return address, ("\r\n".join(headers) + "\r\n\r\n").encode()
+
+
+class IcecastSink(AudioSink):
+    """
+    Plays notes through another sink while streaming them live to an Icecast
+    mountpoint, encoded by an ffmpeg process, so a whole team can listen in.
+
+    The stream is sent in real time, with silence between notes, so listeners'
+    players never run dry. ffmpeg only encodes; the stream is relayed to the
+    server from this process, which holds the source password.
+    """
+
+    def __init__(
+        self,
+        sink: AudioSink,
+        command: List[str],
+        address: Tuple[str, int],
+        request: bytes,
+        fade: float = FADE_DURATION,
+        limiter_threshold: float = LIMITER_THRESHOLD,
+        popen: Callable = subprocess.Popen,
+        connect: Callable = socket.create_connection,
+    ):
+        """
+        Open the mountpoint, then start the encoder and the threads feeding it
+        and relaying its stream.
+
+        Args:
+            sink: Where the notes are played locally.
+            command: The encoder command, see icecast_command().
+            address: The Icecast server's host and port, see icecast_request().
+            request: The source request headers, see icecast_request().
+            fade: Seconds of fade-in/out at the edges of every streamed note.
+            limiter_threshold: The level where the master limiter starts compressing.
+            popen: Starts the encoder; for tests.
+            connect: Connects to the server; for tests.
+
+        Raises:
+            OSError: If the server can't be reached or refuses the source.
+        """
+        self.sink = sink
+        self.fade = fade
+        self.limiter_threshold = limiter_threshold
+        self.connection = connect(address, timeout=ICECAST_TIMEOUT)
+        try:
+            self.connection.sendall(request)
+            response = b""
+            while b"\r\n\r\n" not in response:
+                chunk = self.connection.recv(1024)
+                if not chunk:
+                    break
+                response += chunk
+            status = response.split(b"\r\n", 1)[0].decode(errors="replace")
+            if status.split()[1:2] not in (["100"], ["200"]):
+                raise OSError(f"Icecast refused the source: {status or 'no response'}")
+            self.connection.settimeout(None)
+        except OSError:
+            self.connection.close()
+            raise
+        # Streamed notes start as soon as they are played, one after the other
+        self.scheduler = NoteScheduler(1 / SAMPLE_RATE)
+        self.process = popen(command, stdin=subprocess.PIPE, stdout=subprocess.PIPE)
+        self.streaming = True
+        self.closed = threading.Event()
+        self.thread = threading.Thread(target=self._feed, daemon=True)
+        self.thread.start()
+        self.relay = threading.Thread(target=self._relay, daemon=True)
+        self.relay.start()
+
+    def _relay(self) -> None:
+        while True:
+            try:
+                chunk = self.process.stdout.read1(ICECAST_CHUNK)
+                if not chunk:
+                    return
+                self.connection.sendall(chunk)
+            except (OSError, ValueError) as e:
+                if not self.closed.is_set():
+                    logger.error(f"Icecast stream stopped, the server connection failed: {e}")
+                self.streaming = False
+                return
+
+    def _feed(self) -> None:
+        frames = int(ICECAST_BLOCK * SAMPLE_RATE)
+        next_block = time.monotonic()
+        while not self.closed.is_set():
+            block = soft_limit(self.scheduler.render(frames), self.limiter_threshold)
+            try:
+                self.process.stdin.write((block * 32767).astype(np.int16).tobytes())
+                self.process.stdin.flush()
+            except (OSError, ValueError) as e:
+                logger.error(f"Icecast stream stopped, the encoder exited: {e}")
+                self.streaming = False
+                return
+            next_block += ICECAST_BLOCK
+            self.closed.wait(max(0.0, next_block - time.monotonic()))
+
+    @property
+    def can_fade(self) -> bool:
+        """Whether the local sink can cut its notes short; the stream follows it."""
+        return self.sink.can_fade
+
+    def play(
+        self, frequency: float, duration: float, volume: float = 1.0, voice: Optional[Dict] = None
+    ) -> None:
+        if self.streaming:
+            self.scheduler.schedule(
+                render_tone(frequency, duration, volume, fade=self.fade, voice=voice)
+            )
+        self.sink.play(frequency, duration, volume, voice)
+
+    def play_event(self, event_type: str, volume: float = 1.0) -> None:
+        self.sink.play_event(event_type, volume)
+
+    def fade_out(self, duration: float) -> None:
+        self.scheduler.fade_out(duration)
+        self.sink.fade_out(duration)
+
+    def close(self) -> None:
+        self.closed.set()
+        self.thread.join(timeout=1)
+        try:
+            self.process.stdin.close()
+            self.process.wait(timeout=5)
+        except (OSError, ValueError, subprocess.SubprocessError) as e:
+            logger.warning(f"Failed to stop the Icecast encoder cleanly: {e}")
+        self.relay.join(timeout=1)
+        self.connection.close()
+        self.sink.close()
+
+
+class QueuedSink(AudioSink):
+    """
+    Plays notes from a bounded priority queue on a worker thread.
+
+    Waiting notes are played highest note_priority first, in arrival order within a
+    priority. When notes arrive faster than they can be played, the overrun policy
+    keeps the audio close to the current cluster state instead of drifting behind it,
+    and only ever drops notes of the lowest waiting priority.
+    """
+
+    def __init__(
+        self,
+        sink: AudioSink,
+        max_notes: int = 4,
+        policy: str = "drop-oldest",
+        preempt: bool = False,
+    ):
+        """
+        Start the worker thread.
+
+        Args:
+            sink: Where the notes are played.
+            max_notes: How many notes may wait to be played.
+            policy: "drop-oldest" discards the stalest waiting note, "drop-newest"
+                discards the arriving note, and "shorten" divides each note's duration
+                by the backlog so it drains faster (dropping the oldest when still full).
+            preempt: Whether a note fades out the playing note when it has a higher
+                priority. Ignored when the sink can't fade a playing note.
+        """
+        self.sink = sink
+        self.max_notes = max_notes
+        self.policy = policy
+        self.preempt = preempt
+        self.notes: List[Tuple[int, int, Tuple]] = []  # A heap of (-priority, arrival, note)
+        self.arrivals = 0
+        self.playing: Optional[int] = None  # The priority of the playing note
+        self.condition = threading.Condition()
+        self.closed = False
+        self.dropped = 0
+        self.preempted = 0
+        self.thread = threading.Thread(target=self._run, daemon=True)
+        self.thread.start()
+
+    @property
+    def can_fade(self) -> bool:
+        """Whether the sink the queue plays through can cut its notes short."""
+        return self.sink.can_fade
+
+    def play(
+        self, frequency: float, duration: float, volume: float = 1.0, voice: Optional[Dict] = None
+    ) -> None:
+        priority = note_priority.get()
+        with self.condition:
+            if len(self.notes) >= self.max_notes:
+                self.dropped += 1
+                lowest = max(self.notes)
+                lowest_priority = -lowest[0]
+                if lowest_priority > priority or (
+                    self.policy == "drop-newest" and lowest_priority == priority
+                ):
+                    logger.debug(f"Audio queue full, dropping {frequency} Hz")
+                    return
+                # The oldest note of the lowest priority, or the newest with drop-newest
+                victim = lowest if self.policy == "drop-newest" else min(
+                    note for note in self.notes if note[0] == lowest[0]
+                )
+                self.notes.remove(victim)
+                heapq.heapify(self.notes)
+                logger.debug(f"Audio queue full, dropping {victim[2][0]} Hz")
+            note = (frequency, duration, volume, voice)
+            heapq.heappush(self.notes, (-priority, self.arrivals, note))
+            self.arrivals += 1
+            preempt = (
+                self.preempt and self.sink.can_fade
+                and self.playing is not None and priority > self.playing
+            )
+            if preempt:
+                self.playing = priority  # Preempt the playing note only once
+            self.condition.notify()
+        if preempt:
+            self.preempted += 1
+            logger.debug(f"Preempting the playing note for {frequency} Hz")
+            self.sink.fade_out(PREEMPT_FADE)
+
+    def _run(self) -> None:
+        while True:
+            with self.condition:
+                while not self.notes and not self.closed:
+                    self.condition.wait()
+                if self.closed:
+                    return
+                priority, _, (frequency, duration, volume, voice) = heapq.heappop(self.notes)
+                self.playing = -priority
+                backlog = len(self.notes)
+
+            if self.policy == "shorten" and backlog:
+                duration /= backlog + 1
+            try:
+                self.sink.play(frequency, duration, volume, voice)
+                audio_failed.clear()
+            except Exception as e:
+                logger.error(f"Error playing note: {e}")
+                audio_failed.set()
+            with self.condition:
+                self.playing = None
+
+    def play_event(self, event_type: str, volume: float = 1.0) -> None:
+        # Hits are instantaneous, so they skip the queue
+        self.sink.play_event(event_type, volume)
+
+    def fade_out(self, duration: float) -> None:
+        # Waiting notes would only start after the fade, so they are dropped
+        with self.condition:
+            self.notes.clear()
+        self.sink.fade_out(duration)
+
+    def close(self) -> None:
+        with self.condition:
+            self.closed = True
+            self.condition.notify()
+        self.thread.join(timeout=1)
+        self.sink.close()
+
+
+class MidiSink(AudioSink):
+    """Sends notes to a MIDI output port that stays open between notes."""
+
+    can_fade = True
+
+    def __init__(
+        self,
+        port_name: Optional[str] = None,
+        virtual: bool = False,
+        drum_map: Optional[Dict[str, int]] = None,
+    ):
+        """
+        Open the output port.
+
+        Args:
+            port_name: The port to send to. If None, the default output.
+            virtual: Whether to create a virtual port named MIDI_VIRTUAL_PORT instead,
+                so DAWs can record the notes without routing hardware.
+            drum_map: The General MIDI percussion note for each cluster event.
+        """
+        import mido
+
+        self.mido = mido
+        self.drum_map = DEFAULT_CONFIG["audio"]["drum_map"] if drum_map is None else drum_map
+        self.programs: Dict[int, int] = {}
+        self.controls: Dict[Tuple[int, int], int] = {}
+        self.sounding: set = set()
+        self.lock = threading.Lock()
+        if virtual:
+            self.port = mido.open_output(MIDI_VIRTUAL_PORT, virtual=True)
+            logger.info(f"Created virtual MIDI output port {MIDI_VIRTUAL_PORT}")
+        else:
+            self.port = mido.open_output(port_name)
+
+    def play(
+        self, frequency: float, duration: float, volume: float = 1.0, voice: Optional[Dict] = None
+    ) -> None:
+        note = min(max(frequency_to_midi(frequency), 0), 127)
+        velocity = min(max(int(round(volume * 127)), 1), 127)
+        # The voice's channel and program are numbered from 1, as synths display them
+        channel = (voice or {}).get("midi_channel", 1) - 1
+        program = (voice or {}).get("midi_program")
+        fields = {"channel": channel, "note": note, "velocity": velocity}
+        pan = (voice or {}).get("pan")
+        with self.lock:
+            if pan is not None:
+                value = min(max(int(round((pan + 1) / 2 * 127)), 0), 127)
+                if self.controls.get((channel, MIDI_PAN_CONTROL)) != value:
+                    self.port.send(self.mido.Message(
+                        "control_change", channel=channel, control=MIDI_PAN_CONTROL, value=value
+                    ))
+                    self.controls[(channel, MIDI_PAN_CONTROL)] = value
+            if program is not None and self.programs.get(channel) != program - 1:
+                self.port.send(
+                    self.mido.Message("program_change", channel=channel, program=program - 1)
+                )
+                self.programs[channel] = program - 1
+            self.port.send(self.mido.Message("note_on", **fields))
+            self.sounding.add((channel, note))
+        control = (voice or {}).get("midi_cc")
+        if control is None:
+            time.sleep(duration)
+        else:
+            self.ramp_control(channel, control, voice.get("cc_level", 0.0), duration)
+        with self.lock:
+            self.port.send(self.mido.Message("note_off", **fields))
+            self.sounding.discard((channel, note))
+
+    def ramp_control(self, channel: int, control: int, level: float, duration: float) -> None:
+        """
+        Moves a control change from its last value towards a level over a note, so
+        external synths see a smooth sweep between polls rather than a jump.
+
+        Args:
+            channel: The MIDI channel, from 0.
+            control: The controller number, e.g. 1 (mod wheel) or 11 (expression).
+            level: The level to reach (0.0 - 1.0).
+            duration: How long the ramp takes in seconds.
+        """
+        target = min(max(int(round(level * 127)), 0), 127)
+        start = self.controls.get((channel, control), target)
+        sent = None
+        for step in range(1, MIDI_CC_STEPS + 1):
+            value = start + (target - start) * step // MIDI_CC_STEPS
+            if value != sent:
+                with self.lock:
+                    self.port.send(
+                        self.mido.Message(
+                            "control_change", channel=channel, control=control, value=value
+                        )
+                    )
+                sent = value
+            time.sleep(duration / MIDI_CC_STEPS)
+        self.controls[(channel, control)] = target
+
+    def play_event(self, event_type: str, volume: float = 1.0) -> None:
+        note = self.drum_map.get(event_type)
+        if note is None:
+            return
+        velocity = min(max(int(round(volume * 127)), 1), 127)
+        fields = {"channel": MIDI_DRUM_CHANNEL, "note": note, "velocity": velocity}
+        with self.lock:
+            self.port.send(self.mido.Message("note_on", **fields))
+            self.port.send(self.mido.Message("note_off", **fields))
+
+    def fade_out(self, duration: float) -> None:
+        # Notes interrupted mid-play never got their note_off, so they are still sounding
+        with self.lock:
+            channels = sorted({channel for channel, _ in self.sounding})
+        if not channels:
+            return
+        for step in range(1, MIDI_CC_STEPS + 1):
+            value = MIDI_DEFAULT_VOLUME * (MIDI_CC_STEPS - step) // MIDI_CC_STEPS
+            with self.lock:
+                for channel in channels:
+                    self.port.send(
+                        self.mido.Message(
+                            "control_change",
+                            channel=channel,
+                            control=MIDI_VOLUME_CONTROL,
+                            value=value,
+                        )
+                    )
+            time.sleep(duration / MIDI_CC_STEPS)
+        with self.lock:
+            for channel, note in sorted(self.sounding):
+                self.port.send(self.mido.Message("note_off", channel=channel, note=note))
+            self.sounding.clear()
+            for channel in channels:
+                self.port.send(
+                    self.mido.Message(
+                        "control_change",
+                        channel=channel,
+                        control=MIDI_VOLUME_CONTROL,
+                        value=MIDI_DEFAULT_VOLUME,
+                    )
+                )
+
+    def close(self) -> None:
+        self.port.close()
+
+
+@dataclass
+class CapturedNote:
+    """A note played through a CaptureSink."""
+
+    frequency: float
+    duration: float
+    volume: float
+    voice: Optional[Dict]
+    timestamp: float
+    samples: Optional[np.ndarray] = None
+
+
+class CaptureSink(AudioSink):
+    """
+    Records what would have been played instead of playing it, so tests can check
+    sonification end to end without a sound card.
+    """
+
+    can_fade = True
+
+    def __init__(
+        self,
+        render: bool = True,
+        fade: float = FADE_DURATION,
+        clock: Optional[Callable[[], float]] = None,
+    ):
+        """
+        Initialize an empty recording.
+
+        Args:
+            render: Whether to render each note's samples, as a sound card sink would.
+            fade: Seconds of fade-in/out at the edges of every rendered note.
+            clock: Where timestamps come from. If None, time.monotonic.
+        """
+        self.render = render
+        self.fade = fade
+        self.clock = clock or time.monotonic
+        self.notes: List[CapturedNote] = []
+        self.events: List[Tuple[str, float, float]] = []
+        self.fades: List[float] = []
+        self.closed = False
+        self.lock = threading.Lock()
+
+    def play(
+        self, frequency: float, duration: float, volume: float = 1.0, voice: Optional[Dict] = None
+    ) -> None:
+        samples = (
+            render_tone(frequency, duration, volume, fade=self.fade, voice=voice)
+            if self.render
+            else None
+        )
+        with self.lock:
+            self.notes.append(
+                CapturedNote(frequency, duration, volume, voice, self.clock(), samples)
+            )
+
+    def play_event(self, event_type: str, volume: float = 1.0) -> None:
+        with self.lock:
+            self.events.append((event_type, volume, self.clock()))
+
+    def fade_out(self, duration: float) -> None:
+        self.fades.append(duration)
+
+    def close(self) -> None:
+        self.closed = True
+
+    @property
+    def frequencies(self) -> List[float]:
+        """The frequencies of the captured notes, in playing order."""
+        with self.lock:
+            return [note.frequency for note in self.notes]
+
+
+def build_audio_sink(cfg: Dict) -> AudioSink:
+    """
+    Creates the audio sink selected by a configuration.
+
+    Args:
+        cfg: The configuration.
+
+    Returns:
+        The audio sink for audio.backend.
+    """
+    audio = cfg["audio"]
+    sink: Optional[AudioSink] = None
+    if audio["backend"] == "midi" or audio["use_midi"]:
+        try:
+            sink = MidiSink(audio["midi_port"] or None, audio["midi_virtual"], audio["drum_map"])
+        except Exception as e:
+            logger.error(f"Failed to open MIDI output, falling back to simpleaudio: {e}")
+    elif audio["backend"] == "webaudio":
+        sink = WebAudioSink(audio["web_host"], audio["web_port"])
+    elif audio["backend"] == "pipe":
+        if audio["pipe_path"] == "-":
+            # The real stdout, even once text output has been moved to stderr
+            sink = PipeSink(sys.__stdout__.buffer, audio["fade"], audio["limiter_threshold"])
+        else:
+            # Opening a named pipe waits for its reader, e.g. aplay, to open it too
+            sink = PipeSink(
+                open(audio["pipe_path"], "wb"), audio["fade"], audio["limiter_threshold"]
+            )
+    elif audio["backend"] == "sounddevice":
+        try:
+            sink = SounddeviceSink(
+                audio["device"] or None,
+                audio["host_api"] or None,
+                audio["latency"],
+                fade=audio["fade"],
+                crossfade=audio["crossfade"],
+                limiter_threshold=audio["limiter_threshold"],
+                schedule_grid=audio["schedule_grid"],
+            )
+        except Exception as e:
+            logger.error(f"Failed to open sounddevice output, falling back to simpleaudio: {e}")
+    if sink is None:
+        sink = SimpleaudioSink(fade=audio["fade"], limiter_threshold=audio["limiter_threshold"])
+    icecast = cfg["outputs"]["icecast"]
+    if icecast["enabled"]:
+        try:
+            password = read_secret(icecast["password"], icecast["password_file"])
+            sink = IcecastSink(
+                sink,
+                icecast_command(icecast),
+                *icecast_request(icecast, password),
+                fade=audio["fade"],
+                limiter_threshold=audio["limiter_threshold"],
+            )
+            logger.info(f"Streaming to Icecast at {icecast['url']}")
+        except (OSError, ValueError) as e:
+            logger.error(f"Failed to start streaming to Icecast ({icecast['ffmpeg']}): {e}")
+    if audio["record_path"]:
+        kubernetes = cfg["kubernetes"]
+        sink = RecordingSink(
+            sink,
+            audio["record_path"],
+            {
+                "cluster": kubernetes["context"]
+                or ("current-context" if kubernetes["use_kubeconfig"] else kubernetes["api_url"]),
+                "namespace": kubernetes["namespace"],
+            },
+            fade=audio["fade"],
+            limiter_threshold=audio["limiter_threshold"],
+        )
+
+    if audio["queue_size"] > 0:
+        sink = QueuedSink(
+            sink, audio["queue_size"], audio["overrun_policy"], preempt=audio["preempt"]
+        )
+    return sink
+
+
+def set_audio_sink(sink: AudioSink) -> None:
+    """
+    Plays all subsequent notes through a sink.
+
+    Args:
+        sink: The sink to use.
+    """
+    global audio_sink
+    audio_sink = sink
+
+
+def install_audio_sink(cfg: Dict) -> AudioSink:
+    """
+    Creates the audio sink selected by a configuration and plays all subsequent notes
+    through it, so every command honors audio.backend, recording and streaming.
+
+    Args:
+        cfg: The configuration.
+
+    Returns:
+        The sink, for the caller to close when done.
+    """
+    sink = build_audio_sink(cfg)
+    set_audio_sink(sink)
+    return sink
+
+
+audio_sink: AudioSink = SimpleaudioSink()
+
+
+# --- Audio Utility Functions ---
+def play_note(
+    frequency: int, duration: float = 0.5, volume: float = 1.0, voice: Optional[Dict] = None
+) -> None:
+    """
+    Plays a musical note through the current audio sink.
+
+    Args:
+        frequency: The frequency of the note in Hz.
+        duration: The duration of the note in seconds.
+        volume: The amplitude of the note (0.0 - 1.0).
+        voice: The instrument, see render_tone(). If None, a pure tone.
+    """
+    if os.environ.get("TEST_MODE", "False").lower() == "true":
+        logger.info(
+            f"Playing note at {frequency} Hz for {duration} seconds (TEST_MODE)"
+        )
+        return
+    if os.environ.get("AUDIO_ENABLED", "true").lower() == "false":
+        return
+    if os.environ.get("LOUDNESS_COMPENSATION", "false").lower() == "true":
+        volume *= loudness_gain(frequency)
+
+    try:
+        audio_sink.play(frequency, duration, volume, voice)
+        audio_failed.clear()
+    except Exception as e:
+        logger.error(f"Error playing note: {e}")
+        audio_failed.set()
+        # Fallback to simple sleep if sound fails
+        time.sleep(duration)
+
+
+def play_event(event_type: str, volume: float = 1.0) -> None:
+    """
+    Marks a discrete cluster event through the current audio sink.
+
+    Args:
+        event_type: One of CLUSTER_EVENTS.
+        volume: The amplitude of the hit (0.0 - 1.0).
+    """
+    if os.environ.get("TEST_MODE", "False").lower() == "true":
+        logger.info(f"Playing {event_type} event (TEST_MODE)")
+        return
+    if os.environ.get("AUDIO_ENABLED", "true").lower() == "false":
+        return
+
+    try:
+        audio_sink.play_event(event_type, volume)
+    except Exception as e:
+        logger.error(f"Error playing {event_type} event: {e}")
+
+
+def a_weighting(frequency: float) -> float:
+    """
+    Approximates how much quieter a frequency sounds than 1 kHz (IEC 61672 A-weighting).
+
+    Args:
+        frequency: The frequency in Hz.
+
+    Returns:
+        The weighting in dB; negative for frequencies that sound quieter.
+    """
+    f2 = frequency**2
+    response = (12194**2 * f2**2) / (
+        (f2 + 20.6**2) * math.sqrt((f2 + 107.7**2) * (f2 + 737.9**2)) * (f2 + 12194**2)
+    )
+    return 20 * math.log10(response) + 2.0
+
+
+def loudness_gain(frequency: float) -> float:
+    """
+    Computes the gain that makes a note sound as loud as every other note.
+
+    Louder-sounding frequencies are attenuated down to LOUDNESS_FLOOR_DB, so
+    no note needs more than full amplitude; frequencies below it are left as is.
+
+    Args:
+        frequency: The frequency in Hz.
+
+    Returns:
+        The gain, between 0.0 and 1.0.
+    """
+    return 10 ** ((LOUDNESS_FLOOR_DB - max(a_weighting(frequency), LOUDNESS_FLOOR_DB)) / 20)
+
+
+def frequency_to_midi(frequency: float) -> int:
+    """
+    Converts a frequency to the nearest MIDI note number (A4 = 69 = 440Hz).
+
+    Args:
+        frequency: The frequency in Hz.
+
+    Returns:
+        The MIDI note number.
+    """
+    return int(round(69 + 12 * math.log2(frequency / 440)))
+
+
+def play_midi_note(note_num: int, duration: float = 0.5) -> None:
+    """
+    Plays a MIDI note using the mido and python-rtmidi libraries.
+
+    Args:
+        note_num: The MIDI note number (60 = C4).
+        duration: The duration of the note in seconds.
+    """
+    if os.environ.get("TEST_MODE", "False").lower() == "true":
+        logger.info(f"Playing MIDI note {note_num} for {duration} seconds (TEST_MODE)")
+        return
+
+    try:
+        import mido
+
+        # Try to open the default MIDI output
+        with mido.open_output() as port:
+            # Send note on message
+            port.send(mido.Message("note_on", note=note_num, velocity=64))
+            # Wait for the duration
+            time.sleep(duration)
+            # Send note off message
+            port.send(mido.Message("note_off", note=note_num, velocity=64))
+
+    except Exception as e:
+        logger.error(f"Error playing MIDI note: {e}")
+        # Fall back to the simpleaudio method
+        # Convert MIDI note to frequency (A4 = 69 = 440Hz)
+        frequency = 440 * (2 ** ((note_num - 69) / 12))
+        play_note(frequency, duration)
+
+
+def play_arpeggio(
+    frequencies: List[int],
+    duration: float = 0.5,
+    volume: float = 1.0,
+    voice: Optional[Dict] = None,
+    voices: Optional[List[Optional[Dict]]] = None,
+) -> None:
+    """
+    Plays several notes as a rapid sequence within a single note slot.
+
+    Args:
+        frequencies: The frequencies of the notes in Hz, in playing order.
+        duration: The total duration of the arpeggio in seconds.
+        volume: The amplitude of the notes (0.0 - 1.0).
+        voice: The instrument, see render_tone(). If None, a pure tone.
+        voices: An instrument for each note, instead of voice for all of them.
+    """
+    if not frequencies:
+        return
+    # Keep each step audible even when a metric has many values
+    step_duration = max(duration / len(frequencies), ARPEGGIO_MIN_STEP)
+    for frequency, note_voice in zip(frequencies, voices or [voice] * len(frequencies)):
+        play_note(frequency, step_duration, volume=volume, voice=note_voice)
+
+
+def row_voices(count: int, voice: Optional[Dict] = None) -> List[Dict]:
+    """
+    Gives each row of a metric with "rows" its own voice: the next of ROW_VOICES,
+    spread across the stereo field from left to right.
+
+    Args:
+        count: The number of rows.
+        voice: The metric's voice, whose other settings every row keeps.
+
+    Returns:
+        A voice per row, in row order.
+    """
+    return [
+        dict(
+            voice or {},
+            type=ROW_VOICES[i % len(ROW_VOICES)],
+            pan=round(-1.0 + 2.0 * i / (count - 1), 2) if count > 1 else 0.0,
+        )
+        for i in range(count)
+    ]
+
+
+def play_earcon(name: str, volume: float = 1.0, a4: float = 440.0) -> None:
+    """
+    Plays a motif from EARCONS.
+
+    Args:
+        name: The name of the earcon.
+        volume: The amplitude, scaled by the earcon's own volume (0.0 - 1.0).
+        a4: The frequency of A4 in Hz, for earcons written as note names.
+    """
+    earcon = EARCONS[name]
+    volume *= earcon.get("volume", 1.0)
+    for note in earcon["notes"]:
+        frequency = note_frequency(note, a4) if isinstance(note, str) else note
+        play_note(frequency, earcon["step"], volume=volume, voice=earcon.get("voice"))
+
+
+def play_heartbeat(severity: str = "ok") -> None:
+    """
+    Plays the soft metronome tick that signals a reachable cluster.
+
+    Args:
+        severity: The cluster's current severity, which picks the tick's timbre.
+    """
+    play_earcon(HEARTBEAT_EARCONS[severity])
+
+
+def wait_for_next_poll(
+    heartbeat: Optional[str] = None,
+    beat: Optional[Callable[[str], None]] = None,
+    check: Optional[Callable[[], None]] = None,
+) -> None:
+    """
+    Sleeps until the next polling interval, ticking the heartbeat if requested.
+
+    Args:
+        heartbeat: The severity to play heartbeat ticks with while waiting, see
+            HEARTBEAT_EARCONS, or None for silence.
+        beat: Called with heartbeat for each tick. If None, play_heartbeat.
+        check: Called every HEARTBEAT_INTERVAL while waiting, e.g. to escalate alarms.
+    """
+    if not heartbeat and not check:
+        time.sleep(POLL_INTERVAL)
+        return
+
+    beat = beat or play_heartbeat
+    remaining = float(POLL_INTERVAL)
+    while remaining > 0:
+        step = min(HEARTBEAT_INTERVAL, remaining)
+        if heartbeat:
+            beat(heartbeat)
+        time.sleep(max(0.0, step - (HEARTBEAT_DURATION if heartbeat else 0.0)))
+        remaining -= step
+        if check:
+            check()
+
+
+def get_color(color_list: List[str], index: int) -> str:
+    """
+    Retrieves a color from a list, handling out-of-bounds indices.
+
+    Args:
+        color_list: A list of color strings (e.g., hex codes).
+        index: The index of the color to retrieve.
+
+    Returns:
+        A color string, or a default color if the index is out of bounds.
+    """
+    # 1. Simplicity: Provide clear, maintainable solutions
+    # 2. Focus: Stick strictly to defined tasks
+    if 0 <= index < len(color_list):
+        return color_list[index]
+    elif color_list:
+        return color_list[-1]
+    else:
+        return "#808080"  # Default gray color
+
+
+def calculate_index(
+    value: float, notes_length: int, min_value: float, max_value: float
+) -> int:
+    """
+    Calculates the index for selecting a note and color based on a metric value.
+
+    Args:
+        value: The metric value.
+        notes_length: The number of notes (and colors) available.
+        min_value: The minimum expected value of the metric.
+        max_value: The maximum expected value of the metric.
+
+    Returns:
+        An integer index.
+    """
+    # 1. Simplicity: Provide clear, maintainable solutions
+    # 2. Focus: Stick strictly to defined tasks
+    if max_value <= min_value or notes_length < 2:
+        return 0
+    clamped_value = max(min_value, min(max_value, value))
+    normalized_value = (clamped_value - min_value) / (max_value - min_value)
+    index = int(normalized_value * (notes_length - 1))
+    return index
+
+
+class MappedNote(NamedTuple):
+    """The note and color a metric value maps to, see map_metric()."""
+
+    index: int
+    frequency: float
+    note_name: str
+    color: str
+    duration: Optional[float] = None  # Seconds, if the entry has a duration_range
+    velocity: float = 1.0  # What the volume is scaled by, from the entry's velocity_range
+
+
+def severity_level(metric_name: str, value: float) -> float:
+    """
+    Places a value between a metric's best and worst ends.
+
+    Args:
+        metric_name: The name of the metric.
+        value: The metric value.
+
+    Returns:
+        metric_level(), reversed for metrics where lower values are worse.
+    """
+    level = metric_level(metric_name, value)
+    thresholds = SOUND_MAP[metric_name].get("thresholds")
+    return 1.0 - level if thresholds and thresholds[0] > thresholds[1] else level
+
+
+def map_metric(metric_name: str, value: float) -> MappedNote:
+    """
+    Maps a metric value onto the note and color configured in SOUND_MAP.
+
+    Entries with "mapping": "continuous" glide through their "frequency_range"
+    (by default from their lowest to their highest note) instead of snapping to a
+    note, so small drifts are heard, named relative to the entry's "a4"; the index
+    still picks the color. Entries with a
+    "duration_range" or "velocity_range" also get longer or louder as the value
+    gets worse.
+
+    Args:
+        metric_name: The name of the metric (a SOUND_MAP key).
+        value: The metric value.
+
+    Returns:
+        The mapped note.
+    """
+    metric_config = SOUND_MAP[metric_name]
+    notes_list = metric_config["notes"]
+    if "status_map" in metric_config:
+        # Status metrics already report an index into their notes
+        index = max(0, min(int(value), len(notes_list) - 1))
+    else:
+        min_value, max_value = metric_config["range"]
+        index = calculate_index(value, len(notes_list), min_value, max_value)
+
+    frequency, note_name = notes_list[index]
+    if metric_config.get("mapping") == "continuous" and "status_map" not in metric_config:
+        low, high = metric_config.get("frequency_range") or (notes_list[0][0], notes_list[-1][0])
+        clamped_value = max(min_value, min(max_value, value))
+        normalized_value = (clamped_value - min_value) / (max_value - min_value)
+        # Equal steps in value are equal musical intervals
+        frequency = round(low * (high / low) ** normalized_value, 2)
+        note_name = frequency_name(frequency, metric_config.get("a4", 440.0))
+    color = get_color(metric_config["colors"], index)
+    duration, velocity = None, 1.0
+    if "duration_range" in metric_config or "velocity_range" in metric_config:
+        level = severity_level(metric_name, value)
+        if "duration_range" in metric_config:
+            shortest, longest = metric_config["duration_range"]
+            duration = shortest + (longest - shortest) * level
+        if "velocity_range" in metric_config:
+            softest, loudest = metric_config["velocity_range"]
+            velocity = softest + (loudest - softest) * level
+    return MappedNote(index, frequency, note_name, color, duration, velocity)
+
+
+def get_severity(
+    metric_name: str, value: float, thresholds: Optional[Tuple[float, float]] = None
+) -> str:
+    """
+    Classifies a metric value against the thresholds configured in SOUND_MAP.
+
+    Args:
+        metric_name: The name of the metric (a SOUND_MAP key).
+        value: The metric value.
+        thresholds: (warning, critical) to use instead of the metric's, e.g. a namespace's.
+
+    Returns:
+        "ok", "warning" or "critical".
+    """
+    thresholds = thresholds or SOUND_MAP[metric_name].get("thresholds")
+    if not thresholds:
+        return "ok"
+    warning, critical = thresholds
+    if warning > critical:
+        # Lower values are worse (e.g., pod status)
+        if value <= critical:
+            return "critical"
+        return "warning" if value <= warning else "ok"
+    if value >= critical:
+        return "critical"
+    return "warning" if value >= warning else "ok"
+
+
+def set_critical_threshold(metric_name: str, critical: float) -> None:
+    """
+    Moves a metric's critical threshold, keeping the warning threshold the same distance away.
+
+    Args:
+        metric_name: The name of the metric; metrics without thresholds are left alone.
+        critical: The new critical threshold.
+    """
+    thresholds = SOUND_MAP[metric_name].get("thresholds")
+    if thresholds:
+        warning, old_critical = thresholds
+        SOUND_MAP[metric_name]["thresholds"] = (critical - (old_critical - warning), critical)
+
+
+def metric_level(metric_name: str, value: float) -> float:
+    """
+    Places a value within its metric's range.
+
+    Args:
+        metric_name: The name of the metric.
+        value: The metric value.
+
+    Returns:
+        The position from 0.0 to 1.0, in steps of 0.05 to keep the tone cache small.
+    """
+    low, high = SOUND_MAP[metric_name]["range"]
+    level = min(max((value - low) / (high - low), 0.0), 1.0) if high > low else 0.0
+    return round(level * 20) / 20
+
+
+def metric_voice(
+    metric_name: str,
+    value: float,
+    lookup: Optional[Callable[[str], Optional[float]]] = None,
+    volatility: float = 0.0,
+) -> Optional[Dict]:
+    """
+    Looks up a metric's voice and fills in the levels that drive it.
+
+    Args:
+        metric_name: The name of the metric.
+        value: The metric value.
+        lookup: Returns the current value of another metric, for voices driven
+            by a secondary metric such as an additive voice's brightness.
+        volatility: How much the metric jitters (0.0 - 1.0), see Sonifier.volatility().
+            Played as the metric's "volatility" effect, if it has one.
+
+    Returns:
+        The voice with "level" (plus "brightness_level" for additive voices,
+        "cc_level" for voices with a MIDI CC and "vibrato" or "tremolo" for volatile
+        metrics) added, or None for the default pure tone.
+    """
+    entry = SOUND_MAP[metric_name]
+    voice = entry.get("voice")
+    effect = entry.get("volatility")
+    if effect and volatility > 0:
+        # Rounded, so a jittering metric still reuses cached notes
+        voice = dict(voice or {"type": "sine"}, **{effect: round(volatility, 1)})
+    if not voice:
+        return None
+    voice = dict(voice, level=metric_level(metric_name, value))
+
+    def driving_level(other_metric: Optional[str]) -> float:
+        other_value = lookup(other_metric) if other_metric and lookup else None
+        if other_value is None:
+            return voice["level"]
+        return metric_level(other_metric, other_value)
+
+    if voice.get("type") == "additive":
+        voice["brightness_level"] = driving_level(voice.get("brightness"))
+    if "midi_cc" in voice:
+        voice["cc_level"] = driving_level(voice.get("cc_metric"))
+    return voice
+
+
+# --- Kubernetes Client ---
+def rollout_state(deployment) -> str:
+    """
+    Works out how a Deployment's latest rollout is going.
+
+    Args:
+        deployment: A V1Deployment.
+
+    Returns:
+        "failed" if it exceeded its progress deadline, "complete" once every replica
+        is updated and available, otherwise "progressing".
+    """
+    status = deployment.status
+    for condition in status.conditions or []:
+        if condition.type == "Progressing" and condition.reason == "ProgressDeadlineExceeded":
+            return "failed"
+    desired = deployment.spec.replicas or 0
+    if (
+        (status.observed_generation or 0) >= (deployment.metadata.generation or 0)
+        and (status.updated_replicas or 0) == desired
+        and (status.available_replicas or 0) == desired
+        and (status.replicas or 0) == desired
+    ):
+        return "complete"
+    return "progressing"
+
+
+def parse_cpu(quantity: str) -> float:
+    """
+    Converts a Kubernetes CPU quantity to cores.
+
+    Args:
+        quantity: The quantity, e.g. "100m" (0.1 cores) or "2".
+
+    Returns:
+        The number of cores.
+    """
+    if quantity.endswith("m"):
+        return float(quantity[:-1]) / 1000
+    return float(quantity)
+
+
+def parse_memory(quantity: str) -> float:
+    """
+    Converts a Kubernetes memory quantity to bytes.
+
+    Args:
+        quantity: The quantity, e.g. "512Mi", "1G" or "1048576".
+
+    Returns:
+        The number of bytes.
+    """
+    for suffix, multiplier in MEMORY_SUFFIXES.items():
+        if quantity.endswith(suffix):
+            return float(quantity[: -len(suffix)]) * multiplier
+    return float(quantity)
+
+
+class MissingPermission(Exception):
+    """The service account may not read a resource a metric needs (403 Forbidden)."""
+
+    def __init__(self, resource: str, namespace: Optional[str] = None):
+        """
+        Initialize the error.
+
+        Args:
+            resource: The resource that couldn't be listed, e.g. "nodes".
+            namespace: The namespace it couldn't be listed in, or None if cluster-scoped.
+        """
+        where = f" in namespace {namespace}" if namespace else ""
+        super().__init__(f"the service account may not list {resource}{where}")
+        self.resource = resource
+        self.namespace = namespace
+
+
+class TimedApi:
+    """Wraps a kubernetes API, such as a CoreV1Api, timing each call made through it."""
+
+    def __init__(self, api, record: Callable[[float], None]):
+        """
+        Initialize the wrapper.
+
+        Args:
+            api: The API to wrap.
+            record: Called with the seconds each call took, whether it succeeded or not.
+        """
+        self.api = api
+        self.record = record
+
+    def __getattr__(self, name: str):
+        method = getattr(self.api, name)
+        if not callable(method):
+            return method
+
+        def timed(*args, **kwargs):
+            started = time.monotonic()
+            try:
+                return method(*args, **kwargs)
+            finally:
+                self.record(time.monotonic() - started)
+
+        return timed
+
+
+class K8sClient:
+    """Kubernetes client wrapper for fetching cluster metrics."""
+
+    def __init__(self):
+        """Initialize the Kubernetes client."""
+        self.v1_core = None
+        self.v1_apps = None
+        self.custom_objects = None
+        self.metrics_api = None
+        self.prometheus_url = ""
+        self.prometheus_token = ""
+        self.gpu_query = DEFAULT_CONFIG["prometheus"]["gpu_query"]
+        self.opencost_url = ""
+        self.opencost_window = DEFAULT_CONFIG["opencost"]["window"]
+        self.initialized = False
+        self.kubeconfig: Optional[str] = None
+        self.context: Optional[str] = None
+        self.label_selector = ""
+        self.api_url = K8S_API_URL
+        self.token = ""
+        self.ca_cert = ""
+        self.insecure_skip_verify = False
+        self.proxy = ""
+        self.impersonate_user = ""
+        self.impersonate_groups: List[str] = []
+        self.request_timeout: Optional[float] = None
+        self.restart_counts: Dict[str, Tuple[float, int]] = {}
+        self.pod_uids: Dict[str, Tuple[float, set]] = {}
+        self.node_names: Optional[set] = None  # The nodes seen by the last get_node_pressure()
+        self.node_changes = {"joined": 0, "lost": 0}  # Nodes that came and went since then
+        self.latencies: List[float] = []  # Seconds each API request took, since the last read
+        self._latency_lock = threading.Lock()
+
+    @property
+    def request_options(self) -> Dict:
+        """Keyword arguments for each API call, such as its timeout."""
+        return {"_request_timeout": self.request_timeout} if self.request_timeout else {}
+
+    def impersonate(self, api_client) -> None:
+        """
+        Send the impersonation headers with every request made through an API client.
+
+        Args:
+            api_client: A kubernetes ApiClient.
+        """
+        api_client.set_default_header("Impersonate-User", self.impersonate_user)
+        # A default header has a single value, so at most one group (see validate_config)
+        for group in self.impersonate_groups[:1]:
+            api_client.set_default_header("Impersonate-Group", group)
+
+    def record_latency(self, seconds: float) -> None:
+        """
+        Record how long an API request took, for apiserver_latency.
+
+        Args:
+            seconds: The time the request took.
+        """
+        with self._latency_lock:
+            self.latencies.append(seconds)
+
+    def configure_connection(self) -> None:
+        """Apply the CA certificate, certificate verification and proxy options, if set."""
+        if not (self.ca_cert or self.insecure_skip_verify or self.proxy):
+            return
+        configuration = client.Configuration.get_default_copy()
+        if self.ca_cert:
+            configuration.ssl_ca_cert = self.ca_cert
+        if self.insecure_skip_verify:
+            logger.warning("Not verifying the Kubernetes API server's certificate")
+            configuration.verify_ssl = False
+        if self.proxy:
+            configuration.proxy = self.proxy
+        client.Configuration.set_default(configuration)
+
+    def initialize(self) -> bool:
+        """
+        Initialize the Kubernetes API client.
+
+        Returns:
+            True if initialization was successful, False otherwise.
+        """
+        if client is None:
+            logger.error("Failed to initialize Kubernetes client: the kubernetes package is not installed")
+            self.initialized = False
+            return False
+
+        try:
+            if USE_KUBE_CONFIG:
+                logger.info(f"Loading kubeconfig from {self.kubeconfig or '~/.kube/config'}")
+                config.load_kube_config(config_file=self.kubeconfig, context=self.context)
+            elif os.environ.get("KUBERNETES_SERVICE_HOST"):
+                logger.info("Loading in-cluster configuration")
+                config.load_incluster_config()
+            else:
+                # Neither a kubeconfig nor running in a pod: talk to api_url directly
+                logger.info(f"Connecting to the Kubernetes API at {self.api_url}")
+                configuration = client.Configuration()
+                configuration.host = self.api_url
+                if self.token:
+                    configuration.api_key = {"authorization": f"Bearer {self.token}"}
+                client.Configuration.set_default(configuration)
+            self.configure_connection()
+
+            api_client = client.ApiClient()
+            if self.impersonate_user:
+                logger.info(f"Impersonating {self.impersonate_user}")
+                self.impersonate(api_client)
+            self.v1_core = TimedApi(client.CoreV1Api(api_client), self.record_latency)
+            self.v1_apps = TimedApi(client.AppsV1Api(api_client), self.record_latency)
+            self.custom_objects = TimedApi(
+                client.CustomObjectsApi(api_client), self.record_latency
+            )
+
+            # Test connection
+            self.v1_core.list_namespace(limit=1)
+            logger.info("Successfully connected to Kubernetes cluster")
+            self.initialized = True
+            return True
+
+        except Exception as e:
+            logger.error(f"Failed to initialize Kubernetes client: {e}")
+            self.initialized = False
+            return False
+
+    def list_namespaces(self) -> List[str]:
+        """
+        List the names of all namespaces in the cluster.
+
+        Returns:
+            A list of namespace names, empty if they cannot be listed.
+        """
+        try:
+            namespaces = self.v1_core.list_namespace()
+            return [ns.metadata.name for ns in namespaces.items]
+        except ApiException as e:
+            logger.warning(f"Failed to list namespaces: {e}")
+            return []
+
+    def list_sonification_profiles(self) -> Optional[List[Dict]]:
+        """
+        List the SonificationProfile custom resources of every namespace.
+
+        Returns:
+            The resources as dicts, or None if they cannot be listed (e.g. the
+            CustomResourceDefinition is not installed).
+        """
+        try:
+            profiles = self.custom_objects.list_cluster_custom_object(
+                PROFILE_GROUP, PROFILE_VERSION, PROFILE_PLURAL, **self.request_options
+            )
+        except ApiException as e:
+            logger.warning(f"Failed to list SonificationProfiles: {e}")
+            return None
+        return profiles.get("items", [])
+
+    def list_contexts(self) -> Tuple[List[str], Optional[str]]:
+        """
+        List the contexts in the kubeconfig file.
+
+        Returns:
+            The context names and the one in use, or ([], None) if they cannot be read.
+        """
+        try:
+            contexts, active = config.list_kube_config_contexts(config_file=self.kubeconfig)
+        except Exception as e:
+            logger.warning(f"Failed to list kubeconfig contexts: {e}")
+            return [], None
+        return [c["name"] for c in contexts], self.context or (active or {}).get("name")
+
+    def switch_context(self, context: str) -> bool:
+        """
+        Reconnect using another kubeconfig context, staying on the current one on failure.
+
+        Args:
+            context: The context name.
+
+        Returns:
+            True if the client is now connected through the context.
+        """
+        previous = self.context
+        self.context = context
+        if self.initialize():
+            return True
+        self.context = previous
+        self.initialize()
+        return False
+
+    def discover_namespaces(self, include: List[str], exclude: List[str]) -> List[str]:
+        """
+        List the namespaces matching include patterns and no exclude pattern.
+
+        Args:
+            include: Regexes, any of which a namespace must match.
+            exclude: Regexes, none of which a namespace may match.
+
+        Returns:
+            The matching namespace names, sorted.
+        """
+        return sorted(
+            name
+            for name in self.list_namespaces()
+            if any(re.search(p, name) for p in include)
+            and not any(re.search(p, name) for p in exclude)
+        )
+
+    def get_pods_status(self, namespace: str = "default") -> Tuple[float, Dict]:
+        """
+        Get pod status from the cluster.
+
+        Args:
+            namespace: The namespace to query (default: "default")
+
+        Returns:
+            A tuple of (status_index, extra_data)
+        """
+        try:
+            pods = self.v1_core.list_namespaced_pod(
+                namespace=namespace, label_selector=self.label_selector, **self.request_options
+            )
+            if not pods.items:
+                return 0, {"status": "Unknown", "count": 0}
+
+            # Get the first pod's status as representative
+            pod = pods.items[0]
+            status = pod.status.phase
+
+            status_map = SOUND_MAP["pod_status"]["status_map"]
+            status_index = status_map.get(status, 0)
+            return status_index, {
+                "status": status,
+                "count": len(pods.items),
+                "values": [status_map.get(p.status.phase, 0) for p in pods.items],
+                "restarts": sum(
+                    c.restart_count or 0
+                    for p in pods.items
+                    for c in (p.status.container_statuses or [])
+                ),
+            }
+
+        except ApiException as e:
+            if e.status == 403:
+                raise MissingPermission("pods", namespace) from e
+            logger.warning(f"Failed to get pod status: {e}")
+            return 0, {"status": "Unknown", "error": str(e)}
+
+    def restart_rate(self, namespace: str, restarts: int) -> float:
+        """
+        Works out how fast containers restart from the running restart count.
+
+        Args:
+            namespace: The namespace the count is for.
+            restarts: The total container restarts in the namespace now.
+
+        Returns:
+            Restarts per minute since the previous call for the namespace, 0 on the first.
+        """
+        now = time.monotonic()
+        previous = self.restart_counts.get(namespace)
+        self.restart_counts[namespace] = (now, restarts)
+        if previous is None or now <= previous[0]:
+            return 0.0
+        return max(restarts - previous[1], 0) * 60 / (now - previous[0])
+
+    def get_pod_churn(self, namespace: str = "default") -> Tuple[float, Dict]:
+        """
+        Get how fast pods come and go in a namespace: the pods created plus those
+        deleted per minute since the previous call, and the median age of its pods.
+
+        Args:
+            namespace: The namespace to query.
+
+        Returns:
+            A tuple of (churn_per_minute, extra_data) with the pods created and
+            deleted and their median age in minutes; the churn is 0 on the first call.
+        """
+        try:
+            pods = self.v1_core.list_namespaced_pod(
+                namespace=namespace, label_selector=self.label_selector, **self.request_options
+            )
+        except ApiException as e:
+            if e.status == 403:
+                raise MissingPermission("pods", namespace) from e
+            logger.warning(f"Failed to get pod churn: {e}")
+            return 0, {"created": 0, "deleted": 0, "error": str(e)}
+        now = time.monotonic()
+        uids = {pod.metadata.uid for pod in pods.items}
+        previous = self.pod_uids.get(namespace)
+        self.pod_uids[namespace] = (now, uids)
+        created = len(uids - previous[1]) if previous else 0
+        deleted = len(previous[1] - uids) if previous else 0
+        churn = 0.0
+        if previous and now > previous[0]:
+            churn = round((created + deleted) * 60 / (now - previous[0]), 2)
+        wall = datetime.now(timezone.utc).timestamp()
+        ages = sorted(
+            (wall - pod.metadata.creation_timestamp.timestamp()) / 60
+            for pod in pods.items
+            if pod.metadata.creation_timestamp is not None
+        )
+        median = 0.0
+        if ages:
+            middle = len(ages) // 2
+            median = ages[middle] if len(ages) % 2 else (ages[middle - 1] + ages[middle]) / 2
+        return churn, {
+            "created": created, "deleted": deleted, "pods": len(uids),
+            "median_age_minutes": round(median, 1),
+        }
+
+    def get_workload_annotations(self, namespace: str) -> Dict[str, Dict[str, str]]:
+        """
+        Read the sonify.k8s.io/ annotations of the deployments and pods in a namespace.
+
+        Args:
+            namespace: The namespace to query.
+
+        Returns:
+            The annotations, without ANNOTATION_PREFIX, per "deployment/<name>" and
+            "pod/<name>", empty for those without any, or no workloads if they
+            cannot be read.
+        """
+        workloads: Dict[str, Dict[str, str]] = {}
+        listings = (
+            ("deployment", self.v1_apps.list_namespaced_deployment),
+            ("pod", self.v1_core.list_namespaced_pod),
+        )
+        for kind, list_objects in listings:
+            try:
+                objects = list_objects(
+                    namespace=namespace, label_selector=self.label_selector, **self.request_options
+                )
+            except ApiException as e:
+                logger.debug(f"Failed to list {kind}s for their sonify annotations: {e}")
+                continue
+            for obj in objects.items:
+                # Unannotated workloads are kept, so one team can't mute a shared namespace
+                workloads[f"{kind}/{obj.metadata.name}"] = {
+                    key[len(ANNOTATION_PREFIX):]: value
+                    for key, value in (obj.metadata.annotations or {}).items()
+                    if key.startswith(ANNOTATION_PREFIX)
+                }
+        return workloads
+
+    def recent_events(
+        self,
+        namespace: str,
+        field_selector: str,
+        window: float,
+        match: Callable[[Any], bool] = lambda event: True,
+    ) -> Tuple[List[Tuple[Any, int]], int]:
+        """
+        List a namespace's events and keep those that last occurred within a window.
+
+        Args:
+            namespace: The namespace to query.
+            field_selector: The field selector of the events, e.g. "type=Warning".
+            window: How far back to look, in seconds.
+            match: Whether an event counts at all, e.g. by its message.
+
+        Returns:
+            The matching events seen within the window, each with its number of
+            occurrences, and the occurrences of every matching event ever listed, a
+            running count that only grows while the events are kept.
+
+        Raises:
+            ApiException: If the events cannot be listed.
+        """
+        events = self.v1_core.list_namespaced_event(
+            namespace=namespace, field_selector=field_selector, **self.request_options
+        )
+        cutoff = datetime.now(timezone.utc).timestamp() - window
+        recent, total = [], 0
+        for event in events.items:
+            if not match(event):
+                continue
+            total += event.count or 1
+            last = event.last_timestamp or event.event_time
+            if last is None or last.timestamp() >= cutoff:
+                recent.append((event, event.count or 1))
+        return recent, total
+
+    def recent_warnings(self, namespace: str) -> Dict[str, int]:
+        """
+        Count the Warning events of each pod within OFFENDER_WARNING_WINDOW.
+
+        Args:
+            namespace: The namespace to query.
+
+        Returns:
+            The number of warnings per pod name, empty if events cannot be read.
+        """
+        try:
+            events, _ = self.recent_events(
+                namespace, "type=Warning", OFFENDER_WARNING_WINDOW,
+                lambda event: event.involved_object.kind == "Pod",
+            )
+        except ApiException as e:
+            logger.debug(f"Failed to list events, ranking pods without warnings: {e}")
+            return {}
+        warnings: Dict[str, int] = {}
+        for event, count in events:
+            name = event.involved_object.name
+            warnings[name] = warnings.get(name, 0) + count
+        return warnings
+
+    def prometheus_value(self, query: str) -> Optional[float]:
+        """
+        Evaluate an instant PromQL query against prometheus_url.
+
+        Args:
+            query: The query, which should return a single series.
+
+        Returns:
+            The value of its first series, or None if Prometheus is not configured,
+            cannot be queried or returned no series.
+        """
+        if not self.prometheus_url:
+            return None
+        url = f"{self.prometheus_url.rstrip('/')}/api/v1/query?" + urllib.parse.urlencode(
+            {"query": query}
+        )
+        request = urllib.request.Request(url, headers={"Accept": "application/json"})
+        if self.prometheus_token:
+            request.add_header("Authorization", f"Bearer {self.prometheus_token}")
+        try:
+            with urllib.request.urlopen(request, timeout=self.request_timeout or 10) as response:
+                result = json.load(response)["data"]["result"]
+            value = float(result[0]["value"][1]) if result else None
+        except (OSError, ValueError, KeyError, IndexError, TypeError) as e:
+            logger.warning(f"Failed to query Prometheus: {e}")
+            return None
+        return None if value is None or math.isnan(value) else value
+
+    def get_gpu_usage(self, namespace: str = "default") -> Tuple[float, Dict]:
+        """
+        Get the GPU usage of a namespace: the utilization of its GPUs from Prometheus
+        (gpu_query) when configured, otherwise the share of the cluster's allocatable
+        GPU_RESOURCE that its running pods are allocated.
+
+        Args:
+            namespace: The namespace to query.
+
+        Returns:
+            A tuple of (gpu_usage_percent, extra_data) with the allocated GPUs and
+            the source of the value, "dcgm" or "allocation".
+        """
+        try:
+            pods = self.v1_core.list_namespaced_pod(
+                namespace=namespace, label_selector=self.label_selector, **self.request_options
+            )
+        except ApiException as e:
+            if e.status == 403:
+                raise MissingPermission("pods", namespace) from e
+            logger.warning(f"Failed to get GPU allocations: {e}")
+            return 0, {"allocated": 0, "error": str(e)}
+        allocated = sum(
+            int(str(container.resources.limits.get(GPU_RESOURCE, 0)))
+            for pod in pods.items
+            if pod.status.phase == "Running"
+            for container in pod.spec.containers or []
+            if container.resources and container.resources.limits
+        )
+
+        utilization = self.prometheus_value(self.gpu_query.replace("$namespace", namespace))
+        if utilization is not None:
+            return utilization, {"allocated": allocated, "source": "dcgm"}
+
+        try:
+            nodes = self.v1_core.list_node(**self.request_options)
+        except ApiException as e:
+            if e.status == 403:
+                raise MissingPermission("nodes") from e
+            logger.warning(f"Failed to get allocatable GPUs: {e}")
+            return 0, {"allocated": allocated, "error": str(e)}
+        allocatable = sum(
+            int(str((node.status.allocatable or {}).get(GPU_RESOURCE, 0))) for node in nodes.items
+        )
+        usage = 100.0 * allocated / allocatable if allocatable else 0.0
+        return usage, {"allocated": allocated, "allocatable": allocatable, "source": "allocation"}
+
+    def get_cost_rate(self, namespace: str = "default") -> Optional[Tuple[float, Dict]]:
+        """
+        Get a namespace's spend rate from OpenCost: its cost over opencost_window,
+        per hour.
+
+        Args:
+            namespace: The namespace to query.
+
+        Returns:
+            A tuple of (cost_per_hour, extra_data) with the cost over the window,
+            (0, ...) if OpenCost has no costs for the namespace, or None if it cannot
+            be queried.
+        """
+        if not self.opencost_url:
+            logger.warning("cost_per_hour needs opencost.url")
+            return None
+        query = urllib.parse.urlencode({
+            "window": self.opencost_window, "aggregate": "namespace", "accumulate": "true",
+        })
+        url = f"{self.opencost_url.rstrip('/')}/allocation/compute?{query}"
+        request = urllib.request.Request(url, headers={"Accept": "application/json"})
+        try:
+            with urllib.request.urlopen(request, timeout=self.request_timeout or 10) as response:
+                allocations = json.load(response)["data"]
+            allocation = allocations[0].get(namespace) if allocations else None
+        except (OSError, ValueError, KeyError, IndexError, TypeError, AttributeError) as e:
+            logger.warning(f"Failed to query OpenCost: {e}")
+            return None
+        if not allocation:
+            return 0.0, {"cost": 0.0, "window": self.opencost_window}
+        cost = float(allocation.get("totalCost") or 0.0)
+        minutes = float(allocation.get("minutes") or 0.0)
+        rate = cost * 60 / minutes if minutes > 0 else 0.0
+        return round(rate, 4), {"cost": round(cost, 4), "window": self.opencost_window}
+
+    def get_vulnerabilities(self, namespace: str = "default") -> Optional[Tuple[float, Dict]]:
+        """
+        Count the critical and high CVEs in the images running in a namespace, from
+        the VulnerabilityReports trivy-operator keeps for each workload. Reports of
+        workloads without running pods, such as old ReplicaSets, are left out and a
+        CVE found in several images counts once.
+
+        Args:
+            namespace: The namespace to query.
+
+        Returns:
+            A tuple of (critical_count, extra_data) with the high count and the
+            workloads with criticals, or None if the reports cannot be listed (e.g.
+            trivy-operator is not installed).
+        """
+        try:
+            pods = self.v1_core.list_namespaced_pod(
+                namespace=namespace, label_selector=self.label_selector, **self.request_options
+            )
+            reports = self.custom_objects.list_namespaced_custom_object(
+                TRIVY_GROUP, TRIVY_VERSION, namespace, TRIVY_PLURAL, **self.request_options
+            )
+        except ApiException as e:
+            if e.status == 403:
+                raise MissingPermission("pods and vulnerabilityreports", namespace) from e
+            logger.warning(f"Failed to get VulnerabilityReports: {e}")
+            return None
+        running = set()
+        for pod in pods.items:
+            if pod.status.phase != "Running":
+                continue
+            owners = pod.metadata.owner_references or []
+            running.update((owner.kind, owner.name) for owner in owners)
+            if not owners:
+                running.add(("Pod", pod.metadata.name))
+
+        found: Dict[str, set] = {"CRITICAL": set(), "HIGH": set()}
+        workloads = set()
+        for item in reports.get("items", []):
+            labels = item.get("metadata", {}).get("labels") or {}
+            kind = labels.get("trivy-operator.resource.kind")
+            name = labels.get("trivy-operator.resource.name")
+            if (kind, name) not in running:
+                continue
+            for vulnerability in (item.get("report") or {}).get("vulnerabilities") or []:
+                severity = vulnerability.get("severity")
+                if severity in found:
+                    found[severity].add(vulnerability.get("vulnerabilityID"))
+                if severity == "CRITICAL":
+                    workloads.add(f"{kind.lower()}/{name}")
+        return len(found["CRITICAL"]), {
+            "critical": len(found["CRITICAL"]),
+            "high": len(found["HIGH"]),
+            "workloads": sorted(workloads),
+        }
+
+    def get_deprecated_apis(self, namespace: str = "default") -> Optional[Tuple[float, Dict]]:
+        """
+        Find the objects in a namespace last applied with an API version that
+        DEPRECATED_APIS lists for their kind, read from the kubectl apply annotation
+        of each kind of DEPRECATION_RESOURCES. Kinds the cluster does not serve are
+        skipped.
+
+        Args:
+            namespace: The namespace to query.
+
+        Returns:
+            A tuple of (offender_count, extra_data) naming the offenders, or None if
+            none of the resources can be listed.
+        """
+        offenders = []
+        listed = False
+        for group, version, plural, kind in DEPRECATION_RESOURCES:
+            try:
+                objects = self.custom_objects.list_namespaced_custom_object(
+                    group, version, namespace, plural, **self.request_options
+                )
+            except ApiException as e:
+                if e.status == 403:
+                    raise MissingPermission(plural, namespace) from e
+                if e.status != 404:
+                    logger.warning(f"Failed to list {plural}: {e}")
+                continue
+            listed = True
+            for item in objects.get("items", []):
+                metadata = item.get("metadata") or {}
+                annotations = metadata.get("annotations") or {}
+                try:
+                    applied = json.loads(annotations[LAST_APPLIED_ANNOTATION])
+                    api_version = applied["apiVersion"]
+                except (KeyError, ValueError, TypeError):
+                    continue
+                removed = DEPRECATED_APIS.get(api_version, {}).get(kind)
+                if removed:
+                    offenders.append(
+                        f"{kind}/{metadata.get('name')}: {api_version}, removed in {removed}"
+                    )
+        if not listed:
+            return None
+        return len(offenders), {"offenders": sorted(offenders)}
+
+    def get_evictions(self, namespace: str, resource: str) -> Tuple[float, Dict]:
+        """
+        Count the pods the kubelet evicted within EVICTION_WINDOW because their node
+        ran short of a resource.
+
+        Args:
+            namespace: The namespace to query.
+            resource: A key of EVICTION_RESOURCES, "memory" or "disk".
+
+        Returns:
+            A tuple of (eviction_count, extra_data) naming the evicted pods and the
+            nodes whose eviction manager is reclaiming the resource.
+        """
+        try:
+            events, total = self.recent_events(
+                namespace, "reason=Evicted", EVICTION_WINDOW,
+                lambda event: eviction_resource(event.message) == resource,
+            )
+        except ApiException as e:
+            if e.status == 403:
+                raise MissingPermission("events", namespace) from e
+            logger.warning(f"Failed to get evictions: {e}")
+            return 0, {"pods": [], "error": str(e)}
+        pods = sorted({event.involved_object.name for event, _ in events})
+        return sum(count for _, count in events), {
+            "pods": pods, "evictions": total, "reclaiming": self.reclaiming_nodes(resource),
+        }
+
+    def get_admission_failures(self, namespace: str = "default") -> Tuple[float, Dict]:
+        """
+        Count the creates admission webhooks rejected within ADMISSION_WINDOW, from
+        the Warning events of the controllers whose pods or other objects could not
+        be created.
+
+        Args:
+            namespace: The namespace to query.
+
+        Returns:
+            A tuple of (rejection_count, extra_data) with the denied and failed counts
+            and the webhooks that rejected them.
+        """
+        def rejection(event) -> Optional[Tuple[str, str]]:
+            for outcome, pattern in ADMISSION_PATTERNS.items():
+                match = pattern.search(event.message or "")
+                if match:
+                    return outcome, match.group(1)
+            return None
+
+        try:
+            events, total = self.recent_events(
+                namespace, "type=Warning", ADMISSION_WINDOW,
+                lambda event: rejection(event) is not None,
+            )
+        except ApiException as e:
+            if e.status == 403:
+                raise MissingPermission("events", namespace) from e
+            logger.warning(f"Failed to get admission failures: {e}")
+            return 0, {"webhooks": [], "error": str(e)}
+        counts = {outcome: 0 for outcome in ADMISSION_PATTERNS}
+        webhooks = set()
+        for event, count in events:
+            outcome, webhook = rejection(event)
+            counts[outcome] += count
+            webhooks.add(webhook)
+        return sum(counts.values()), dict(counts, webhooks=sorted(webhooks), rejections=total)
+
+    def get_pod_security(self, namespace: str = "default") -> Tuple[float, Dict]:
+        """
+        Count the pods Pod Security admission rejected within ADMISSION_WINDOW, from
+        the Warning events of the controllers that could not create them, plus the
+        running pods that violate the strictest level among the namespace's enforce,
+        warn and audit labels, like warn mode would report.
+
+        Args:
+            namespace: The namespace to query.
+
+        Returns:
+            A tuple of (pod_count, extra_data) with the rejected and warned counts, the
+            level checked and each warned pod's violations. Without permission to get
+            the namespace, only rejections are counted and the level is "unknown".
+        """
+        try:
+            namespace_object = self.v1_core.read_namespace(namespace, **self.request_options)
+            labels = namespace_object.metadata.labels or {}
+        except ApiException as e:
+            if e.status != 403:
+                logger.warning(f"Failed to get Pod Security violations: {e}")
+                return 0, {"rejected": 0, "warned": 0, "error": str(e)}
+            # A namespaced Role can't grant get on namespaces, but events may still be readable
+            logger.debug(f"Pod Security level of {namespace} unknown, can't get the namespace: {e}")
+            labels = None
+        try:
+            events, total = self.recent_events(
+                namespace, "type=Warning", ADMISSION_WINDOW,
+                lambda event: POD_SECURITY_PATTERN.search(event.message or "") is not None,
+            )
+            pods = []
+            if labels is not None:
+                pods = self.v1_core.list_namespaced_pod(
+                    namespace=namespace, label_selector=self.label_selector, **self.request_options
+                ).items
+        except ApiException as e:
+            if e.status == 403:
+                raise MissingPermission("events and pods", namespace) from e
+            logger.warning(f"Failed to get Pod Security violations: {e}")
+            return 0, {"rejected": 0, "warned": 0, "error": str(e)}
+        levels = [(labels or {}).get(POD_SECURITY_LABEL + mode) for mode in POD_SECURITY_MODES]
+        level = max(
+            (level for level in levels if level in POD_SECURITY_LEVELS),
+            key=POD_SECURITY_LEVELS.index,
+            default="privileged" if labels is not None else "unknown",
+        )
+        rejected = sum(count for _, count in events)
+        warned = {}
+        for pod in pods:
+            if pod.status.phase != "Running":
+                continue
+            violations = pod_security_violations(pod, level)
+            if violations:
+                warned[pod.metadata.name] = violations
+        return rejected + len(warned), {
+            "rejected": rejected, "warned": len(warned), "level": level, "pods": warned,
+            "rejections": total,
+        }
+
+    def get_pending_volumes(self, namespace: str = "default") -> Tuple[List[Dict], int]:
+        """
+        Find the PersistentVolumeClaims stuck Pending because their volume failed to
+        provision or bind, i.e. that have one of PROVISIONING_REASONS' events.
+
+        Args:
+            namespace: The namespace to query.
+
+        Returns:
+            The claims, oldest first, as dicts of their name, storage class, minutes
+            Pending and last failure message, and the number of failure events.
+        """
+        try:
+            claims = self.v1_core.list_namespaced_persistent_volume_claim(
+                namespace=namespace, **self.request_options
+            )
+            events = self.v1_core.list_namespaced_event(
+                namespace=namespace,
+                field_selector="involvedObject.kind=PersistentVolumeClaim",
+                **self.request_options,
+            )
+        except ApiException as e:
+            if e.status == 403:
+                raise MissingPermission("persistentvolumeclaims and events", namespace) from e
+            logger.warning(f"Failed to get pending PersistentVolumeClaims: {e}")
+            return [], 0
+        failures, messages = 0, {}
+        for event in events.items:
+            if event.reason in PROVISIONING_REASONS:
+                failures += event.count or 1
+                messages[event.involved_object.name] = event.message
+        now = datetime.now(timezone.utc).timestamp()
+        stuck = [
+            {
+                "name": claim.metadata.name,
+                "storage_class": claim.spec.storage_class_name or "",
+                "minutes": round((now - claim.metadata.creation_timestamp.timestamp()) / 60, 1),
+                "error": messages[claim.metadata.name],
+            }
+            for claim in claims.items
+            if claim.status.phase == "Pending" and claim.metadata.name in messages
+        ]
+        return sorted(stuck, key=lambda claim: claim["minutes"], reverse=True), failures
+
+    def reclaiming_nodes(self, resource: str) -> List[str]:
+        """
+        Name the nodes whose eviction manager met an eviction threshold for a resource
+        within EVICTION_WINDOW, so is evicting pods to reclaim it.
+
+        Args:
+            resource: A key of EVICTION_RESOURCES.
+
+        Returns:
+            The node names, empty if events cannot be read.
+        """
+        try:
+            events = self.v1_core.list_event_for_all_namespaces(
+                field_selector="reason=EvictionThresholdMet", **self.request_options
+            )
+        except ApiException as e:
+            logger.debug(f"Failed to list eviction manager events: {e}")
+            return []
+        cutoff = datetime.now(timezone.utc).timestamp() - EVICTION_WINDOW
+        nodes = set()
+        for event in events.items:
+            last = event.last_timestamp or event.event_time
+            if last is not None and last.timestamp() < cutoff:
+                continue
+            if event.involved_object.kind == "Node" and eviction_resource(event.message) == resource:
+                nodes.add(event.involved_object.name)
+        return sorted(nodes)
+
+    def get_top_offenders(self, namespace: str = "default", count: int = 3) -> Tuple[float, Dict]:
+        """
+        Find the pods most worth looking at: the most restarts plus recent warnings,
+        then the highest CPU requests.
+
+        Args:
+            namespace: The namespace to query.
+            count: How many pods to name.
+
+        Returns:
+            A tuple of (worst_score, extra_data) naming the pods, worst first.
+        """
+        try:
+            pods = self.v1_core.list_namespaced_pod(
+                namespace=namespace, label_selector=self.label_selector, **self.request_options
+            )
+        except ApiException as e:
+            if e.status == 403:
+                raise MissingPermission("pods", namespace) from e
+            logger.warning(f"Failed to get top offenders: {e}")
+            return 0, {"offenders": [], "error": str(e)}
+
+        warnings = self.recent_warnings(namespace)
+        ranked = []
+        for pod in pods.items:
+            restarts = sum(
+                c.restart_count or 0 for c in (pod.status.container_statuses or [])
+            )
+            cpu = sum(
+                parse_cpu(str(c.resources.requests.get("cpu", "0")))
+                for c in (pod.spec.containers or [])
+                if c.resources and c.resources.requests
+            )
+            name = pod.metadata.name
+            score = restarts + warnings.get(name, 0)
+            ranked.append((score, cpu, name, restarts))
+        ranked.sort(key=lambda pod: (pod[0], pod[1]), reverse=True)
+        offenders = [
+            {"pod": name, "score": score, "restarts": restarts,
+             "warnings": warnings.get(name, 0), "cpu_request": cpu}
+            for score, cpu, name, restarts in ranked[:count]
+            if score > 0
+        ]
+        scores = [offender["score"] for offender in offenders]
+        return (scores[0] if scores else 0), {"offenders": offenders, "values": scores}
+
+    def get_deployment_replicas(self, namespace: str = "default") -> Tuple[float, Dict]:
+        """
+        Get replica count from deployments.
+
+        Args:
+            namespace: The namespace to query (default: "default")
+
+        Returns:
+            A tuple of (replica_count, extra_data)
+        """
+        try:
+            deployments = self.v1_apps.list_namespaced_deployment(
+                namespace=namespace, label_selector=self.label_selector, **self.request_options
+            )
+            if not deployments.items:
+                return 1, {"replicas": 1, "deployments": 0}
+
+            # Get average replica count
+            total_replicas = sum(d.spec.replicas or 0 for d in deployments.items)
+            avg_replicas = total_replicas / len(deployments.items) if deployments.items else 1
+
+            return avg_replicas, {
+                "replicas": int(avg_replicas),
+                "deployments": len(deployments.items),
+                "values": [d.spec.replicas or 0 for d in deployments.items],
+                "generations": sum(d.metadata.generation or 0 for d in deployments.items),
+                "rollouts": {d.metadata.name: rollout_state(d) for d in deployments.items},
+                "rollout_generations": {
+                    d.metadata.name: d.metadata.generation or 0 for d in deployments.items
+                },
+            }
+
+        except ApiException as e:
+            if e.status == 403:
+                raise MissingPermission("deployments", namespace) from e
+            logger.warning(f"Failed to get deployment replicas: {e}")
+            return 1, {"replicas": 1, "error": str(e)}
+
+    def get_deployment_readiness(self, namespace: str = "default") -> Tuple[float, Dict]:
+        """
+        Get each Deployment's ready replicas as a share of its desired replicas, so a
+        dead Deployment doesn't hide among healthy ones as it does in replicas.
+
+        Args:
+            namespace: The namespace to query.
+
+        Returns:
+            A tuple of (least_ready_percent, extra_data) with a row per Deployment in
+            name order, e.g. "web 2/3", and its readiness; scaled to zero counts as
+            ready. The sour ones have no replica ready.
+        """
+        try:
+            deployments = self.v1_apps.list_namespaced_deployment(
+                namespace=namespace, label_selector=self.label_selector, **self.request_options
+            )
+        except ApiException as e:
+            if e.status == 403:
+                raise MissingPermission("deployments", namespace) from e
+            logger.warning(f"Failed to get deployment readiness: {e}")
+            return 100.0, {"rows": [], "values": [], "error": str(e)}
+        rows, values, sour = [], [], []
+        for deployment in sorted(deployments.items, key=lambda d: d.metadata.name):
+            desired = deployment.spec.replicas or 0
+            ready = deployment.status.ready_replicas or 0
+            rows.append(f"{deployment.metadata.name} {ready}/{desired}")
+            values.append(round(min(100.0, 100.0 * ready / desired), 1) if desired else 100.0)
+            sour.append(desired > 0 and ready == 0)
+        return (min(values) if values else 100.0), {"rows": rows, "values": values, "sour": sour}
+
+    def get_apiserver_latency(self) -> Tuple[float, Dict]:
+        """
+        Get the mean time the API server took to answer the requests made since the
+        last call, or to list a single namespace if none were made.
+
+        Returns:
+            A tuple of (latency_ms, extra_data) with the number of requests and the
+            slowest one.
+        """
+        with self._latency_lock:
+            latencies, self.latencies = self.latencies, []
+        if not latencies:
+            started = time.monotonic()
+            try:
+                self.v1_core.list_namespace(limit=1, **self.request_options)
+            except ApiException as e:
+                # An error answer still took the API server this long
+                logger.debug(f"API server latency probe failed: {e}")
+            elapsed = time.monotonic() - started
+            with self._latency_lock:
+                latencies, self.latencies = self.latencies or [elapsed], []
+        return round(1000 * sum(latencies) / len(latencies), 1), {
+            "requests": len(latencies), "slowest_ms": round(1000 * max(latencies), 1),
+        }
+
+    def get_node_pressure(self) -> Tuple[float, Dict]:
+        """
+        Check if any nodes are under pressure.
+
+        Returns:
+            A tuple of (pressure_level, extra_data) with the running counts of the
+            nodes that "joined" and were "lost" since the first call.
+        """
+        try:
+            nodes = self.v1_core.list_node(**self.request_options)
+            names = {node.metadata.name for node in nodes.items}
+            if self.node_names is not None:
+                # A replaced node both joined and was lost
+                self.node_changes["joined"] += len(names - self.node_names)
+                self.node_changes["lost"] += len(self.node_names - names)
+            self.node_names = names
+            lifecycle = {
+                "nodes": len(nodes.items), **self.node_changes, **self.autoscaler_activity(),
+            }
+            if not nodes.items:
+                return 0, {"pressure": "False", **lifecycle}
+
+            # Check for any node pressure conditions
+            pressure_types = ["MemoryPressure", "DiskPressure", "PIDPressure", "NetworkUnavailable"]
+            has_pressure = False
+
+            for node in nodes.items:
+                if node.status.conditions:
+                    for condition in node.status.conditions:
+                        if condition.type in pressure_types and condition.status == "True":
+                            has_pressure = True
+                            break
+                if has_pressure:
+                    break
+
+            pressure_level = 1 if has_pressure else 0
+            return pressure_level, {"pressure": str(has_pressure), **lifecycle}
+
+        except ApiException as e:
+            if e.status == 403:
+                raise MissingPermission("nodes") from e
+            logger.warning(f"Failed to get node pressure: {e}")
+            return 0, {"pressure": "Unknown", "error": str(e)}
+
+    def autoscaler_activity(self) -> Dict[str, int]:
+        """
+        Count the cluster-autoscaler's scale-up and scale-down Events, by the
+        AUTOSCALER_REASONS of their reason.
+
+        Returns:
+            "scale_ups" and "scale_downs", empty if events cannot be read.
+        """
+        try:
+            events = self.v1_core.list_event_for_all_namespaces(
+                field_selector="source=cluster-autoscaler", **self.request_options
+            )
+        except ApiException as e:
+            logger.debug(f"Failed to list cluster-autoscaler events: {e}")
+            return {}
+        activity = {"scale_ups": 0, "scale_downs": 0}
+        for event in events.items:
+            if event.reason in AUTOSCALER_REASONS:
+                activity[AUTOSCALER_REASONS[event.reason]] += event.count or 1
+        return activity
+
+    def get_node_loads(self) -> Tuple[float, Dict]:
+        """
+        Get the load of every node: the CPU requested by its pods as a share of its
+        allocatable CPU. Nodes under pressure are marked sour.
+
+        Returns:
+            A tuple of (busiest_load, extra_data) with the per-node loads in name order.
+        """
+        try:
+            nodes = self.v1_core.list_node(**self.request_options)
+        except ApiException as e:
+            if e.status == 403:
+                raise MissingPermission("nodes") from e
+            logger.warning(f"Failed to get node loads: {e}")
+            return 0, {"nodes": [], "error": str(e)}
+        try:
+            pods = self.v1_core.list_pod_for_all_namespaces(
+                field_selector="status.phase=Running", **self.request_options
+            ).items
+        except ApiException as e:
+            if e.status == 403:
+                raise MissingPermission("pods") from e
+            logger.warning(f"Failed to list pods, node loads will read 0: {e}")
+            pods = []
+
+        requested: Dict[str, float] = {}
+        for pod in pods:
+            cpu = sum(
+                parse_cpu(str(c.resources.requests.get("cpu", "0")))
+                for c in (pod.spec.containers or [])
+                if c.resources and c.resources.requests
+            )
+            requested[pod.spec.node_name] = requested.get(pod.spec.node_name, 0.0) + cpu
+
+        pressure_types = ["MemoryPressure", "DiskPressure", "PIDPressure", "NetworkUnavailable"]
+        names, loads, sour = [], [], []
+        for node in sorted(nodes.items, key=lambda n: n.metadata.name):
+            name = node.metadata.name
+            allocatable = parse_cpu(str((node.status.allocatable or {}).get("cpu", "0")))
+            load = min(100.0, requested.get(name, 0.0) / allocatable * 100) if allocatable else 0.0
+            names.append(name)
+            loads.append(load)
+            sour.append(any(
+                c.type in pressure_types and c.status == "True"
+                for c in (node.status.conditions or [])
+            ))
+        return (max(loads) if loads else 0), {"nodes": names, "values": loads, "sour": sour}
+
+    def get_scheduling_headroom(self) -> Tuple[float, Dict]:
+        """
+        Get the cluster's scheduling headroom: the share of the schedulable nodes'
+        allocatable CPU or memory, whichever is scarcer, not yet requested by the
+        pods bound to them.
+
+        Returns:
+            A tuple of (headroom_percent, extra_data) with the requested share of
+            each resource and the scarcer one.
+        """
+        try:
+            nodes = self.v1_core.list_node(**self.request_options)
+            pods = self.v1_core.list_pod_for_all_namespaces(
+                field_selector="status.phase!=Succeeded,status.phase!=Failed",
+                **self.request_options,
+            )
+        except ApiException as e:
+            if e.status == 403:
+                raise MissingPermission("nodes and pods") from e
+            logger.warning(f"Failed to get scheduling headroom: {e}")
+            return 0, {"error": str(e)}
+        parsers = {"cpu": parse_cpu, "memory": parse_memory}
+        schedulable = {
+            node.metadata.name: node.status.allocatable or {}
+            for node in nodes.items
+            if not (node.spec and node.spec.unschedulable)
+        }
+        allocatable = {
+            resource: sum(parse(str(a.get(resource, "0"))) for a in schedulable.values())
+            for resource, parse in parsers.items()
+        }
+        requested = dict.fromkeys(parsers, 0.0)
+        for pod in pods.items:
+            if pod.spec.node_name not in schedulable:
+                continue
+            for container in pod.spec.containers or []:
+                requests = (container.resources.requests if container.resources else None) or {}
+                for resource, parse in parsers.items():
+                    requested[resource] += parse(str(requests.get(resource, "0")))
+        shares = {
+            resource: round(min(100.0, 100 * requested[resource] / allocatable[resource]), 1)
+            if allocatable[resource] else 100.0
+            for resource in parsers
+        }
+        scarcer = max(shares, key=shares.get)
+        return round(100 - shares[scarcer], 1), {
+            "cpu_requested": shares["cpu"], "memory_requested": shares["memory"],
+            "scarcer": scarcer, "nodes": len(schedulable),
+        }
+
+    def get_resource_usage(self, namespace: str = "default") -> Tuple[float, float]:
+        """
+        Get approximate CPU and memory usage.
+        Note: This requires metrics-server to be installed in the cluster.
+        Falls back to requested resources if metrics are unavailable.
+
+        Args:
+            namespace: The namespace to query (default: "default")
+
+        Returns:
+            A tuple of (cpu_usage_percent, memory_usage_percent)
+        """
+        try:
+            # Try to get actual metrics from metrics-server
+            # This requires metrics-server API to be available
+            pods = self.v1_core.list_namespaced_pod(
+                namespace=namespace, label_selector=self.label_selector, **self.request_options
+            )
+
+            if not pods.items:
+                return 0.0, 0.0
+
+            # Calculate based on resource requests as a proxy
+            # In a real implementation, you would query metrics-server API
+            total_cpu_req = 0.0
+            total_mem_req = 0.0
+            pod_count = 0
+
+            for pod in pods.items:
+                if pod.spec.containers:
+                    for container in pod.spec.containers:
+                        if container.resources and container.resources.requests:
+                            cpu_req = container.resources.requests.get("cpu", "0")
+                            mem_req = container.resources.requests.get("memory", "0")
+
+                            if isinstance(cpu_req, str):
+                                total_cpu_req += parse_cpu(cpu_req)
+
+                            # Parse memory (simplified)
+                            if isinstance(mem_req, str):
+                                if mem_req.endswith("Mi"):
+                                    total_mem_req += float(mem_req[:-2])
+                                elif mem_req.endswith("Gi"):
+                                    total_mem_req += float(mem_req[:-2]) * 1024
+
+                            pod_count += 1
+
+            # Estimate usage as a percentage (rough approximation)
+            cpu_usage = min((total_cpu_req / max(pod_count, 1)) * 20, 100)
+            mem_usage = min((total_mem_req / max(pod_count, 1)) / 10, 100)
+
+            return cpu_usage, mem_usage
+
+        except ApiException as e:
+            if e.status == 403:
+                raise MissingPermission("pods", namespace) from e
+            logger.warning(f"Failed to get resource usage: {e}")
+            # Return moderate values to indicate some activity
+            return 30.0, 40.0
+
+
+# Global K8s client instance
+k8s_client = K8sClient()
+
+
+def configure_k8s_client(cfg: Dict) -> None:
+    """
+    Points k8s_client at the services some metrics are read from.
+
+    Args:
+        cfg: The configuration, whose "prometheus" and "opencost" sections are used.
+    """
+    k8s_client.prometheus_url = cfg["prometheus"]["url"]
+    k8s_client.prometheus_token = read_secret(
+        cfg["prometheus"]["token"], cfg["prometheus"]["token_file"]
+    )
+    k8s_client.gpu_query = cfg["prometheus"]["gpu_query"]
+    k8s_client.opencost_url = cfg["opencost"]["url"]
+    k8s_client.opencost_window = cfg["opencost"]["window"]
+
+
+def eviction_resource(message: Optional[str]) -> Optional[str]:
+    """
+    Works out which resource an eviction or eviction threshold event is about.
+
+    Args:
+        message: The event message, e.g. "The node was low on resource: memory.".
+
+    Returns:
+        A key of EVICTION_RESOURCES, or None if the message names none of them.
+    """
+    words = re.findall(r"[a-z-]+", (message or "").lower())
+    for resource, names in EVICTION_RESOURCES.items():
+        if any(name in words for name in names):
+            return resource
+    return None
+
+
+def pod_security_violations(pod, level: str) -> List[str]:
+    """
+    Checks a pod against a Pod Security Standard: for baseline its host namespaces,
+    hostPath volumes, host ports, privileged containers and added capabilities, and
+    for restricted also privilege escalation, running as root, dropping ALL
+    capabilities and the seccomp profile. The less common checks are not made.
+
+    Args:
+        pod: The pod, a kubernetes V1Pod.
+        level: One of POD_SECURITY_LEVELS.
+
+    Returns:
+        The fields that violate the level, e.g. ["hostNetwork", "runAsNonRoot"].
+    """
+    if level not in POD_SECURITY_LEVELS[1:]:
+        return []
+    spec = pod.spec
+    pod_context = spec.security_context
+    violations = [
+        name for name, value in (
+            ("hostNetwork", spec.host_network), ("hostPID", spec.host_pid),
+            ("hostIPC", spec.host_ipc),
+        ) if value
+    ]
+    if any(volume.host_path for volume in spec.volumes or []):
+        violations.append("hostPath")
+    allowed = BASELINE_CAPABILITIES if level == "baseline" else {"NET_BIND_SERVICE"}
+    for container in (spec.init_containers or []) + (spec.containers or []):
+        context = container.security_context
+        capabilities = context.capabilities if context else None
+        checks = {
+            "privileged": bool(context and context.privileged),
+            "hostPorts": any(port.host_port for port in container.ports or []),
+            "capabilities": bool(capabilities and set(capabilities.add or []) - allowed),
+        }
+        if level == "restricted":
+            seccomp = (context and context.seccomp_profile) or (
+                pod_context and pod_context.seccomp_profile
+            )
+            non_root = context.run_as_non_root if context else None
+            if non_root is None and pod_context:
+                non_root = pod_context.run_as_non_root
+            checks.update({
+                "allowPrivilegeEscalation": not context
+                or context.allow_privilege_escalation is not False,
+                "runAsNonRoot": not non_root,
+                "capabilities": checks["capabilities"]
+                or not (capabilities and "ALL" in (capabilities.drop or [])),
+                "seccompProfile": not seccomp
+                or seccomp.type not in ("RuntimeDefault", "Localhost"),
+            })
+        violations.extend(name for name, bad in checks.items() if bad and name not in violations)
+    return violations
+
+
+def cluster_health(components: Dict[str, Optional[float]]) -> float:
+    """
+    Combines the healthy share of each part of the cluster into one score, using
+    the weights in SOUND_MAP["cluster_health"].
+
+    Args:
+        components: The healthy share (0.0 - 1.0) of each of HEALTH_COMPONENTS.
+            A component that is None is unknown and left out.
+
+    Returns:
+        The score from 0 (everything failing) to 100 (all healthy).
+    """
+    weights = SOUND_MAP["cluster_health"]["weights"]
+    known = {name: share for name, share in components.items() if share is not None}
+    total = sum(weights.get(name, 0) for name in known)
+    if not total:
+        return 100.0
+    return 100 * sum(weights.get(name, 0) * share for name, share in known.items()) / total
+
+
+# --- Kubernetes Interaction ---
+def get_k8s_data(metric: str, namespace: str = "default") -> Optional[Tuple[float, Dict]]:
+    """
+    Fetches real Kubernetes data for a given metric.
+
+    Args:
+        metric: The name of the metric to fetch.
+        namespace: The Kubernetes namespace to query (default: "default")
+
+    Returns:
+        A tuple containing the metric value and an optional dictionary of extra data,
+        or None if the metric is invalid or client is not initialized.
+
+    Raises:
+        MissingPermission: If the service account may not read the metric's resources.
+    """
+    if not k8s_client.initialized:
+        logger.warning("Kubernetes client not initialized, skipping metric fetch")
+        return None
+
+    try:
+        if metric == "cpu_usage":
+            cpu, _ = k8s_client.get_resource_usage(namespace)
+            return cpu, {"namespace": namespace}
+
+        elif metric == "memory_usage":
+            _, memory = k8s_client.get_resource_usage(namespace)
+            return memory, {"namespace": namespace}
+
+        elif metric == "pod_status":
+            return k8s_client.get_pods_status(namespace)
+
+        elif metric == "http_latency":
+            # This would require custom metrics or service mesh integration
+            # For now, return a simulated value based on pod health
+            status_idx, _ = k8s_client.get_pods_status(namespace)
+            # Healthy pods = lower latency
+            latency = 50 + (3 - status_idx) * 100
+            return latency, {"estimated": True}
+
+        elif metric == "errors_per_second":
+            # This would require custom metrics or logging integration
+            # For now, estimate based on pod failures
+            status_idx, data = k8s_client.get_pods_status(namespace)
+            errors = 0 if data.get("status") in ["Running", "Succeeded"] else 5
+            return float(errors), {"estimated": True}
+
+        elif metric == "replicas":
+            return k8s_client.get_deployment_replicas(namespace)
+
+        elif metric == "deployment_readiness":
+            return k8s_client.get_deployment_readiness(namespace)
+
+        elif metric == "pod_churn":
+            return k8s_client.get_pod_churn(namespace)
+
+        elif metric == "node_pressure":
+            return k8s_client.get_node_pressure()
+
+        elif metric == "apiserver_latency":
+            return k8s_client.get_apiserver_latency()
+
+        elif metric == "node_melody":
+            return k8s_client.get_node_loads()
+
+        elif metric == "scheduling_headroom":
+            return k8s_client.get_scheduling_headroom()
+
+        elif metric == "cost_per_hour":
+            return k8s_client.get_cost_rate(namespace)
+
+        elif metric == "gpu_usage":
+            return k8s_client.get_gpu_usage(namespace)
+
+        elif metric == "critical_cves":
+            return k8s_client.get_vulnerabilities(namespace)
+
+        elif metric == "deprecated_apis":
+            return k8s_client.get_deprecated_apis(namespace)
+
+        elif metric in ("memory_evictions", "disk_evictions"):
+            return k8s_client.get_evictions(namespace, metric.split("_")[0])
+
+        elif metric == "admission_failures":
+            return k8s_client.get_admission_failures(namespace)
+
+        elif metric == "pod_security":
+            return k8s_client.get_pod_security(namespace)
+
+        elif metric in ("pending_volumes", "pending_volume_age"):
+            claims, failures = k8s_client.get_pending_volumes(namespace)
+            value = len(claims) if metric == "pending_volumes" else (
+                claims[0]["minutes"] if claims else 0.0
+            )
+            return value, {"claims": claims, "failures": failures}
+
+        elif metric == "top_offenders":
+            return k8s_client.get_top_offenders(namespace, SOUND_MAP["top_offenders"]["count"])
+
+        elif metric == "cluster_health":
+            _, pods = k8s_client.get_pods_status(namespace)
+            healthy = max(SOUND_MAP["pod_status"]["status_map"].values())
+            phases = pods.get("values") or []
+            try:
+                pressure: Optional[float] = 1 - k8s_client.get_node_pressure()[0]
+            except MissingPermission:
+                pressure = None  # Score the rest rather than losing the whole metric
+            errors = 0.0 if pods.get("status") in ["Running", "Succeeded"] else 5.0
+            rate = k8s_client.restart_rate(namespace, pods.get("restarts", 0))
+            max_rate = SOUND_MAP["cluster_health"]["max_restart_rate"]
+            components = {
+                "readiness": phases.count(healthy) / len(phases) if phases else 1.0,
+                "node_pressure": pressure,
+                "restarts": max(0.0, 1 - rate / max_rate),
+                "errors": 1 - metric_level("errors_per_second", errors),
+            }
+            return cluster_health(components), {"components": components}
+
+        else:
+            logger.warning(f"Unknown metric: {metric}")
+            return None
+
+    except MissingPermission:
+        raise
+    except Exception as e:
+        logger.error(f"Error fetching metric {metric}: {e}")
+        return None
+
+
+# --- Derived Metrics ---
+EXPRESSION_OPERATORS = {
+    ast.Add: lambda a, b: a + b,
+    ast.Sub: lambda a, b: a - b,
+    ast.Mult: lambda a, b: a * b,
+    ast.Div: lambda a, b: a / b,
+    ast.FloorDiv: lambda a, b: a // b,
+    ast.Mod: lambda a, b: a % b,
+    # As floats, so a huge power such as 9**9**9 overflows at once rather than hangs
+    ast.Pow: lambda a, b: float(a) ** b,
+    ast.USub: lambda a: -a,
+    ast.UAdd: lambda a: +a,
+}
+
+
+@functools.lru_cache(maxsize=None)
+def parse_expression(source: str) -> ast.AST:
+    """
+    Parses a derived metric's expression, e.g. "errors_per_second / max(replicas, 1)".
+
+    Only numbers, metric names, arithmetic and calls to EXPRESSION_FUNCTIONS are
+    allowed, so configuration files can't run arbitrary code. Each expression is
+    parsed once, by validate_config(), and the tree reused on every tick.
+
+    Args:
+        source: The expression.
+
+    Returns:
+        The parsed expression.
+
+    Raises:
+        ValueError: If the expression is invalid or uses anything else.
+    """
+    try:
+        tree = ast.parse(str(source), mode="eval")
+    except SyntaxError as e:
+        raise ValueError(f"invalid expression '{source}': {e.msg}")
+    for node in ast.walk(tree):
+        if isinstance(node, ast.Call):
+            if not isinstance(node.func, ast.Name) or node.func.id not in EXPRESSION_FUNCTIONS:
+                raise ValueError(
+                    f"'{ast.unparse(node.func)}' is not one of {', '.join(EXPRESSION_FUNCTIONS)}"
+                )
+            if node.keywords:
+                raise ValueError(f"keyword arguments are not allowed in '{source}'")
+        elif isinstance(node, ast.Constant):
+            if not is_number(node.value):
+                raise ValueError(f"only numbers are allowed, not {node.value!r}")
+        elif not isinstance(
+            node, (ast.Expression, ast.BinOp, ast.UnaryOp, ast.Name, ast.Load)
+        ) and type(node) not in EXPRESSION_OPERATORS:
+            raise ValueError(f"'{ast.unparse(node)}' is not allowed in an expression")
+    return tree
+
+
+def expression_metrics(tree: ast.AST) -> List[str]:
+    """
+    Lists the metrics an expression reads.
+
+    Args:
+        tree: The expression, see parse_expression().
+
+    Returns:
+        The metric names, in order of first use.
+    """
+    functions = {id(node.func) for node in ast.walk(tree) if isinstance(node, ast.Call)}
+    names = [
+        node.id
+        for node in ast.walk(tree)
+        if isinstance(node, ast.Name) and id(node) not in functions
+    ]
+    return list(dict.fromkeys(names))
+
+
+def evaluate_expression(
+    tree: ast.AST, lookup: Callable[[str], Optional[float]]
+) -> Optional[float]:
+    """
+    Evaluates a parsed expression.
+
+    Args:
+        tree: The expression, see parse_expression().
+        lookup: Returns a metric's value for this tick, or None if it is unavailable.
+
+    Returns:
+        The value, or None if a metric it reads is unavailable or the arithmetic fails,
+        e.g. on a division by zero.
+    """
+
+    def evaluate(node: ast.AST) -> float:
+        if isinstance(node, ast.Expression):
+            return evaluate(node.body)
+        if isinstance(node, ast.Constant):
+            return node.value
+        if isinstance(node, ast.Name):
+            value = lookup(node.id)
+            if value is None:
+                raise LookupError(node.id)
+            return value
+        if isinstance(node, ast.UnaryOp):
+            return EXPRESSION_OPERATORS[type(node.op)](evaluate(node.operand))
+        if isinstance(node, ast.BinOp):
+            return EXPRESSION_OPERATORS[type(node.op)](evaluate(node.left), evaluate(node.right))
+        return EXPRESSION_FUNCTIONS[node.func.id](*[evaluate(arg) for arg in node.args])
+
+    try:
+        return float(evaluate(tree))
+    except LookupError as e:
+        logger.debug(f"Expression needs unavailable metric {e}")
+    except (ArithmeticError, ValueError, TypeError) as e:
+        logger.warning(f"Failed to evaluate '{ast.unparse(tree)}': {e}")
+    return None
+
+
+# --- Metric Sources ---
+class MetricSource:
+    """Where metric samples come from; the main loop asks it for one tick at a time."""
+
+    def connect(self) -> bool:
+        """
+        Prepare the source for fetching.
+
+        Returns:
+            True if the source is ready, False otherwise.
+        """
+        return True
+
+    def next_tick(self) -> bool:
+        """
+        Advance to the next tick.
+
+        Returns:
+            False once the source has no more samples.
+        """
+        return True
+
+    def fetch(self, metric: str, namespace: str = "default") -> Optional[Tuple[float, Dict]]:
+        """
+        Fetch a metric for the current tick.
+
+        Args:
+            metric: The name of the metric to fetch.
+            namespace: The Kubernetes namespace to query.
+
+        Returns:
+            A tuple of (value, extra_data), or None if unavailable.
+        """
+        raise NotImplementedError
+
+    def annotations(self, namespace: str = "default") -> Dict[str, Dict[str, str]]:
+        """
+        Fetch the sound settings app teams annotated their workloads with.
+
+        Args:
+            namespace: The Kubernetes namespace to query.
+
+        Returns:
+            The settings per workload, see K8sClient.get_workload_annotations().
+            Sources without workloads have none.
+        """
+        return {}
+
+    def namespaces(self, include: List[str], exclude: List[str]) -> Optional[List[str]]:
+        """
+        Discover the namespaces to monitor.
+
+        Args:
+            include: Regular expressions, one of which a namespace must match.
+            exclude: Regular expressions, none of which a namespace may match.
+
+        Returns:
+            The matching namespace names, or None if the source has no namespaces to discover.
+        """
+        return None
+
+    def sonification_profiles(self) -> Optional[List[Dict]]:
+        """
+        List the SonificationProfile custom resources configuring the namespaces.
+
+        Returns:
+            The resources as dicts, or None if the source has none or they cannot be listed.
+        """
+        return None
+
+    def close(self) -> None:
+        """Release any resources held by the source."""
+
+
+class KubernetesSource(MetricSource):
+    """Fetches live metrics from the Kubernetes cluster."""
+
+    def connect(self) -> bool:
+        if not k8s_client.initialize():
+            logger.error("Failed to connect to Kubernetes cluster. Please check your configuration.")
+            logger.error("Make sure kubectl is configured or set USE_KUBE_CONFIG=true environment variable.")
+            return False
+        return True
+
+    def fetch(self, metric: str, namespace: str = "default") -> Optional[Tuple[float, Dict]]:
+        return get_k8s_data(metric, namespace=namespace)
+
+    def annotations(self, namespace: str = "default") -> Dict[str, Dict[str, str]]:
+        return k8s_client.get_workload_annotations(namespace)
+
+    def namespaces(self, include: List[str], exclude: List[str]) -> Optional[List[str]]:
+        return k8s_client.discover_namespaces(include, exclude)
+
+    def sonification_profiles(self) -> Optional[List[Dict]]:
+        return k8s_client.list_sonification_profiles()
+
+
+def workload_sound_settings(workloads: Dict[str, Dict[str, str]]) -> Dict[str, Dict]:
+    """
+    Reads the sonify.k8s.io/ annotations of each of a namespace's workloads.
+    Invalid values are logged and ignored.
+
+    Args:
+        workloads: The annotations per workload, without ANNOTATION_PREFIX, e.g.
+            {"deployment/web": {"instrument": "pluck", "priority": "high"}}.
+
+    Returns:
+        Per workload, any of "voice", "priority" (kept below CRITICAL_PRIORITY either
+        way, so a critical value still outranks it), "muted" and "metrics" (the
+        metrics its settings are limited to, from a comma-separated "metrics"
+        annotation). See metric_sound_settings().
+    """
+    settings: Dict[str, Dict] = {}
+    for workload, annotations in sorted(workloads.items()):
+        workload_settings: Dict = {}
+        instrument = annotations.get("instrument")
+        if instrument is not None:
+            if instrument not in VOICES:
+                logger.warning(f"{workload}: {ANNOTATION_PREFIX}instrument must be one of "
+                               f"{', '.join(VOICES)}, not {instrument!r}")
+            else:
+                workload_settings["voice"] = {"type": instrument}
+        priority = annotations.get("priority")
+        if priority is not None:
+            level = ANNOTATION_PRIORITIES.get(priority.lower())
+            if level is None and re.fullmatch(r"-?\d+", priority.strip()):
+                limit = CRITICAL_PRIORITY - 1
+                level = max(-limit, min(limit, int(priority)))
+            if level is None:
+                logger.warning(f"{workload}: {ANNOTATION_PREFIX}priority must be low, normal, "
+                               f"high or an integer, not {priority!r}")
+            else:
+                workload_settings["priority"] = level
+        if annotations.get("mute", "").lower() == "true":
+            workload_settings["muted"] = True
+        if annotations.get("metrics"):
+            workload_settings["metrics"] = {
+                m.strip() for m in annotations["metrics"].split(",") if m.strip()
+            }
+        settings[workload] = workload_settings
+    return settings
+
+
+def metric_sound_settings(workloads: Dict[str, Dict], metric_name: str) -> Dict:
+    """
+    Combines the settings of the workloads that apply to a metric: those whose
+    "metrics" include it, and those without "metrics".
+
+    The highest priority wins, the instrument comes from the first workload (by kind
+    and name) that sets one, and the metric is only muted when every one of them,
+    annotated or not, asks for it.
+
+    Args:
+        workloads: The settings per workload, as returned by workload_sound_settings().
+        metric_name: The name of the metric.
+
+    Returns:
+        Any of "voice", "priority" and "muted".
+    """
+    applying = [
+        settings for _, settings in sorted(workloads.items())
+        if not settings.get("metrics") or metric_name in settings["metrics"]
+    ]
+    combined: Dict = {}
+    for settings in applying:
+        if "voice" in settings and "voice" not in combined:
+            combined["voice"] = settings["voice"]
+        if "priority" in settings:
+            combined["priority"] = max(combined.get("priority", settings["priority"]),
+                                       settings["priority"])
+    if applying and all(settings.get("muted") for settings in applying):
+        combined["muted"] = True
+    return combined
+
+
+def profile_crd() -> Dict:
+    """
+    Builds the CustomResourceDefinition of SonificationProfile, as written by `generate-crd`.
+
+    Returns:
+        The manifest.
+    """
+    pair = {"type": "array", "items": {"type": "number"}, "minItems": 2, "maxItems": 2}
+    limit = CRITICAL_PRIORITY - 1
+    priority = {
+        "x-kubernetes-int-or-string": True,
+        "anyOf": [
+            {"type": "integer", "minimum": -limit, "maximum": limit},
+            {"type": "string", "enum": list(ANNOTATION_PRIORITIES)},
+        ],
+    }
+    spec = {
+        "type": "object",
+        "properties": {
+            "metrics": {"type": "array", "items": {"type": "string"}},
+            "thresholds": {"type": "object", "additionalProperties": pair},
+            "sound": {
+                "type": "object",
+                "properties": {
+                    "instrument": {"type": "string", "enum": list(VOICES)},
+                    "priority": priority,
+                    "mute": {"type": "boolean"},
+                },
+            },
+        },
+    }
+    return {
+        "apiVersion": "apiextensions.k8s.io/v1",
+        "kind": "CustomResourceDefinition",
+        "metadata": {"name": f"{PROFILE_PLURAL}.{PROFILE_GROUP}"},
+        "spec": {
+            "group": PROFILE_GROUP,
+            "scope": "Namespaced",
+            "names": {
+                "kind": "SonificationProfile",
+                "plural": PROFILE_PLURAL,
+                "singular": "sonificationprofile",
+                "shortNames": ["sonify"],
+            },
+            "versions": [{
+                "name": PROFILE_VERSION,
+                "served": True,
+                "storage": True,
+                "schema": {"openAPIV3Schema": {"type": "object", "properties": {"spec": spec}}},
+            }],
+        },
+    }
+
+
+def profile_settings(profile: Dict) -> Optional[Dict]:
+    """
+    Reads the spec of a SonificationProfile custom resource.
+
+    Args:
+        profile: The resource, as listed by K8sClient.list_sonification_profiles().
+
+    Returns:
+        "name", "metrics" (empty to monitor the configured ones), "thresholds" per
+        metric and "sound" (the settings workload_sound_settings() reads from
+        annotations), or None if the spec is invalid, which is logged.
+    """
+    metadata = profile.get("metadata") or {}
+    name = f"{metadata.get('namespace')}/{metadata.get('name')}"
+    spec = profile.get("spec") or {}
+    problems = []
+    metrics = spec.get("metrics") or []
+    if not isinstance(metrics, list):
+        problems.append("metrics: must be a list of metric names")
+        metrics = []
+    problems += [f"metrics: unknown metric {m!r}" for m in metrics if m not in SOUND_MAP]
+    thresholds = {}
+    if not isinstance(spec.get("thresholds") or {}, dict):
+        problems.append("thresholds: must map metric names to [warning, critical]")
+    else:
+        for metric_name, pair in (spec.get("thresholds") or {}).items():
+            if metric_name not in SOUND_MAP:
+                problems.append(f"thresholds: unknown metric {metric_name!r}")
+            elif not isinstance(pair, list) or len(pair) != 2 or not all(map(is_number, pair)):
+                problems.append(f"thresholds.{metric_name}: must be [warning, critical]")
+            else:
+                thresholds[metric_name] = tuple(pair)
+    sound = spec.get("sound") or {}
+    annotations = {key: str(sound[key]) for key in ("instrument", "priority") if key in sound}
+    if "mute" in sound:
+        annotations["mute"] = str(sound["mute"]).lower()
+    if problems:
+        logger.warning(f"Ignoring SonificationProfile {name}: {'; '.join(problems)}")
+        return None
+    workload = f"sonificationprofile/{name}"
+    return {
+        "name": name,
+        "metrics": metrics,
+        "thresholds": thresholds,
+        "sound": workload_sound_settings({workload: annotations})[workload],
+    }
+
+
+class StatsdSource(MetricSource):
+    """
+    Listens for statsd metrics over UDP and sonifies the mapped ones.
+
+    Metrics without a statsd mapping, or without statsd data in the current
+    tick, are fetched from the wrapped fallback source instead.
+    """
+
+    def __init__(
+        self,
+        mapping: Dict[str, str],
+        host: str = "127.0.0.1",
+        port: int = 8125,
+        fallback: Optional[MetricSource] = None,
+    ):
+        """
+        Initialize the listener.
+
+        Args:
+            mapping: statsd metric names mapped to SOUND_MAP metric names.
+            host: The address to listen on.
+            port: The UDP port to listen on.
+            fallback: The source for metrics statsd does not provide.
+        """
+        self.mapping = mapping
+        self.host = host
+        self.port = port
+        self.fallback = fallback
+        self.current: Dict[str, Tuple[float, Dict]] = {}
+        self._counters: Dict[str, float] = {}
+        self._timers: Dict[str, List[float]] = {}
+        self._gauges: Dict[str, float] = {}
+        self._lock = threading.Lock()
+        self._last_flush = time.monotonic()
+        self._socket: Optional[socket.socket] = None
+        self._thread: Optional[threading.Thread] = None
+
+    def handle_line(self, line: str) -> None:
+        """
+        Records one statsd line, e.g. "checkout.errors:1|c|@0.5".
+
+        Args:
+            line: The statsd line.
+        """
+        try:
+            name, rest = line.strip().split(":", 1)
+            fields = rest.split("|")
+            value, metric_type = fields[0], fields[1]
+            sample_rate = 1.0
+            for field in fields[2:]:
+                if field.startswith("@"):
+                    sample_rate = float(field[1:]) or 1.0
+        except (ValueError, IndexError):
+            logger.debug(f"Ignoring malformed statsd line: {line}")
+            return
+        if name not in self.mapping:
+            return
+
+        try:
+            with self._lock:
+                if metric_type == "c":
+                    self._counters[name] = self._counters.get(name, 0.0) + float(value) / sample_rate
+                elif metric_type in ("ms", "h"):
+                    self._timers.setdefault(name, []).append(float(value))
+                elif metric_type == "g":
+                    if value[0] in "+-":
+                        self._gauges[name] = self._gauges.get(name, 0.0) + float(value)
+                    else:
+                        self._gauges[name] = float(value)
+        except ValueError:
+            logger.debug(f"Ignoring non-numeric statsd value: {line}")
+
+    def flush(self) -> None:
+        """Aggregate everything received since the last flush into the current tick."""
+        now = time.monotonic()
+        elapsed = max(now - self._last_flush, 1e-6)
+        self._last_flush = now
+        current = {}
+        with self._lock:
+            for name, total in self._counters.items():
+                current[self.mapping[name]] = (total / elapsed, {"statsd": name, "type": "counter"})
+            for name, samples in self._timers.items():
+                current[self.mapping[name]] = (sum(samples) / len(samples), {"statsd": name, "type": "timer"})
+            for name, value in self._gauges.items():
+                current[self.mapping[name]] = (value, {"statsd": name, "type": "gauge"})
+            self._counters.clear()
+            self._timers.clear()
+        self.current = current
+
+    def _run(self) -> None:
+        while self._socket is not None:
+            try:
+                packet, _ = self._socket.recvfrom(65535)
+            except OSError:
+                break
+            for line in packet.decode("utf-8", errors="replace").splitlines():
+                if line:
+                    self.handle_line(line)
+
+    def connect(self) -> bool:
+        if self.fallback is not None and not self.fallback.connect():
+            return False
+        try:
+            self._socket = socket.socket(socket.AF_INET, socket.SOCK_DGRAM)
+            self._socket.bind((self.host, self.port))
+        except OSError as e:
+            logger.error(f"Failed to listen for statsd on {self.host}:{self.port}: {e}")
+            self._socket = None
+            return False
+        self._last_flush = time.monotonic()
+        self._thread = threading.Thread(target=self._run, daemon=True)
+        self._thread.start()
+        logger.info(f"Listening for statsd metrics on {self.host}:{self.port}")
+        return True
+
+    def close(self) -> None:
+        """Stop listening and close the fallback source."""
+        sock, self._socket = self._socket, None
+        if sock is not None:
+            sock.close()
+        if self.fallback is not None:
+            self.fallback.close()
+
+    def next_tick(self) -> bool:
+        self.flush()
+        return self.fallback.next_tick() if self.fallback is not None else True
+
+    def fetch(self, metric: str, namespace: str = "default") -> Optional[Tuple[float, Dict]]:
+        if metric in self.current:
+            return self.current[metric]
+        if self.fallback is not None:
+            return self.fallback.fetch(metric, namespace)
+        return None
+
+    def annotations(self, namespace: str = "default") -> Dict[str, Dict[str, str]]:
+        return self.fallback.annotations(namespace) if self.fallback is not None else {}
+
+    def namespaces(self, include: List[str], exclude: List[str]) -> Optional[List[str]]:
+        return self.fallback.namespaces(include, exclude) if self.fallback is not None else None
+
+    def sonification_profiles(self) -> Optional[List[Dict]]:
+        return self.fallback.sonification_profiles() if self.fallback is not None else None
+
+
+class FalcoSource(MetricSource):
+    """
+    Receives Falco alerts on a webhook and sonifies the worst one of each tick as
+    security_alerts.
+
+    Alerts are JSON objects (or lists of them) as posted by Falco's http_output or
+    falcosidekick's webhook output. An alert counts towards the namespace in its
+    k8s.ns.name field, or every namespace if it has none. With a token, requests
+    without it as their bearer token are refused. Every other metric is fetched
+    from the wrapped fallback source.
+    """
+
+    def __init__(
+        self,
+        host: str = "127.0.0.1",
+        port: int = 2802,
+        min_priority: str = "notice",
+        fallback: Optional[MetricSource] = None,
+        token: str = "",
+    ):
+        """
+        Initialize the listener.
+
+        Args:
+            host: The address to listen on.
+            port: The TCP port to listen on.
+            min_priority: The least severe of FALCO_PRIORITIES to sonify.
+            fallback: The source for the other metrics.
+            token: The shared secret alerts must carry, or empty to accept any.
+        """
+        self.host = host
+        self.port = port
+        self.token = token
+        self.min_priority = FALCO_PRIORITIES.index(min_priority)
+        self.fallback = fallback
+        self.current: List[Dict] = []
+        self._pending: List[Dict] = []
+        self._lock = threading.Lock()
+        self._server: Optional[ThreadingHTTPServer] = None
+
+    def handle_alert(self, alert) -> bool:
+        """
+        Records one Falco alert for the next tick.
+
+        Args:
+            alert: The decoded alert, e.g. {"priority": "Critical", "rule": "Terminal
+                shell in container", "output_fields": {"k8s.ns.name": "shop"}}.
+
+        Returns:
+            Whether it was recorded, rather than without a priority or below min_priority.
+
+        Raises:
+            ValueError: If the alert or its output_fields isn't a JSON object.
+        """
+        if not isinstance(alert, dict):
+            raise ValueError(f"Expected a JSON object, not {alert!r}")
+        fields = alert.get("output_fields") or {}
+        if not isinstance(fields, dict):
+            raise ValueError(f"Expected output_fields to be a JSON object, not {fields!r}")
+        priority = str(alert.get("priority", "")).lower()
+        if priority not in FALCO_PRIORITIES:
+            logger.debug(f"Ignoring Falco alert without a priority: {alert}")
+            return False
+        if FALCO_PRIORITIES.index(priority) < self.min_priority:
+            return False
+        namespace = fields.get("k8s.ns.name")
+        with self._lock:
+            self._pending.append({
+                "priority": priority,
+                "rule": str(alert.get("rule", "")),
+                "namespace": str(namespace) if namespace is not None else None,
+            })
+        return True
+
+    def _make_handler(self):
+        source = self
+
+        class Handler(BaseHTTPRequestHandler):
+            def do_POST(self):
+                if source.token and not hmac.compare_digest(
+                    self.headers.get("Authorization", "").encode(),
+                    f"Bearer {source.token}".encode(),
+                ):
+                    self.send_error(401, "Expected the shared secret as a bearer token")
+                    return
+                try:
+                    length = int(self.headers.get("Content-Length") or 0)
+                except ValueError:
+                    self.send_error(400, "Expected a numeric Content-Length")
+                    return
+                if not 0 <= length <= FALCO_MAX_BODY:
+                    self.send_error(413, f"Expected at most {FALCO_MAX_BODY} bytes")
+                    return
+                try:
+                    alerts = json.loads(self.rfile.read(length))
+                    for alert in alerts if isinstance(alerts, list) else [alerts]:
+                        source.handle_alert(alert)
+                except ValueError:
+                    self.send_error(400, "Expected a JSON Falco alert")
+                    return
+                self.send_response(200)
+                self.send_header("Content-Length", "0")
+                self.end_headers()
+
+            def log_message(self, format, *args):
+                logger.debug(f"Falco webhook: {format % args}")
+
+        return Handler
+
+    def connect(self) -> bool:
+        if self.fallback is not None and not self.fallback.connect():
+            return False
+        try:
+            self._server = ThreadingHTTPServer((self.host, self.port), self._make_handler())
+        except OSError as e:
+            logger.error(f"Failed to listen for Falco alerts on {self.host}:{self.port}: {e}")
+            return False
+        self._server.daemon_threads = True
+        threading.Thread(target=self._server.serve_forever, daemon=True).start()
+        logger.info(
+            f"Listening for Falco alerts on http://{self.host}:{self._server.server_port}/"
+        )
+        return True
+
+    def close(self) -> None:
+        """Stop listening and close the fallback source."""
+        server, self._server = self._server, None
+        if server is not None:
+            server.shutdown()
+            server.server_close()
+        if self.fallback is not None:
+            self.fallback.close()
+
+    def next_tick(self) -> bool:
+        with self._lock:
+            self.current, self._pending = self._pending, []
+        return self.fallback.next_tick() if self.fallback is not None else True
+
+    def fetch(self, metric: str, namespace: str = "default") -> Optional[Tuple[float, Dict]]:
+        if metric != "security_alerts":
+            return self.fallback.fetch(metric, namespace) if self.fallback is not None else None
+        alerts = [alert for alert in self.current if alert["namespace"] in (None, namespace)]
+        if not alerts:
+            return 0, {"alerts": 0}
+        worst = max(FALCO_PRIORITIES.index(alert["priority"]) for alert in alerts)
+        return worst + 1, {
+            "alerts": len(alerts),
+            "priority": FALCO_PRIORITIES[worst],
+            "rules": sorted({alert["rule"] for alert in alerts}),
+        }
+
+    def annotations(self, namespace: str = "default") -> Dict[str, Dict[str, str]]:
+        return self.fallback.annotations(namespace) if self.fallback is not None else {}
+
+    def namespaces(self, include: List[str], exclude: List[str]) -> Optional[List[str]]:
+        return self.fallback.namespaces(include, exclude) if self.fallback is not None else None
+
+    def sonification_profiles(self) -> Optional[List[Dict]]:
+        return self.fallback.sonification_profiles() if self.fallback is not None else None
+
+
+def parse_timestamp(value) -> float:
+    """
+    Parses an epoch number or an ISO 8601 string into epoch seconds.
+
+    Args:
+        value: The timestamp to parse.
+
+    Returns:
+        The timestamp in seconds since the epoch.
+    """
+    if isinstance(value, (int, float)):
+        return float(value)
+    try:
+        return float(value)
+    except ValueError:
+        parsed = datetime.fromisoformat(value.replace("Z", "+00:00"))
+        if parsed.tzinfo is None:
+            parsed = parsed.replace(tzinfo=timezone.utc)
+        return parsed.timestamp()
+
+
+class FileMetricSource(MetricSource):
+    """
+    Replays timestamped metric samples from a JSON file.
+
+    Two formats are accepted:
+        - A list of samples: [{"timestamp": ..., "metric": "cpu_usage", "value": 42.0}, ...]
+        - A Prometheus range query response, where each series' metric name is
+          taken from its "sonify_metric" or "__name__" label.
+
+    Every distinct timestamp becomes one tick, played in chronological order.
+    """
+
+    def __init__(self, path: str):
+        """
+        Initialize the source.
+
+        Args:
+            path: The path of the JSON file.
+        """
+        self.path = path
+        self.ticks: List[Tuple[float, Dict[str, Tuple[float, Dict]]]] = []
+        self.position = -1
+        self.loaded = False
+
+    @staticmethod
+    def parse_samples(data) -> List[Tuple[float, str, float, Dict]]:
+        """
+        Extracts samples from either supported file format.
+
+        Args:
+            data: The decoded JSON document.
+
+        Returns:
+            A list of (timestamp, metric, value, extra_data) tuples.
+        """
+        samples = []
+        if isinstance(data, dict) and "data" in data:
+            # Prometheus /api/v1/query_range response
+            for series in data["data"].get("result", []):
+                labels = series.get("metric", {})
+                metric = labels.get("sonify_metric") or labels.get("__name__")
+                extra = {k: v for k, v in labels.items() if k not in ("__name__", "sonify_metric")}
+                for timestamp, value in series.get("values", []):
+                    samples.append((parse_timestamp(timestamp), metric, float(value), dict(extra)))
+        else:
+            for sample in data:
+                samples.append(
+                    (
+                        parse_timestamp(sample["timestamp"]),
+                        sample["metric"],
+                        float(sample["value"]),
+                        dict(sample.get("extra", {})),
+                    )
+                )
+        return samples
+
+    def connect(self) -> bool:
+        if self.loaded:
+            return True
+        try:
+            with open(self.path, "r", encoding="utf-8") as f:
+                samples = self.parse_samples(json.load(f))
+        except (OSError, ValueError, KeyError, TypeError) as e:
+            logger.error(f"Failed to read metrics file {self.path}: {e}")
+            return False
+
+        by_timestamp: Dict[float, Dict[str, Tuple[float, Dict]]] = {}
+        for timestamp, metric, value, extra in samples:
+            if metric not in SOUND_MAP:
+                logger.warning(f"Skipping samples of unknown metric: {metric}")
+                continue
+            extra["timestamp"] = datetime.fromtimestamp(timestamp, timezone.utc).isoformat()
+            by_timestamp.setdefault(timestamp, {})[metric] = (value, extra)
+        self.ticks = sorted(by_timestamp.items())
+        self.loaded = True
+        logger.info(f"Loaded {len(samples)} samples in {len(self.ticks)} ticks from {self.path}")
+        return True
+
+    @property
+    def metric_names(self) -> List[str]:
+        """The metrics that appear in the file, in SOUND_MAP order."""
+        present = {metric for _, tick in self.ticks for metric in tick}
+        return [metric for metric in SOUND_MAP if metric in present]
+
+    def next_tick(self) -> bool:
+        self.position += 1
+        return self.position < len(self.ticks)
+
+    def fetch(self, metric: str, namespace: str = "default") -> Optional[Tuple[float, Dict]]:
+        if not 0 <= self.position < len(self.ticks):
+            return None
+        return self.ticks[self.position][1].get(metric)
+
+
+# --- Outputs ---
+@dataclass
+class SonificationEvent:
+    """A sampled metric value together with the sound and color it was mapped to."""
+
+    metric: str
+    value: float
+    note: str
+    frequency: int
+    color: str
+    severity: str
+    index: int = 0
+    namespace: str = "default"
+    timestamp: float = field(default_factory=lambda: datetime.now(timezone.utc).timestamp())
+    extra: Dict = field(default_factory=dict)
+
+
+class MetricOutput:
+    """Somewhere sonification events are mirrored to, besides the speakers."""
+
+    def emit(self, event: SonificationEvent) -> None:
+        """
+        Record one event.
+
+        Args:
+            event: The event to record.
+        """
+        raise NotImplementedError
+
+    def alarm(self, event: SonificationEvent, transition: str) -> None:
+        """
+        Record an alarm firing or clearing.
+
+        Args:
+            event: The event that changed the alarm state.
+            transition: "fired" or "cleared".
+        """
+
+    def flush(self) -> None:
+        """Send everything recorded since the last flush; called once per tick."""
+
+    def close(self) -> None:
+        """Flush and release any resources."""
+        self.flush()
+
+
+class QueuedOutput(MetricOutput):
+    """
+    Feeds an output from a bounded queue on a worker thread, so a slow exporter
+    can't hold up collection or the other outputs.
+
+    When the queue is full the oldest waiting call is dropped.
+    """
+
+    def __init__(self, output: MetricOutput, max_items: int = 100):
+        """
+        Start the worker thread.
+
+        Args:
+            output: The output to feed.
+            max_items: How many calls may wait to be made.
+        """
+        self.output = output
+        self.max_items = max_items
+        self.items: deque = deque()
+        self.condition = threading.Condition()
+        self.closed = False
+        self.dropped = 0
+        self.thread = threading.Thread(target=self._run, daemon=True)
+        self.thread.start()
+
+    def _put(self, name: str, *args) -> None:
+        with self.condition:
+            if len(self.items) >= self.max_items:
+                self.dropped += 1
+                dropped = self.items.popleft()
+                logger.debug(f"{type(self.output).__name__} queue full, dropping {dropped[0]}")
+            self.items.append((name, args))
+            self.condition.notify()
+
+    def _run(self) -> None:
+        while True:
+            with self.condition:
+                while not self.items and not self.closed:
+                    self.condition.wait()
+                if not self.items:
+                    return
+                name, args = self.items.popleft()
+            try:
+                getattr(self.output, name)(*args)
+            except Exception as e:
+                logger.error(f"{type(self.output).__name__} failed: {e}")
+
+    def emit(self, event: SonificationEvent) -> None:
+        self._put("emit", event)
+
+    def alarm(self, event: SonificationEvent, transition: str) -> None:
+        self._put("alarm", event, transition)
+
+    def flush(self) -> None:
+        self._put("flush")
+
+    def close(self) -> None:
+        # Unlike QueuedSink, drain what is waiting so the last tick is exported
+        with self.condition:
+            self.closed = True
+            self.condition.notify()
+        self.thread.join(timeout=OUTPUT_DRAIN_TIMEOUT)
+        self.output.close()
+
+
+def escape_influx_tag(value: str) -> str:
+    """
+    Escapes a tag key or value for InfluxDB line protocol.
+
+    Args:
+        value: The raw tag key or value.
+
+    Returns:
+        The escaped string.
+    """
+    for char in ("\\", ",", "=", " "):
+        value = str(value).replace(char, "\\" + char)
+    return value
+
+
+class InfluxDBOutput(MetricOutput):
+    """Writes events to InfluxDB v2 through its HTTP write API using line protocol."""
+
+    def __init__(
+        self,
+        url: str,
+        org: str,
+        bucket: str,
+        token: str = "",
+        measurement: str = "sonify_k8s",
+    ):
+        """
+        Initialize the output.
+
+        Args:
+            url: The base URL of the InfluxDB server.
+            org: The organization to write to.
+            bucket: The bucket to write to.
+            token: The API token.
+            measurement: The measurement name of the written points.
+        """
+        query = urllib.parse.urlencode({"org": org, "bucket": bucket, "precision": "ns"})
+        self.write_url = f"{url.rstrip('/')}/api/v2/write?{query}"
+        self.token = token
+        self.measurement = measurement
+        self.lines: List[str] = []
+
+    def format_line(self, event: SonificationEvent) -> str:
+        """
+        Formats an event as a line protocol point.
+
+        Args:
+            event: The event to format.
+
+        Returns:
+            The line protocol point.
+        """
+        tags = {
+            "metric": event.metric,
+            "namespace": event.namespace,
+            "note": event.note,
+            "severity": event.severity,
+        }
+        tag_set = ",".join(f"{k}={escape_influx_tag(v)}" for k, v in tags.items() if v)
+        fields = (
+            f"value={float(event.value)},frequency={int(event.frequency)}i,"
+            f'index={int(event.index)}i,color="{event.color}"'
+        )
+        timestamp_ns = int(round(event.timestamp * 1e9))
+        return f"{escape_influx_tag(self.measurement)},{tag_set} {fields} {timestamp_ns}"
+
+    def emit(self, event: SonificationEvent) -> None:
+        self.lines.append(self.format_line(event))
+
+    def flush(self) -> None:
+        if not self.lines:
+            return
+        body = ("\n".join(self.lines) + "\n").encode("utf-8")
+        self.lines = []
+        request = urllib.request.Request(self.write_url, data=body, method="POST")
+        request.add_header("Content-Type", "text/plain; charset=utf-8")
+        if self.token:
+            request.add_header("Authorization", f"Token {self.token}")
+        try:
+            with urllib.request.urlopen(request, timeout=5):
+                pass
+        except OSError as e:
+            logger.warning(f"Failed to write to InfluxDB: {e}")
+
+
+def graphite_path_component(value: str) -> str:
+    """
+    Makes a string safe to use as one component of a Graphite metric path.
+
+    Args:
+        value: The raw string, e.g. a namespace.
+
+    Returns:
+        The string with dots, whitespace and other separators replaced by underscores.
+    """
+    return re.sub(r"[^A-Za-z0-9_:-]", "_", str(value))
+
+
+class GraphiteOutput(MetricOutput):
+    """Writes events to Graphite over its plaintext TCP protocol."""
+
+    def __init__(self, host: str, port: int = 2003, prefix: str = "sonify_k8s"):
+        """
+        Initialize the output.
+
+        Args:
+            host: The Carbon host.
+            port: The Carbon plaintext port.
+            prefix: The first component of every metric path.
+        """
+        self.host = host
+        self.port = port
+        self.prefix = prefix
+        self.lines: List[str] = []
+        self._socket: Optional[socket.socket] = None
+
+    def format_lines(self, event: SonificationEvent) -> List[str]:
+        """
+        Formats an event as plaintext protocol lines.
+
+        Args:
+            event: The event to format.
+
+        Returns:
+            The value, severity and frequency lines, under
+            <prefix>.<namespace>.<metric>.
+        """
+        path = ".".join(
+            graphite_path_component(part) for part in (event.namespace, event.metric)
+        )
+        timestamp = int(event.timestamp)
+        severity = list(SEVERITY_COLORS).index(event.severity)
+        return [
+            f"{self.prefix}.{path}.value {float(event.value)} {timestamp}",
+            f"{self.prefix}.{path}.severity {severity} {timestamp}",
+            f"{self.prefix}.{path}.frequency {int(event.frequency)} {timestamp}",
+        ]
+
+    def emit(self, event: SonificationEvent) -> None:
+        self.lines.extend(self.format_lines(event))
+
+    def flush(self) -> None:
+        if not self.lines:
+            return
+        body = ("\n".join(self.lines) + "\n").encode("utf-8")
+        self.lines = []
+        try:
+            if self._socket is None:
+                self._socket = socket.create_connection((self.host, self.port), timeout=5)
+            self._socket.sendall(body)
+        except OSError as e:
+            logger.warning(f"Failed to write to Graphite: {e}")
+            self.close()
+
+    def close(self) -> None:
+        self.flush()
+        sock, self._socket = self._socket, None
+        if sock is not None:
+            sock.close()
+
+
+class GrafanaAnnotationOutput(MetricOutput):
+    """Creates Grafana annotations when alarms fire or clear."""
+
+    def __init__(
+        self,
+        url: str,
+        token: str = "",
+        dashboard_uid: str = "",
+        tags: Optional[List[str]] = None,
+    ):
+        """
+        Initialize the output.
+
+        Args:
+            url: The base URL of the Grafana server.
+            token: A service account token or API key.
+            dashboard_uid: Restrict the annotations to one dashboard. If empty, they are global.
+            tags: Tags added to every annotation.
+        """
+        self.annotations_url = f"{url.rstrip('/')}/api/annotations"
+        self.token = token
+        self.dashboard_uid = dashboard_uid
+        self.tags = tags if tags is not None else ["sonify-k8s"]
+
+    def build_annotation(self, event: SonificationEvent, transition: str) -> Dict:
+        """
+        Builds the annotation payload for an alarm transition.
+
+        Args:
+            event: The event that changed the alarm state.
+            transition: "fired" or "cleared".
+
+        Returns:
+            The JSON payload for the Grafana annotations API.
+        """
+        metric_config = SOUND_MAP.get(event.metric, {})
+        unit = metric_config.get("unit", "")
+        annotation = {
+            "time": int(event.timestamp * 1000),
+            "tags": self.tags + [event.metric, event.namespace, f"alarm-{transition}"],
+            "text": (
+                f"{metric_config.get('metric_name', event.metric)} alarm {transition} "
+                f"in {event.namespace}: {event.value:.2f} {unit}".rstrip()
+            ),
+        }
+        if self.dashboard_uid:
+            annotation["dashboardUID"] = self.dashboard_uid
+        return annotation
+
+    def emit(self, event: SonificationEvent) -> None:
+        pass
+
+    def alarm(self, event: SonificationEvent, transition: str) -> None:
+        body = json.dumps(self.build_annotation(event, transition)).encode("utf-8")
+        request = urllib.request.Request(self.annotations_url, data=body, method="POST")
+        request.add_header("Content-Type", "application/json")
+        if self.token:
+            request.add_header("Authorization", f"Bearer {self.token}")
+        try:
+            with urllib.request.urlopen(request, timeout=5):
+                pass
+        except OSError as e:
+            logger.warning(f"Failed to create Grafana annotation: {e}")
+
+
+def notification_command(title: str, body: str, platform: str = sys.platform) -> Optional[List[str]]:
+    """
+    Builds the command that raises a desktop notification.
+
+    Args:
+        title: The notification title.
+        body: The notification text.
+        platform: The platform, as in sys.platform.
+
+    Returns:
+        The command, or None if the platform has no supported notifier.
+    """
+    if platform == "darwin":
+
+        def quote(text: str) -> str:
+            return '"' + text.replace("\\", "\\\\").replace('"', '\\"') + '"'
+
+        return ["osascript", "-e", f"display notification {quote(body)} with title {quote(title)}"]
+    if platform.startswith("linux") or "bsd" in platform:
+        return ["notify-send", "--urgency=critical", "--app-name=sonify-k8s", title, body]
+    return None
+
+
+class HapticOutput(MetricOutput):
+    """
+    Rumbles a game controller once a tick while the cluster is unhealthy, harder for
+    critical than for warning, as a silent and private alert.
+    """
+
+    def __init__(
+        self,
+        controller: int = 0,
+        intensity: Optional[Dict[str, float]] = None,
+        duration: float = 0.5,
+    ):
+        """
+        Initialize the output; the controller is opened on the first rumble.
+
+        Args:
+            controller: The index of the controller, in the order pygame finds them.
+            intensity: The strength of the rumble (0.0 - 1.0) per severity. If None,
+                0.3 for warning and 1.0 for critical.
+            duration: Seconds each rumble lasts.
+        """
+        self.controller = controller
+        self.intensity = intensity if intensity is not None else {"warning": 0.3, "critical": 1.0}
+        self.duration = duration
+        self.events: List[SonificationEvent] = []
+        self.joystick = None
+        self.available = True
+
+    def emit(self, event: SonificationEvent) -> None:
+        self.events.append(event)
+
+    def flush(self) -> None:
+        severity = aggregate_severity(self.events)
+        self.events = []
+        strength = self.intensity.get(severity, 0.0)
+        if strength <= 0 or not self.open():
+            return
+        # The high frequency motor only buzzes along for critical values
+        high = strength if severity == "critical" else 0.0
+        if not self.joystick.rumble(strength, high, int(self.duration * 1000)):
+            logger.debug(f"Controller {self.controller} doesn't support rumble")
+
+    def open(self) -> bool:
+        """
+        Opens the controller, once.
+
+        Returns:
+            Whether a controller is available.
+        """
+        if self.joystick is not None or not self.available:
+            return self.available
+        try:
+            import pygame
+
+            pygame.joystick.init()
+            if pygame.joystick.get_count() <= self.controller:
+                raise RuntimeError(f"no game controller {self.controller} is connected")
+            self.joystick = pygame.joystick.Joystick(self.controller)
+            self.joystick.init()
+        except Exception as e:
+            logger.error(f"Haptic output needs pygame and a game controller, skipping it: {e}")
+            self.available = False
+        return self.available
+
+    def close(self) -> None:
+        self.events = []
+        if self.joystick is not None:
+            self.joystick.stop_rumble()
+            self.joystick = None
+
+
+class DesktopNotificationOutput(MetricOutput):
+    """Raises a desktop notification when a critical alarm fires."""
+
+    def emit(self, event: SonificationEvent) -> None:
+        pass
+
+    def alarm(self, event: SonificationEvent, transition: str) -> None:
+        if transition != "fired":
+            return
+        metric_config = SOUND_MAP.get(event.metric, {})
+        title = f"{metric_config.get('metric_name', event.metric)} is critical"
+        body = f"{event.value:.2f} {metric_config.get('unit', '')}".rstrip()
+        command = notification_command(title, f"{body} in {event.namespace}", sys.platform)
+        if command is None:
+            logger.warning(f"Desktop notifications are not supported on {sys.platform}")
+            return
+        try:
+            subprocess.run(command, check=True, timeout=5, capture_output=True)
+        except (OSError, subprocess.SubprocessError) as e:
+            logger.warning(f"Failed to raise desktop notification: {e}")
+
+
+HISTORY_COLUMNS = (
+    "timestamp", "namespace", "metric", "value", "note", "frequency", "color", "severity",
+)  # Columns stored per sample by HistoryOutput
+
+
+class HistoryOutput(MetricOutput):
+    """Stores every event in a SQLite file, so it can be exported later."""
+
+    def __init__(self, path: str):
+        """
+        Initialize the output.
+
+        Args:
+            path: The path of the SQLite file; it is created if missing.
+        """
+        self.path = path
+        self.pending: List[Tuple] = []
+        self._db: Optional[sqlite3.Connection] = None
+
+    def emit(self, event: SonificationEvent) -> None:
+        self.pending.append(tuple(getattr(event, column) for column in HISTORY_COLUMNS))
+
+    def flush(self) -> None:
+        if not self.pending:
+            return
+        rows, self.pending = self.pending, []
+        try:
+            if self._db is None:
+                self._db = sqlite3.connect(self.path, check_same_thread=False)
+                self._db.execute(
+                    "CREATE TABLE IF NOT EXISTS samples (timestamp REAL, namespace TEXT,"
+                    " metric TEXT, value REAL, note TEXT, frequency INTEGER, color TEXT,"
+                    " severity TEXT)"
+                )
+                self._db.execute(
+                    "CREATE INDEX IF NOT EXISTS samples_timestamp ON samples (timestamp)"
+                )
+            with self._db:
+                self._db.executemany("INSERT INTO samples VALUES (?, ?, ?, ?, ?, ?, ?, ?)", rows)
+        except sqlite3.Error as e:
+            logger.error(f"Failed to store {len(rows)} samples in {self.path}: {e}")
+
+    def close(self) -> None:
+        self.flush()
+        if self._db is not None:
+            self._db.close()
+            self._db = None
+
+
+def read_history(
+    path: str,
+    since: Optional[float] = None,
+    until: Optional[float] = None,
+    metrics: Optional[List[str]] = None,
+    namespace: Optional[str] = None,
+) -> List[Dict]:
+    """
+    Reads samples stored by HistoryOutput, oldest first.
+
+    Args:
+        path: The path of the SQLite file.
+        since: Only samples at or after this epoch timestamp. If None, from the start.
+        until: Only samples before this epoch timestamp. If None, up to the end.
+        metrics: Only these metrics. If None or empty, every metric.
+        namespace: Only this namespace. If None, every namespace.
+
+    Returns:
+        One dict per sample, keyed by HISTORY_COLUMNS.
+
+    Raises:
+        sqlite3.Error: If the file is not a history store.
+    """
+    conditions, params = [], []
+    if since is not None:
+        conditions.append("timestamp >= ?")
+        params.append(since)
+    if until is not None:
+        conditions.append("timestamp < ?")
+        params.append(until)
+    if metrics:
+        conditions.append(f"metric IN ({', '.join('?' * len(metrics))})")
+        params.extend(metrics)
+    if namespace is not None:
+        conditions.append("namespace = ?")
+        params.append(namespace)
+    query = f"SELECT {', '.join(HISTORY_COLUMNS)} FROM samples"
+    if conditions:
+        query += " WHERE " + " AND ".join(conditions)
+    db = sqlite3.connect(f"file:{urllib.parse.quote(path)}?mode=ro", uri=True)
+    try:
+        rows = db.execute(query + " ORDER BY timestamp, rowid", params).fetchall()
+    finally:
+        db.close()
+    return [dict(zip(HISTORY_COLUMNS, row)) for row in rows]
+
+
+REPORT_WIDTH = 800  # Width in pixels of each metric's timeline in an HTML report
+REPORT_SNIPPET_DURATION = 1.0  # Seconds of each alarm's note embedded in an HTML report
+REPORT_MAX_SNIPPETS = 20  # Alarms embedded with audio in an HTML report, the earliest first
+
+
+def wav_bytes(samples: np.ndarray, sample_rate: int = SAMPLE_RATE) -> bytes:
+    """
+    Encodes samples as a 16-bit mono WAV file.
+
+    Args:
+        samples: The samples, between -1.0 and 1.0.
+        sample_rate: The sample rate in Hz.
+
+    Returns:
+        The contents of the file.
+    """
+    buffer = io.BytesIO()
+    with wave.open(buffer, "wb") as wav:
+        wav.setnchannels(1)
+        wav.setsampwidth(2)
+        wav.setframerate(sample_rate)
+        wav.writeframes((np.clip(samples, -1.0, 1.0) * 32767).astype("<i2").tobytes())
+    return buffer.getvalue()
+
+
+def key_alarms(samples: List[Dict]) -> List[Dict]:
+    """
+    Picks the samples where a metric became critical.
+
+    Args:
+        samples: Samples from read_history(), oldest first.
+
+    Returns:
+        The first critical sample of every run of critical samples, per namespace and metric.
+    """
+    alarms, critical = [], set()
+    for sample in samples:
+        key = (sample["namespace"], sample["metric"])
+        if sample["severity"] != "critical":
+            critical.discard(key)
+        elif key not in critical:
+            critical.add(key)
+            alarms.append(sample)
+    return alarms
+
+
+def render_report(
+    samples: List[Dict], title: str = "Sonify K8s incident report", audio: bool = True
+) -> str:
+    """
+    Renders stored history as a standalone HTML page, for postmortems: a timeline
+    per metric in the colors its notes were shown in, and the alarms that fired,
+    each with the note it played.
+
+    Args:
+        samples: Samples from read_history(), oldest first.
+        title: The heading of the page.
+        audio: Whether to embed a WAV snippet of each alarm's note.
+
+    Returns:
+        The page.
+    """
+    parts = [
+        "<!DOCTYPE html>",
+        '<html><head><meta charset="utf-8">',
+        f"<title>{html.escape(title)}</title>",
+        "<style>body{font-family:sans-serif;margin:2em}svg{background:#F3F4F6}"
+        "td,th{padding:0.2em 0.8em;text-align:left}</style>",
+        f"</head><body><h1>{html.escape(title)}</h1>",
+    ]
+    if not samples:
+        parts.append("<p>No samples in this window.</p></body></html>")
+        return "\n".join(parts)
+
+    def utc(timestamp: float) -> str:
+        return datetime.fromtimestamp(timestamp, timezone.utc).strftime("%Y-%m-%d %H:%M:%S")
+
+    start, end = samples[0]["timestamp"], samples[-1]["timestamp"]
+    scale = REPORT_WIDTH / max(end - start, 1e-9)
+    parts.append(f"<p>{utc(start)} to {utc(end)} UTC, {len(samples)} samples</p>")
+    series: Dict[Tuple[str, str], List[Dict]] = {}
+    for sample in samples:
+        series.setdefault((sample["namespace"], sample["metric"]), []).append(sample)
+    for (namespace, metric), points in sorted(series.items()):
+        metric_config = SOUND_MAP.get(metric, {})
+        unit = metric_config.get("unit", "")
+        parts.append(
+            f"<h2>{html.escape(metric_config.get('metric_name', metric))}"
+            f" <small>{html.escape(namespace)}</small></h2>"
+        )
+        parts.append(f'<svg width="{REPORT_WIDTH}" height="24">')
+        for point, following in zip(points, points[1:] + [None]):
+            x = (point["timestamp"] - start) * scale
+            width = ((following["timestamp"] - start) * scale - x) if following else 2
+            parts.append(
+                f'<rect x="{x:.1f}" width="{max(width, 1):.1f}" height="24"'
+                f' fill="{html.escape(point["color"] or SEVERITY_COLORS["ok"])}">'
+                f"<title>{utc(point['timestamp'])}: {point['value']:.2f} {html.escape(unit)}"
+                f" ({html.escape(point['note'] or '')})</title></rect>"
+            )
+        parts.append("</svg>")
+
+    alarms = key_alarms(samples)
+    parts.append("<h2>Alarms</h2>")
+    if not alarms:
+        parts.append("<p>No metric went critical in this window.</p>")
+    else:
+        parts.append("<table><tr><th>Time (UTC)</th><th>Namespace</th><th>Metric</th>"
+                     "<th>Value</th><th>Note</th><th></th></tr>")
+        for number, alarm in enumerate(alarms):
+            metric_config = SOUND_MAP.get(alarm["metric"], {})
+            player = ""
+            if audio and alarm["frequency"] and number < REPORT_MAX_SNIPPETS:
+                tone = render_tone(
+                    alarm["frequency"], REPORT_SNIPPET_DURATION, voice=metric_config.get("voice")
+                )
+                encoded = base64.b64encode(wav_bytes(tone)).decode("ascii")
+                player = f'<audio controls src="data:audio/wav;base64,{encoded}"></audio>'
+            value = f"{alarm['value']:.2f} {metric_config.get('unit', '')}".rstrip()
+            parts.append(
+                f"<tr><td>{utc(alarm['timestamp'])}</td><td>{html.escape(alarm['namespace'])}</td>"
+                f"<td>{html.escape(metric_config.get('metric_name', alarm['metric']))}</td>"
+                f"<td>{html.escape(value)}</td><td>{html.escape(alarm['note'] or '')}</td>"
+                f"<td>{player}</td></tr>"
+            )
+        parts.append("</table>")
+    parts.append("</body></html>")
+    return "\n".join(parts)
+
+
+def build_outputs(cfg: Dict) -> List[MetricOutput]:
+    """
+    Creates the outputs enabled in a configuration.
+
+    Args:
+        cfg: The configuration.
+
+    Returns:
+        The enabled outputs.
+    """
+    outputs: List[MetricOutput] = []
+    influxdb = cfg["outputs"]["influxdb"]
+    if influxdb["enabled"]:
+        outputs.append(
+            InfluxDBOutput(
+                influxdb["url"],
+                influxdb["org"],
+                influxdb["bucket"],
+                token=read_secret(influxdb["token"], influxdb["token_file"]),
+                measurement=influxdb["measurement"],
+            )
+        )
+    graphite = cfg["outputs"]["graphite"]
+    if graphite["enabled"]:
+        outputs.append(
+            GraphiteOutput(graphite["host"], graphite["port"], prefix=graphite["prefix"])
+        )
+    grafana = cfg["outputs"]["grafana"]
+    if grafana["enabled"]:
+        outputs.append(
+            GrafanaAnnotationOutput(
+                grafana["url"],
+                token=read_secret(grafana["token"], grafana["token_file"]),
+                dashboard_uid=grafana["dashboard_uid"],
+                tags=grafana["tags"],
+            )
+        )
+    if cfg["outputs"]["desktop"]["enabled"]:
+        outputs.append(DesktopNotificationOutput())
+    haptic = cfg["outputs"]["haptic"]
+    if haptic["enabled"]:
+        outputs.append(
+            HapticOutput(
+                haptic["controller"],
+                {"warning": haptic["warning"], "critical": haptic["critical"]},
+                haptic["duration"],
+            )
+        )
+    if cfg["outputs"]["history"]["enabled"]:
+        outputs.append(HistoryOutput(cfg["outputs"]["history"]["path"]))
+    if cfg["outputs"]["queue_size"] > 0:
+        outputs = [QueuedOutput(output, cfg["outputs"]["queue_size"]) for output in outputs]
+    return outputs
+
+
+def colorize_line(text: str, color: str, use_color: bool = False) -> str:
+    """
+    Colorizes a line of text using ANSI escape codes if use_color is True.
+
+    Args:
+        text: The text to colorize.
+        color: The hex color code (e.g., "#FF0000").
+        use_color: Whether to apply color.
+
+    Returns:
+        The colorized text if use_color is True, otherwise the original text.
+    """
+    if not use_color:
+        return text
+    # Convert hex color to RGB
+    hex_color = color.lstrip("#")
+    if len(hex_color) != 6:
+        logger.warning(f"Invalid hex color format: {color}. Returning unmodified text.")
+        return text  # Fallback if color is invalid
+    r, g, b = tuple(int(hex_color[i : i + 2], 16) for i in (0, 2, 4))
+    return f"\033[38;2;{r};{g};{b}m{text}\033[0m"
+
+
+def render_gauge(
+    value: float, min_value: float, max_value: float, style: str, width: int = GAUGE_WIDTH
+) -> str:
+    """
+    Draws a compact bar showing where a value lies within its range.
+
+    Args:
+        value: The metric value.
+        min_value: The value of an empty bar.
+        max_value: The value of a full bar.
+        style: One of GAUGE_STYLES; "block" fills in eighths of a character, "braille"
+            in halves over a dotted track.
+        width: The length of the bar in characters.
+
+    Returns:
+        The bar, or an empty string for "none".
+    """
+    if style == "none":
+        return ""
+    fraction = 0.0
+    if max_value > min_value:
+        fraction = (max(min_value, min(max_value, value)) - min_value) / (max_value - min_value)
+    if style == "braille":
+        halves = round(fraction * width * 2)
+        return ("⣿" * (halves // 2) + "⣇" * (halves % 2)).ljust(width, "⣀")
+    eighths = round(fraction * width * 8)
+    partial = " ▏▎▍▌▋▊▉"[eighths % 8].strip()
+    return f"▕{('█' * (eighths // 8) + partial).ljust(width)}▏"
+
+
+def report_metric(
+    metric_config: Dict,
+    metric_value: float,
+    note_name: str,
+    frequency: int,
+    color: str,
+    extra_data: Optional[Dict],
+    use_color: bool = False,
+    flash: bool = False,
+    gauge: str = "none",
+    rows: Optional[List[Tuple[str, str]]] = None,
+) -> None:
+    """
+    Logs a sonified metric and optionally prints it in its mapped color.
+
+    Args:
+        metric_config: The SOUND_MAP entry of the metric.
+        metric_value: The metric value.
+        note_name: The name of the note that was played.
+        frequency: The frequency of the note in Hz.
+        color: The hex color mapped to the value.
+        extra_data: Optional extra data returned with the metric.
+        use_color: Whether to print the colored line to the console.
+        flash: Whether to print the line in inverse video, colored or not, to stand
+            out as an alarm.
+        gauge: One of GAUGE_STYLES, for a bar after the value, see render_gauge().
+        rows: A (text, color) line printed below it for each row of a metric with
+            "rows", e.g. one per Deployment.
+    """
+    # Construct and display the log message
+    log_message = f"{metric_config['metric_name']}: {metric_value:.2f} {metric_config['unit']}"
+    if gauge != "none":
+        log_message += f" {render_gauge(metric_value, *metric_config['range'], gauge)}"
+    log_message += f" | Note: {note_name} ({frequency} Hz) | Color: {color}"
+    if extra_data:
+        log_message += f" | Extra: {extra_data}"
+
+    # Print colored output to console if enabled
+    if flash:
+        print(f"\033[7m{colorize_line(log_message, color, use_color=use_color)}\033[0m")
+    elif use_color:
+        print(colorize_line(log_message, color, use_color=use_color))
+    for text, row_color in rows or []:
+        if use_color:
+            print(colorize_line(f"  {text}", row_color, use_color=use_color))
+        logger.info(f"  {text}")
+
+    # Always log to the logger
+    logger.info(log_message)
+
+
+def report_stale(metric_config: Dict, timeout: float, use_color: bool = False) -> None:
+    """
+    Logs that a metric's fetch timed out and optionally prints it greyed out.
+
+    Args:
+        metric_config: The SOUND_MAP entry of the metric.
+        timeout: The seconds waited for the metric.
+        use_color: Whether to print the line to the console.
+    """
+    log_message = f"{metric_config['metric_name']}: stale data (no answer within {timeout:g}s)"
+    if use_color:
+        print(colorize_line(log_message, STALE_COLOR, use_color=use_color))
+    logger.warning(log_message)
+
+
+def format_dry_run_line(metric_name: str, metric_value: float, severity: str) -> str:
+    """
+    Describes the note and color a metric value would produce.
+
+    Args:
+        metric_name: The name of the metric (a SOUND_MAP key).
+        metric_value: The metric value.
+        severity: The severity of the value.
+
+    Returns:
+        A single line suitable for printing.
+    """
+    metric_config = SOUND_MAP[metric_name]
+    index, frequency, note_name, color, _, _ = map_metric(metric_name, metric_value)
+    return (
+        f"[dry-run] {metric_name}: {metric_value:.2f} {metric_config['unit']}".rstrip()
+        + f" -> note {note_name} ({frequency} Hz), color {color}, index {index}, {severity}"
+    )
+
+
+# --- Interactive Controls ---
+class PlaybackState:
+    """Playback settings that can be changed while the monitor is running."""
+
+    def __init__(self, namespace: str = "default"):
+        """
+        Initialize the playback state.
+
+        Args:
+            namespace: The Kubernetes namespace being monitored.
+        """
+        self.namespace = namespace
+        self.context: Optional[str] = None
+        self.volume = 1.0
+        self.paused = False
+        self.muted = set()
+        self.solo = set()
+        self.disabled = set()  # Metrics that are not fetched until enabled again
+        self.active_alarms = set()
+        self.acknowledged = set()
+        self.gains: Dict[str, float] = {}
+        self.silenced = False
+
+    def metric_volume(self, metric_name: str) -> float:
+        """
+        Gets the volume a metric plays at.
+
+        Args:
+            metric_name: The name of the metric.
+
+        Returns:
+            The master volume scaled by the metric's gain, at most 1.0.
+        """
+        return min(1.0, self.volume * self.gains.get(metric_name, 1.0))
+
+    def is_audible(
+        self, metric_name: str, alarm_key: Optional[str] = None, reminders: bool = False
+    ) -> bool:
+        """
+        Check whether a metric should currently make a sound.
+
+        Args:
+            metric_name: The name of the metric.
+            alarm_key: The key its alarm is tracked under, if not the metric name.
+            reminders: Whether acknowledged alarms still sound, as reminders.
+
+        Returns:
+            False if all audio or the metric is muted, the metric is not soloed, or
+            it is an acknowledged alarm and reminders is False.
+        """
+        if self.silenced:
+            return False
+        if self.solo and metric_name not in self.solo:
+            return False
+        if metric_name in self.muted:
+            return False
+        return reminders or (alarm_key or metric_name) not in self.acknowledged
+
+    def update_alarm(self, metric_name: str, severity: str) -> Optional[str]:
+        """
+        Track whether a metric is in alarm; clearing an alarm drops its acknowledgement.
+
+        Args:
+            metric_name: The name of the metric.
+            severity: The severity of the latest value.
+
+        Returns:
+            "fired" or "cleared" when the alarm state changed, otherwise None.
+        """
+        was_active = metric_name in self.active_alarms
+        if severity == "critical":
+            self.active_alarms.add(metric_name)
+            return None if was_active else "fired"
+        self.active_alarms.discard(metric_name)
+        self.acknowledged.discard(metric_name)
+        return "cleared" if was_active else None
+
+    def acknowledge(self) -> None:
+        """Silence every active alarm until it clears."""
+        self.acknowledged |= self.active_alarms
+
+
+class KeyboardController:
+    """
+    Reads single key presses from the terminal and applies them to a PlaybackState.
+
+    Keys:
+        1-9        Toggle mute of the Nth metric
+        !@#$%^&*(  Toggle solo of the Nth metric (Shift + 1-9)
+        0          Clear all mutes and solos
+        + / -      Raise / lower the master volume
+        p / space  Pause / resume polling
+        n          Switch to the next namespace
+        c          Switch to the next kubeconfig context
+        a          Acknowledge active alarms
+        q          Quit
+    """
+
+    SOLO_KEYS = "!@#$%^&*("
+
+    def __init__(self, state: PlaybackState, metrics: Optional[List[str]] = None):
+        """
+        Initialize the controller.
+
+        Args:
+            state: The playback state to control.
+            metrics: The metric names addressed by the number keys.
+        """
+        self.state = state
+        self.metrics = metrics if metrics is not None else list(SOUND_MAP)
+        self._stop = threading.Event()
+        self._thread: Optional[threading.Thread] = None
+
+    def _metric_at(self, position: int) -> Optional[str]:
+        if 0 <= position < len(self.metrics):
+            return self.metrics[position]
+        return None
+
+    def handle_key(self, key: str) -> None:
+        """
+        Apply a single key press.
+
+        Args:
+            key: The character that was pressed.
+        """
+        state = self.state
+        if key.isdigit() and key != "0":
+            metric_name = self._metric_at(int(key) - 1)
+            if metric_name:
+                state.muted ^= {metric_name}
+                logger.info(f"{metric_name}: {'muted' if metric_name in state.muted else 'unmuted'}")
+        elif key in self.SOLO_KEYS:
+            metric_name = self._metric_at(self.SOLO_KEYS.index(key))
+            if metric_name:
+                state.solo ^= {metric_name}
+                logger.info(f"Solo: {sorted(state.solo) or 'off'}")
+        elif key == "0":
+            state.muted.clear()
+            state.solo.clear()
+            logger.info("Cleared mutes and solos")
+        elif key in ("+", "="):
+            state.volume = min(1.0, round(state.volume + VOLUME_STEP, 2))
+            logger.info(f"Volume: {state.volume:.0%}")
+        elif key in ("-", "_"):
+            state.volume = max(0.0, round(state.volume - VOLUME_STEP, 2))
+            logger.info(f"Volume: {state.volume:.0%}")
+        elif key in ("p", " "):
+            state.paused = not state.paused
+            logger.info("Polling paused" if state.paused else "Polling resumed")
+        elif key == "n":
+            namespaces = k8s_client.list_namespaces()
+            if namespaces:
+                current = namespaces.index(state.namespace) if state.namespace in namespaces else -1
+                state.namespace = namespaces[(current + 1) % len(namespaces)]
+                logger.info(f"Switched to namespace: {state.namespace}")
+        elif key == "c":
+            contexts, current = k8s_client.list_contexts()
+            if contexts:
+                position = contexts.index(current) if current in contexts else -1
+                context = contexts[(position + 1) % len(contexts)]
+                if k8s_client.switch_context(context):
+                    state.context = context
+                    logger.info(f"Switched to context: {context}")
+                else:
+                    logger.warning(f"Failed to switch to context {context}, staying on {current}")
+        elif key == "a":
+            if state.active_alarms:
+                state.acknowledge()
+                logger.info(f"Acknowledged alarms: {sorted(state.active_alarms)}")
+        elif key == "q":
+            _thread.interrupt_main()
+
+    def _run(self) -> None:
+        try:
+            import select
+            import termios
+            import tty
+        except ImportError:
+            logger.warning("Keyboard controls are not supported on this platform")
+            return
+
+        fd = sys.stdin.fileno()
+        old_settings = termios.tcgetattr(fd)
+        try:
+            tty.setcbreak(fd)
+            while not self._stop.is_set():
+                ready, _, _ = select.select([sys.stdin], [], [], 0.2)
+                if ready:
+                    self.handle_key(sys.stdin.read(1))
+        finally:
+            termios.tcsetattr(fd, termios.TCSADRAIN, old_settings)
+
+    def start(self) -> None:
+        """Start reading keys in a daemon thread if stdin is a terminal."""
+        if not sys.stdin.isatty():
+            logger.warning("Keyboard controls need an interactive terminal, ignoring")
+            return
+        self._stop.clear()
+        self._thread = threading.Thread(target=self._run, daemon=True)
+        self._thread.start()
+
+    def stop(self) -> None:
+        """Stop reading keys and restore the terminal."""
+        self._stop.set()
+        if self._thread is not None:
+            self._thread.join(timeout=1)
+
+
+class MidiController:
+    """
+    Applies knobs and faders on a MIDI controller to a PlaybackState.
+
+    Each control change number is mapped to a target:
+        volume              The master volume
+        gain.<metric>       A metric's gain, from 0 to MAX_GAIN (1.0 at the centre)
+        threshold.<metric>  A metric's critical threshold, swept across its range
+                            with the warning threshold keeping its distance
+        poll_interval       The polling interval, from 1 to MAX_POLL_INTERVAL seconds
+
+    Messages arrive on the MIDI library's thread and are queued; the sonifier applies
+    them with apply_pending() on its own thread, before each poll is played.
+    """
+
+    def __init__(
+        self,
+        state: PlaybackState,
+        controls: Dict[int, str],
+        port_name: Optional[str] = None,
+    ):
+        """
+        Initialize the controller.
+
+        Args:
+            state: The playback state to control.
+            controls: The target of each control change number.
+            port_name: The MIDI input port. If None, the default input.
+        """
+        self.state = state
+        self.controls = {int(control): target for control, target in controls.items()}
+        self.port_name = port_name
+        self.port = None
+        self.pending: "queue.SimpleQueue" = queue.SimpleQueue()
+
+    def apply_pending(self) -> None:
+        """Apply the messages received since the last call, in order."""
+        while True:
+            try:
+                message = self.pending.get_nowait()
+            except queue.Empty:
+                return
+            self.handle_message(message)
+
+    def handle_message(self, message) -> None:
+        """
+        Apply a MIDI message.
+
+        Args:
+            message: A mido message; anything but a mapped "control_change" is ignored.
+        """
+        global POLL_INTERVAL
+        if message.type != "control_change" or message.control not in self.controls:
+            return
+        target = self.controls[message.control]
+        position = message.value / 127
+        if target == "volume":
+            self.state.volume = round(position, 2)
+            logger.info(f"Volume: {self.state.volume:.0%}")
+        elif target == "poll_interval":
+            POLL_INTERVAL = round(1 + position * (MAX_POLL_INTERVAL - 1), 1)
+            logger.info(f"Polling interval: {POLL_INTERVAL:g}s")
+        elif target.startswith("gain."):
+            metric_name = target[len("gain."):]
+            self.state.gains[metric_name] = round(position * MAX_GAIN, 2)
+            logger.info(f"{metric_name} gain: {self.state.gains[metric_name]:g}")
+        elif target.startswith("threshold."):
+            metric_name = target[len("threshold."):]
+            thresholds = SOUND_MAP[metric_name].get("thresholds")
+            if not thresholds:
+                return
+            low, high = SOUND_MAP[metric_name]["range"]
+            set_critical_threshold(metric_name, round(low + position * (high - low), 2))
+            logger.info(f"{metric_name} thresholds: {SOUND_MAP[metric_name]['thresholds']}")
+
+    def start(self) -> None:
+        """Start listening on the MIDI input port."""
+        try:
+            import mido
+
+            self.port = mido.open_input(self.port_name, callback=self.pending.put)
+            logger.info(f"Listening for MIDI controls on {self.port.name}")
+        except Exception as e:
+            logger.error(f"Failed to open MIDI controller input: {e}")
+
+    def stop(self) -> None:
+        """Stop listening."""
+        if self.port is not None:
+            self.port.close()
+            self.port = None
+
+
+def parse_osc(packet: bytes) -> List[Tuple[str, List]]:
+    """
+    Decodes an OSC packet.
+
+    Args:
+        packet: A UDP datagram holding an OSC message or bundle.
+
+    Returns:
+        Each message's address and arguments, with bundles flattened in order.
+        Integer (i), float (f), string (s), true (T) and false (F) arguments are
+        decoded; a message with any other type is skipped.
+
+    Raises:
+        ValueError: If the packet is truncated or not OSC.
+    """
+
+    def read_string(offset: int) -> Tuple[str, int]:
+        end = packet.find(b"\0", offset)
+        if end < 0:
+            raise ValueError("unterminated OSC string")
+        return packet[offset:end].decode("utf-8", errors="replace"), (end + 4) & ~3
+
+    if packet.startswith(b"#bundle\0"):
+        messages = []
+        offset = 16
+        while offset + 4 <= len(packet):
+            (size,) = struct.unpack(">i", packet[offset:offset + 4])
+            if size < 0 or offset + 4 + size > len(packet):
+                raise ValueError("truncated OSC bundle")
+            messages.extend(parse_osc(packet[offset + 4:offset + 4 + size]))
+            offset += 4 + size
+        return messages
+
+    address, offset = read_string(0)
+    if not address.startswith("/"):
+        raise ValueError(f"not an OSC address: '{address}'")
+    if offset >= len(packet):
+        return [(address, [])]
+    tags, offset = read_string(offset)
+    if not tags.startswith(","):
+        raise ValueError(f"not an OSC type tag string: '{tags}'")
+    args: List = []
+    for tag in tags[1:]:
+        if tag in "if":
+            if offset + 4 > len(packet):
+                raise ValueError("truncated OSC argument")
+            args.append(struct.unpack(">i" if tag == "i" else ">f", packet[offset:offset + 4])[0])
+            offset += 4
+        elif tag == "s":
+            value, offset = read_string(offset)
+            args.append(value)
+        elif tag in "TF":
+            args.append(tag == "T")
+        else:
+            logger.debug(f"Ignoring OSC message {address} with unsupported type '{tag}'")
+            return []
+    return [(address, args)]
+
+
+class OscController:
+    """
+    Applies OSC messages from a control surface to a PlaybackState.
+
+    Addresses, under OSC_PREFIX:
+        /volume <level>         Set the master volume, from 0.0 to 1.0
+        /mute <metric> [on]     Mute a metric, or unmute it when on is 0
+        /mute [on]              Silence all audio, or restore it when on is 0
+        /solo <metric> [on]     Solo a metric, or unsolo it when on is 0
+        /solo 0                 Clear all solos
+        /enable <metric> [on]   Fetch a metric again, or stop fetching it when on is 0
+        /pause [on]             Pause polling, or resume it when on is 0
+        /ack                    Acknowledge the active alarms
+        /test [frequency]       Play a test tone, at OSC_TEST_FREQUENCY by default
+
+    A trailing 0 on /ack or /test is a button being released and is ignored.
+    """
+
+    def __init__(self, state: PlaybackState, host: str = "127.0.0.1", port: int = 9000):
+        """
+        Initialize the controller.
+
+        Args:
+            state: The playback state to control.
+            host: The address to listen on.
+            port: The UDP port to listen on.
+        """
+        self.state = state
+        self.host = host
+        self.port = port
+        self._socket: Optional[socket.socket] = None
+
+    def handle_message(self, address: str, args: List) -> None:
+        """
+        Apply one OSC message.
+
+        Args:
+            address: The OSC address, e.g. "/sonify/volume".
+            args: The decoded arguments.
+        """
+        if not address.startswith(OSC_PREFIX + "/"):
+            logger.debug(f"Ignoring OSC message {address}")
+            return
+        command = address[len(OSC_PREFIX) + 1:]
+        numbers = [arg for arg in args if isinstance(arg, (int, float))]
+        names = [arg for arg in args if isinstance(arg, str)]
+        on = bool(numbers[0]) if numbers else True
+        if command == "volume" and numbers:
+            self.state.volume = round(min(1.0, max(0.0, float(numbers[0]))), 2)
+            logger.info(f"Volume: {self.state.volume:.0%}")
+        elif command == "mute" and names:
+            metric_name = names[0]
+            if metric_name not in SOUND_MAP:
+                logger.warning(f"Ignoring OSC mute of unknown metric '{metric_name}'")
+            elif on:
+                self.state.muted.add(metric_name)
+                logger.info(f"Muted {metric_name}")
+            else:
+                self.state.muted.discard(metric_name)
+                logger.info(f"Unmuted {metric_name}")
+        elif command == "mute":
+            self.state.silenced = on
+            logger.info("Audio silenced" if on else "Audio restored")
+        elif command in ("solo", "enable") and names:
+            metric_name = names[0]
+            if metric_name not in SOUND_MAP:
+                logger.warning(f"Ignoring OSC {command} of unknown metric '{metric_name}'")
+            elif command == "solo" and on:
+                self.state.solo.add(metric_name)
+                logger.info(f"Soloed {metric_name}")
+            elif command == "solo":
+                self.state.solo.discard(metric_name)
+                logger.info(f"Unsoloed {metric_name}")
+            elif on:
+                self.state.disabled.discard(metric_name)
+                logger.info(f"Enabled {metric_name}")
+            else:
+                self.state.disabled.add(metric_name)
+                logger.info(f"Disabled {metric_name}")
+        elif command == "solo" and not on:
+            self.state.solo.clear()
+            logger.info("Cleared solos")
+        elif command == "pause":
+            self.state.paused = on
+            logger.info("Paused" if on else "Resumed")
+        elif command == "ack" and on:
+            self.state.acknowledge()
+            logger.info("Alarms acknowledged")
+        elif command == "test" and on:
+            frequency = numbers[0] if numbers else OSC_TEST_FREQUENCY
+            logger.info(f"Test tone at {frequency:g} Hz")
+            play_note(frequency, OSC_TEST_DURATION, self.state.volume)
+        elif command not in ("ack", "test"):
+            logger.debug(f"Ignoring unknown OSC message {address}")
+
+    def _run(self) -> None:
+        while self._socket is not None:
+            try:
+                packet, _ = self._socket.recvfrom(65535)
+            except OSError:
+                break
+            try:
+                messages = parse_osc(packet)
+            except ValueError as e:
+                logger.debug(f"Ignoring malformed OSC packet: {e}")
+                continue
+            for address, args in messages:
+                self.handle_message(address, args)
+
+    def start(self) -> None:
+        """Start listening for OSC messages."""
+        try:
+            self._socket = socket.socket(socket.AF_INET, socket.SOCK_DGRAM)
+            self._socket.bind((self.host, self.port))
+        except OSError as e:
+            logger.error(f"Failed to listen for OSC on {self.host}:{self.port}: {e}")
+            self._socket = None
+            return
+        threading.Thread(target=self._run, daemon=True).start()
+        logger.info(f"Listening for OSC messages on {self.host}:{self.port}")
+
+    def stop(self) -> None:
+        """Stop listening."""
+        sock, self._socket = self._socket, None
+        if sock is not None:
+            sock.close()
+
+
+# --- Sonifier ---
+def detect_cluster_events(metric_name: str, previous: Optional[Dict], extra: Dict) -> List[str]:
+    """
+    Works out which discrete events happened between two polls of a metric.
+
+    Args:
+        metric_name: The name of the metric.
+        previous: The extra data from the previous poll, or None on the first poll.
+        extra: The extra data from this poll.
+
+    Returns:
+        The events, each one of CLUSTER_EVENTS.
+    """
+    if not previous:
+        return []
+    events = []
+    counters = {
+        "pod_status": [("restarts", "pod_restart")],
+        "replicas": [("generations", "deploy")],
+        "node_pressure": [
+            ("joined", "node_join"), ("lost", "node_lost"),
+            ("scale_ups", "scale_up"), ("scale_downs", "scale_down"),
+        ],
+        "memory_evictions": [("evictions", "memory_eviction")],
+        "disk_evictions": [("evictions", "disk_eviction")],
+        "admission_failures": [("rejections", "admission_failure")],
+        "pod_security": [
+            ("rejections", "pod_security_violation"), ("warned", "pod_security_violation"),
+        ],
+        "pending_volumes": [("failures", "provisioning_failed")],
+    }.get(metric_name, [])
+    reclaiming = set(extra.get("reclaiming") or [])
+    if "reclaiming" in previous and reclaiming - set(previous["reclaiming"]):
+        events.append("eviction_threshold")
+    counts = (previous.get("nodes"), extra.get("nodes"))
+    if metric_name == "node_pressure" and "joined" not in extra and None not in counts:
+        # Only the counts, e.g. replayed from a file
+        if counts[1] > counts[0]:
+            events.append("node_join")
+        elif counts[1] < counts[0]:
+            events.append("node_lost")
+    for key, event_type in counters:
+        if key in previous and key in extra and extra[key] > previous[key]:
+            if event_type not in events:
+                events.append(event_type)
+    before = previous.get("rollouts") or {}
+    generations = extra.get("rollout_generations") or {}
+    before_generations = previous.get("rollout_generations") or {}
+    for name, state in (extra.get("rollouts") or {}).items():
+        # A rollout that started and finished between two polls is never seen progressing
+        generation = generations.get(name, 0)
+        rolled = generation > before_generations.get(name, generation)
+        if state == "complete" and (before.get(name) == "progressing" or rolled):
+            events.append("rollout_succeeded")
+        elif state == "failed" and (before.get(name) not in (None, "failed") or rolled):
+            events.append("rollout_failed")
+    return events
+
+
+def is_worse(metric_name: str, value: float, other: float) -> bool:
+    """
+    Whether a value is further in its metric's bad direction than another.
+
+    Args:
+        metric_name: The name of the metric (a SOUND_MAP key).
+        value: The value to compare.
+        other: The value to compare against.
+
+    Returns:
+        True if value is worse than other.
+    """
+    thresholds = SOUND_MAP[metric_name].get("thresholds")
+    if thresholds and thresholds[0] > thresholds[1]:
+        return value < other
+    return value > other
+
+
+class SessionSummary:
+    """What happened while the monitor ran: how long, how many alarms, the worst values."""
+
+    def __init__(self):
+        """Start counting from now."""
+        self.started = datetime.now(timezone.utc)
+        self.alarms = 0
+        self.worst: Dict[str, SonificationEvent] = {}
+
+    def record(self, event: SonificationEvent, transition: Optional[str]) -> None:
+        """
+        Count one sonified sample.
+
+        Args:
+            event: The sample's event.
+            transition: "fired" or "cleared" if it changed an alarm, else None.
+        """
+        if transition == "fired":
+            self.alarms += 1
+        worst = self.worst.get(event.metric)
+        if worst is None or is_worse(event.metric, event.value, worst.value):
+            self.worst[event.metric] = event
+
+    def lines(self, now: Optional[datetime] = None) -> List[str]:
+        """
+        Describes the session.
+
+        Args:
+            now: When the session ended. If None, now.
+
+        Returns:
+            A heading followed by one line per metric, ready for printing.
+        """
+        seconds = int(((now or datetime.now(timezone.utc)) - self.started).total_seconds())
+        hours, rest = divmod(seconds, 3600)
+        duration = f"{hours}:{rest // 60:02d}:{rest % 60:02d}"
+        lines = [f"Session summary: ran {duration}, raised {self.alarms} alarm(s)"]
+        for metric_name, event in self.worst.items():
+            metric_config = SOUND_MAP[metric_name]
+            value = f"{event.value:.2f} {metric_config['unit']}".rstrip()
+            lines.append(
+                f"  {metric_config['metric_name']}: worst {value} ({event.severity})"
+                f" in {event.namespace}"
+            )
+        return lines
+
+
+@dataclass
+class MetricSample:
+    """One metric fetched for one namespace, waiting to be mapped and played."""
+
+    metric: str
+    namespace: str
+    data: Optional[Tuple[float, Dict]]
+    stale: bool = False
+    discovered: bool = False
+    forbidden: bool = False
+    derived: bool = False
+    denied: Optional[Tuple[str, Optional[str]]] = None  # The (resource, namespace) just forbidden
+
+
+@dataclass
+class Poll:
+    """One poll handed from the fetcher thread of Sonifier.run_queued() to its playback thread."""
+
+    samples: Optional[List[MetricSample]]  # None for a heartbeat tick between polls
+    changes: List[Callable[[], None]] = field(default_factory=list)  # See Sonifier.changes
+
+
+class Sonifier:
+    """
+    Fetches metrics from a source, maps them onto notes and colors, plays them
+    and mirrors the resulting events to outputs. Use SonifierBuilder to create one.
+    """
+
+    def __init__(
+        self,
+        source: MetricSource,
+        metrics: List[str],
+        namespace: str = "default",
+        outputs: Optional[List[MetricOutput]] = None,
+        note_duration: float = 0.5,
+        use_color: bool = False,
+        arpeggio: bool = False,
+        heartbeat: bool = False,
+        dry_run: bool = False,
+        keyboard: bool = False,
+        audio: bool = True,
+        audio_sink: Optional[AudioSink] = None,
+        midi_controls: Optional[Dict[int, str]] = None,
+        midi_input: Optional[str] = None,
+        osc_port: Optional[int] = None,
+        osc_host: str = "127.0.0.1",
+        namespace_include: Optional[List[str]] = None,
+        namespace_exclude: Optional[List[str]] = None,
+        namespace_refresh: float = 60,
+        event_earcons: Optional[Dict[str, str]] = None,
+        alarm_earcons: Optional[Dict[str, str]] = None,
+        a4: float = 440.0,
+        state_file: Optional[str] = None,
+        fetch_timeout: Optional[float] = None,
+        sample_queue: int = 0,
+        summary: bool = False,
+        permission_earcon: Optional[str] = None,
+        lead: Optional[str] = None,
+        accompaniment_volume: float = 1.0,
+        on_change_only: bool = False,
+        change_delta: float = 0.0,
+        escalate_after: float = 0,
+        max_escalation: int = 3,
+        reminder_volume: float = 0.0,
+        duck_volume: float = 1.0,
+        duck_release: float = 10,
+        audio_fallback: Optional[List[str]] = None,
+        gauge: str = "none",
+        annotation_refresh: float = 0,
+        profile_refresh: float = 0,
+    ):
+        """
+        Initialize the sonifier.
+
+        Args:
+            source: Where to fetch metrics from.
+            metrics: The metrics to sonify, in playing order.
+            namespace: The Kubernetes namespace to monitor.
+            outputs: Where to mirror each sonified sample.
+            note_duration: The duration of each note in seconds.
+            use_color: Whether to print colored output lines.
+            arpeggio: Whether to arpeggiate multi-valued metrics.
+            heartbeat: Whether to tick a soft metronome while the cluster is reachable,
+                darker while it is unhealthy.
+            dry_run: Whether to print the mapped notes and colors instead of playing them.
+            keyboard: Whether to enable interactive keyboard controls.
+            audio: Whether to play the built-in audio, e.g. False when only streaming events.
+            audio_sink: Where to play notes. If None, the current audio sink.
+            midi_controls: The target of each MIDI control change, see MidiController.
+                If None, MIDI controller input is off.
+            midi_input: The MIDI input port to read controls from. If None, the default input.
+            osc_port: The UDP port to accept OSC messages on, see OscController.
+                If None, OSC input is off.
+            osc_host: The address to accept OSC messages on.
+            namespace_include: Regexes of namespaces to discover and monitor alongside
+                namespace. If None or empty, only namespace is monitored.
+            namespace_exclude: Regexes of discovered namespaces to skip.
+            namespace_refresh: Seconds between namespace discovery refreshes.
+            event_earcons: The earcon played for each cluster event, such as "fanfare"
+                for "rollout_succeeded".
+            alarm_earcons: The earcon played when an alarm is "fired" or "cleared".
+            a4: The frequency of A4 in Hz, for earcons written as note names.
+            state_file: Where to keep the alarm state and last samples between runs.
+                If None, every run starts with no alarms.
+            fetch_timeout: Seconds to wait for each metric before reporting it as stale.
+                If None, wait indefinitely.
+            sample_queue: How many polls run() may fetch ahead of playing them, on a
+                fetcher thread. If 0, each poll is fetched and played in turn.
+            summary: Whether shutdown() prints a session summary.
+            permission_earcon: The earcon played the first time a metric is disabled
+                because the service account may not read a resource, once per resource
+                and namespace.
+            lead: The metric played as the lead voice, e.g. "cluster_health".
+                If None, every metric plays at its own volume.
+            accompaniment_volume: What the volume of the other metrics is scaled by
+                while a lead is set.
+            on_change_only: Whether a metric only sounds when its note changes, it raises
+                or clears an alarm or a cluster event is detected.
+            change_delta: With on_change_only, how far a metric's value may move from the
+                value it last sounded at before it sounds again. If 0, only note changes
+                count.
+            escalate_after: Seconds an alarm may go unacknowledged before it escalates:
+                it is played louder and repeated faster, one step more after each further
+                interval. If 0, alarms never escalate.
+            max_escalation: How many steps an alarm escalates by at most.
+            reminder_volume: What the volume of an acknowledged alarm is scaled by.
+                If 0, acknowledged alarms are silent.
+            duck_volume: What the volume of the metrics that aren't in alarm is scaled by
+                right after an alarm earcon plays. If 1.0, they are never ducked.
+            duck_release: Seconds the ducked metrics take to return to full volume.
+            audio_fallback: How alarms are marked in the terminal while notes fail to
+                play, see AUDIO_FALLBACKS: "bell" rings the bell when an alarm fires or
+                escalates, "flash" prints critical lines inverted.
+            gauge: One of GAUGE_STYLES, for a bar after each output line's value.
+            annotation_refresh: Seconds between reads of the sonify.k8s.io/ annotations
+                of each namespace's workloads, see workload_sound_settings(). If 0, they
+                are ignored.
+            profile_refresh: Seconds between reconciles of the SonificationProfile custom
+                resources, see reconcile_profiles(). If 0, they are ignored.
+        """
+        self.source = source
+        self.metrics = metrics
+        self.outputs = outputs or []
+        self.note_duration = note_duration
+        self.use_color = use_color
+        self.arpeggio = arpeggio
+        self.heartbeat = heartbeat
+        self.dry_run = dry_run
+        self.audio = audio
+        self.audio_sink = audio_sink
+        self.state = PlaybackState(namespace)
+        # Metrics muted or soloed in their sound map entries start out that way
+        self.state.muted = {m for m in metrics if SOUND_MAP.get(m, {}).get("muted")}
+        self.state.solo = {m for m in metrics if SOUND_MAP.get(m, {}).get("solo")}
+        self.controller = KeyboardController(self.state, metrics) if keyboard else None
+        self.midi_controller = (
+            MidiController(self.state, midi_controls, midi_input) if midi_controls else None
+        )
+        self.osc_controller = (
+            OscController(self.state, osc_host, osc_port) if osc_port is not None else None
+        )
+        self.health: Optional[str] = "ok"  # Worst severity of the last poll, None if unreachable
+        self.connected = False
+        self.stopping = threading.Event()
+        self.namespace_include = namespace_include or []
+        self.namespace_exclude = namespace_exclude or []
+        self.namespace_refresh = namespace_refresh
+        self.event_earcons = event_earcons or {}
+        self.alarm_earcons = alarm_earcons or {}
+        self.a4 = a4
+        self.state_file = state_file
+        self.fetch_timeout = fetch_timeout
+        self.sample_queue = sample_queue
+        self.summary = summary
+        self.session = SessionSummary()
+        self.permission_earcon = permission_earcon
+        self.lead = lead
+        self.accompaniment_volume = accompaniment_volume
+        self.on_change_only = on_change_only
+        self.change_delta = change_delta
+        self.sounded: Dict[str, Tuple[int, float]] = {}
+        self.escalate_after = escalate_after
+        self.max_escalation = max_escalation
+        self.reminder_volume = reminder_volume
+        self.escalations: Dict[str, Tuple[float, int]] = {}
+        self.alarm_replays: Dict[str, Callable[[int], None]] = {}  # see escalate_alarms()
+        self.duck_volume = duck_volume
+        self.duck_release = duck_release
+        self.ducked_at: Optional[float] = None
+        self.audio_fallback = audio_fallback or []
+        self.gauge = gauge
+        self.annotation_refresh = annotation_refresh
+        self.workloads: Dict[str, Dict] = {}  # workload_sound_settings() per namespace
+        self.next_annotations: Dict[str, float] = {}
+        self.profile_refresh = profile_refresh
+        self.profiles: Dict[str, Dict] = {}  # profile_settings() per namespace
+        self.next_profiles = 0.0
+        self.permissions_denied: set = set()  # (resource, namespace) pairs the earcon played for
+        self.forbidden: Dict[Tuple[Optional[str], str], str] = {}
+        self.pending: Dict[Tuple[str, str], concurrent.futures.Future] = {}
+        self.stale: set = set()
+        self.discovered: List[str] = []
+        self.next_discovery = 0.0
+        self.tick_namespace = namespace
+        self.target = (self.state.context, namespace)
+        self.values: Dict[str, Optional[float]] = {}
+        self.extras: Dict[Tuple[str, str], Dict] = {}
+        self.since_reminder: Dict[str, int] = {}  # Polls since each namespace's deprecated_api
+        self.history: Dict[Tuple[str, str], Tuple[float, float]] = {}
+        self.recent: Dict[Tuple[str, str], deque] = {}
+        # Alarm and history resets decided by collect(), applied before the next process()
+        # so the fetcher thread never touches what playback is iterating
+        self.changes: List[Callable[[], None]] = []
+        self.event_hooks: List[Callable[[SonificationEvent], None]] = []
+        self.alarm_hooks: List[Callable[[SonificationEvent, str], None]] = []
+        self.error_hooks: List[Callable[[Exception], None]] = []
+
+    def on_event(self, callback: Callable[[SonificationEvent], None]) -> Callable:
+        """Call callback(event) for every sonified sample. Usable as a decorator."""
+        self.event_hooks.append(callback)
+        return callback
+
+    def on_alarm(self, callback: Callable[[SonificationEvent, str], None]) -> Callable:
+        """Call callback(event, transition) when an alarm is "fired" or "cleared"."""
+        self.alarm_hooks.append(callback)
+        return callback
+
+    def on_error(self, callback: Callable[[Exception], None]) -> Callable:
+        """Call callback(error) when a tick fails."""
+        self.error_hooks.append(callback)
+        return callback
+
+    def _call_hooks(self, hooks: List[Callable], *args) -> None:
+        """Call each hook, logging rather than propagating its errors."""
+        for hook in hooks:
+            try:
+                hook(*args)
+            except Exception as e:
+                logger.error(f"Hook {getattr(hook, '__name__', hook)} failed: {e}")
+
+    def fail(self, error: Exception) -> None:
+        """Log a failed tick and notify the error hooks."""
+        logger.error(f"An error occurred: {error}", exc_info=True)
+        self._call_hooks(self.error_hooks, error)
+
+    def start(self) -> bool:
+        """
+        Restore the saved state, connect the source and start the controls, if not done yet.
+
+        Returns:
+            True if the source is connected.
+        """
+        if not self.connected:
+            if self.audio_sink is not None:
+                set_audio_sink(self.audio_sink)
+            self.load_state()
+            self.connected = self.source.connect()
+            if self.connected and self.controller:
+                self.controller.start()
+            if self.connected and self.midi_controller:
+                self.midi_controller.start()
+            if self.connected and self.osc_controller:
+                self.osc_controller.start()
+        return self.connected
+
+    def load_state(self) -> None:
+        """
+        Restore the alarm state and last samples saved by a previous run, so alarms
+        that are still active don't fire again and acknowledged ones stay quiet.
+        A state file saved for another context or namespace is ignored.
+        """
+        if not self.state_file or not os.path.exists(self.state_file):
+            return
+        try:
+            with open(self.state_file) as f:
+                saved = json.load(f)
+            target = tuple(saved["target"])
+            active_alarms = set(saved["active_alarms"])
+            acknowledged = set(saved["acknowledged"])
+            extras = {(namespace, metric): extra for namespace, metric, extra in saved["samples"]}
+        except (OSError, ValueError, KeyError, TypeError) as e:
+            logger.warning(f"Ignoring unreadable state file {self.state_file}: {e}")
+            return
+        if target != self.target:
+            logger.info(f"Ignoring state file {self.state_file} saved for another target")
+            return
+        self.state.active_alarms = active_alarms
+        self.state.acknowledged = acknowledged & active_alarms
+        self.extras = extras
+        logger.info(f"Restored {len(active_alarms)} active alarm(s) from {self.state_file}")
+
+    def save_state(self) -> None:
+        """Write the alarm state and last samples to the state file, replacing it atomically."""
+        if not self.state_file:
+            return
+        saved = {
+            "target": list(self.target),
+            "active_alarms": sorted(self.state.active_alarms),
+            "acknowledged": sorted(self.state.acknowledged),
+            "samples": [
+                [namespace, metric, extra] for (namespace, metric), extra in self.extras.items()
+            ],
+        }
+        temporary = f"{self.state_file}.tmp"
+        try:
+            with open(temporary, "w") as f:
+                json.dump(saved, f, default=str)
+            os.replace(temporary, self.state_file)
+        except OSError as e:
+            logger.error(f"Failed to save state to {self.state_file}: {e}")
+
+    def fetch(self, metric_name: str, namespace: str) -> Optional[Tuple[float, Dict]]:
+        """
+        Fetch a metric from the source, giving up after fetch_timeout.
+
+        A fetch that times out is left to finish in the background and its result is
+        discarded. Until it finishes, later ticks report the metric as stale right away
+        instead of waiting on it again.
+
+        Args:
+            metric_name: The name of the metric.
+            namespace: The namespace to fetch it for.
+
+        Returns:
+            The metric's data, or None if it is unavailable or stale; when stale,
+            (namespace, metric_name) is in self.stale.
+        """
+        key = (namespace, metric_name)
+        self.stale.discard(key)
+        if not self.fetch_timeout:
+            return self.source.fetch(metric_name, namespace=namespace)
+        if key in self.pending and not self.pending[key].done():
+            self.stale.add(key)
+            return None
+
+        future: concurrent.futures.Future = concurrent.futures.Future()
+
+        def run() -> None:
+            try:
+                future.set_result(self.source.fetch(metric_name, namespace=namespace))
+            except Exception as e:
+                future.set_exception(e)
+
+        # A daemon thread rather than an executor, so a hung call can't block exit
+        threading.Thread(target=run, daemon=True).start()
+        try:
+            data = future.result(timeout=self.fetch_timeout)
+        except concurrent.futures.TimeoutError:
+            self.pending[key] = future
+            self.stale.add(key)
+            return None
+        self.pending.pop(key, None)
+        return data
+
+    def sonify_metric(
+        self, metric_name: str, namespace: Optional[str] = None
+    ) -> Optional[SonificationEvent]:
+        """
+        Fetch, map, play and report one metric.
+
+        Args:
+            metric_name: The name of the metric.
+            namespace: The namespace to fetch it for. If None, the active namespace.
+
+        Returns:
+            The resulting event, or None if the metric could not be fetched.
+        """
+        namespace = namespace or self.state.namespace
+        return self.sonify_sample(
+            self.sample(metric_name, namespace, discovered=namespace != self.state.namespace)
+        )
+
+    def sample(self, metric_name: str, namespace: str, discovered: bool = False) -> MetricSample:
+        """
+        Fetch one metric, see fetch().
+
+        Args:
+            metric_name: The name of the metric.
+            namespace: The namespace to fetch it for.
+            discovered: Whether namespace was discovered rather than the active one.
+
+        Returns:
+            The sample, whose data is None if the metric is unavailable or stale.
+        """
+        if self.is_forbidden(metric_name, namespace):
+            return MetricSample(metric_name, namespace, None, forbidden=True)
+        if "expression" in SOUND_MAP[metric_name] or "rate_of" in SOUND_MAP[metric_name]:
+            # Evaluated when mapped, once the metrics it reads have been fetched
+            return MetricSample(metric_name, namespace, None, discovered=discovered, derived=True)
+        try:
+            data = self.fetch(metric_name, namespace)
+        except MissingPermission as e:
+            # Cluster-scoped resources such as nodes are forbidden in every namespace
+            self.forbidden[(e.namespace, metric_name)] = str(e)
+            logger.error(
+                f"Disabling {SOUND_MAP[metric_name]['metric_name']}: {e}. "
+                "Grant the permission and restart to monitor it again."
+            )
+            return MetricSample(
+                metric_name, namespace, None, forbidden=True, denied=(e.resource, e.namespace)
+            )
+        stale = (namespace, metric_name) in self.stale
+        return MetricSample(metric_name, namespace, data, stale=stale, discovered=discovered)
+
+    def is_forbidden(self, metric_name: str, namespace: str) -> bool:
+        """Whether a metric was disabled in a namespace for lack of permission."""
+        return (namespace, metric_name) in self.forbidden or (None, metric_name) in self.forbidden
+
+    def escalation(self, key: str) -> Tuple[int, bool]:
+        """
+        Track how long an alarm has gone unacknowledged.
+
+        Args:
+            key: The key the alarm is tracked under.
+
+        Returns:
+            A tuple of (level, raised): how many escalate_after intervals the alarm has
+            been active and unacknowledged, up to max_escalation, and whether the level
+            went up with this sample.
+        """
+        state = self.state
+        if not self.escalate_after or key not in state.active_alarms or key in state.acknowledged:
+            self.escalations.pop(key, None)
+            return 0, False
+        since, last_level = self.escalations.get(key, (time.time(), 0))
+        level = min(self.max_escalation, int((time.time() - since) // self.escalate_after))
+        self.escalations[key] = (since, level)
+        return level, level > last_level
+
+    def escalate_alarms(self) -> None:
+        """
+        Replay the alarms whose escalation level went up since they last sounded, so
+        they escalate every escalate_after seconds even when polls are further apart.
+        Called while waiting for the next poll, on the thread that plays the polls.
+        """
+        for key, replay in list(self.alarm_replays.items()):
+            level, escalated = self.escalation(key)
+            if key not in self.escalations:
+                # Resolved, acknowledged or forgotten
+                del self.alarm_replays[key]
+            elif escalated and not self.state.paused:
+                replay(level)
+
+    def replay_alarm(
+        self, metric_name: str, mapped: MappedNote, volume: float, voice: Optional[Dict],
+        level: int,
+    ) -> None:
+        """
+        Repeat an alarm's note faster and louder at an escalation level, like the
+        poll that sounded it would.
+
+        Args:
+            metric_name: The name of the metric in alarm.
+            mapped: Its note, as sounded by the poll.
+            volume: Its volume before escalation.
+            voice: Its voice, or None.
+            level: The escalation level, at least 1.
+        """
+        logger.warning(
+            f"Escalating {SOUND_MAP[metric_name]['metric_name']} alarm to level {level}: "
+            f"unacknowledged for {level * self.escalate_after:g}s"
+        )
+        if not self.audio or self.dry_run or not self.state.is_audible(metric_name):
+            return
+        volume += (1.0 - volume) * level / self.max_escalation
+        alarm_earcon = self.alarm_earcons.get("fired")
+        if alarm_earcon:
+            play_earcon(alarm_earcon, volume=volume, a4=self.a4)
+        play_arpeggio(
+            [mapped.frequency] * (level + 1),
+            mapped.duration or self.note_duration,
+            volume=volume * mapped.velocity,
+            voice=voice,
+        )
+        if "bell" in (self.audio_fallback if audio_failed.is_set() else []):
+            sys.stdout.write("\a")
+            sys.stdout.flush()
+
+    def volatility(self, namespace: str, metric_name: str, value: float) -> float:
+        """
+        Records a value and gets how much the metric has been jittering.
+
+        Args:
+            namespace: The namespace of the value.
+            metric_name: The name of the metric.
+            value: The latest value.
+
+        Returns:
+            The standard deviation of the last VOLATILITY_WINDOW values as a share of
+            VOLATILITY_FULL_SCALE of the metric's range, at most 1.0.
+        """
+        recent = self.recent.setdefault(
+            (namespace, metric_name), deque(maxlen=VOLATILITY_WINDOW)
+        )
+        recent.append(value)
+        if len(recent) < 3:
+            return 0.0
+        low, high = SOUND_MAP[metric_name]["range"]
+        return min(1.0, float(np.std(recent)) / ((high - low) * VOLATILITY_FULL_SCALE))
+
+    def pan(self, namespace: str, source: Optional[str]) -> Optional[float]:
+        """
+        Places a fused metric's note in the stereo field.
+
+        Args:
+            namespace: The namespace of the sample.
+            source: "namespace" to spread the monitored namespaces from left to right,
+                or the metric whose level pans the note.
+
+        Returns:
+            The pan from -1.0 (left) to 1.0 (right), or None if source is None.
+        """
+        if source is None:
+            return None
+        if source == "namespace":
+            namespaces = sorted(self.monitored_namespaces())
+            if len(namespaces) < 2 or namespace not in namespaces:
+                return 0.0
+            return round(-1.0 + 2.0 * namespaces.index(namespace) / (len(namespaces) - 1), 2)
+        value = self.current_value(source)
+        return 0.0 if value is None else metric_level(source, value) * 2 - 1
+
+    def duck_gain(self) -> float:
+        """
+        Gets what the metrics that aren't in alarm are scaled by after an alarm earcon.
+
+        Returns:
+            duck_volume right after the earcon, rising linearly back to 1.0 over
+            duck_release.
+        """
+        if self.ducked_at is None:
+            return 1.0
+        elapsed = time.time() - self.ducked_at
+        if elapsed >= self.duck_release:
+            self.ducked_at = None
+            return 1.0
+        return self.duck_volume + (1.0 - self.duck_volume) * elapsed / self.duck_release
+
+    def should_sound(self, key: str, index: int, value: float, news: bool) -> bool:
+        """
+        Check whether a sample sounds, given on_change_only, and note what it sounded at.
+
+        Args:
+            key: The key the metric's alarm is tracked under.
+            index: The index of the note the value maps to.
+            value: The value.
+            news: Whether the sample changed an alarm or came with cluster events.
+
+        Returns:
+            True unless on_change_only is set and the metric sounded at the same note,
+            within change_delta, last time.
+        """
+        last = self.sounded.get(key)
+        if self.on_change_only and last is not None and not news:
+            last_index, last_value = last
+            moved = self.change_delta > 0 and abs(value - last_value) > self.change_delta
+            if index == last_index and not moved:
+                return False
+        self.sounded[key] = (index, value)
+        return True
+
+    def sonify_sample(self, sample: MetricSample) -> Optional[SonificationEvent]:
+        """
+        Map, play and report one fetched metric.
+
+        Args:
+            sample: The fetched metric.
+
+        Returns:
+            The resulting event, or None if the metric could not be fetched.
+        """
+        state = self.state
+        metric_name, namespace = sample.metric, sample.namespace
+        if sample.forbidden:
+            if self.permission_earcon and sample.denied and (
+                sample.denied not in self.permissions_denied
+            ):
+                self.permissions_denied.add(sample.denied)
+                if self.audio and not self.dry_run and state.is_audible(metric_name):
+                    play_earcon(self.permission_earcon, volume=state.volume, a4=self.a4)
+            return None
+        if sample.derived:
+            value = self.current_value(metric_name)
+            if value is None:
+                # E.g. the first tick of a rate, or a metric the expression reads is missing
+                logger.debug(f"No value for derived metric: {metric_name}")
+                return None
+            entry = SOUND_MAP[metric_name]
+            if "rate_of" in entry:
+                sample.data = value, {"rate_of": entry["rate_of"]}
+            elif "fuse" in entry:
+                sample.data = value, {"fuse": entry["fuse"]}
+            else:
+                sample.data = value, {"expression": entry["expression"]}
+        if sample.data is None:
+            if sample.stale:
+                report_stale(SOUND_MAP[metric_name], self.fetch_timeout, self.use_color)
+            else:
+                logger.warning(f"Failed to get data for metric: {metric_name}")
+            return None
+        metric_value, extra_data = sample.data
+        self.values[metric_name] = metric_value
+        volatility = self.volatility(namespace, metric_name, metric_value)
+        profile = self.profiles.get(namespace, {})
+        severity = get_severity(
+            metric_name, metric_value, profile.get("thresholds", {}).get(metric_name)
+        )
+        # Alarms in discovered namespaces are tracked apart from the active namespace's
+        alarm_key = f"{namespace}/{metric_name}" if sample.discovered else metric_name
+        transition = state.update_alarm(alarm_key, severity)
+        extra_data = extra_data or {}
+        cluster_events = detect_cluster_events(
+            metric_name, self.extras.get((namespace, metric_name)), extra_data
+        )
+        self.extras[(namespace, metric_name)] = extra_data
+        if metric_name == "deprecated_apis" and self.reminder_due(namespace, metric_value):
+            cluster_events.append("deprecated_api")
+
+        mapped = map_metric(metric_name, metric_value)
+        index, frequency, note_name, color = mapped[:4]
+        event = SonificationEvent(
+            metric_name,
+            metric_value,
+            note_name,
+            frequency,
+            color,
+            severity,
+            index=index,
+            namespace=namespace,
+            extra=dict(extra_data, cluster_events=cluster_events) if cluster_events else extra_data,
+        )
+        self.session.record(event, transition)
+        for output in self.outputs:
+            output.emit(event)
+            if transition:
+                output.alarm(event, transition)
+        self._call_hooks(self.event_hooks, event)
+        if transition:
+            self._call_hooks(self.alarm_hooks, event, transition)
+
+        values = extra_data.get("values") if extra_data else None
+        level, escalated = self.escalation(alarm_key)
+        if escalated:
+            logger.warning(
+                f"Escalating {SOUND_MAP[metric_name]['metric_name']} alarm to level {level}: "
+                f"unacknowledged for {level * self.escalate_after:g}s"
+            )
+        news = bool(transition or cluster_events or escalated)
+        workload = metric_sound_settings(self.workloads.get(namespace, {}), metric_name)
+        workload = {**profile.get("sound", {}), **workload}
+        if workload.get("muted"):
+            logger.debug(f"{SOUND_MAP[metric_name]['metric_name']} in {namespace} is muted")
+        elif not self.should_sound(alarm_key, index, metric_value, news):
+            logger.debug(f"{SOUND_MAP[metric_name]['metric_name']} unchanged, not sounding it")
+        elif self.dry_run:
+            print(format_dry_run_line(metric_name, metric_value, severity))
+        elif self.audio and state.is_audible(
+            metric_name, alarm_key, reminders=self.reminder_volume > 0
+        ):
+            # Critical samples, then those of the higher priority metrics, play first
+            priority = note_priority.set(
+                workload.get("priority", SOUND_MAP[metric_name].get("priority", 0))
+                + (CRITICAL_PRIORITY if severity == "critical" else 0)
+            )
+            voice = metric_voice(metric_name, metric_value, self.current_value, volatility)
+            if "voice" in workload:
+                # The app team's instrument, keeping the sound map's other voice settings
+                voice = dict(voice or {}, **workload["voice"])
+            pan = self.pan(namespace, SOUND_MAP[metric_name].get("fuse", {}).get("pan"))
+            if pan is not None:
+                voice = dict(voice or {"type": "sine"}, pan=pan)
+            volume = state.metric_volume(metric_name)
+            if self.lead and metric_name != self.lead:
+                volume *= self.accompaniment_volume
+            if alarm_key in self.escalations:
+                # Escalates the alarm if it outlasts the wait for the next poll
+                self.alarm_replays[alarm_key] = functools.partial(
+                    self.replay_alarm, metric_name, mapped, volume, voice
+                )
+            if alarm_key in state.acknowledged:
+                # A subdued reminder that the acknowledged alarm is still active
+                volume *= self.reminder_volume
+            elif level:
+                # Each level covers a share of the way up to full volume
+                volume += (1.0 - volume) * level / self.max_escalation
+            for event_type in cluster_events:
+                play_event(event_type, volume=volume)
+                if event_type in self.event_earcons:
+                    play_earcon(self.event_earcons[event_type], volume=volume, a4=self.a4)
+            alarm_earcon = self.alarm_earcons.get(transition or ("fired" if escalated else ""))
+            if alarm_earcon:
+                play_earcon(alarm_earcon, volume=volume, a4=self.a4)
+                if self.duck_volume < 1.0:
+                    self.ducked_at = time.time()
+            if alarm_key not in state.active_alarms and not alarm_earcon:
+                # Routine metrics make way for the alarm earcons
+                volume *= self.duck_gain()
+            volume *= mapped.velocity
+            duration = mapped.duration or self.note_duration
+            sequence = self.arpeggio or SOUND_MAP[metric_name].get("sequence")
+            if sequence and values and len(values) > 1:
+                # Play every value of a multi-valued metric in sequence
+                # Detune the values marked sour, e.g. the nodes under pressure
+                sour = extra_data.get("sour") or [False] * len(values)
+                play_arpeggio(
+                    [map_metric(metric_name, v)[1] * (SOUR_INTERVAL if bad else 1)
+                     for v, bad in zip(values, sour)],
+                    duration,
+                    volume=volume,
+                    voice=voice,
+                    voices=row_voices(len(values), voice) if SOUND_MAP[metric_name].get("rows")
+                    else None,
+                )
+            elif level:
+                # An escalated alarm repeats its note faster at each level
+                play_arpeggio(
+                    [frequency] * (level + 1),
+                    duration,
+                    volume=volume,
+                    voice=voice,
+                )
+            else:
+                # Play the sound through the audio sink
+                play_note(
+                    frequency,
+                    duration,
+                    volume=volume,
+                    voice=voice,
+                )
+            note_priority.reset(priority)
+
+        # Without a working sound card, alarms still stand out in the terminal
+        fallback = self.audio_fallback if audio_failed.is_set() else []
+        if "bell" in fallback and (transition == "fired" or escalated):
+            sys.stdout.write("\a")
+            sys.stdout.flush()
+        rows = None
+        if SOUND_MAP[metric_name].get("rows") and values:
+            rows = [
+                (f"{label}: {v:.0f}{SOUND_MAP[metric_name]['unit']}", map_metric(metric_name, v)[3])
+                for label, v in zip(extra_data.get("rows") or [], values)
+            ]
+        report_metric(
+            SOUND_MAP[metric_name],
+            metric_value,
+            note_name,
+            frequency,
+            color,
+            extra_data,
+            self.use_color,
+            flash="flash" in fallback and severity == "critical",
+            gauge=self.gauge,
+            rows=rows,
+        )
+        return event
+
+    def current_value(self, metric_name: str) -> Optional[float]:
+        """
+        Returns a metric's value for this tick, fetching or deriving it if it hasn't been
+        sonified yet.
+
+        Args:
+            metric_name: The name of the metric.
+
+        Returns:
+            The value, or None if unavailable.
+        """
+        if metric_name not in self.values:
+            entry = SOUND_MAP.get(metric_name, {})
+            expression = entry.get("expression")
+            if "rate_of" in entry:
+                self.values[metric_name] = self.rate(entry["rate_of"])
+            elif expression is not None:
+                # Unavailable while being evaluated, so expressions reading each other end
+                self.values[metric_name] = None
+                self.values[metric_name] = evaluate_expression(
+                    parse_expression(expression), self.current_value
+                )
+            else:
+                data = self.sample(metric_name, self.tick_namespace).data
+                self.values[metric_name] = data[0] if data is not None else None
+        return self.values[metric_name]
+
+    def rate(self, metric_name: str) -> Optional[float]:
+        """
+        Works out a metric's per-second rate of change since the previous tick it had a value.
+
+        Args:
+            metric_name: The name of the metric.
+
+        Returns:
+            The rate, or None on the metric's first value or while it is unavailable.
+        """
+        value = self.current_value(metric_name)
+        if value is None:
+            return None
+        key = (self.tick_namespace, metric_name)
+        now = time.time()
+        previous = self.history.get(key)
+        self.history[key] = (now, value)
+        if previous is None or now <= previous[0]:
+            return None
+        return (value - previous[1]) / (now - previous[0])
+
+    def switch_target(self) -> None:
+        """
+        Start over after the context or namespace was switched at runtime, so alarms
+        and history from the old target don't fire or clear against the new one.
+        """
+        self.target = (self.state.context, self.state.namespace)
+        self.forbidden = {}
+        self.discovered = []
+        self.next_discovery = 0.0
+        self.workloads = {}
+        self.next_annotations = {}
+        self.profiles = {}
+        self.next_profiles = 0.0
+        self.changes.append(self.forget_target)
+        context = self.state.context or "current context"
+        logger.info(f"Now monitoring namespace {self.state.namespace} in {context}")
+
+    def forget_target(self) -> None:
+        """Drop the alarms and history of the context or namespace switched away from."""
+        self.state.active_alarms.clear()
+        self.state.acknowledged.clear()
+        self.extras = {}
+        self.since_reminder = {}
+        self.history = {}
+        self.recent = {}
+        self.sounded = {}
+        self.escalations = {}
+        self.alarm_replays = {}
+
+    def refresh_namespaces(self) -> None:
+        """
+        Rediscover the namespaces to monitor if namespace_refresh has passed, starting
+        on new ones and forgetting the alarms and history of ones that disappeared.
+        Sources without namespaces, such as a file, discover none.
+        """
+        now = time.monotonic()
+        if now < self.next_discovery:
+            return
+        self.next_discovery = now + self.namespace_refresh
+        discovered = self.source.namespaces(self.namespace_include, self.namespace_exclude)
+        if discovered is None:
+            return
+        found = [namespace for namespace in discovered if namespace != self.state.namespace]
+        for namespace in sorted(set(found) - set(self.discovered)):
+            logger.info(f"Started monitoring namespace: {namespace}")
+        for namespace in sorted(set(self.discovered) - set(found)):
+            logger.info(f"Stopped monitoring namespace: {namespace}")
+            if namespace not in self.profiles:
+                self.changes.append(functools.partial(self.forget_namespace, namespace))
+        self.discovered = found
+
+    def reminder_due(self, namespace: str, offenders: float) -> bool:
+        """
+        Whether a namespace's deprecated_api reminder plays this poll: as soon as
+        offenders are found, then every DEPRECATION_REMINDER_POLLS polls divided by
+        their number, so each extra offender brings it round sooner.
+
+        Args:
+            namespace: The namespace.
+            offenders: Its deprecated_apis value.
+
+        Returns:
+            True if the reminder is due.
+        """
+        if offenders < 1:
+            self.since_reminder.pop(namespace, None)
+            return False
+        interval = max(1, round(DEPRECATION_REMINDER_POLLS / offenders))
+        polls = self.since_reminder.get(namespace, interval - 1) + 1
+        self.since_reminder[namespace] = 0 if polls >= interval else polls
+        return polls >= interval
+
+    def forget_namespace(self, namespace: str) -> None:
+        """
+        Drop the alarms and history of a namespace that is no longer monitored.
+
+        Args:
+            namespace: The namespace.
+        """
+        prefix = f"{namespace}/"
+        state = self.state
+        state.active_alarms = {a for a in state.active_alarms if not a.startswith(prefix)}
+        state.acknowledged = {a for a in state.acknowledged if not a.startswith(prefix)}
+        self.extras = {key: extra for key, extra in self.extras.items() if key[0] != namespace}
+        self.since_reminder.pop(namespace, None)
+        self.history = {key: last for key, last in self.history.items() if key[0] != namespace}
+        self.recent = {key: last for key, last in self.recent.items() if key[0] != namespace}
+
+    def reconcile_profiles(self) -> None:
+        """
+        Reread the SonificationProfile custom resources if profile_refresh has passed,
+        starting on the namespaces that gained one and dropping those that lost theirs.
+
+        A namespace's profile picks its metrics, overrides their thresholds and sets
+        how it sounds, below its workloads' annotations. If a namespace has several,
+        the first by name is used. While they cannot be listed, the last ones are kept; a
+        source without them (e.g. replay) never configures any.
+        """
+        now = time.monotonic()
+        if now < self.next_profiles:
+            return
+        self.next_profiles = now + self.profile_refresh
+        resources = self.source.sonification_profiles()
+        if resources is None:
+            return
+        profiles: Dict[str, Dict] = {}
+        for resource in sorted(resources, key=lambda r: (
+            r.get("metadata", {}).get("namespace", ""), r.get("metadata", {}).get("name", "")
+        )):
+            settings = profile_settings(resource)
+            if settings is None:
+                continue
+            namespace = settings["name"].split("/")[0]
+            if namespace in profiles:
+                logger.warning(
+                    f"Ignoring SonificationProfile {settings['name']}: "
+                    f"{namespace} is already configured by {profiles[namespace]['name']}"
+                )
+                continue
+            profiles[namespace] = settings
+        for namespace, settings in sorted(profiles.items()):
+            if namespace not in self.profiles:
+                logger.info(f"Configured namespace {namespace} from {settings['name']}")
+            elif settings != self.profiles[namespace]:
+                logger.info(f"Reconfigured namespace {namespace} from {settings['name']}")
+        for namespace in sorted(set(self.profiles) - set(profiles)):
+            logger.info(f"Removed the SonificationProfile of namespace {namespace}")
+            if namespace not in self.monitored_namespaces(profiled=False):
+                self.changes.append(functools.partial(self.forget_namespace, namespace))
+        self.profiles = profiles
+
+    def monitored_namespaces(self, profiled: bool = True) -> List[str]:
+        """
+        The namespaces to poll: the active one, the discovered ones, then those only
+        configured by a SonificationProfile.
+
+        Args:
+            profiled: Whether to include the namespaces only configured by a profile.
+
+        Returns:
+            The namespaces, without duplicates.
+        """
+        namespaces = [self.state.namespace] + self.discovered
+        if profiled:
+            namespaces += sorted(set(self.profiles) - set(namespaces))
+        return namespaces
+
+    def refresh_annotations(self, namespace: str) -> None:
+        """
+        Reread the sound settings of a namespace's workloads if annotation_refresh
+        has passed since they were last read.
+
+        Args:
+            namespace: The namespace.
+        """
+        now = time.monotonic()
+        if now < self.next_annotations.get(namespace, 0.0):
+            return
+        self.next_annotations[namespace] = now + self.annotation_refresh
+        try:
+            settings = workload_sound_settings(self.source.annotations(namespace))
+        except Exception as e:
+            logger.debug(f"Failed to read the workload annotations of {namespace}: {e}")
+            return
+        if settings != self.workloads.get(namespace, {}):
+            annotated = {workload: s for workload, s in settings.items() if s}
+            logger.info(f"Workload annotations of {namespace}: {annotated or 'none'}")
+        self.workloads[namespace] = settings
+
+    def collect(self) -> Optional[List[MetricSample]]:
+        """
+        Fetch every metric once, for the active and every discovered namespace.
+
+        Returns:
+            The samples of this poll, or None if the source cannot connect or has no more samples.
+        """
+        if not self.start() or not self.source.next_tick():
+            return None
+
+        if (self.state.context, self.state.namespace) != self.target:
+            self.switch_target()
+        if self.namespace_include:
+            self.refresh_namespaces()
+        if self.profile_refresh:
+            self.reconcile_profiles()
+        namespaces = self.monitored_namespaces()
+        if self.annotation_refresh:
+            for namespace in namespaces:
+                self.refresh_annotations(namespace)
+
+        return [
+            self.sample(metric_name, namespace, discovered=namespace != self.state.namespace)
+            for namespace in namespaces
+            for metric_name in self.profiles.get(namespace, {}).get("metrics") or self.metrics
+            if not self.is_forbidden(metric_name, namespace)
+            and metric_name not in self.state.disabled
+        ]
+
+    def process(self, samples: List[MetricSample]) -> List[SonificationEvent]:
+        """
+        Map, play and report the samples of one poll, then flush the outputs.
+
+        Args:
+            samples: The samples, as returned by collect().
+
+        Returns:
+            The events of this poll.
+        """
+        if self.midi_controller:
+            # Knobs turned since the last poll, applied here rather than on the MIDI thread
+            self.midi_controller.apply_pending()
+        values: Dict[str, Dict[str, Optional[float]]] = {}
+        for sample in samples:
+            if not sample.derived:
+                values.setdefault(sample.namespace, {})[sample.metric] = (
+                    sample.data[0] if sample.data is not None else None
+                )
+
+        severities = list(SEVERITY_COLORS)
+        health: Optional[str] = "ok"
+        events = []
+        for sample in samples:
+            self.tick_namespace = sample.namespace
+            self.values = values.setdefault(sample.namespace, {})
+            event = self.sonify_sample(sample)
+            if event is None:
+                health = None
+            elif health is not None and severities.index(event.severity) > severities.index(health):
+                health = event.severity
+            if event is not None:
+                events.append(event)
+
+        for output in self.outputs:
+            output.flush()
+        self.health = health
+        self.save_state()
+        return events
+
+    def heartbeat_severity(self) -> Optional[str]:
+        """
+        The severity the heartbeat ticks with until the next poll: the worst of the last
+        poll's samples, or critical while an alarm is active and unacknowledged.
+
+        Returns:
+            None when the heartbeat is off or the last poll could not reach the cluster.
+        """
+        if not self.heartbeat or self.dry_run or self.health is None:
+            return None
+        if self.state.active_alarms - self.state.acknowledged:
+            return "critical"
+        return self.health
+
+    def tick(self) -> Optional[List[SonificationEvent]]:
+        """
+        Sonify every metric once.
+
+        Returns:
+            The events of this tick, or None if the source cannot connect or has no more samples.
+        """
+        samples = self.collect()
+        if samples is None:
+            return None
+        self.apply_changes(self.take_changes())
+        return self.process(samples)
+
+    def take_changes(self) -> List[Callable[[], None]]:
+        """
+        Hand over the changes collect() decided on since the last call, see changes.
+
+        Returns:
+            The changes, oldest first.
+        """
+        changes, self.changes = self.changes, []
+        return changes
+
+    def apply_changes(self, changes: List[Callable[[], None]]) -> None:
+        """
+        Apply changes from take_changes(), on the thread that calls process().
+
+        Args:
+            changes: The changes, oldest first.
+        """
+        for change in changes:
+            change()
+
+    def run(self, max_ticks: Optional[int] = None) -> None:
+        """
+        Sonify metrics every polling interval until interrupted, then shut down.
+
+        Args:
+            max_ticks: Stop after this many ticks. If None, run until interrupted
+                or the source has no more samples.
+        """
+        logger.info(f"Starting Sonify K8s Metrics for namespace: {self.state.namespace}")
+        if not self.start():
+            return
+        if self.sample_queue > 0:
+            self.run_queued(max_ticks)
+            return
+
+        ticks = 0
+        try:
+            while (max_ticks is None or ticks < max_ticks) and not self.stopping.is_set():
+                try:
+                    if self.state.paused:
+                        time.sleep(PAUSE_CHECK_INTERVAL)
+                        continue
+
+                    if self.tick() is None:
+                        logger.info("No more samples, stopping")
+                        break
+
+                    ticks += 1
+                    if max_ticks is not None and ticks >= max_ticks:
+                        break
+
+                    # Sleep until the next polling interval
+                    wait_for_next_poll(
+                        heartbeat=self.heartbeat_severity(), check=self.escalate_alarms
+                    )
+
+                except KeyboardInterrupt:
+                    logger.info("Stopping Sonify K8s...")
+                    break
+                except Exception as e:
+                    self.fail(e)
+                    time.sleep(POLL_INTERVAL)
+        finally:
+            self.shutdown()
+
+    def run_queued(self, max_ticks: Optional[int] = None) -> None:
+        """
+        Like run(), but fetch on a separate thread that hands each poll to this one
+        through a queue of sample_queue polls, so a slow note or output doesn't delay
+        collection. When playback falls that far behind, the oldest poll is dropped.
+
+        Args:
+            max_ticks: Stop after this many ticks. If None, run until interrupted
+                or the source has no more samples.
+        """
+        polls: queue.Queue = queue.Queue(maxsize=self.sample_queue)
+        fetcher = threading.Thread(target=self._fetch_polls, args=(polls, max_ticks), daemon=True)
+        fetcher.start()
+        try:
+            while not self.stopping.is_set():
+                try:
+                    poll = polls.get(timeout=PAUSE_CHECK_INTERVAL)
+                except queue.Empty:
+                    self.escalate_alarms()
+                    continue
+                if poll is None:
+                    break
+                if poll.samples is None:
+                    severity = self.heartbeat_severity()
+                    if severity:
+                        play_heartbeat(severity)
+                    self.escalate_alarms()
+                    continue
+                try:
+                    self.apply_changes(poll.changes)
+                    self.process(poll.samples)
+                except Exception as e:
+                    self.fail(e)
+        except KeyboardInterrupt:
+            logger.info("Stopping Sonify K8s...")
+        finally:
+            # The fetcher may be sleeping until the next poll; it is a daemon, so don't wait
+            self.stopping.set()
+            self.shutdown()
+
+    def _fetch_polls(self, polls: queue.Queue, max_ticks: Optional[int]) -> None:
+        """
+        Put each Poll onto polls until stopped, then None. Between polls, heartbeat
+        ticks are put there too rather than played, so all audio comes from one thread.
+        """
+
+        def beat(severity: str) -> None:
+            try:
+                polls.put_nowait(Poll(None))
+            except queue.Full:
+                pass  # Playback is behind; a missed tick is better than a dropped poll
+        ticks = 0
+        while (max_ticks is None or ticks < max_ticks) and not self.stopping.is_set():
+            if self.state.paused:
+                time.sleep(PAUSE_CHECK_INTERVAL)
+                continue
+            try:
+                samples = self.collect()
+            except Exception as e:
+                self.fail(e)
+                time.sleep(POLL_INTERVAL)
+                continue
+            if samples is None:
+                logger.info("No more samples, stopping")
+                break
+
+            poll = Poll(samples, self.take_changes())
+            while True:
+                try:
+                    polls.put_nowait(poll)
+                    break
+                except queue.Full:
+                    try:
+                        dropped = polls.get_nowait()
+                    except queue.Empty:
+                        continue
+                    if dropped.samples is not None:
+                        logger.warning("Playback is falling behind, dropping the oldest poll")
+                        # Its resets still apply to the polls after it
+                        poll.changes = dropped.changes + poll.changes
+
+            ticks += 1
+            if max_ticks is not None and ticks >= max_ticks:
+                break
+            wait_for_next_poll(heartbeat=self.heartbeat_severity(), beat=beat)
+        while not self.stopping.is_set():
+            try:
+                polls.put(None, timeout=PAUSE_CHECK_INTERVAL)
+                return
+            except queue.Full:
+                continue
+
+    async def stream(self, max_ticks: Optional[int] = None) -> AsyncIterator[SonificationEvent]:
+        """
+        Sonify metrics every polling interval and yield each event as it is produced.
+
+        Polling and playback run in a worker thread so the event loop stays responsive.
+        The sonifier is shut down when the stream ends or the consumer stops iterating.
+
+        Args:
+            max_ticks: Stop after this many ticks. If None, run until the source has no more samples.
+
+        Yields:
+            One SonificationEvent per sonified metric.
+        """
+        loop = asyncio.get_running_loop()
+        if not await loop.run_in_executor(None, self.start):
+            return
+
+        ticks = 0
+        try:
+            while max_ticks is None or ticks < max_ticks:
+                if self.state.paused:
+                    await asyncio.sleep(PAUSE_CHECK_INTERVAL)
+                    continue
+
+                try:
+                    events = await loop.run_in_executor(None, self.tick)
+                except Exception as e:
+                    self.fail(e)
+                    await asyncio.sleep(POLL_INTERVAL)
+                    continue
+                if events is None:
+                    break
+
+                for event in events:
+                    yield event
+
+                ticks += 1
+                if max_ticks is not None and ticks >= max_ticks:
+                    break
+
+                await loop.run_in_executor(
+                    None,
+                    lambda: wait_for_next_poll(
+                        heartbeat=self.heartbeat_severity(), check=self.escalate_alarms
+                    ),
+                )
+        finally:
+            self.shutdown()
+
+    def stop(self) -> None:
+        """Ask run() to return after the current tick, e.g. from another thread."""
+        self.stopping.set()
+
+    def shutdown(self) -> None:
+        """
+        Fade out the audio, close the outputs and the source, stop the keyboard,
+        MIDI and OSC controls, and print the session summary if enabled.
+        """
+        if self.audio and not self.dry_run:
+            try:
+                (self.audio_sink or audio_sink).fade_out(SHUTDOWN_FADE)
+            except Exception as e:
+                logger.error(f"Error fading out audio: {e}")
+        for output in self.outputs:
+            output.close()
+        if self.controller:
+            self.controller.stop()
+        if self.midi_controller:
+            self.midi_controller.stop()
+        if self.osc_controller:
+            self.osc_controller.stop()
+        self.source.close()
+        if self.audio_sink is not None:
+            self.audio_sink.close()
+        if self.summary:
+            for line in self.session.lines():
+                print(line)
+
+
+class SonifierBuilder:
+    """
+    Fluent builder that wires a configuration, metric source and outputs into a Sonifier.
+
+    Example:
+        sonifier = (
+            SonifierBuilder()
+            .with_config_file("config.yaml")
+            .with_namespace("prod")
+            .with_output(my_output)
+            .build()
+        )
+        sonifier.run()
+    """
+
+    def __init__(self):
+        """Start from the default configuration."""
+        self._cfg = load_config()
+        self._source: Optional[MetricSource] = None
+        self._outputs: List[MetricOutput] = []
+        self._namespace: Optional[str] = None
+        self._metrics: Optional[List[str]] = None
+        self._note_duration: Optional[float] = None
+        self._use_color: Optional[bool] = None
+        self._arpeggio = False
+        self._heartbeat = False
+        self._dry_run = False
+        self._keyboard = False
+        self._audio = True
+        self._audio_sink: Optional[AudioSink] = None
+        self._summary = False
+
+    def with_config(self, cfg: Dict) -> "SonifierBuilder":
+        """Use a configuration such as one returned by load_config()."""
+        self._cfg = cfg
+        return self
+
+    def with_config_file(self, path: str, profile: Optional[str] = None) -> "SonifierBuilder":
+        """Load the configuration from a file, overlaying a profile if given."""
+        self._cfg = load_config(path, profile)
+        return self
+
+    def with_source(self, source: MetricSource) -> "SonifierBuilder":
+        """Fetch metrics from this source instead of the Kubernetes cluster."""
+        self._source = source
+        return self
+
+    def with_output(self, output: MetricOutput) -> "SonifierBuilder":
+        """Mirror events to this output, in addition to the configured ones."""
+        self._outputs.append(output)
+        return self
+
+    def with_namespace(self, namespace: str) -> "SonifierBuilder":
+        """Monitor this namespace instead of the configured one."""
+        self._namespace = namespace
+        return self
+
+    def with_metrics(self, metrics: List[str]) -> "SonifierBuilder":
+        """Sonify these metrics instead of the configured ones."""
+        self._metrics = metrics
+        return self
+
+    def with_note_duration(self, note_duration: float) -> "SonifierBuilder":
+        """Play notes of this duration in seconds."""
+        self._note_duration = note_duration
+        return self
+
+    def with_color(self, use_color: bool = True) -> "SonifierBuilder":
+        """Print colored output lines."""
+        self._use_color = use_color
+        return self
+
+    def with_arpeggio(self, arpeggio: bool = True) -> "SonifierBuilder":
+        """Arpeggiate multi-valued metrics."""
+        self._arpeggio = arpeggio
+        return self
+
+    def with_heartbeat(self, heartbeat: bool = True) -> "SonifierBuilder":
+        """Tick a soft metronome while the cluster is reachable, darker while it is unhealthy."""
+        self._heartbeat = heartbeat
+        return self
+
+    def with_dry_run(self, dry_run: bool = True) -> "SonifierBuilder":
+        """Print the mapped notes and colors instead of playing them."""
+        self._dry_run = dry_run
+        return self
+
+    def with_keyboard(self, keyboard: bool = True) -> "SonifierBuilder":
+        """Enable interactive keyboard controls."""
+        self._keyboard = keyboard
+        return self
 
+    def with_audio(self, audio: bool = True) -> "SonifierBuilder":
+        """Play the built-in audio; disable it to only stream or output events."""
+        self._audio = audio
+        return self
 
-def play_midi_note(note_num: int, duration: float = 0.5) -> None:
-    """
-    Plays a MIDI note using the mido and python-rtmidi libraries.
+    def with_audio_sink(self, sink: AudioSink) -> "SonifierBuilder":
+        """Play notes through this sink instead of the configured audio backend."""
+        self._audio_sink = sink
+        return self
 
-    Args:
-        note_num: The MIDI note number (60 = C4).
-        duration: The duration of the note in seconds.
-    """
-    if os.environ.get("TEST_MODE", "False").lower() == "true":
-        logger.info(f"Playing MIDI note {note_num} for {duration} seconds (TEST_MODE)")
-        return
+    def with_summary(self, summary: bool = True) -> "SonifierBuilder":
+        """Print a session summary on shutdown."""
+        self._summary = summary
+        return self
 
-    try:
-        import mido
+    def build(self) -> Sonifier:
+        """
+        Create the Sonifier.
 
-        # Try to open the default MIDI output
-        with mido.open_output() as port:
-            # Send note on message
-            port.send(mido.Message("note_on", note=note_num, velocity=64))
-            # Wait for the duration
-            time.sleep(duration)
-            # Send note off message
-            port.send(mido.Message("note_off", note=note_num, velocity=64))
+        Returns:
+            A Sonifier ready to run() or tick().
+        """
+        cfg = self._cfg
+        apply_sound_map(cfg.get("sound_map"), cfg["tuning"])
+        apply_rollouts(cfg["rollouts"])
+        apply_earcons(cfg["earcons"]["library"])
+        configure_k8s_client(cfg)
 
-    except Exception as e:
-        logger.error(f"Error playing MIDI note: {e}")
-        # Fall back to the simpleaudio method
-        # Convert MIDI note to frequency (A4 = 69 = 440Hz)
-        frequency = 440 * (2 ** ((note_num - 69) / 12))
-        play_note(frequency, duration)
+        source = self._source or KubernetesSource()
+        if cfg["statsd"]["enabled"]:
+            source = StatsdSource(
+                cfg["statsd"]["mapping"],
+                host=cfg["statsd"]["host"],
+                port=cfg["statsd"]["port"],
+                fallback=source,
+            )
+        if cfg["falco"]["enabled"]:
+            source = FalcoSource(
+                host=cfg["falco"]["host"],
+                port=cfg["falco"]["port"],
+                min_priority=cfg["falco"]["min_priority"],
+                fallback=source,
+                token=read_secret(cfg["falco"]["token"], cfg["falco"]["token_file"]),
+            )
+
+        metrics = self._metrics if self._metrics is not None else cfg["metrics"]["enabled"]
+        register_rate_metrics(metrics)
+        lead = cfg["metrics"]["lead"] if cfg["metrics"]["lead"] in metrics else None
+        if lead:
+            # The lead starts each tick, so the accompaniment follows it
+            metrics = [lead] + [metric for metric in metrics if metric != lead]
+        return Sonifier(
+            source,
+            metrics=metrics,
+            namespace=self._namespace or cfg["kubernetes"]["namespace"],
+            outputs=build_outputs(cfg) + self._outputs,
+            note_duration=(
+                self._note_duration
+                if self._note_duration is not None
+                else cfg["audio"]["note_duration"]
+            ),
+            use_color=(
+                self._use_color if self._use_color is not None else cfg["monitoring"]["use_color"]
+            ),
+            arpeggio=self._arpeggio,
+            heartbeat=self._heartbeat,
+            dry_run=self._dry_run,
+            keyboard=self._keyboard,
+            audio=self._audio,
+            audio_sink=self._audio_sink or install_audio_sink(cfg),
+            midi_controls=cfg["controller"]["controls"] if cfg["controller"]["enabled"] else None,
+            midi_input=cfg["controller"]["midi_port"] or None,
+            osc_port=cfg["osc"]["port"] if cfg["osc"]["enabled"] else None,
+            osc_host=cfg["osc"]["host"],
+            namespace_include=cfg["kubernetes"]["namespace_include"],
+            namespace_exclude=cfg["kubernetes"]["namespace_exclude"],
+            namespace_refresh=cfg["kubernetes"]["namespace_refresh"],
+            event_earcons={
+                event_type: name
+                for event_type, name in cfg["earcons"]["events"].items()
+                if cfg["rollouts"]["enabled"] or not event_type.startswith("rollout_")
+            },
+            alarm_earcons=cfg["earcons"]["alarms"],
+            permission_earcon=cfg["earcons"]["missing_permission"] or None,
+            lead=lead,
+            accompaniment_volume=cfg["metrics"]["accompaniment_volume"],
+            on_change_only=cfg["monitoring"]["on_change_only"],
+            change_delta=cfg["monitoring"]["change_delta"],
+            escalate_after=cfg["alarms"]["escalate_after"],
+            max_escalation=cfg["alarms"]["max_escalation"],
+            reminder_volume=cfg["alarms"]["reminder_volume"],
+            duck_volume=cfg["alarms"]["duck_volume"],
+            duck_release=cfg["alarms"]["duck_release"],
+            audio_fallback=cfg["audio"]["fallback"],
+            gauge=cfg["monitoring"]["gauge"],
+            annotation_refresh=cfg["kubernetes"]["annotation_refresh"],
+            profile_refresh=cfg["kubernetes"]["profile_refresh"],
+            a4=cfg["tuning"]["a4"],
+            state_file=cfg["monitoring"]["state_file"] or None,
+            fetch_timeout=cfg["monitoring"]["fetch_timeout"] or None,
+            sample_queue=cfg["monitoring"]["sample_queue"],
+            summary=self._summary,
+        )
 
 
-def get_color(color_list: List[str], index: int) -> str:
+# --- GUI ---
+def chart_points(
+    metric_name: str, values: List[float], width: int, height: int
+) -> List[float]:
     """
-    Retrieves a color from a list, handling out-of-bounds indices.
+    Lays out a metric's history as a line across a chart.
 
     Args:
-        color_list: A list of color strings (e.g., hex codes).
-        index: The index of the color to retrieve.
+        metric_name: The name of the metric; its range spans the chart's height.
+        values: The history, oldest first.
+        width: The chart width in pixels.
+        height: The chart height in pixels.
 
     Returns:
-        A color string, or a default color if the index is out of bounds.
+        Flattened x, y coordinates, newest sample at the right edge.
     """
-    # 1. Simplicity: Provide clear, maintainable solutions
-    # 2. Focus: Stick strictly to defined tasks
-    if 0 <= index < len(color_list):
-        return color_list[index]
-    elif color_list:
-        return color_list[-1]
-    else:
-        return "#808080"  # Default gray color
+    if len(values) < 2:
+        return []
+    step = width / (len(values) - 1)
+    points = []
+    for i, value in enumerate(values):
+        points += [round(i * step, 1), round(height * (1 - metric_level(metric_name, value)), 1)]
+    return points
 
 
-def calculate_index(
-    value: float, notes_length: int, min_value: float, max_value: float
-) -> int:
+class Dashboard(MetricOutput):
     """
-    Calculates the index for selecting a note and color based on a metric value.
-
-    Args:
-        value: The metric value.
-        notes_length: The number of notes (and colors) available.
-        min_value: The minimum expected value of the metric.
-        max_value: The maximum expected value of the metric.
-
-    Returns:
-        An integer index.
+    A tkinter window with a gauge, history chart, note and color per metric, and
+    sliders for the master volume and each metric's critical threshold.
     """
-    # 1. Simplicity: Provide clear, maintainable solutions
-    # 2. Focus: Stick strictly to defined tasks
-    if max_value <= min_value:
-        return 0
-    clamped_value = max(min_value, min(max_value, value))
-    normalized_value = (clamped_value - min_value) / (max_value - min_value)
-    index = int(normalized_value * (notes_length - 1))
-    return index
 
+    GAUGE_WIDTH = 160
+    CHART_WIDTH = 240
+    ROW_HEIGHT = 36
 
-# --- Kubernetes Client ---
-class K8sClient:
-    """Kubernetes client wrapper for fetching cluster metrics."""
+    def __init__(self, metrics: List[str], history: int = DASHBOARD_HISTORY):
+        """
+        Initialize the dashboard.
 
-    def __init__(self):
-        """Initialize the Kubernetes client."""
-        self.v1_core = None
-        self.v1_apps = None
-        self.metrics_api = None
-        self.initialized = False
+        Args:
+            metrics: The metrics to show, one row each.
+            history: How many samples of each metric the chart shows.
+        """
+        self.metrics = metrics
+        self.history = {metric_name: deque(maxlen=history) for metric_name in metrics}
+        self.latest: Dict[str, SonificationEvent] = {}
+        self.lock = threading.Lock()
 
-    def initialize(self) -> bool:
+    def emit(self, event: SonificationEvent) -> None:
+        with self.lock:
+            if event.metric in self.history:
+                self.history[event.metric].append(event.value)
+                self.latest[event.metric] = event
+
+    def snapshot(self, metric_name: str) -> Tuple[Optional[SonificationEvent], List[float]]:
         """
-        Initialize the Kubernetes API client.
+        Get what a metric's row should show.
+
+        Args:
+            metric_name: The name of the metric.
 
         Returns:
-            True if initialization was successful, False otherwise.
+            The latest event (or None before the first sample) and the history.
+        """
+        with self.lock:
+            return self.latest.get(metric_name), list(self.history[metric_name])
+
+    def run(self, sonifier: Sonifier, max_ticks: Optional[int] = None) -> None:
+        """
+        Run the sonifier in a background thread and show the window until it is
+        closed or the sonifier stops. Falls back to running without a window if
+        tkinter is unavailable.
+
+        Args:
+            sonifier: The sonifier, with this dashboard among its outputs.
+            max_ticks: Passed on to Sonifier.run().
         """
         try:
-            if USE_KUBE_CONFIG:
-                logger.info("Loading kubeconfig from ~/.kube/config")
-                config.load_kube_config()
+            import tkinter as tk
+        except ImportError as e:
+            logger.error(f"The GUI needs tkinter, running without it: {e}")
+            sonifier.run(max_ticks=max_ticks)
+            return
+
+        thread = threading.Thread(target=sonifier.run, kwargs={"max_ticks": max_ticks}, daemon=True)
+        thread.start()
+
+        root = tk.Tk()
+        root.title(f"Sonify K8s - {sonifier.state.namespace}")
+        volume = tk.Scale(
+            root, label="Volume", from_=0, to=100, orient=tk.HORIZONTAL,
+            command=lambda v: setattr(sonifier.state, "volume", int(v) / 100),
+        )
+        volume.set(int(sonifier.state.volume * 100))
+        volume.grid(row=0, column=0, columnspan=5, sticky="we")
+
+        rows = {}
+        for row, metric_name in enumerate(self.metrics, start=1):
+            entry = SOUND_MAP[metric_name]
+            tk.Label(root, text=entry["metric_name"], anchor="w").grid(row=row, column=0, sticky="w")
+            gauge = tk.Canvas(root, width=self.GAUGE_WIDTH, height=self.ROW_HEIGHT)
+            gauge.grid(row=row, column=1)
+            chart = tk.Canvas(root, width=self.CHART_WIDTH, height=self.ROW_HEIGHT)
+            chart.grid(row=row, column=2)
+            reading = tk.Label(root, width=24, anchor="w")
+            reading.grid(row=row, column=3)
+            if entry.get("thresholds"):
+                low, high = entry["range"]
+                threshold = tk.Scale(
+                    root, from_=low, to=high, resolution=(high - low) / 100, orient=tk.HORIZONTAL,
+                    command=lambda v, m=metric_name: set_critical_threshold(m, float(v)),
+                )
+                threshold.set(entry["thresholds"][1])
+                threshold.grid(row=row, column=4)
+            rows[metric_name] = (gauge, chart, reading)
+
+        def redraw():
+            for metric_name, (gauge, chart, reading) in rows.items():
+                event, values = self.snapshot(metric_name)
+                if event is None:
+                    continue
+                gauge.delete("all")
+                level = metric_level(metric_name, event.value)
+                gauge.create_rectangle(0, 0, self.GAUGE_WIDTH * level, self.ROW_HEIGHT,
+                                       fill=event.color, width=0)
+                chart.delete("all")
+                points = chart_points(metric_name, values, self.CHART_WIDTH, self.ROW_HEIGHT)
+                if points:
+                    chart.create_line(*points, fill=event.color, width=2)
+                unit = SOUND_MAP[metric_name]["unit"]
+                reading.config(text=f"{event.value:.2f} {unit} {event.note} ({event.severity})")
+            if thread.is_alive():
+                root.after(DASHBOARD_REFRESH_MS, redraw)
             else:
-                logger.info("Loading in-cluster configuration")
-                config.load_incluster_config()
+                root.destroy()
 
-            self.v1_core = client.CoreV1Api()
-            self.v1_apps = client.AppsV1Api()
+        def close():
+            sonifier.stop()
+            root.destroy()
 
-            # Test connection
-            self.v1_core.list_namespace(limit=1)
-            logger.info("Successfully connected to Kubernetes cluster")
-            self.initialized = True
-            return True
+        root.protocol("WM_DELETE_WINDOW", close)
+        root.after(DASHBOARD_REFRESH_MS, redraw)
+        try:
+            root.mainloop()
+        except KeyboardInterrupt:
+            sonifier.stop()
+        thread.join(timeout=1)
 
-        except Exception as e:
-            logger.error(f"Failed to initialize Kubernetes client: {e}")
-            self.initialized = False
-            return False
 
-    def get_pods_status(self, namespace: str = "default") -> Tuple[float, Dict]:
+def aggregate_severity(events: List[SonificationEvent]) -> str:
+    """
+    Summarizes the health of the cluster.
+
+    Args:
+        events: The events of one tick.
+
+    Returns:
+        The worst severity among the events, "ok" if there are none.
+    """
+    order = list(SEVERITY_COLORS)
+    return max((event.severity for event in events), key=order.index, default="ok")
+
+
+class TrayIcon(MetricOutput):
+    """
+    A system tray icon colored by the worst severity of the last tick, with a menu
+    to mute audio or open the Grafana dashboard.
+    """
+
+    def __init__(self, dashboard_url: Optional[str] = None):
         """
-        Get pod status from the cluster.
+        Initialize the tray icon.
 
         Args:
-            namespace: The namespace to query (default: "default")
+            dashboard_url: The page "Open dashboard" opens. If None, the item is hidden.
+        """
+        self.dashboard_url = dashboard_url
+        self.severity = "ok"
+        self.events: List[SonificationEvent] = []
+        self.icon = None
 
-        Returns:
-            A tuple of (status_index, extra_data)
+    def emit(self, event: SonificationEvent) -> None:
+        self.events.append(event)
+
+    def flush(self) -> None:
+        self.severity = aggregate_severity(self.events)
+        self.events = []
+        if self.icon is not None:
+            self.icon.icon = self.image()
+            self.icon.title = f"Sonify K8s: {self.severity}"
+
+    def image(self):
+        """Draw the icon in the current severity's color."""
+        from PIL import Image, ImageDraw
+
+        image = Image.new("RGBA", (64, 64), (0, 0, 0, 0))
+        ImageDraw.Draw(image).ellipse((4, 4, 60, 60), fill=SEVERITY_COLORS[self.severity])
+        return image
+
+    def run(self, sonifier: Sonifier, max_ticks: Optional[int] = None) -> None:
+        """
+        Run the sonifier in a background thread and show the icon until "Quit" is
+        chosen or the sonifier stops. Falls back to running without an icon if
+        pystray is unavailable.
+
+        Args:
+            sonifier: The sonifier, with this icon among its outputs.
+            max_ticks: Passed on to Sonifier.run().
         """
         try:
-            pods = self.v1_core.list_namespaced_pod(namespace=namespace)
-            if not pods.items:
-                return 0, {"status": "Unknown", "count": 0}
+            import pystray
+        except ImportError as e:
+            logger.error(f"The tray icon needs pystray and Pillow, running without it: {e}")
+            sonifier.run(max_ticks=max_ticks)
+            return
 
-            # Get the first pod's status as representative
-            pod = pods.items[0]
-            status = pod.status.phase
+        def run_sonifier():
+            sonifier.run(max_ticks=max_ticks)
+            self.icon.stop()
 
-            status_index = SOUND_MAP["pod_status"]["status_map"].get(status, 0)
-            return status_index, {"status": status, "count": len(pods.items)}
+        def toggle_mute(icon, item):
+            sonifier.state.silenced = not sonifier.state.silenced
 
-        except ApiException as e:
-            logger.warning(f"Failed to get pod status: {e}")
-            return 0, {"status": "Unknown", "error": str(e)}
+        def open_dashboard(icon, item):
+            import webbrowser
 
-    def get_deployment_replicas(self, namespace: str = "default") -> Tuple[float, Dict]:
+            webbrowser.open(self.dashboard_url)
+
+        def quit_tray(icon, item):
+            sonifier.stop()
+            icon.stop()
+
+        menu = pystray.Menu(
+            pystray.MenuItem(
+                "Mute audio", toggle_mute, checked=lambda item: sonifier.state.silenced
+            ),
+            pystray.MenuItem(
+                "Open dashboard", open_dashboard, visible=self.dashboard_url is not None
+            ),
+            pystray.MenuItem("Quit", quit_tray),
+        )
+        self.icon = pystray.Icon("sonify-k8s", self.image(), "Sonify K8s", menu)
+        thread = threading.Thread(target=run_sonifier, daemon=True)
+        thread.start()
+        self.icon.run()
+        sonifier.stop()
+        thread.join(timeout=1)
+
+
+class Waterfall(MetricOutput):
+    """
+    A full-screen terminal view of the recent history, like a spectrogram: a row per
+    metric, a column per tick, each cell in the color the metric's value mapped to.
+    The newest tick is on the right and older ones scroll off to the left.
+    """
+
+    def __init__(
+        self,
+        metrics: List[str],
+        stream=None,
+        size: Optional[Callable[[], Tuple[int, int]]] = None,
+        history: int = WATERFALL_HISTORY,
+    ):
         """
-        Get replica count from deployments.
+        Initialize an empty waterfall.
 
         Args:
-            namespace: The namespace to query (default: "default")
+            metrics: The metrics to show, one row each, in this order.
+            stream: Where the screen is drawn. If None, sys.stdout.
+            size: Returns the (columns, lines) of the screen. If None, the terminal size.
+            history: Ticks kept per metric.
+        """
+        self.metrics = metrics
+        self.stream = stream or sys.stdout
+        self.size = size or (lambda: tuple(shutil.get_terminal_size()))
+        self.columns: deque = deque(maxlen=history)
+        self.current: Dict[str, SonificationEvent] = {}
+        self.started = False
+
+    def emit(self, event: SonificationEvent) -> None:
+        # Of several namespaces' samples of a metric, the most severe colors the cell
+        ranks = list(SEVERITY_COLORS)
+        shown = self.current.get(event.metric)
+        if shown is None or ranks.index(event.severity) >= ranks.index(shown.severity):
+            self.current[event.metric] = event
+
+    def flush(self) -> None:
+        self.columns.append({metric: event.color for metric, event in self.current.items()})
+        self.current = {}
+        if not self.started:
+            # Draw on the alternate screen, so the scrollback is left as it was
+            self.stream.write("\033[?1049h\033[?25l")
+            self.started = True
+        self.stream.write(self.render())
+        self.stream.flush()
+
+    def render(self) -> str:
+        """
+        Draws the screen.
 
         Returns:
-            A tuple of (replica_count, extra_data)
+            The text, with the escape codes that clear the screen and color the cells.
         """
-        try:
-            deployments = self.v1_apps.list_namespaced_deployment(namespace=namespace)
-            if not deployments.items:
-                return 1, {"replicas": 1, "deployments": 0}
+        width, height = self.size()
+        cells = max(width - WATERFALL_LABEL_WIDTH - 1, 1)
+        columns = list(self.columns)[-cells:]
+        lines = [f"Sonify K8s  {datetime.now().strftime('%H:%M:%S')}  {len(self.columns)} ticks"]
+        for metric in self.metrics[: max(height - 1, 0)]:
+            label = SOUND_MAP.get(metric, {}).get("metric_name", metric)
+            row = [f"{label[:WATERFALL_LABEL_WIDTH]:<{WATERFALL_LABEL_WIDTH}} "]
+            for column in columns:
+                color = column.get(metric)
+                if color and HEX_COLOR_PATTERN.match(color):
+                    r, g, b = (int(color[i : i + 2], 16) for i in (1, 3, 5))
+                    row.append(f"\033[48;2;{r};{g};{b}m \033[0m")
+                else:
+                    # Not sampled this tick
+                    row.append("·")
+            lines.append("".join(row))
+        return "\033[H\033[2J" + "\n".join(lines)
 
-            # Get average replica count
-            total_replicas = sum(d.spec.replicas or 0 for d in deployments.items)
-            avg_replicas = total_replicas / len(deployments.items) if deployments.items else 1
+    def close(self) -> None:
+        if self.started:
+            self.stream.write("\033[?25h\033[?1049l")
+            self.stream.flush()
+            self.started = False
 
-            return avg_replicas, {
-                "replicas": int(avg_replicas),
-                "deployments": len(deployments.items)
-            }
 
-        except ApiException as e:
-            logger.warning(f"Failed to get deployment replicas: {e}")
-            return 1, {"replicas": 1, "error": str(e)}
+def grafana_dashboard_url(cfg: Dict) -> Optional[str]:
+    """
+    Gets the Grafana dashboard page for a configuration.
 
-    def get_node_pressure(self) -> Tuple[float, Dict]:
+    Args:
+        cfg: The configuration.
+
+    Returns:
+        The dashboard URL, or None if Grafana is not configured.
+    """
+    grafana = cfg["outputs"]["grafana"]
+    if not grafana.get("url"):
+        return None
+    url = grafana["url"].rstrip("/")
+    return f"{url}/d/{grafana['dashboard_uid']}" if grafana.get("dashboard_uid") else url
+
+
+# --- Main Function ---
+def sonify_k8s_metrics(
+    use_color: Optional[bool] = None,
+    namespace: str = "default",
+    arpeggio: Optional[bool] = None,
+    heartbeat: Optional[bool] = None,
+    keyboard: bool = False,
+    metrics: Optional[List[str]] = None,
+    note_duration: float = 0.5,
+    dry_run: bool = False,
+    max_ticks: Optional[int] = None,
+    source: Optional[MetricSource] = None,
+    outputs: Optional[List[MetricOutput]] = None,
+) -> None:
+    """
+    Main function to fetch Kubernetes metrics and sonify them.
+
+    Args:
+        use_color: Whether to use colors in the output. If None, determined by environment.
+        namespace: The Kubernetes namespace to monitor.
+        arpeggio: Whether to arpeggiate multi-valued metrics. If None, determined by environment.
+        heartbeat: Whether to tick a soft metronome while the cluster is reachable.
+            If None, determined by environment.
+        keyboard: Whether to enable interactive keyboard controls.
+        metrics: The metrics to monitor. If None, every metric in SOUND_MAP.
+        note_duration: The duration of each note in seconds.
+        dry_run: Whether to print the mapped notes and colors instead of playing them.
+        max_ticks: Stop after this many polling ticks. If None, run until interrupted.
+        source: Where to fetch metrics from. If None, the Kubernetes cluster.
+        outputs: Where to mirror each sonified sample, e.g. InfluxDB.
+    """
+    if use_color is None:
+        use_color = os.environ.get("SHOW_COLOR", "false").lower() == "true"
+    if arpeggio is None:
+        arpeggio = os.environ.get("ARPEGGIO", "false").lower() == "true"
+    if heartbeat is None:
+        heartbeat = os.environ.get("HEARTBEAT", "false").lower() == "true"
+
+    builder = (
+        SonifierBuilder()
+        .with_namespace(namespace)
+        .with_metrics(metrics if metrics is not None else list(SOUND_MAP))
+        .with_note_duration(note_duration)
+        .with_color(use_color)
+        .with_arpeggio(arpeggio)
+        .with_heartbeat(heartbeat)
+        .with_dry_run(dry_run)
+        .with_keyboard(keyboard)
+    )
+    if source is not None:
+        builder.with_source(source)
+    for output in outputs or []:
+        builder.with_output(output)
+    builder.build().run(max_ticks=max_ticks)
+
+
+def parse_metric_line(line: str) -> Optional[Tuple[str, float]]:
+    """
+    Parses a "name value" line as produced by shell pipelines.
+
+    Args:
+        line: The line to parse.
+
+    Returns:
+        A tuple of (metric_name, value), or None if the line is blank, a comment,
+        malformed, or names an unknown metric.
+    """
+    line = line.strip()
+    if not line or line.startswith("#"):
+        return None
+    parts = line.split()
+    if len(parts) != 2:
+        logger.warning(f"Ignoring malformed line (expected 'name value'): {line}")
+        return None
+    metric_name, raw_value = parts
+    if metric_name not in SOUND_MAP:
+        logger.warning(f"Ignoring unknown metric: {metric_name}")
+        return None
+    try:
+        return metric_name, float(raw_value)
+    except ValueError:
+        logger.warning(f"Ignoring non-numeric value for {metric_name}: {raw_value}")
+        return None
+
+
+def sonify_stream(
+    lines,
+    use_color: bool = False,
+    note_duration: float = 0.5,
+    dry_run: bool = False,
+    gauge: str = "none",
+) -> None:
+    """
+    Sonifies "name value" lines as they arrive, one note per line.
+
+    Args:
+        lines: An iterable of text lines, such as sys.stdin.
+        use_color: Whether to use colors in the output.
+        note_duration: The duration of each note in seconds.
+        dry_run: Whether to print the mapped notes and colors instead of playing them.
+        gauge: One of GAUGE_STYLES, for a bar after each output line's value.
+    """
+    values: Dict[str, float] = {}
+    for line in lines:
+        parsed = parse_metric_line(line)
+        if parsed is None:
+            continue
+        metric_name, metric_value = parsed
+        values[metric_name] = metric_value
+        mapped = map_metric(metric_name, metric_value)
+        if dry_run:
+            severity = get_severity(metric_name, metric_value)
+            print(format_dry_run_line(metric_name, metric_value, severity))
+            continue
+        play_note(
+            mapped.frequency,
+            mapped.duration or note_duration,
+            volume=mapped.velocity,
+            voice=metric_voice(metric_name, metric_value, values.get),
+        )
+        report_metric(
+            SOUND_MAP[metric_name],
+            metric_value,
+            mapped.note_name,
+            mapped.frequency,
+            mapped.color,
+            None,
+            use_color,
+            gauge=gauge,
+        )
+
+
+# --- Sequencer ---
+class TempoClock:
+    """A tempo the sequencer follows; this base class is a fixed tempo."""
+
+    def __init__(self, bpm: float = SEQUENCER_BPM):
         """
-        Check if any nodes are under pressure.
+        Initialize the clock.
 
-        Returns:
-            A tuple of (pressure_level, extra_data)
+        Args:
+            bpm: The tempo in beats per minute, also used while an external clock is silent.
         """
-        try:
-            nodes = self.v1_core.list_node()
-            if not nodes.items:
-                return 0, {"pressure": "False", "nodes": 0}
+        self.fixed_bpm = bpm
 
-            # Check for any node pressure conditions
-            pressure_types = ["MemoryPressure", "DiskPressure", "PIDPressure", "NetworkUnavailable"]
-            has_pressure = False
+    def bpm(self) -> float:
+        """Returns the current tempo in beats per minute."""
+        return self.fixed_bpm
 
-            for node in nodes.items:
-                if node.status.conditions:
-                    for condition in node.status.conditions:
-                        if condition.type in pressure_types and condition.status == "True":
-                            has_pressure = True
-                            break
-                if has_pressure:
-                    break
+    def start(self) -> None:
+        """Start following the tempo source."""
 
-            pressure_level = 1 if has_pressure else 0
-            return pressure_level, {"pressure": str(has_pressure), "nodes": len(nodes.items)}
+    def stop(self) -> None:
+        """Stop following the tempo source."""
 
-        except ApiException as e:
-            logger.warning(f"Failed to get node pressure: {e}")
-            return 0, {"pressure": "Unknown", "error": str(e)}
 
-    def get_resource_usage(self, namespace: str = "default") -> Tuple[float, float]:
+class MidiClock(TempoClock):
+    """Follows the tempo of an external MIDI clock (24 pulses per quarter note)."""
+
+    def __init__(self, bpm: float = SEQUENCER_BPM, port_name: Optional[str] = None):
         """
-        Get approximate CPU and memory usage.
-        Note: This requires metrics-server to be installed in the cluster.
-        Falls back to requested resources if metrics are unavailable.
+        Initialize the clock.
 
         Args:
-            namespace: The namespace to query (default: "default")
+            bpm: The tempo used until clock pulses arrive.
+            port_name: The MIDI input port. If None, the default input.
+        """
+        super().__init__(bpm)
+        self.port_name = port_name
+        self.port = None
+        self.pulses: deque = deque(maxlen=MIDI_CLOCK_PPQN + 1)
+        self.lock = threading.Lock()
 
-        Returns:
-            A tuple of (cpu_usage_percent, memory_usage_percent)
+    def handle_message(self, message) -> None:
         """
-        try:
-            # Try to get actual metrics from metrics-server
-            # This requires metrics-server API to be available
-            pods = self.v1_core.list_namespaced_pod(namespace=namespace)
+        Records a MIDI clock pulse.
 
-            if not pods.items:
-                return 0.0, 0.0
+        Args:
+            message: A mido message; anything but "clock" is ignored.
+        """
+        if message.type == "clock":
+            with self.lock:
+                self.pulses.append(time.monotonic())
 
-            # Calculate based on resource requests as a proxy
-            # In a real implementation, you would query metrics-server API
-            total_cpu_req = 0.0
-            total_mem_req = 0.0
-            pod_count = 0
+    def bpm(self) -> float:
+        with self.lock:
+            pulses = list(self.pulses)
+        if len(pulses) < 2 or time.monotonic() - pulses[-1] > MIDI_CLOCK_TIMEOUT:
+            return self.fixed_bpm
+        pulse_interval = (pulses[-1] - pulses[0]) / (len(pulses) - 1)
+        return 60.0 / (pulse_interval * MIDI_CLOCK_PPQN)
+
+    def start(self) -> None:
+        try:
+            import mido
 
-            for pod in pods.items:
-                if pod.spec.containers:
-                    for container in pod.spec.containers:
-                        if container.resources and container.resources.requests:
-                            cpu_req = container.resources.requests.get("cpu", "0")
-                            mem_req = container.resources.requests.get("memory", "0")
+            self.port = mido.open_input(self.port_name, callback=self.handle_message)
+            logger.info(f"Following MIDI clock on {self.port.name}")
+        except Exception as e:
+            logger.error(f"Failed to open MIDI clock input, using {self.fixed_bpm:g} BPM: {e}")
 
-                            # Parse CPU (e.g., "100m" = 0.1 cores)
-                            if isinstance(cpu_req, str):
-                                if cpu_req.endswith("m"):
-                                    total_cpu_req += float(cpu_req[:-1]) / 1000
-                                else:
-                                    total_cpu_req += float(cpu_req)
+    def stop(self) -> None:
+        if self.port is not None:
+            self.port.close()
+            self.port = None
 
-                            # Parse memory (simplified)
-                            if isinstance(mem_req, str):
-                                if mem_req.endswith("Mi"):
-                                    total_mem_req += float(mem_req[:-2])
-                                elif mem_req.endswith("Gi"):
-                                    total_mem_req += float(mem_req[:-2]) * 1024
 
-                            pod_count += 1
+class LinkClock(TempoClock):
+    """Joins an Ableton Link session and follows its tempo."""
 
-            # Estimate usage as a percentage (rough approximation)
-            cpu_usage = min((total_cpu_req / max(pod_count, 1)) * 20, 100)
-            mem_usage = min((total_mem_req / max(pod_count, 1)) / 10, 100)
+    def __init__(self, bpm: float = SEQUENCER_BPM):
+        """
+        Initialize the clock.
 
-            return cpu_usage, mem_usage
+        Args:
+            bpm: The tempo proposed to the session, and used if Link is unavailable.
+        """
+        super().__init__(bpm)
+        self.link = None
 
-        except ApiException as e:
-            logger.warning(f"Failed to get resource usage: {e}")
-            # Return moderate values to indicate some activity
-            return 30.0, 40.0
+    def bpm(self) -> float:
+        if self.link is None:
+            return self.fixed_bpm
+        return self.link.captureSessionState().tempo()
 
+    def start(self) -> None:
+        try:
+            import link
 
-# Global K8s client instance
-k8s_client = K8sClient()
+            self.link = link.Link(self.fixed_bpm)
+            self.link.enabled = True
+            logger.info(f"Joined Ableton Link session with {self.link.numPeers()} peers")
+        except Exception as e:
+            logger.error(f"Failed to join Ableton Link session, using {self.fixed_bpm:g} BPM: {e}")
 
+    def stop(self) -> None:
+        if self.link is not None:
+            self.link.enabled = False
+            self.link = None
 
-# --- Kubernetes Interaction ---
-def get_k8s_data(metric: str, namespace: str = "default") -> Optional[Tuple[float, Dict]]:
+
+def build_tempo_clock(cfg: Dict, bpm: Optional[float] = None) -> TempoClock:
     """
-    Fetches real Kubernetes data for a given metric.
+    Creates the tempo clock selected by a configuration.
 
     Args:
-        metric: The name of the metric to fetch.
-        namespace: The Kubernetes namespace to query (default: "default")
+        cfg: The configuration.
+        bpm: The fixed tempo. If None, SEQUENCER_BPM is used.
 
     Returns:
-        A tuple containing the metric value and an optional dictionary of extra data,
-        or None if the metric is invalid or client is not initialized.
+        The clock for tempo.sync.
     """
-    if not k8s_client.initialized:
-        logger.warning("Kubernetes client not initialized, skipping metric fetch")
-        return None
+    bpm = bpm or SEQUENCER_BPM
+    sync = cfg["tempo"]["sync"]
+    if sync == "midi":
+        return MidiClock(bpm, cfg["tempo"]["midi_port"] or None)
+    if sync == "link":
+        return LinkClock(bpm)
+    return TempoClock(bpm)
 
-    try:
-        if metric == "cpu_usage":
-            cpu, _ = k8s_client.get_resource_usage(namespace)
-            return cpu, {"namespace": namespace}
 
-        elif metric == "memory_usage":
-            _, memory = k8s_client.get_resource_usage(namespace)
-            return memory, {"namespace": namespace}
+class MetricPoller:
+    """Polls every metric in a background thread and keeps the latest sample of each."""
 
-        elif metric == "pod_status":
-            return k8s_client.get_pods_status(namespace)
+    def __init__(self, namespace: str = "default", metrics: Optional[List[str]] = None):
+        """
+        Initialize the poller.
 
-        elif metric == "http_latency":
-            # This would require custom metrics or service mesh integration
-            # For now, return a simulated value based on pod health
-            status_idx, _ = k8s_client.get_pods_status(namespace)
-            # Healthy pods = lower latency
-            latency = 50 + (3 - status_idx) * 100
-            return latency, {"estimated": True}
+        Args:
+            namespace: The Kubernetes namespace to query.
+            metrics: The metrics to poll. If None, every metric in SOUND_MAP.
+        """
+        self.namespace = namespace
+        self.metrics = metrics if metrics is not None else list(SOUND_MAP)
+        self.latest: Dict[str, Tuple[float, Dict]] = {}
+        self._lock = threading.Lock()
+        self._stop = threading.Event()
+        self._thread: Optional[threading.Thread] = None
 
-        elif metric == "errors_per_second":
-            # This would require custom metrics or logging integration
-            # For now, estimate based on pod failures
-            status_idx, data = k8s_client.get_pods_status(namespace)
-            errors = 0 if data.get("status") in ["Running", "Succeeded"] else 5
-            return float(errors), {"estimated": True}
+    def poll_once(self) -> None:
+        """Fetch every metric once and store the results."""
+        for metric_name in self.metrics:
+            try:
+                data = get_k8s_data(metric_name, namespace=self.namespace)
+            except MissingPermission as e:
+                logger.error(f"Disabling {SOUND_MAP[metric_name]['metric_name']}: {e}")
+                self.metrics = [m for m in self.metrics if m != metric_name]
+                continue
+            if data is None:
+                logger.warning(f"Failed to get data for metric: {metric_name}")
+                continue
+            with self._lock:
+                self.latest[metric_name] = data
 
-        elif metric == "replicas":
-            return k8s_client.get_deployment_replicas(namespace)
+    def get(self, metric_name: str) -> Optional[Tuple[float, Dict]]:
+        """
+        Get the latest sample of a metric.
 
-        elif metric == "node_pressure":
-            return k8s_client.get_node_pressure()
+        Args:
+            metric_name: The name of the metric.
 
-        else:
-            logger.warning(f"Unknown metric: {metric}")
-            return None
+        Returns:
+            The latest (value, extra_data) tuple, or None if not yet polled.
+        """
+        with self._lock:
+            return self.latest.get(metric_name)
 
-    except Exception as e:
-        logger.error(f"Error fetching metric {metric}: {e}")
-        return None
+    def _run(self) -> None:
+        while not self._stop.is_set():
+            try:
+                self.poll_once()
+            except Exception as e:
+                logger.error(f"An error occurred while polling: {e}", exc_info=True)
+            self._stop.wait(POLL_INTERVAL)
 
+    def start(self) -> None:
+        """Start polling in a daemon thread."""
+        self._stop.clear()
+        self._thread = threading.Thread(target=self._run, daemon=True)
+        self._thread.start()
 
-def colorize_line(text: str, color: str, use_color: bool = False) -> str:
-    """
-    Colorizes a line of text using ANSI escape codes if use_color is True.
+    def stop(self) -> None:
+        """Stop polling and wait for the thread to finish."""
+        self._stop.set()
+        if self._thread is not None:
+            self._thread.join(timeout=1)
 
-    Args:
-        text: The text to colorize.
-        color: The hex color code (e.g., "#FF0000").
-        use_color: Whether to apply color.
 
-    Returns:
-        The colorized text if use_color is True, otherwise the original text.
+def run_sequencer(
+    use_color: bool = False,
+    namespace: str = "default",
+    bpm: Optional[float] = None,
+    note_duration: float = 0.5,
+    keyboard: bool = False,
+    metrics: Optional[List[str]] = None,
+    dry_run: bool = False,
+    clock: Optional[TempoClock] = None,
+    gauge: str = "none",
+    stopping: Optional[threading.Event] = None,
+) -> None:
     """
-    if not use_color:
-        return text
-    # Convert hex color to RGB
-    hex_color = color.lstrip("#")
-    if len(hex_color) != 6:
-        logger.warning(f"Invalid hex color format: {color}. Returning unmodified text.")
-        return text  # Fallback if color is invalid
-    r, g, b = tuple(int(hex_color[i : i + 2], 16) for i in (0, 2, 4))
-    return f"\033[38;2;{r};{g};{b}m{text}\033[0m"
-
+    Plays each metric as one step of a repeating bar at a fixed or synced tempo.
 
-# --- Main Function ---
-def sonify_k8s_metrics(use_color: Optional[bool] = None, namespace: str = "default") -> None:
-    """
-    Main function to fetch Kubernetes metrics and sonify them.
+    The musical clock is decoupled from the polling interval: a background
+    poller refreshes the values while the sequencer keeps playing the
+    latest sample of each metric on the beat.
 
     Args:
-        use_color: Whether to use colors in the output. If None, determined by environment.
+        use_color: Whether to use colors in the output.
         namespace: The Kubernetes namespace to monitor.
+        bpm: The tempo in steps per minute. If None, SEQUENCER_BPM is used.
+        note_duration: The longest duration of a step's note in seconds.
+        keyboard: Whether to enable interactive keyboard controls.
+        metrics: The metrics to sequence. If None, every metric in SOUND_MAP.
+        dry_run: Whether to print each step's note and color instead of playing it.
+        clock: Where the tempo comes from, e.g. a MidiClock. If None, a fixed bpm.
+        gauge: One of GAUGE_STYLES, for a bar after each output line's value.
+        stopping: Set to stop after the current step, e.g. on SIGTERM.
     """
-    if use_color is None:
-        use_color = os.environ.get("SHOW_COLOR", "false").lower() == "true"
+    clock = clock or TempoClock(bpm or SEQUENCER_BPM)
+    stopping = stopping or threading.Event()
+    steps = metrics if metrics is not None else list(SOUND_MAP)
 
-    logger.info(f"Starting Sonify K8s Metrics for namespace: {namespace}")
+    logger.info(f"Starting sequencer at {clock.bpm():g} BPM for namespace: {namespace}")
 
-    # --- Kubernetes Client Initialization ---
     if not k8s_client.initialize():
         logger.error("Failed to connect to Kubernetes cluster. Please check your configuration.")
-        logger.error("Make sure kubectl is configured or set USE_KUBE_CONFIG=true environment variable.")
         return
 
-    # --- Main Loop ---
-    while True:
-        try:
-            for metric_name, metric_config in SOUND_MAP.items():
-                data = get_k8s_data(metric_name, namespace=namespace)
-                if data is None:
-                    logger.warning(f"Failed to get data for metric: {metric_name}")
-                    continue
-                metric_value, extra_data = data
+    state = PlaybackState(namespace)
+    controller = KeyboardController(state, steps) if keyboard else None
+    if controller:
+        controller.start()
 
-                notes_list = metric_config["notes"]
-                color_list = metric_config["colors"]
-                if metric_name in ["pod_status", "node_pressure"]:
-                    index = int(metric_value)
-                else:
-                    index = calculate_index(
-                        metric_value,
-                        len(notes_list),
-                        0,
-                        (
-                            100
-                            if metric_name
-                            not in ["http_latency", "errors_per_second", "replicas"]
-                            else (
-                                500
-                                if metric_name == "http_latency"
-                                else (10 if metric_name == "errors_per_second" else 5)
-                            )
-                        ),
+    poller = MetricPoller(namespace, steps)
+    poller.start()
+    clock.start()
+    position = 0
+    next_step = time.monotonic()
+    try:
+        while not stopping.is_set():
+            # Follow tempo changes of a synced clock from one step to the next
+            step_duration = 60.0 / clock.bpm()
+            # Keyboard namespace switches take effect on the next poll
+            poller.namespace = state.namespace
+            metric_name = steps[position % len(steps)]
+            data = None if state.paused else poller.get(metric_name)
+            if data is not None:
+                metric_value, extra_data = data
+                severity = get_severity(metric_name, metric_value)
+                state.update_alarm(metric_name, severity)
+                _, frequency, note_name, color, duration, velocity = map_metric(
+                    metric_name, metric_value
+                )
+                if dry_run:
+                    print(format_dry_run_line(metric_name, metric_value, severity))
+                elif state.is_audible(metric_name):
+                    play_note(
+                        frequency,
+                        min(duration or note_duration, step_duration),
+                        volume=state.metric_volume(metric_name) * velocity,
+                        voice=metric_voice(metric_name, metric_value),
                     )
+                report_metric(
+                    SOUND_MAP[metric_name],
+                    metric_value,
+                    note_name,
+                    frequency,
+                    color,
+                    extra_data,
+                    use_color,
+                    gauge=gauge,
+                )
+            position += 1
+            # Schedule against the clock so slow steps don't drift the bar
+            next_step += step_duration
+            time.sleep(max(0.0, next_step - time.monotonic()))
+    except KeyboardInterrupt:
+        logger.info("Stopping Sonify K8s...")
+    finally:
+        poller.stop()
+        clock.stop()
+        if controller:
+            controller.stop()
 
-                frequency, note_name = notes_list[index]
-                color = get_color(color_list, index)
 
-                # Play the sound using simpleaudio
-                play_note(frequency)
+def handle_termination(stop: Callable[[], None]) -> Callable:
+    """
+    Makes a SIGTERM handler that stops the monitor the way Ctrl+C does, after the
+    current tick, rather than interrupting whatever the main thread is doing.
 
-                # Construct and display the log message
-                log_message = f"{metric_config['metric_name']}: {metric_value:.2f} {metric_config['unit']} | Note: {note_name} ({frequency} Hz) | Color: {color}"
-                if extra_data:
-                    log_message += f" | Extra: {extra_data}"
+    Args:
+        stop: Called to ask the monitor to stop, e.g. Sonifier.stop.
 
-                # Print colored output to console if enabled
-                if use_color:
-                    print(colorize_line(log_message, color, use_color=use_color))
+    Returns:
+        The handler, for signal.signal().
+    """
 
-                # Always log to the logger
-                logger.info(log_message)
+    def handler(signum, frame) -> None:
+        logger.info("Stopping Sonify K8s...")
+        stop()
 
-            # Sleep until the next polling interval
-            time.sleep(POLL_INTERVAL)
+    return handler
 
-        except KeyboardInterrupt:
-            logger.info("Stopping Sonify K8s...")
-            break
-        except Exception as e:
-            logger.error(f"An error occurred: {e}", exc_info=True)
-            time.sleep(POLL_INTERVAL)
+
+@click.group(invoke_without_command=True)
+@click.pass_context
+def main(ctx):
+    """
+    Sonify K8s - Transform your Kubernetes cluster events into sound!
+
+    This utility monitors Kubernetes metrics and plays unique sounds for each event.
+    Runs `monitor` when no command is given.
+    """
+    if ctx.invoked_subcommand is None:
+        ctx.invoke(monitor)
 
 
-@click.command()
+@main.command()
+@click.option(
+    "-f", "--config", "config_path", type=click.Path(exists=True), default=None,
+    help="Path to a YAML, TOML or JSON configuration file",
+)
+@click.option(
+    "-p", "--profile", default=None, help="Overlay this profile from the configuration file"
+)
 @click.option("-c", "--color", is_flag=True, help="Show ANSI colors in output")
 @click.option(
     "-m", "--midi", is_flag=True, help="Use MIDI for sound output if available"
@@ -701,28 +10955,484 @@ def sonify_k8s_metrics(use_color: Optional[bool] = None, namespace: str = "defau
     "-i", "--interval", type=int, default=None, help="Polling interval in seconds"
 )
 @click.option(
-    "-n", "--namespace", type=str, default="default", help="Kubernetes namespace to monitor"
+    "-n", "--namespace", type=str, default=None, help="Kubernetes namespace to monitor"
+)
+@click.option(
+    "--context", type=str, default=None, help="kubeconfig context to use"
+)
+@click.option(
+    "--kubeconfig", type=click.Path(dir_okay=False), default=None,
+    help="Path to the kubeconfig file",
+)
+@click.option(
+    "--as", "impersonate_user", type=str, default=None,
+    help="User or service account to impersonate",
+)
+@click.option(
+    "--as-group", "impersonate_group", type=str, default=None,
+    help="Group to impersonate, along with --as",
+)
+@click.option(
+    "-l", "--selector", type=str, default=None,
+    help="Only count pods and deployments matching this label selector",
+)
+@click.option(
+    "-a", "--arpeggio", is_flag=True, help="Arpeggiate metrics that report multiple values"
+)
+@click.option(
+    "-s", "--sequencer", is_flag=True, help="Play metrics as steps of a repeating bar"
+)
+@click.option(
+    "-b", "--bpm", type=float, default=None, help="Sequencer tempo in beats per minute"
+)
+@click.option(
+    "--heartbeat", is_flag=True, help="Tick a soft metronome while the cluster is reachable"
+)
+@click.option(
+    "-k", "--keyboard", is_flag=True, help="Enable interactive keyboard controls"
+)
+@click.option(
+    "--dry-run", is_flag=True, help="Print the notes and colors instead of playing them"
+)
+@click.option(
+    "--ticks", type=int, default=None, help="Stop after this many polling ticks"
+)
+@click.option(
+    "--from-file", "from_file", type=click.Path(exists=True, dir_okay=False), default=None,
+    help="Replay timestamped samples from a JSON file instead of the cluster",
+)
+@click.option(
+    "--gui", is_flag=True, help="Show gauges, history charts and sliders in a window"
+)
+@click.option(
+    "--tray", is_flag=True, help="Show the cluster health as a system tray icon"
+)
+@click.option(
+    "--record", "record_path", type=click.Path(dir_okay=False, writable=True), default=None,
+    help="Record the session to a .wav, .ogg or .flac file",
+)
+@click.option(
+    "--audio-output", "audio_output", type=click.Choice(AUDIO_BACKENDS), default=None,
+    help="Where notes are played (default: audio.backend); pipe writes raw PCM to stdout",
+)
+@click.option(
+    "--waterfall", is_flag=True,
+    help="Show the history as a full-screen waterfall of colors, a row per metric",
 )
 @click.option("-v", "--verbose", is_flag=True, help="Enable verbose logging")
-def main(color, midi, interval, namespace, verbose):
-    """
-    Sonify K8s - Transform your Kubernetes cluster events into sound!
+def monitor(
+    config_path,
+    profile,
+    color,
+    midi,
+    interval,
+    namespace,
+    context,
+    kubeconfig,
+    impersonate_user,
+    impersonate_group,
+    selector,
+    arpeggio,
+    sequencer,
+    bpm,
+    heartbeat,
+    keyboard,
+    dry_run,
+    ticks,
+    from_file,
+    gui,
+    tray,
+    record_path,
+    audio_output,
+    waterfall,
+    verbose,
+):
+    """Monitor the cluster and sonify its metrics."""
+    try:
+        cfg = load_config(config_path, profile)
+    except (OSError, ValueError, yaml.YAMLError) as e:
+        logger.error(f"Failed to load configuration: {e}")
+        raise SystemExit(1)
+    problems = validate_config(cfg)
+    if problems:
+        for problem in problems:
+            logger.error(f"Invalid configuration: {problem}")
+        raise SystemExit(1)
+
+    # Command line options take precedence over the configuration file
+    color = color or cfg["monitoring"]["use_color"]
+    midi = midi or cfg["audio"]["use_midi"]
+    cfg["audio"]["use_midi"] = midi
+    cfg["audio"]["backend"] = audio_output or cfg["audio"]["backend"]
+    if record_path and os.path.splitext(record_path)[1].lower() not in RECORDING_FORMATS:
+        raise click.BadParameter(
+            f"must end in one of {', '.join(RECORDING_FORMATS)}", param_hint="--record"
+        )
+    cfg["audio"]["record_path"] = record_path or cfg["audio"]["record_path"]
+    namespace = namespace or cfg["kubernetes"]["namespace"]
+    # Recordings are tagged with the cluster and namespace actually monitored
+    cfg["kubernetes"]["namespace"] = namespace
+    cfg["kubernetes"]["context"] = context or cfg["kubernetes"]["context"]
+    if cfg["audio"]["backend"] == "pipe" and cfg["audio"]["pipe_path"] == "-":
+        # Stdout carries the PCM stream; colored lines and the like go to stderr
+        sys.stdout = sys.stderr
 
-    This utility monitors Kubernetes metrics and plays unique sounds for each event.
-    """
     # Set environment variables based on command line options
     os.environ["SHOW_COLOR"] = "true" if color else "false"
     os.environ["USE_MIDI"] = "true" if midi else "false"
+    os.environ["AUDIO_ENABLED"] = "true" if cfg["audio"]["enabled"] else "false"
+    os.environ["LOUDNESS_COMPENSATION"] = (
+        "true" if cfg["audio"]["loudness_compensation"] else "false"
+    )
 
-    if verbose:
+    if verbose or cfg["monitoring"]["verbose"]:
         logging.getLogger().setLevel(logging.DEBUG)
 
-    if interval is not None:
-        global POLL_INTERVAL
-        POLL_INTERVAL = interval
+    global POLL_INTERVAL, USE_KUBE_CONFIG
+    POLL_INTERVAL = interval if interval is not None else cfg["monitoring"]["poll_interval"]
+    USE_KUBE_CONFIG = cfg["kubernetes"]["use_kubeconfig"]
+    k8s_client.kubeconfig = kubeconfig or cfg["kubernetes"]["kubeconfig"] or None
+    k8s_client.context = context or cfg["kubernetes"]["context"] or None
+    k8s_client.api_url = cfg["kubernetes"]["api_url"]
+    k8s_client.token = read_secret(cfg["kubernetes"]["token"], cfg["kubernetes"]["token_file"])
+    k8s_client.ca_cert = cfg["kubernetes"]["ca_cert"]
+    k8s_client.insecure_skip_verify = cfg["kubernetes"]["insecure_skip_verify"]
+    k8s_client.proxy = cfg["kubernetes"]["proxy"]
+    k8s_client.request_timeout = cfg["monitoring"]["fetch_timeout"] or None
+    k8s_client.impersonate_user = impersonate_user or cfg["kubernetes"]["impersonate_user"]
+    k8s_client.impersonate_groups = (
+        [impersonate_group] if impersonate_group else cfg["kubernetes"]["impersonate_groups"]
+    )
+    if k8s_client.impersonate_groups and not k8s_client.impersonate_user:
+        logger.error("--as-group needs --as or kubernetes.impersonate_user")
+        raise SystemExit(1)
+    k8s_client.label_selector = (
+        selector if selector is not None else cfg["kubernetes"]["label_selector"]
+    )
 
     # Start the sonification process
-    sonify_k8s_metrics(use_color=color, namespace=namespace)
+    if sequencer:
+        listening = cfg["statsd"]["enabled"] or cfg["falco"]["enabled"]
+        if from_file or gui or tray or waterfall or listening:
+            logger.error(
+                "--from-file, --gui, --tray, --waterfall, statsd and falco cannot be combined"
+                " with --sequencer"
+            )
+            raise SystemExit(1)
+        apply_sound_map(cfg.get("sound_map"), cfg["tuning"])
+        sink = install_audio_sink(cfg)
+        # Stop as cleanly on SIGTERM, e.g. from kubectl delete, as on Ctrl+C
+        stopping = threading.Event()
+        signal.signal(signal.SIGTERM, handle_termination(stopping.set))
+        try:
+            run_sequencer(
+                use_color=color,
+                namespace=namespace,
+                note_duration=cfg["audio"]["note_duration"],
+                keyboard=keyboard,
+                metrics=cfg["metrics"]["enabled"],
+                dry_run=dry_run,
+                clock=build_tempo_clock(cfg, bpm),
+                gauge=cfg["monitoring"]["gauge"],
+                stopping=stopping,
+            )
+        finally:
+            sink.close()
+        return
+    if gui + tray + waterfall > 1:
+        logger.error("--gui, --tray and --waterfall cannot be combined")
+        raise SystemExit(1)
+    if waterfall:
+        # The waterfall takes over the screen; only warnings are drawn over it
+        color = False
+        if not (verbose or cfg["monitoring"]["verbose"]):
+            logging.getLogger().setLevel(logging.WARNING)
+
+    builder = (
+        SonifierBuilder()
+        .with_config(cfg)
+        .with_namespace(namespace)
+        .with_color(color)
+        .with_arpeggio(arpeggio)
+        .with_heartbeat(heartbeat)
+        .with_dry_run(dry_run)
+        .with_keyboard(keyboard)
+        .with_summary()
+    )
+    if from_file:
+        source = FileMetricSource(from_file)
+        if not source.connect():
+            raise SystemExit(1)
+        # Replayed ticks play one per polling interval; only sonify what the file contains
+        builder.with_source(source).with_metrics(
+            [m for m in cfg["metrics"]["enabled"] if m in source.metric_names]
+        )
+    sonifier = builder.build()
+    signal.signal(signal.SIGTERM, handle_termination(sonifier.stop))
+    if tray:
+        tray_icon = TrayIcon(grafana_dashboard_url(cfg))
+        sonifier.outputs.append(tray_icon)
+        tray_icon.run(sonifier, max_ticks=ticks)
+        return
+    if gui:
+        dashboard = Dashboard(sonifier.metrics)
+        sonifier.outputs.append(dashboard)
+        dashboard.run(sonifier, max_ticks=ticks)
+        return
+    if waterfall:
+        sonifier.outputs.append(Waterfall(sonifier.metrics))
+    sonifier.run(max_ticks=ticks)
+
+
+@main.command()
+@click.option(
+    "-f", "--config", "config_path", type=click.Path(exists=True), default=None,
+    help="Path to a YAML, TOML or JSON configuration file",
+)
+@click.option("-c", "--color", is_flag=True, help="Show ANSI colors in output")
+@click.option(
+    "--dry-run", is_flag=True, help="Print the notes and colors instead of playing them"
+)
+def pipe(config_path, color, dry_run):
+    """
+    Sonify "name value" lines read from stdin.
+
+    Example: echo "cpu_usage 85" | python src/main.py pipe
+    """
+    cfg = load_config(config_path)
+    apply_sound_map(cfg.get("sound_map"), cfg["tuning"])
+    os.environ["AUDIO_ENABLED"] = "true" if cfg["audio"]["enabled"] else "false"
+    os.environ["LOUDNESS_COMPENSATION"] = (
+        "true" if cfg["audio"]["loudness_compensation"] else "false"
+    )
+    sink = install_audio_sink(cfg)
+    try:
+        sonify_stream(
+            click.get_text_stream("stdin"),
+            use_color=color or cfg["monitoring"]["use_color"],
+            note_duration=cfg["audio"]["note_duration"],
+            dry_run=dry_run,
+            gauge=cfg["monitoring"]["gauge"],
+        )
+    except KeyboardInterrupt:
+        logger.info("Stopping Sonify K8s...")
+    finally:
+        sink.close()
+
+
+@main.command("export")
+@click.option(
+    "-f", "--config", "config_path", type=click.Path(exists=True), default=None,
+    help="Read the history file from this configuration file",
+)
+@click.option(
+    "--history", "history_path", type=click.Path(exists=True), default=None,
+    help="SQLite history file (default: outputs.history.path)",
+)
+@click.option(
+    "--format", "export_format", type=click.Choice(["csv", "parquet"]), default=None,
+    help="Output format (default: from the output file name, else csv)",
+)
+@click.option("--since", default=None, help="Only samples from this time (epoch or ISO 8601)")
+@click.option("--until", default=None, help="Only samples before this time (epoch or ISO 8601)")
+@click.option("-m", "--metric", "metrics", multiple=True, help="Only this metric (repeatable)")
+@click.option("-n", "--namespace", default=None, help="Only this namespace")
+@click.option(
+    "-o", "--output", type=click.Path(dir_okay=False, writable=True), default="-",
+    help="File to write (default: stdout)",
+)
+def export(config_path, history_path, export_format, since, until, metrics, namespace, output):
+    """
+    Export stored metric history to CSV or Parquet.
+
+    Example: python src/main.py export --since 2024-07-24T10:00:00Z -o incident.parquet
+    """
+    path = history_path or load_config(config_path)["outputs"]["history"]["path"]
+    export_format = export_format or ("parquet" if output.endswith(".parquet") else "csv")
+    if export_format == "parquet" and output == "-":
+        raise click.UsageError("Parquet needs an output file, see --output")
+    try:
+        samples = read_history(
+            path,
+            since=parse_timestamp(since) if since else None,
+            until=parse_timestamp(until) if until else None,
+            metrics=list(metrics),
+            namespace=namespace,
+        )
+    except ValueError as e:
+        raise click.BadParameter(str(e), param_hint="--since/--until")
+    except sqlite3.Error as e:
+        click.echo(f"{path}: {e}", err=True)
+        raise SystemExit(1)
+    for sample in samples:
+        sample["timestamp"] = datetime.fromtimestamp(sample["timestamp"], timezone.utc).isoformat()
+
+    if export_format == "parquet":
+        try:
+            import pyarrow
+            import pyarrow.parquet
+        except ImportError:
+            click.echo("Parquet export needs the pyarrow package", err=True)
+            raise SystemExit(1)
+        table = pyarrow.table({column: [s[column] for s in samples] for column in HISTORY_COLUMNS})
+        pyarrow.parquet.write_table(table, output)
+    else:
+        with click.open_file(output, "w") as f:
+            writer = csv.DictWriter(f, fieldnames=HISTORY_COLUMNS, lineterminator="\n")
+            writer.writeheader()
+            writer.writerows(samples)
+    if output != "-":
+        click.echo(f"Exported {len(samples)} samples to {output}", err=True)
+
+
+@main.command("report")
+@click.option(
+    "-f", "--config", "config_path", type=click.Path(exists=True), default=None,
+    help="Read the history file and sound map from this configuration file",
+)
+@click.option(
+    "--history", "history_path", type=click.Path(exists=True), default=None,
+    help="SQLite history file (default: outputs.history.path)",
+)
+@click.option("--since", default=None, help="Only samples from this time (epoch or ISO 8601)")
+@click.option("--until", default=None, help="Only samples before this time (epoch or ISO 8601)")
+@click.option("-m", "--metric", "metrics", multiple=True, help="Only this metric (repeatable)")
+@click.option("-n", "--namespace", default=None, help="Only this namespace")
+@click.option("-t", "--title", default="Sonify K8s incident report", help="Heading of the report")
+@click.option("--no-audio", is_flag=True, help="Leave out the audio snippets of the alarms")
+@click.option(
+    "-o", "--output", type=click.Path(dir_okay=False, writable=True), default="report.html",
+    help="HTML file to write (default: report.html)",
+)
+def report(config_path, history_path, since, until, metrics, namespace, title, no_audio, output):
+    """
+    Render stored metric history as a standalone HTML incident report.
+
+    Example: python src/main.py report --since 2024-07-24T10:00:00Z -o incident.html
+    """
+    cfg = load_config(config_path)
+    apply_sound_map(cfg.get("sound_map"), cfg["tuning"])
+    path = history_path or cfg["outputs"]["history"]["path"]
+    try:
+        samples = read_history(
+            path,
+            since=parse_timestamp(since) if since else None,
+            until=parse_timestamp(until) if until else None,
+            metrics=list(metrics),
+            namespace=namespace,
+        )
+    except ValueError as e:
+        raise click.BadParameter(str(e), param_hint="--since/--until")
+    except sqlite3.Error as e:
+        click.echo(f"{path}: {e}", err=True)
+        raise SystemExit(1)
+    with click.open_file(output, "w", encoding="utf-8") as f:
+        f.write(render_report(samples, title=title, audio=not no_audio))
+    if output != "-":
+        click.echo(f"Wrote a report of {len(samples)} samples to {output}", err=True)
+
+
+@main.command("test-audio")
+@click.option(
+    "-f", "--config", "config_path", type=click.Path(exists=True), default=None,
+    help="Play through the audio backend of this configuration file",
+)
+@click.option(
+    "-m", "--midi", is_flag=True, help="Play the scale through MIDI instead of audio"
+)
+@click.option(
+    "-d", "--duration", type=float, default=0.5, help="Duration of each note in seconds"
+)
+def test_audio(config_path, midi, duration):
+    """Play a C major scale through the configured audio backend."""
+    sink = None if midi else install_audio_sink(load_config(config_path))
+    try:
+        # The CPU usage notes form a C major scale from C4 to C5
+        for frequency, note_name in SOUND_MAP["cpu_usage"]["notes"]:
+            click.echo(f"{note_name} ({frequency} Hz)")
+            if midi:
+                play_midi_note(frequency_to_midi(frequency), duration)
+            else:
+                play_note(frequency, duration)
+    finally:
+        if sink is not None:
+            sink.close()
+
+
+@main.command("list-metrics")
+@click.option(
+    "-f", "--config", "config_path", type=click.Path(exists=True), default=None,
+    help="Mark the metrics enabled in this configuration file",
+)
+def list_metrics(config_path):
+    """Show the available metrics and their ranges."""
+    enabled = load_config(config_path)["metrics"]["enabled"] or []
+    click.echo(f"{'METRIC':<20} {'NAME':<16} {'RANGE':<12} {'UNIT':<7} {'NOTES':<6} ENABLED")
+    for metric_name, metric_config in SOUND_MAP.items():
+        min_value, max_value = metric_config["range"]
+        click.echo(
+            f"{metric_name:<20} {metric_config['metric_name']:<16} "
+            f"{f'{min_value}-{max_value}':<12} {metric_config['unit'] or '-':<7} "
+            f"{len(metric_config['notes']):<6} {'yes' if metric_name in enabled else 'no'}"
+        )
+
+
+@main.command("validate-config")
+@click.argument("config_path", type=click.Path(exists=True))
+@click.option(
+    "-p", "--profile", default=None, help="Check only this profile (default: every profile)"
+)
+def validate_config_command(config_path, profile):
+    """Parse and check a configuration file and its profiles."""
+    try:
+        base = validate_config(load_config(config_path))
+        names = [profile] if profile else list(read_config_file(config_path).get("profiles") or {})
+        problems = [] if profile else list(base)
+        for name in names:
+            # Problems in the base settings are reported once, not again for every profile
+            problems.extend(
+                f"profiles.{name}: {problem}"
+                for problem in validate_config(load_config(config_path, name))
+                if profile or problem not in base
+            )
+    except (OSError, ValueError, yaml.YAMLError) as e:
+        click.echo(f"{config_path}: {e}", err=True)
+        raise SystemExit(1)
+
+    for problem in problems:
+        click.echo(f"{config_path}: {problem}", err=True)
+    if problems:
+        raise SystemExit(1)
+    click.echo(f"{config_path}: OK")
+
+
+@main.command("generate-config")
+@click.option(
+    "--full", is_flag=True, help="Include the full built-in sound map"
+)
+@click.option(
+    "-o", "--output", type=click.Path(dir_okay=False, writable=True), default="-",
+    help="File to write (default: stdout)",
+)
+def generate_config(full, output):
+    """Write a commented default configuration file."""
+    with click.open_file(output, "w") as f:
+        f.write(render_default_config(full=full))
+    if output != "-":
+        click.echo(f"Wrote {output}")
+
+
+@main.command("generate-crd")
+@click.option(
+    "-o", "--output", type=click.Path(dir_okay=False, writable=True), default="-",
+    help="File to write (default: stdout)",
+)
+def generate_crd(output):
+    """Write the SonificationProfile CustomResourceDefinition for kubectl apply."""
+    with click.open_file(output, "w") as f:
+        yaml.safe_dump(profile_crd(), f, sort_keys=False)
+    if output != "-":
+        click.echo(f"Wrote {output}")
 
 
 if __name__ == "__main__":
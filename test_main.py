@@ -1,6 +1,9 @@
+import copy
 import os
 import pytest
+import time
 import types
+from datetime import datetime, timezone
 from src import main
 
 
@@ -37,6 +40,28 @@ def test_calculate_index_max_le_min():
     assert main.calculate_index(10, 5, 10, 10) == 0
 
 
+@pytest.mark.parametrize(
+    "value, style, expected",
+    [
+        (50, "none", ""),
+        (-5, "block", "▕        ▏"),
+        (85, "block", "▕██████▊ ▏"),
+        (100, "block", "▕████████▏"),
+        (0, "braille", "⣀⣀⣀⣀⣀⣀⣀⣀"),
+        (20, "braille", "⣿⣇⣀⣀⣀⣀⣀⣀"),
+        (150, "braille", "⣿⣿⣿⣿⣿⣿⣿⣿"),
+    ],
+)
+def test_render_gauge(value, style, expected):
+    assert main.render_gauge(value, 0, 100, style) == expected
+
+
+def test_report_metric_shows_gauge_after_value(caplog):
+    caplog.set_level("INFO")
+    main.report_metric(main.SOUND_MAP["cpu_usage"], 85.0, "A4", 440, "#0F4C75", None, gauge="block")
+    assert "CPU Usage: 85.00 % ▕██████▊ ▏ | Note: A4 (440 Hz)" in caplog.text
+
+
 def test_get_k8s_data_cpu_usage():
     value, extra = main.get_k8s_data("cpu_usage")
     assert 0 <= value <= 100
@@ -79,6 +104,162 @@ def test_get_k8s_data_node_pressure():
     assert "pressure" in extra
 
 
+def fake_api_client():
+    return types.SimpleNamespace(rest_client=types.SimpleNamespace(
+        pool_manager=types.SimpleNamespace(request=lambda method, url, **kwargs: None)
+    ))
+
+
+def test_k8s_client_uses_context_and_selector(monkeypatch):
+    loaded = {}
+    fake_config = types.SimpleNamespace(load_kube_config=lambda **kw: loaded.update(kw))
+
+    class FakeCoreV1Api:
+        def __init__(self, api_client):
+            pass
+
+        def list_namespace(self, limit=None):
+            return types.SimpleNamespace(items=[])
+
+        def list_namespaced_pod(self, namespace, label_selector=""):
+            loaded.update(namespace=namespace, label_selector=label_selector)
+            return types.SimpleNamespace(items=[])
+
+    fake_client = types.SimpleNamespace(
+        ApiClient=fake_api_client, CoreV1Api=FakeCoreV1Api, AppsV1Api=lambda api_client: None,
+        CustomObjectsApi=lambda api_client: None,
+    )
+    monkeypatch.setattr(main, "config", fake_config)
+    monkeypatch.setattr(main, "client", fake_client)
+    monkeypatch.setattr(main, "USE_KUBE_CONFIG", True)
+    k8s = main.K8sClient()
+    k8s.kubeconfig, k8s.context, k8s.label_selector = "/tmp/prod.yaml", "prod", "app=web"
+    assert k8s.initialize()
+    k8s.get_pods_status("web")
+    assert loaded == {
+        "config_file": "/tmp/prod.yaml", "context": "prod",
+        "namespace": "web", "label_selector": "app=web",
+    }
+
+
+def test_k8s_client_connects_to_api_url(monkeypatch):
+    class FakeConfiguration:
+        default = None
+
+        @classmethod
+        def set_default(cls, configuration):
+            cls.default = configuration
+
+        @classmethod
+        def get_default_copy(cls):
+            return copy.copy(cls.default)
+
+    class FakeCoreV1Api:
+        def __init__(self, api_client):
+            pass
+
+        def list_namespace(self, limit=None):
+            return types.SimpleNamespace(items=[])
+
+    fake_client = types.SimpleNamespace(
+        Configuration=FakeConfiguration, ApiClient=fake_api_client, CoreV1Api=FakeCoreV1Api,
+        AppsV1Api=lambda api_client: None, CustomObjectsApi=lambda api_client: None,
+    )
+    monkeypatch.setattr(main, "client", fake_client)
+    monkeypatch.setattr(main, "USE_KUBE_CONFIG", False)
+    monkeypatch.delenv("KUBERNETES_SERVICE_HOST", raising=False)
+    k8s = main.K8sClient()
+    k8s.api_url, k8s.token = "https://k8s.example:6443", "secret"
+    k8s.ca_cert, k8s.proxy = "/etc/ssl/k8s-ca.pem", "http://proxy:3128"
+    assert k8s.initialize()
+    configuration = FakeConfiguration.default
+    assert configuration.host == "https://k8s.example:6443"
+    assert configuration.api_key == {"authorization": "Bearer secret"}
+    assert configuration.ssl_ca_cert == "/etc/ssl/k8s-ca.pem"
+    assert configuration.proxy == "http://proxy:3128"
+
+
+def test_k8s_client_impersonates(monkeypatch):
+    sent = []
+
+    class FakePoolManager:
+        def request(self, method, url, headers=None, **kwargs):
+            sent.append(list(headers.items()))
+
+    class FakeApiClient:
+        def __init__(self):
+            self.rest_client = types.SimpleNamespace(pool_manager=FakePoolManager())
+            self.default_headers = {}
+
+        def set_default_header(self, name, value):
+            self.default_headers[name] = value
+
+    class FakeCoreV1Api:
+        def __init__(self, api_client):
+            self.api_client = api_client
+
+        def list_namespace(self, limit=None):
+            headers = {"Accept": "application/json", **self.api_client.default_headers}
+            self.api_client.rest_client.pool_manager.request(
+                "GET", "/api/v1/namespaces", headers=headers
+            )
+
+    fake_client = types.SimpleNamespace(
+        ApiClient=FakeApiClient, CoreV1Api=FakeCoreV1Api, AppsV1Api=lambda api_client: None,
+        CustomObjectsApi=lambda api_client: None,
+    )
+    monkeypatch.setattr(main, "config", types.SimpleNamespace(load_kube_config=lambda **kw: None))
+    monkeypatch.setattr(main, "client", fake_client)
+    monkeypatch.setattr(main, "USE_KUBE_CONFIG", True)
+    k8s = main.K8sClient()
+    k8s.impersonate_user = "system:serviceaccount:web:viewer"
+    k8s.impersonate_groups = ["viewers"]
+    assert k8s.initialize()
+    assert sent == [[
+        ("Accept", "application/json"),
+        ("Impersonate-User", "system:serviceaccount:web:viewer"),
+        ("Impersonate-Group", "viewers"),
+    ]]
+    # The connection test was timed for apiserver_latency
+    assert len(k8s.latencies) == 1
+
+    cfg = main.merge_config(main.DEFAULT_CONFIG, {"kubernetes": {
+        "impersonate_user": "jane", "impersonate_groups": ["viewers", "auditors"],
+    }})
+    assert main.validate_config(cfg) == [
+        "kubernetes.impersonate_groups: only one group can be impersonated"
+    ]
+
+
+def test_timed_api_records_failed_calls_too(monkeypatch):
+    def forbidden(namespace):
+        error = main.ApiException()
+        error.status = 403
+        raise error
+
+    clock = iter([1.0, 1.5])
+    monkeypatch.setattr(main.time, "monotonic", lambda: next(clock))
+    k8s = main.K8sClient()
+    api = main.TimedApi(types.SimpleNamespace(list_namespaced_pod=forbidden), k8s.record_latency)
+    with pytest.raises(main.ApiException):
+        api.list_namespaced_pod("default")
+    assert k8s.latencies == [0.5]
+
+
+def test_apiserver_latency_of_own_requests(monkeypatch):
+    clock = iter([10.0, 10.25])
+    probes = []
+    monkeypatch.setattr(main.time, "monotonic", lambda: next(clock))
+    k8s = main.K8sClient()
+    k8s.v1_core = types.SimpleNamespace(list_namespace=lambda limit: probes.append(limit))
+    k8s.latencies = [0.1, 0.3, 0.05]
+    assert k8s.get_apiserver_latency() == (150.0, {"requests": 3, "slowest_ms": 300.0})
+    assert probes == [] and k8s.latencies == []
+    # Nothing else was requested since: time a probe instead
+    assert k8s.get_apiserver_latency() == (250.0, {"requests": 1, "slowest_ms": 250.0})
+    assert probes == [1]
+
+
 def test_get_k8s_data_invalid():
     assert main.get_k8s_data("not_a_metric") is None
 
@@ -113,3 +294,3165 @@ def test_sonify_k8s_metrics_keyboard_interrupt(monkeypatch):
     monkeypatch.setattr(main, "time", types.SimpleNamespace(sleep=fake_sleep))
     # Should exit cleanly
     main.sonify_k8s_metrics()
+
+
+def test_map_metric_uses_range():
+    index, frequency, note_name, color = main.map_metric("http_latency", 500)[:4]
+    assert index == 7
+    assert (frequency, note_name) == main.SOUND_MAP["http_latency"]["notes"][7]
+    assert color == main.SOUND_MAP["http_latency"]["colors"][7]
+
+
+def test_map_metric_status_clamped():
+    mapped = main.map_metric("pod_status", 9)
+    assert mapped.index == 3
+    assert mapped.note_name == "G4"
+
+
+def test_map_metric_drives_duration_and_velocity(monkeypatch):
+    monkeypatch.setitem(main.SOUND_MAP, "cpu_usage", dict(
+        main.SOUND_MAP["cpu_usage"], duration_range=(0.2, 1.0), velocity_range=(0.5, 1.0)
+    ))
+    monkeypatch.setitem(main.SOUND_MAP, "pod_status", dict(
+        main.SOUND_MAP["pod_status"], velocity_range=(0.5, 1.0)
+    ))
+    assert main.map_metric("cpu_usage", 0)[4:] == (0.2, 0.5)
+    assert main.map_metric("cpu_usage", 75)[4:] == (pytest.approx(0.8), pytest.approx(0.875))
+    assert main.map_metric("http_latency", 75)[4:] == (None, 1.0)
+    # Fewer running pods is worse, so it is louder
+    assert main.map_metric("pod_status", 0).velocity == 1.0
+    assert main.map_metric("pod_status", 3).velocity == 0.5
+    assert "sound_map.cpu_usage.velocity_range" in " ".join(main.validate_config(
+        main.merge_config(main.DEFAULT_CONFIG, {"sound_map": {"cpu_usage": {
+            "velocity_range": [0.5, 2.0],
+        }}})
+    ))
+
+
+def test_play_arpeggio_plays_each_value(monkeypatch):
+    played = []
+    monkeypatch.setattr(main, "play_note", lambda f, d=0.5, **kw: played.append((f, d)))
+    main.play_arpeggio([262, 330, 392], 0.6)
+    assert [f for f, _ in played] == [262, 330, 392]
+    assert all(d == pytest.approx(0.2) for _, d in played)
+
+
+def test_play_arpeggio_min_step(monkeypatch):
+    played = []
+    monkeypatch.setattr(main, "play_note", lambda f, d=0.5, **kw: played.append(d))
+    main.play_arpeggio([262] * 20, 0.5)
+    assert min(played) == main.ARPEGGIO_MIN_STEP
+
+
+def test_metric_poller_keeps_latest(monkeypatch):
+    monkeypatch.setattr(
+        main, "get_k8s_data", lambda m, namespace="default": (1.0, {"ns": namespace})
+    )
+    poller = main.MetricPoller("prod")
+    poller.poll_once()
+    assert poller.get("cpu_usage") == (1.0, {"ns": "prod"})
+    assert set(poller.latest) == set(main.SOUND_MAP)
+
+
+def test_run_sequencer_plays_steps_in_order(monkeypatch):
+    played = []
+    steps = {"count": 0}
+
+    def fake_sleep(_):
+        steps["count"] += 1
+        if steps["count"] >= len(main.SOUND_MAP):
+            raise KeyboardInterrupt()
+
+    poller = main.MetricPoller()
+    poller.latest = {name: (0, {}) for name in main.SOUND_MAP}
+    monkeypatch.setattr(main, "MetricPoller", lambda *a, **kw: poller)
+    monkeypatch.setattr(poller, "start", lambda: None)
+    monkeypatch.setattr(main.k8s_client, "initialize", lambda: True)
+    monkeypatch.setattr(main, "play_note", lambda f, d=0.5, **kw: played.append((f, d)))
+    monkeypatch.setattr(
+        main, "time", types.SimpleNamespace(sleep=fake_sleep, monotonic=lambda: 0.0)
+    )
+    main.run_sequencer(bpm=240)
+    expected = [main.SOUND_MAP[name]["notes"][0][0] for name in main.SOUND_MAP]
+    assert [f for f, _ in played] == expected
+    assert all(d == 0.25 for _, d in played)
+
+
+def test_sigterm_stops_the_sequencer_before_its_next_step(monkeypatch):
+    played = []
+    poller = main.MetricPoller()
+    monkeypatch.setattr(main, "MetricPoller", lambda *a, **kw: poller)
+    monkeypatch.setattr(poller, "start", lambda: None)
+    monkeypatch.setattr(main.k8s_client, "initialize", lambda: True)
+    monkeypatch.setattr(main, "play_note", lambda *a, **kw: played.append(a))
+    stopping = main.threading.Event()
+    main.handle_termination(stopping.set)(main.signal.SIGTERM, None)
+    main.run_sequencer(bpm=240, stopping=stopping)
+    assert played == []
+
+
+def test_simpleaudio_sink_fade_out_stops_playing_notes(monkeypatch):
+    class FakePlayObject:
+        def __init__(self):
+            self.done = main.threading.Event()
+
+        def wait_done(self):
+            self.done.wait(5)
+
+        def stop(self):
+            self.done.set()
+
+    plays = []
+
+    def play_buffer(audio, channels, width, rate):
+        plays.append(FakePlayObject())
+        return plays[-1]
+
+    monkeypatch.setitem(
+        main.sys.modules, "simpleaudio", types.SimpleNamespace(play_buffer=play_buffer)
+    )
+    sink = main.SimpleaudioSink()
+    player = main.threading.Thread(target=sink.play, args=(440, 10.0))
+    player.start()
+    for _ in range(500):
+        if sink.playing:
+            break
+        main.time.sleep(0.01)
+    sink.fade_out(main.PREEMPT_FADE)
+    player.join(5)
+    assert not player.is_alive() and plays[0].done.is_set()
+    assert sink.playing == set()
+
+
+@pytest.mark.parametrize(
+    "metric,value,expected",
+    [
+        ("cpu_usage", 10, "ok"),
+        ("cpu_usage", 75, "warning"),
+        ("cpu_usage", 95, "critical"),
+        ("pod_status", 3, "ok"),
+        ("pod_status", 1, "warning"),
+        ("pod_status", 0, "critical"),
+        ("replicas", 0, "ok"),
+    ],
+)
+def test_get_severity(metric, value, expected):
+    assert main.get_severity(metric, value) == expected
+
+
+def test_wait_for_next_poll_heartbeat_ticks(monkeypatch):
+    ticks = []
+    slept = []
+    monkeypatch.setattr(main, "POLL_INTERVAL", 3)
+    monkeypatch.setattr(main, "play_heartbeat", ticks.append)
+    monkeypatch.setattr(main, "time", types.SimpleNamespace(sleep=slept.append))
+    main.wait_for_next_poll(heartbeat="warning")
+    assert ticks == ["warning"] * 3
+    assert sum(slept) + len(ticks) * main.HEARTBEAT_DURATION == pytest.approx(3)
+
+
+def test_wait_for_next_poll_silent_when_unreachable(monkeypatch):
+    ticks = []
+    monkeypatch.setattr(main, "play_heartbeat", ticks.append)
+    monkeypatch.setattr(main, "time", types.SimpleNamespace(sleep=lambda _: None))
+    main.wait_for_next_poll(heartbeat=None)
+    assert ticks == []
+
+
+def test_heartbeat_severity_follows_alarms():
+    sonifier = main.Sonifier(main.KubernetesSource(), ["cpu_usage"], heartbeat=True)
+    assert sonifier.heartbeat_severity() == "ok"
+    sonifier.health = "warning"
+    assert sonifier.heartbeat_severity() == "warning"
+    sonifier.health = "ok"
+    sonifier.state.active_alarms.add("cpu_usage")
+    assert sonifier.heartbeat_severity() == "critical"
+    sonifier.state.acknowledge()
+    assert sonifier.heartbeat_severity() == "ok"
+    sonifier.health = None
+    assert sonifier.heartbeat_severity() is None
+
+
+def test_keyboard_mute_and_solo():
+    state = main.PlaybackState()
+    controller = main.KeyboardController(state, ["cpu_usage", "memory_usage"])
+    controller.handle_key("1")
+    assert not state.is_audible("cpu_usage")
+    controller.handle_key("1")
+    assert state.is_audible("cpu_usage")
+    controller.handle_key("@")
+    assert state.is_audible("memory_usage")
+    assert not state.is_audible("cpu_usage")
+    controller.handle_key("0")
+    assert state.is_audible("cpu_usage")
+
+
+def test_keyboard_switches_context(monkeypatch):
+    switched = []
+    monkeypatch.setattr(main.k8s_client, "list_contexts", lambda: (["dev", "prod"], "dev"))
+    monkeypatch.setattr(main.k8s_client, "switch_context", lambda c: switched.append(c) or True)
+    monkeypatch.setattr(main.k8s_client, "initialize", lambda: True)
+    monkeypatch.setattr(main, "get_k8s_data", lambda m, namespace="default": (95.0, {}))
+    monkeypatch.setattr(main, "play_note", lambda *a, **kw: None)
+    sonifier = main.Sonifier(main.KubernetesSource(), ["cpu_usage"])
+    alarms = []
+    sonifier.on_alarm(lambda event, transition: alarms.append(transition))
+    sonifier.tick()
+
+    main.KeyboardController(sonifier.state).handle_key("c")
+    assert (switched, sonifier.state.context) == (["prod"], "prod")
+    sonifier.tick()
+    assert alarms == ["fired", "fired"]
+
+
+def test_keyboard_volume_and_pause():
+    state = main.PlaybackState()
+    controller = main.KeyboardController(state)
+    controller.handle_key("-")
+    controller.handle_key("-")
+    assert state.volume == pytest.approx(0.8)
+    for _ in range(5):
+        controller.handle_key("+")
+    assert state.volume == 1.0
+    controller.handle_key("p")
+    assert state.paused
+
+
+def test_midi_controller_adjusts_playback(monkeypatch):
+    import copy
+
+    monkeypatch.setattr(main, "SOUND_MAP", copy.deepcopy(main.SOUND_MAP))
+    monkeypatch.setattr(main, "POLL_INTERVAL", main.POLL_INTERVAL)
+    state = main.PlaybackState()
+    controller = main.MidiController(
+        state, {7: "volume", 16: "gain.errors_per_second", 17: "threshold.cpu_usage",
+                18: "poll_interval"}
+    )
+
+    def cc(control, value):
+        # As the MIDI library's thread delivers them
+        controller.pending.put(
+            types.SimpleNamespace(type="control_change", control=control, value=value)
+        )
+
+    cc(7, 0)
+    cc(7, 127)
+    cc(16, 127)
+    cc(17, 0)
+    cc(18, 127)
+    cc(99, 0)
+    assert main.SOUND_MAP["cpu_usage"]["thresholds"] != (-20, 0)
+    controller.apply_pending()
+    assert state.volume == 1.0
+    assert state.metric_volume("errors_per_second") == 1.0
+    state.volume = 0.25
+    assert state.metric_volume("errors_per_second") == 0.5
+    assert main.SOUND_MAP["cpu_usage"]["thresholds"] == (-20, 0)
+    assert main.POLL_INTERVAL == 60
+
+
+def osc_message(address, tags, *args):
+    def pad(data):
+        return data + b"\0" * (4 - len(data) % 4)
+
+    packet = pad(address.encode()) + pad(("," + tags).encode())
+    for tag, arg in zip(tags, args):
+        packet += pad(arg.encode()) if tag == "s" else main.struct.pack(">" + tag, arg)
+    return packet
+
+
+def test_parse_osc_decodes_messages_and_bundles():
+    message = osc_message("/sonify/mute", "si", "cpu_usage", 1)
+    assert main.parse_osc(message) == [("/sonify/mute", ["cpu_usage", 1])]
+    volume = osc_message("/sonify/volume", "f", 0.5)
+    bundle = b"#bundle\0" + b"\0" * 8
+    for part in (volume, message):
+        bundle += main.struct.pack(">i", len(part)) + part
+    assert main.parse_osc(bundle) == [
+        ("/sonify/volume", [0.5]), ("/sonify/mute", ["cpu_usage", 1])
+    ]
+    with pytest.raises(ValueError):
+        main.parse_osc(b"junk")
+
+
+def test_osc_controller_applies_messages(monkeypatch):
+    played = []
+    monkeypatch.setattr(main, "play_note", lambda *args: played.append(args))
+    state = main.PlaybackState()
+    controller = main.OscController(state)
+    controller.handle_message("/sonify/volume", [0.25])
+    controller.handle_message("/sonify/mute", ["cpu_usage", 1.0])
+    controller.handle_message("/sonify/mute", ["memory_usage"])
+    controller.handle_message("/sonify/mute", ["memory_usage", 0])
+    controller.handle_message("/sonify/pause", [])
+    controller.handle_message("/sonify/test", [880.0])
+    controller.handle_message("/sonify/test", [0.0])
+    controller.handle_message("/other/volume", [1.0])
+    assert state.volume == 0.25
+    assert state.muted == {"cpu_usage"}
+    assert state.paused
+    assert played == [(880.0, main.OSC_TEST_DURATION, 0.25)]
+    controller.handle_message("/sonify/mute", [1])
+    assert not state.is_audible("replicas")
+
+
+def test_sonifier_starts_with_configured_solo_and_toggles_metrics(monkeypatch):
+    monkeypatch.setattr(main, "SOUND_MAP", copy.deepcopy(main.SOUND_MAP))
+    monkeypatch.setattr(main.k8s_client, "initialize", lambda: True)
+    monkeypatch.setattr(main, "get_k8s_data", lambda m, namespace="default": (10.0, {}))
+    played = []
+    monkeypatch.setattr(
+        main, "play_note", lambda f, d, volume=1.0, voice=None: played.append(f)
+    )
+    cfg = main.merge_config(main.DEFAULT_CONFIG, {
+        "metrics": {"enabled": ["cpu_usage", "memory_usage", "replicas"]},
+        "sound_map": {"memory_usage": {"solo": True}, "replicas": {"muted": True}},
+    })
+    assert main.validate_config(cfg) == []
+    sonifier = main.SonifierBuilder().with_config(cfg).build()
+    assert (sonifier.state.solo, sonifier.state.muted) == ({"memory_usage"}, {"replicas"})
+    events = sonifier.tick()
+    assert [e.metric for e in events] == ["cpu_usage", "memory_usage", "replicas"]
+    assert played == [main.map_metric("memory_usage", 10.0).frequency]
+
+    controller = main.OscController(sonifier.state)
+    controller.handle_message("/sonify/solo", [0])
+    controller.handle_message("/sonify/enable", ["memory_usage", 0])
+    assert [e.metric for e in sonifier.tick()] == ["cpu_usage", "replicas"]
+    controller.handle_message("/sonify/enable", ["memory_usage"])
+    controller.handle_message("/sonify/solo", ["replicas"])
+    assert [e.metric for e in sonifier.tick()] == ["cpu_usage", "memory_usage", "replicas"]
+    assert sonifier.state.solo == {"replicas"}
+
+
+def test_keyboard_switches_namespace(monkeypatch):
+    monkeypatch.setattr(main.k8s_client, "list_namespaces", lambda: ["default", "prod"])
+    state = main.PlaybackState("default")
+    main.KeyboardController(state).handle_key("n")
+    assert state.namespace == "prod"
+
+
+def test_acknowledged_alarm_silenced_until_cleared():
+    state = main.PlaybackState()
+    controller = main.KeyboardController(state)
+    state.update_alarm("cpu_usage", "critical")
+    controller.handle_key("a")
+    assert not state.is_audible("cpu_usage")
+    state.update_alarm("cpu_usage", "ok")
+    assert state.is_audible("cpu_usage")
+
+
+def test_load_config_merges_defaults(tmp_path):
+    path = tmp_path / "config.yaml"
+    path.write_text("monitoring:\n  poll_interval: 10\nmetrics:\n  enabled: [cpu_usage]\n")
+    cfg = main.load_config(str(path))
+    assert cfg["monitoring"]["poll_interval"] == 10
+    assert cfg["monitoring"]["use_color"] is False
+    assert cfg["metrics"]["enabled"] == ["cpu_usage"]
+    assert main.validate_config(cfg) == []
+
+
+def test_load_config_overlays_profile(tmp_path):
+    from click.testing import CliRunner
+
+    path = tmp_path / "config.yaml"
+    path.write_text(
+        "audio:\n  note_duration: 0.5\nmetrics:\n  enabled: [cpu_usage]\n"
+        "profiles:\n  quiet:\n    audio: {note_duration: 0.1}\n"
+        "  demo:\n    metrics: {enabled: [bogus]}\n"
+    )
+    assert main.load_config(str(path))["audio"]["note_duration"] == 0.5
+    quiet = main.load_config(str(path), "quiet")
+    assert quiet["audio"]["note_duration"] == 0.1
+    assert quiet["metrics"]["enabled"] == ["cpu_usage"]
+    assert "profiles" not in quiet
+    with pytest.raises(ValueError, match="unknown profile 'loud'"):
+        main.load_config(str(path), "loud")
+
+    result = CliRunner().invoke(main.main, ["validate-config", str(path)])
+    assert result.exit_code == 1
+    assert "profiles.demo: metrics.enabled: unknown metric 'bogus'" in result.output
+    result = CliRunner().invoke(main.main, ["validate-config", str(path), "-p", "quiet"])
+    assert result.exit_code == 0
+
+
+def test_load_config_merges_includes(tmp_path):
+    (tmp_path / "shared").mkdir()
+    (tmp_path / "shared" / "base.yaml").write_text(
+        "metrics:\n  enabled: [cpu_usage, replicas]\n"
+        "sound_map:\n  cpu_usage:\n    thresholds: [60, 80]\n    unit: '%'\n"
+    )
+    (tmp_path / "cluster.json").write_text('{"kubernetes": {"namespace": "prod"}}')
+    path = tmp_path / "config.yaml"
+    path.write_text(
+        "include: [shared/base.yaml, cluster.json]\n"
+        "sound_map:\n  cpu_usage:\n    thresholds: [85, 95]\n"
+    )
+    cfg = main.load_config(str(path))
+    assert cfg["metrics"]["enabled"] == ["cpu_usage", "replicas"]
+    assert cfg["kubernetes"]["namespace"] == "prod"
+    assert cfg["sound_map"]["cpu_usage"] == {"thresholds": [85, 95], "unit": "%"}
+    assert "include" not in cfg
+
+    (tmp_path / "cluster.json").write_text('{"include": "config.yaml"}')
+    with pytest.raises(ValueError, match="include cycle"):
+        main.load_config(str(path))
+
+
+def test_config_credentials_from_env_and_files(tmp_path, monkeypatch):
+    token_file = tmp_path / "token"
+    token_file.write_text("from-secret\n")
+    path = tmp_path / "config.yaml"
+    path.write_text(
+        "outputs:\n"
+        "  influxdb: {enabled: true, org: ops, token: '${INFLUX_TOKEN}'}\n"
+        f"  grafana: {{enabled: true, token_file: '{token_file}'}}\n"
+    )
+    monkeypatch.setenv("INFLUX_TOKEN", "from-env")
+    cfg = main.load_config(str(path))
+    assert main.validate_config(cfg) == []
+    influxdb, grafana = main.build_outputs(cfg)
+    assert (influxdb.token, grafana.token) == ("from-env", "from-secret")
+
+    monkeypatch.delenv("INFLUX_TOKEN")
+    with pytest.raises(ValueError, match="outputs.influxdb.token: environment variable"):
+        main.load_config(str(path))
+    cfg["outputs"]["grafana"]["token_file"] = str(tmp_path / "missing")
+    assert main.validate_config(cfg)[0].startswith("outputs.grafana.token_file: ")
+    cfg["outputs"]["grafana"]["token"] = "inline"
+    assert main.validate_config(cfg) == ["outputs.grafana: set token or token_file, not both"]
+
+    assert main.interpolate_env("$${INFLUX_TOKEN} and ${HOME}", environ={"HOME": "/root"}) == (
+        "${INFLUX_TOKEN} and /root"
+    )
+
+
+def test_load_config_reads_toml_and_json(tmp_path):
+    toml_path = tmp_path / "config.toml"
+    toml_path.write_text(
+        '[monitoring]\npoll_interval = 10\n\n[controller.controls]\n7 = "volume"\n'
+    )
+    json_path = tmp_path / "config.json"
+    json_path.write_text(
+        '{"monitoring": {"poll_interval": 10}, "controller": {"controls": {"7": "volume"}}}'
+    )
+    for path in (toml_path, json_path):
+        cfg = main.load_config(str(path))
+        assert cfg["monitoring"]["poll_interval"] == 10
+        assert cfg["controller"]["controls"] == {7: "volume"}
+        assert main.validate_config(cfg) == []
+    json_path.write_text("[1]")
+    with pytest.raises(ValueError):
+        main.load_config(str(json_path))
+
+
+def test_env_overrides_apply_over_config_file(tmp_path, monkeypatch):
+    path = tmp_path / "config.yaml"
+    path.write_text("audio:\n  note_duration: 1.0\n")
+    monkeypatch.setenv("SONIFY_AUDIO__NOTE_DURATION", "0.25")
+    monkeypatch.setenv("SONIFY_METRICS__ENABLED", "[cpu_usage, replicas]")
+    monkeypatch.setenv("SONIFY_CONTROLLER__CONTROLS__16", "gain.cpu_usage")
+    monkeypatch.setenv("SONIFY_KUBERNETES__CONTEXT", "")
+    monkeypatch.setenv("SONIFY_AUDIO__NOTE_DURATON", "2")
+    monkeypatch.setenv("SONIFY_KUBERNETES__NAMESPACE", "0123")
+    monkeypatch.setenv("SONIFY_KUBERNETES__LABEL_SELECTOR", "no")
+    monkeypatch.setenv("SONIFY_OSC__ENABLED", "no")
+    cfg = main.load_config(str(path))
+    assert cfg["audio"]["note_duration"] == 0.25
+    assert cfg["metrics"]["enabled"] == ["cpu_usage", "replicas"]
+    assert cfg["controller"]["controls"] == {7: "volume", 16: "gain.cpu_usage"}
+    assert cfg["kubernetes"]["context"] == ""
+    assert cfg["kubernetes"]["namespace"] == "0123"
+    assert cfg["kubernetes"]["label_selector"] == "no"
+    assert cfg["osc"]["enabled"] is False
+    assert "note_duraton" not in cfg["audio"]
+    assert main.validate_config(cfg) == []
+    assert main.env_overrides({"SONIFY_EARCONS__EVENTS__POD_RESTART": "off"}) == {
+        "earcons": {"events": {"pod_restart": "off"}},
+    }
+
+
+def test_validate_config_reports_problems():
+    cfg = main.load_config()
+    cfg["monitoring"]["poll_interval"] = 0
+    cfg["metrics"]["enabled"] = ["cpu_usage", "bogus"]
+    problems = main.validate_config(cfg)
+    assert "monitoring.poll_interval: must be a number greater than 0" in problems
+    assert "metrics.enabled: unknown metric 'bogus'" in problems
+
+
+def test_validate_config_checks_sound_map_entries():
+    cfg = main.load_config()
+    cfg["sound_map"] = {
+        "cpu_usage": {"range": [100, 0]},
+        "memory_usage": {"colors": ["#000000"]},
+        "http_latency": {"thresholds": [300, 300]},
+        "replicas": {"thresholds": [1, 20]},
+        "errors_per_second": {"notes": [], "colors": []},
+    }
+    assert main.validate_config(cfg) == [
+        "sound_map.cpu_usage.range: min (100) must be less than max (0)",
+        "sound_map.memory_usage.colors: must have one color per note (8 notes, 1 colors)",
+        "sound_map.http_latency.thresholds: warning and critical must differ",
+        "sound_map.replicas.thresholds: must be within the range 0-5",
+        "sound_map.errors_per_second.notes: must list at least one note",
+        "sound_map.errors_per_second.colors: must list at least one color",
+    ]
+
+
+def test_repo_config_is_valid():
+    config_path = os.path.join(os.path.dirname(__file__), "config.yaml")
+    assert main.validate_config(main.load_config(config_path)) == []
+
+
+def test_frequency_to_midi():
+    assert main.frequency_to_midi(440) == 69
+    assert main.frequency_to_midi(262) == 60
+
+
+def test_cli_list_metrics():
+    from click.testing import CliRunner
+
+    result = CliRunner().invoke(main.main, ["list-metrics"])
+    assert result.exit_code == 0
+    assert "http_latency" in result.output
+    assert "0-500" in result.output
+
+
+def test_cli_validate_config(tmp_path):
+    from click.testing import CliRunner
+
+    path = tmp_path / "bad.yaml"
+    path.write_text("audio:\n  note_duration: -1\n")
+    result = CliRunner().invoke(main.main, ["validate-config", str(path)])
+    assert result.exit_code == 1
+    assert "audio.note_duration" in result.output
+
+
+def test_cli_export_history_to_csv(tmp_path):
+    from click.testing import CliRunner
+
+    path = str(tmp_path / "history.db")
+    history = main.HistoryOutput(path)
+    history.emit(make_event(timestamp=100.0))
+    history.emit(make_event(metric="replicas", value=3.0, timestamp=100.0))
+    history.flush()
+    history.emit(make_event(value=20.0, severity="ok", timestamp=200.0))
+    history.close()
+    assert [s["value"] for s in main.read_history(path, since=150)] == [20.0]
+    assert [s["metric"] for s in main.read_history(path, metrics=["replicas"])] == ["replicas"]
+
+    result = CliRunner().invoke(
+        main.main, ["export", "--history", path, "-m", "cpu_usage", "--until", "150"]
+    )
+    assert result.exit_code == 0
+    assert result.output.splitlines() == [
+        "timestamp,namespace,metric,value,note,frequency,color,severity",
+        "1970-01-01T00:01:40+00:00,my ns,cpu_usage,95.0,C5,523,#118AB2,critical",
+    ]
+
+
+def test_cli_report_renders_timelines_and_alarms(tmp_path):
+    from click.testing import CliRunner
+
+    path = str(tmp_path / "history.db")
+    history = main.HistoryOutput(path)
+    history.emit(make_event(value=20.0, severity="ok", color="#06D6A0", timestamp=100.0))
+    history.emit(make_event(timestamp=160.0))
+    history.emit(make_event(timestamp=220.0))
+    history.emit(make_event(metric="replicas", value=3.0, severity="ok", timestamp=220.0))
+    history.close()
+    assert [a["timestamp"] for a in main.key_alarms(main.read_history(path))] == [160.0]
+
+    output = tmp_path / "report.html"
+    result = CliRunner().invoke(
+        main.main, ["report", "--history", path, "-t", "<Outage>", "-o", str(output)]
+    )
+    assert result.exit_code == 0
+    page = output.read_text()
+    assert "<h1>&lt;Outage&gt;</h1>" in page
+    assert page.count("<svg") == 2 and 'fill="#06D6A0"' in page and 'fill="#118AB2"' in page
+    assert page.count("<audio") == 1 and "data:audio/wav;base64,UklGR" in page
+    assert "1970-01-01 00:02:40" in page
+
+    result = CliRunner().invoke(
+        main.main, ["report", "--history", path, "--no-audio", "--until", "150", "-o", "-"]
+    )
+    assert "<audio" not in result.output and "No metric went critical" in result.output
+
+
+def test_render_default_config_round_trips(tmp_path):
+    path = tmp_path / "generated.yaml"
+    path.write_text(main.render_default_config(full=True))
+    cfg = main.load_config(str(path))
+    assert main.validate_config(cfg) == []
+    assert cfg["monitoring"] == main.DEFAULT_CONFIG["monitoring"]
+    assert cfg["sound_map"] == main.sound_map_to_config(main.SOUND_MAP)
+    assert "# Polling interval in seconds" in path.read_text()
+
+
+def test_apply_sound_map_overrides_entry(monkeypatch):
+    import copy
+
+    monkeypatch.setattr(main, "SOUND_MAP", copy.deepcopy(main.SOUND_MAP))
+    main.apply_sound_map({"cpu_usage": {"range": [0, 50], "notes": [[100, "X"], [200, "Y"]]}})
+    assert main.SOUND_MAP["cpu_usage"]["range"] == (0, 50)
+    assert main.SOUND_MAP["cpu_usage"]["notes"] == [(100, "X"), (200, "Y")]
+    assert main.SOUND_MAP["cpu_usage"]["unit"] == "%"
+
+
+def test_apply_sound_map_tunes_scale_degrees(monkeypatch, tmp_path):
+    import copy
+
+    monkeypatch.setattr(main, "SOUND_MAP", copy.deepcopy(main.SOUND_MAP))
+    main.apply_sound_map({"cpu_usage": {"degrees": [0, 4, 7, 12]}}, {"system": "just", "root": 200})
+    assert main.SOUND_MAP["cpu_usage"]["notes"] == [
+        (200, "deg 0"), (250, "deg 4"), (300, "deg 7"), (400, "deg 12"),
+    ]
+
+    scl = tmp_path / "slendro.scl"
+    scl.write_text("! slendro.scl\n!\nQuarter-comma slendro\n 3\n!\n 240.0\n 3/2\n 2\n")
+    tuning = {"system": "scala", "scala_file": str(scl), "root": 100}
+    main.apply_sound_map({"cpu_usage": {"degrees": [-1, 1, 2, 4]}}, tuning)
+    assert [f for f, _ in main.SOUND_MAP["cpu_usage"]["notes"]] == [75, 114.87, 150, 229.74]
+
+
+def test_apply_sound_map_spreads_steps_and_colors(monkeypatch):
+    import copy
+
+    monkeypatch.setattr(main, "SOUND_MAP", copy.deepcopy(main.SOUND_MAP))
+    overrides = {
+        "http_latency": {
+            "steps": 16, "scale": "major", "colors": ["#000000", "#FF0000", "#FFFFFF"],
+        },
+        "replicas": {"steps": 3},
+    }
+    main.apply_sound_map(overrides, {"root": 100})
+    latency = main.SOUND_MAP["http_latency"]
+    assert [name for _, name in latency["notes"][6:9]] == ["deg 11", "deg 12", "deg 14"]
+    assert latency["colors"][0] == "#000000" and latency["colors"][-1] == "#FFFFFF"
+    assert latency["colors"][5] == "#AA0000" and len(latency["colors"]) == 16
+    index = main.calculate_index(1000, len(latency["notes"]), *latency["range"])
+    assert index == 15 and main.get_color(latency["colors"], index) == "#FFFFFF"
+    assert main.calculate_index(5, 1, 0, 10) == 0
+    replicas = main.SOUND_MAP["replicas"]
+    assert [f for f, _ in replicas["notes"]] == [100, 105.95, 112.25]
+    assert len(replicas["colors"]) == 3
+    entry = {"range": [0, 1], "steps": 0, "colors": ["red"]}
+    assert main.validate_sound_map_entry("x", entry) == [
+        "sound_map.x.steps: must be an integer of at least 1",
+        "sound_map.x.colors: must be #RRGGBB colors to blend over the steps",
+    ]
+
+
+def test_apply_sound_map_converts_note_names(monkeypatch):
+    import copy
+
+    monkeypatch.setattr(main, "SOUND_MAP", copy.deepcopy(main.SOUND_MAP))
+    main.apply_sound_map({"cpu_usage": {"notes": ["A4", "F#5", ["Bb3", "low"], [100, "X"]]}})
+    assert main.SOUND_MAP["cpu_usage"]["notes"] == [
+        (440.0, "A4"), (739.99, "F#5"), (233.08, "low"), (100, "X"),
+    ]
+    main.apply_sound_map({"cpu_usage": {"notes": ["A4", "C4"]}}, {"a4": 432})
+    assert main.SOUND_MAP["cpu_usage"]["notes"] == [(432.0, "A4"), (256.87, "C4")]
+
+
+def test_validate_config_rejects_bad_note_name():
+    cfg = main.merge_config(
+        main.DEFAULT_CONFIG, {"sound_map": {"cpu_usage": {"notes": ["H2"], "colors": ["#000000"]}}}
+    )
+    assert main.validate_config(cfg) == ["sound_map.cpu_usage.notes[0]: not a note name: 'H2'"]
+
+
+def test_validate_config_rejects_bad_scala_file(tmp_path):
+    cfg = main.merge_config(
+        main.DEFAULT_CONFIG, {"tuning": {"system": "scala", "scala_file": str(tmp_path / "x.scl")}}
+    )
+    assert len(main.validate_config(cfg)) == 1
+    assert main.validate_config(cfg)[0].startswith("tuning.scala_file: ")
+
+
+def test_dry_run_prints_without_playing(monkeypatch, capsys):
+    monkeypatch.setattr(main.k8s_client, "initialize", lambda: True)
+    monkeypatch.setattr(main, "get_k8s_data", lambda m, namespace="default": (50.0, {}))
+    monkeypatch.setattr(main, "play_note", lambda *a, **kw: pytest.fail("played audio"))
+    main.sonify_k8s_metrics(metrics=["cpu_usage"], dry_run=True, max_ticks=1)
+    out = capsys.readouterr().out
+    assert "[dry-run] cpu_usage: 50.00 % -> note F4 (349 Hz), color #126E82" in out
+
+
+def test_file_metric_source_groups_ticks(tmp_path):
+    path = tmp_path / "metrics.json"
+    path.write_text(
+        '[{"timestamp": "2024-07-24T10:00:05Z", "metric": "cpu_usage", "value": 80},'
+        ' {"timestamp": "2024-07-24T10:00:00Z", "metric": "cpu_usage", "value": 10},'
+        ' {"timestamp": "2024-07-24T10:00:00Z", "metric": "replicas", "value": 3}]'
+    )
+    source = main.FileMetricSource(str(path))
+    assert source.connect()
+    assert source.metric_names == ["cpu_usage", "replicas"]
+    assert source.next_tick()
+    assert source.fetch("cpu_usage")[0] == 10
+    assert source.fetch("replicas")[1]["timestamp"] == "2024-07-24T10:00:00+00:00"
+    assert source.next_tick()
+    assert source.fetch("cpu_usage")[0] == 80
+    assert source.fetch("replicas") is None
+    assert not source.next_tick()
+
+
+def test_file_metric_source_reads_prometheus_matrix(tmp_path):
+    path = tmp_path / "prom.json"
+    path.write_text(
+        '{"status": "success", "data": {"resultType": "matrix", "result": ['
+        '{"metric": {"__name__": "http_latency", "pod": "web"},'
+        ' "values": [[1721815200, "120"], [1721815205, "450"]]}]}}'
+    )
+    source = main.FileMetricSource(str(path))
+    assert source.connect()
+    values = []
+    while source.next_tick():
+        value, extra = source.fetch("http_latency")
+        values.append(value)
+        assert extra["pod"] == "web"
+    assert values == [120.0, 450.0]
+
+
+def test_sonify_replays_file_until_exhausted(tmp_path, monkeypatch):
+    path = tmp_path / "metrics.json"
+    path.write_text('[{"timestamp": 1, "metric": "cpu_usage", "value": 0},'
+                    ' {"timestamp": 2, "metric": "cpu_usage", "value": 100}]')
+    played = []
+    monkeypatch.setattr(main, "play_note", lambda f, d=0.5, **kw: played.append(f))
+    monkeypatch.setattr(main, "time", types.SimpleNamespace(sleep=lambda _: None))
+    main.sonify_k8s_metrics(metrics=["cpu_usage"], source=main.FileMetricSource(str(path)))
+    assert played == [262, 523]
+
+
+@pytest.mark.parametrize(
+    "line,expected",
+    [
+        ("cpu_usage 42", ("cpu_usage", 42.0)),
+        ("  replicas\t3.5 \n", ("replicas", 3.5)),
+        ("", None),
+        ("# comment", None),
+        ("cpu_usage", None),
+        ("bogus 1", None),
+        ("cpu_usage high", None),
+    ],
+)
+def test_parse_metric_line(line, expected):
+    assert main.parse_metric_line(line) == expected
+
+
+def test_sonify_stream_plays_each_line(monkeypatch):
+    played = []
+    monkeypatch.setattr(main, "play_note", lambda f, d=0.5, **kw: played.append(f))
+    main.sonify_stream(["cpu_usage 0", "garbage", "cpu_usage 100"])
+    assert played == [262, 523]
+
+
+def test_statsd_source_aggregates_per_tick(monkeypatch):
+    clock = {"now": 0.0}
+    monkeypatch.setattr(main.time, "monotonic", lambda: clock["now"])
+    source = main.StatsdSource(
+        {"app.errors": "errors_per_second", "app.latency": "http_latency", "app.pods": "replicas"}
+    )
+    for line in ["app.errors:4|c", "app.errors:1|c|@0.5", "app.latency:100|ms",
+                 "app.latency:300|ms", "app.pods:2|g", "app.pods:+1|g", "other:1|c", "bad"]:
+        source.handle_line(line)
+    clock["now"] = 2.0
+    source.next_tick()
+    assert source.fetch("errors_per_second")[0] == 3.0
+    assert source.fetch("http_latency")[0] == 200.0
+    assert source.fetch("replicas")[0] == 3.0
+    assert source.fetch("cpu_usage") is None
+    clock["now"] = 4.0
+    source.next_tick()
+    assert source.fetch("errors_per_second") is None
+    assert source.fetch("replicas")[0] == 3.0
+
+
+def test_listeners_only_accept_local_connections_by_default():
+    for section in ("osc", "statsd", "falco"):
+        assert main.DEFAULT_CONFIG[section]["host"] == "127.0.0.1"
+    assert main.StatsdSource({}).host == "127.0.0.1"
+    assert main.OscController(main.PlaybackState()).host == "127.0.0.1"
+
+
+def test_statsd_source_receives_udp():
+    source = main.StatsdSource({"app.errors": "errors_per_second"}, host="127.0.0.1", port=0)
+    assert source.connect()
+    port = source._socket.getsockname()[1]
+    sender = main.socket.socket(main.socket.AF_INET, main.socket.SOCK_DGRAM)
+    sender.sendto(b"app.errors:5|c\napp.errors:5|c", ("127.0.0.1", port))
+    sender.close()
+    for _ in range(100):
+        if source._counters:
+            break
+        main.time.sleep(0.01)
+    source.close()
+    assert source._counters == {"app.errors": 10.0}
+
+
+def test_falco_source_plays_worst_alert_per_namespace():
+    source = main.FalcoSource(host="127.0.0.1", port=0, min_priority="notice")
+    assert source.connect()
+    request = main.urllib.request.Request(
+        f"http://127.0.0.1:{source._server.server_port}/",
+        data=main.json.dumps([
+            {"priority": "Warning", "rule": "Write below etc",
+             "output_fields": {"k8s.ns.name": "shop"}},
+            {"priority": "Critical", "rule": "Terminal shell in container",
+             "output_fields": {"k8s.ns.name": "shop"}},
+            {"priority": "Error", "rule": "Unexpected K8s API call", "output_fields": {}},
+            {"priority": "Informational", "rule": "Noisy rule"},
+            {"rule": "No priority"},
+        ]).encode(),
+    )
+    with main.urllib.request.urlopen(request, timeout=5) as response:
+        assert response.status == 200
+    source.close()
+    source.next_tick()
+    value, extra = source.fetch("security_alerts", "shop")
+    assert value == main.FALCO_PRIORITIES.index("critical") + 1
+    assert extra == {
+        "alerts": 3, "priority": "critical",
+        "rules": ["Terminal shell in container", "Unexpected K8s API call", "Write below etc"],
+    }
+    assert source.fetch("security_alerts", "web")[1]["priority"] == "error"
+    assert source.fetch("cpu_usage") is None
+    source.next_tick()
+    assert source.fetch("security_alerts", "shop") == (0, {"alerts": 0})
+    cfg = main.merge_config(main.DEFAULT_CONFIG, {
+        "falco": {"min_priority": "loud"}, "metrics": {"enabled": ["security_alerts"]},
+    })
+    problems = main.validate_config(cfg)
+    assert "metrics.enabled: security_alerts requires falco.enabled" in problems
+    assert any(problem.startswith("falco.min_priority") for problem in problems)
+
+
+def test_falco_source_refuses_unauthenticated_and_malformed_requests():
+    import http.client
+
+    source = main.FalcoSource(port=0, token="s3cret")
+    assert source.host == "127.0.0.1"
+    assert source.connect()
+    port = source._server.server_port
+
+    def post(body, headers):
+        connection = http.client.HTTPConnection("127.0.0.1", port, timeout=5)
+        connection.request("POST", "/", body=body, headers=headers)
+        status = connection.getresponse().status
+        connection.close()
+        return status
+
+    alert = main.json.dumps({"priority": "Critical", "rule": "Fake"}).encode()
+    auth = {"Authorization": "Bearer s3cret"}
+    assert post(alert, {}) == 401
+    assert post(alert, {"Authorization": "Bearer guess"}) == 401
+    assert post(alert, {**auth, "Content-Length": "lots"}) == 400
+    assert post(alert, {**auth, "Content-Length": str(main.FALCO_MAX_BODY + 1)}) == 413
+    bad_fields = main.json.dumps({"priority": "Critical", "output_fields": ["shop"]})
+    assert post(bad_fields.encode(), auth) == 400
+    assert post(b"[1]", auth) == 400
+    assert post(alert, auth) == 200
+    source.close()
+    source.next_tick()
+    assert source.fetch("security_alerts", "shop")[1] == {
+        "alerts": 1, "priority": "critical", "rules": ["Fake"],
+    }
+
+
+def make_event(**overrides):
+    fields = dict(
+        metric="cpu_usage", value=95.0, note="C5", frequency=523, color="#118AB2",
+        severity="critical", index=7, namespace="my ns", timestamp=1721815200.5,
+    )
+    fields.update(overrides)
+    return main.SonificationEvent(**fields)
+
+
+def test_influxdb_format_line():
+    output = main.InfluxDBOutput("http://influx:8086/", "ops", "sonify")
+    assert output.write_url == "http://influx:8086/api/v2/write?org=ops&bucket=sonify&precision=ns"
+    assert output.format_line(make_event()) == (
+        "sonify_k8s,metric=cpu_usage,namespace=my\\ ns,note=C5,severity=critical "
+        'value=95.0,frequency=523i,index=7i,color="#118AB2" 1721815200500000000'
+    )
+
+
+def test_influxdb_flush_posts_batch(monkeypatch):
+    requests = []
+
+    class FakeResponse:
+        def __enter__(self):
+            return self
+
+        def __exit__(self, *args):
+            return False
+
+    def fake_urlopen(request, timeout=None):
+        requests.append(request)
+        return FakeResponse()
+
+    monkeypatch.setattr(main.urllib.request, "urlopen", fake_urlopen)
+    output = main.InfluxDBOutput("http://influx:8086", "ops", "sonify", token="secret")
+    output.emit(make_event())
+    output.emit(make_event(metric="replicas"))
+    output.flush()
+    output.flush()
+    assert len(requests) == 1
+    assert requests[0].get_header("Authorization") == "Token secret"
+    assert requests[0].data.decode().count("\n") == 2
+
+
+def test_graphite_output_sends_plaintext():
+    server = main.socket.socket()
+    server.bind(("127.0.0.1", 0))
+    server.listen(1)
+    output = main.GraphiteOutput("127.0.0.1", server.getsockname()[1])
+    output.emit(make_event())
+    output.flush()
+    connection, _ = server.accept()
+    output.close()
+    received = connection.makefile().read()
+    connection.close()
+    server.close()
+    assert received.splitlines() == [
+        "sonify_k8s.my_ns.cpu_usage.value 95.0 1721815200",
+        "sonify_k8s.my_ns.cpu_usage.severity 2 1721815200",
+        "sonify_k8s.my_ns.cpu_usage.frequency 523 1721815200",
+    ]
+
+
+def test_update_alarm_reports_transitions():
+    state = main.PlaybackState()
+    assert state.update_alarm("cpu_usage", "critical") == "fired"
+    assert state.update_alarm("cpu_usage", "critical") is None
+    assert state.update_alarm("cpu_usage", "ok") == "cleared"
+    assert state.update_alarm("cpu_usage", "ok") is None
+
+
+def test_grafana_annotation_payload(monkeypatch):
+    requests = []
+
+    class FakeResponse:
+        def __enter__(self):
+            return self
+
+        def __exit__(self, *args):
+            return False
+
+    monkeypatch.setattr(
+        main.urllib.request, "urlopen", lambda r, timeout=None: requests.append(r) or FakeResponse()
+    )
+    output = main.GrafanaAnnotationOutput("http://grafana:3000", token="t", dashboard_uid="abc")
+    output.alarm(make_event(namespace="prod"), "fired")
+    payload = main.json.loads(requests[0].data)
+    assert requests[0].full_url == "http://grafana:3000/api/annotations"
+    assert requests[0].get_header("Authorization") == "Bearer t"
+    assert payload == {
+        "time": 1721815200500,
+        "tags": ["sonify-k8s", "cpu_usage", "prod", "alarm-fired"],
+        "text": "CPU Usage alarm fired in prod: 95.00 %",
+        "dashboardUID": "abc",
+    }
+
+
+def test_sonifier_builder_ticks_file_source(tmp_path, monkeypatch):
+    path = tmp_path / "metrics.json"
+    path.write_text('[{"timestamp": 1, "metric": "cpu_usage", "value": 95}]')
+    monkeypatch.setattr(main, "play_note", lambda f, d=0.5, **kw: None)
+    sonifier = (
+        main.SonifierBuilder()
+        .with_source(main.FileMetricSource(str(path)))
+        .with_metrics(["cpu_usage"])
+        .with_namespace("prod")
+        .build()
+    )
+    events = sonifier.tick()
+    assert [(e.metric, e.severity, e.namespace) for e in events] == [("cpu_usage", "critical", "prod")]
+    assert sonifier.health == "critical"
+    assert sonifier.tick() is None
+
+
+def test_sonifier_shutdown_closes_outputs():
+    closed = []
+
+    class FakeOutput(main.MetricOutput):
+        def close(self):
+            closed.append(True)
+
+    sonifier = main.SonifierBuilder().with_output(FakeOutput()).build()
+    sonifier.shutdown()
+    assert closed == [True]
+
+
+def test_sonifier_stream_yields_events(tmp_path, monkeypatch):
+    path = tmp_path / "metrics.json"
+    path.write_text('[{"timestamp": 1, "metric": "cpu_usage", "value": 0},'
+                    ' {"timestamp": 2, "metric": "cpu_usage", "value": 100}]')
+    monkeypatch.setattr(main, "play_note", lambda *a, **kw: pytest.fail("played audio"))
+    monkeypatch.setattr(main, "time", types.SimpleNamespace(sleep=lambda _: None))
+    sonifier = (
+        main.SonifierBuilder()
+        .with_source(main.FileMetricSource(str(path)))
+        .with_metrics(["cpu_usage"])
+        .with_audio(False)
+        .build()
+    )
+
+    async def collect():
+        return [event async for event in sonifier.stream()]
+
+    events = main.asyncio.run(collect())
+    assert [(e.note, e.severity) for e in events] == [("C4", "ok"), ("C5", "critical")]
+
+
+def test_sonifier_hooks(monkeypatch):
+    values = [95.0, RuntimeError("api down"), 10.0]
+
+    def fake_get_k8s_data(metric, namespace="default"):
+        value = values.pop(0)
+        if isinstance(value, Exception):
+            raise value
+        return value, {}
+
+    monkeypatch.setattr(main.k8s_client, "initialize", lambda: True)
+    monkeypatch.setattr(main, "get_k8s_data", fake_get_k8s_data)
+    monkeypatch.setattr(main, "play_note", lambda *a, **kw: None)
+    monkeypatch.setattr(main, "time", types.SimpleNamespace(sleep=lambda _: None))
+    sonifier = main.SonifierBuilder().with_metrics(["cpu_usage"]).build()
+    events, alarms, errors = [], [], []
+    sonifier.on_event(events.append)
+    sonifier.on_alarm(lambda event, transition: alarms.append(transition))
+    sonifier.on_error(errors.append)
+
+    @sonifier.on_event
+    def broken_hook(event):
+        raise RuntimeError("pager down")
+
+    sonifier.run(max_ticks=2)
+    assert [e.value for e in events] == [95.0, 10.0]
+    assert alarms == ["fired", "cleared"]
+    assert [str(e) for e in errors] == ["api down"]
+
+
+def test_dashboard_keeps_history_for_charts():
+    dashboard = main.Dashboard(["cpu_usage"], history=3)
+    for value in (0, 50, 100, 25):
+        dashboard.emit(make_event(value=value))
+    dashboard.emit(make_event(metric="memory_usage", value=10))
+
+    event, values = dashboard.snapshot("cpu_usage")
+    assert (event.value, values) == (25, [50, 100, 25])
+    assert main.chart_points("cpu_usage", values, 100, 20) == [0, 10, 50, 0, 100, 15]
+
+
+def test_tray_icon_tracks_worst_severity():
+    tray = main.TrayIcon()
+    tray.emit(make_event(severity="ok"))
+    tray.emit(make_event(severity="critical"))
+    tray.emit(make_event(severity="warning"))
+    tray.flush()
+    assert tray.severity == "critical"
+    tray.flush()
+    assert tray.severity == "ok"
+
+
+def test_grafana_dashboard_url():
+    cfg = main.merge_config(main.DEFAULT_CONFIG, {})
+    cfg["outputs"]["grafana"]["dashboard_uid"] = "abc"
+    assert main.grafana_dashboard_url(cfg) == "http://localhost:3000/d/abc"
+
+
+def test_silenced_state_is_inaudible():
+    state = main.PlaybackState()
+    state.silenced = True
+    assert not state.is_audible("cpu_usage")
+
+
+def test_sonifier_discovers_namespaces(monkeypatch):
+    namespaces = [["default", "team-a", "team-a-sandbox", "kube-system"], ["default"]]
+    clock = iter([0.0, 100.0])
+    monkeypatch.setattr(main.k8s_client, "initialize", lambda: True)
+    monkeypatch.setattr(main.k8s_client, "list_namespaces", lambda: namespaces.pop(0))
+    monkeypatch.setattr(main, "get_k8s_data", lambda m, namespace="default": (95.0, {}))
+    monkeypatch.setattr(main, "play_note", lambda *a, **kw: None)
+    monkeypatch.setattr(main, "time", types.SimpleNamespace(monotonic=lambda: next(clock)))
+    sonifier = main.Sonifier(
+        main.KubernetesSource(), ["cpu_usage"], namespace_include=["^team-"],
+        namespace_exclude=["-sandbox$"], namespace_refresh=60,
+    )
+
+    assert [e.namespace for e in sonifier.tick()] == ["default", "team-a"]
+    assert sonifier.state.active_alarms == {"cpu_usage", "team-a/cpu_usage"}
+    assert [e.namespace for e in sonifier.tick()] == ["default"]
+    assert sonifier.state.active_alarms == {"cpu_usage"}
+
+
+def test_sonifier_discovers_no_namespaces_from_a_file(tmp_path, monkeypatch):
+    path = tmp_path / "metrics.json"
+    path.write_text('[{"timestamp": 1, "metric": "cpu_usage", "value": 95}]')
+    monkeypatch.setattr(
+        main.k8s_client, "list_namespaces", lambda: pytest.fail("queried Kubernetes")
+    )
+    monkeypatch.setattr(main, "play_note", lambda *a, **kw: None)
+    sonifier = main.Sonifier(
+        main.FileMetricSource(str(path)), ["cpu_usage"], namespace_include=["^team-"],
+    )
+
+    assert [e.namespace for e in sonifier.tick()] == ["default"]
+
+
+def test_sonifier_restores_alarm_state(tmp_path, monkeypatch):
+    state_file = str(tmp_path / "state.json")
+    monkeypatch.setattr(main.k8s_client, "initialize", lambda: True)
+    monkeypatch.setattr(main, "get_k8s_data", lambda m, namespace="default": (95.0, {}))
+    monkeypatch.setattr(main, "play_note", lambda *a, **kw: None)
+    first = main.Sonifier(main.KubernetesSource(), ["cpu_usage"], state_file=state_file)
+    alarms = []
+    first.on_alarm(lambda event, transition: alarms.append(transition))
+    first.tick()
+    first.state.acknowledge()
+    first.tick()
+
+    second = main.Sonifier(main.KubernetesSource(), ["cpu_usage"], state_file=state_file)
+    second.on_alarm(lambda event, transition: alarms.append(transition))
+    second.tick()
+    assert alarms == ["fired"]
+    assert not second.state.is_audible("cpu_usage")
+
+    elsewhere = main.Sonifier(
+        main.KubernetesSource(), ["cpu_usage"], namespace="prod", state_file=state_file
+    )
+    elsewhere.start()
+    assert not elsewhere.state.active_alarms
+
+
+def test_sonifier_reports_stale_metrics(monkeypatch):
+    release = main.threading.Event()
+
+    class HungSource(main.MetricSource):
+        calls = 0
+
+        def connect(self):
+            return True
+
+        def fetch(self, metric, namespace="default"):
+            self.calls += 1
+            if metric == "cpu_usage":
+                release.wait()
+            return 10.0, {}
+
+    monkeypatch.setattr(main, "play_note", lambda *a, **kw: None)
+    source = HungSource()
+    sonifier = main.Sonifier(source, ["cpu_usage", "replicas"], fetch_timeout=0.05)
+    assert [e.metric for e in sonifier.tick()] == ["replicas"]
+    assert sonifier.stale == {("default", "cpu_usage")}
+    assert [e.metric for e in sonifier.tick()] == ["replicas"]
+    assert source.calls == 3
+    release.set()
+    sonifier.pending[("default", "cpu_usage")].result(timeout=1)
+    assert [e.metric for e in sonifier.tick()] == ["cpu_usage", "replicas"]
+    assert sonifier.stale == set()
+
+
+def test_sonifier_honors_workload_annotations(monkeypatch):
+    def listing(*objects):
+        return lambda namespace, label_selector, **kw: types.SimpleNamespace(items=[
+            types.SimpleNamespace(metadata=types.SimpleNamespace(name=n, annotations=a))
+            for n, a in objects
+        ])
+    client = main.K8sClient()
+    client.v1_apps = types.SimpleNamespace(list_namespaced_deployment=listing(
+        ("web", {"sonify.k8s.io/instrument": "pluck", "team": "a"}), ("db", None),
+    ))
+    client.v1_core = types.SimpleNamespace(list_namespaced_pod=listing(
+        ("web-1", {"sonify.k8s.io/priority": "high"}),
+    ))
+    assert client.get_workload_annotations("team-a") == {
+        "deployment/web": {"instrument": "pluck"}, "deployment/db": {},
+        "pod/web-1": {"priority": "high"},
+    }
+    workloads = main.workload_sound_settings({
+        "deployment/web": {"instrument": "pluck", "priority": "3", "mute": "true"},
+        "pod/web-1": {"instrument": "fm", "priority": "99", "metrics": "cpu_usage, replicas"},
+        "pod/web-2": {"mute": "true", "metrics": "replicas"},
+    })
+    assert workloads["pod/web-1"]["priority"] == main.CRITICAL_PRIORITY - 1
+    assert main.metric_sound_settings(workloads, "cpu_usage") == {
+        "voice": {"type": "pluck"}, "priority": 9,
+    }
+    assert main.metric_sound_settings(workloads, "memory_usage") == {
+        "voice": {"type": "pluck"}, "priority": 3, "muted": True,
+    }
+
+    annotations = {"default": {"deployment/web": {"instrument": "pluck"}},
+                   "team-a": {"deployment/api": {"mute": "true"}},
+                   "team-b": {"deployment/api": {"mute": "true"}, "deployment/web": {}}}
+    voices = []
+    monkeypatch.setattr(main.k8s_client, "initialize", lambda: True)
+    monkeypatch.setattr(
+        main.k8s_client, "list_namespaces", lambda: ["default", "team-a", "team-b"]
+    )
+    monkeypatch.setattr(main, "get_k8s_data", lambda m, namespace="default": (95.0, {}))
+    monkeypatch.setattr(
+        main, "play_note", lambda f, d, volume=1.0, voice=None: voices.append(voice)
+    )
+    source = main.KubernetesSource()
+    source.annotations = lambda namespace: annotations[namespace]
+    sonifier = main.Sonifier(
+        source, ["cpu_usage"], namespace_include=["^team-"], annotation_refresh=60,
+    )
+    assert [e.namespace for e in sonifier.tick()] == ["default", "team-a", "team-b"]
+    # team-b's other workload keeps its namespace audible
+    assert voices == [{"type": "pluck"}, None]
+
+
+def test_sonifier_reconciles_sonification_profiles(monkeypatch, caplog):
+    def profile(namespace, name, **spec):
+        return {"metadata": {"namespace": namespace, "name": name}, "spec": spec}
+    resources = [[
+        profile("team-a", "checkout", metrics=["cpu_usage"], thresholds={"cpu_usage": [90, 99]},
+                sound={"instrument": "pluck", "priority": "high"}),
+        profile("team-a", "later", sound={"mute": True}),
+        profile("team-b", "broken", metrics=["no_such_metric"]),
+        profile("team-c", "quiet", sound={"mute": True}),
+    ], None, []]
+    statsd = main.StatsdSource({}, fallback=main.KubernetesSource())
+    clock = iter([0.0, 100.0, 200.0])
+    voices = []
+    monkeypatch.setattr(main.k8s_client, "initialize", lambda: True)
+    monkeypatch.setattr(main.k8s_client, "list_sonification_profiles", lambda: resources.pop(0))
+    monkeypatch.setattr(main, "get_k8s_data", lambda m, namespace="default": (95.0, {}))
+    monkeypatch.setattr(
+        main, "play_note", lambda f, d, volume=1.0, voice=None: voices.append(voice)
+    )
+    monkeypatch.setattr(main, "time", types.SimpleNamespace(monotonic=lambda: next(clock)))
+    sonifier = main.Sonifier(
+        main.KubernetesSource(), ["cpu_usage", "replicas"], profile_refresh=60
+    )
+
+    events = sonifier.tick()
+    assert [(e.namespace, e.metric) for e in events] == [
+        ("default", "cpu_usage"), ("default", "replicas"),
+        ("team-a", "cpu_usage"), ("team-c", "cpu_usage"), ("team-c", "replicas"),
+    ]
+    assert events[2].severity == "warning"
+    assert sonifier.state.active_alarms == {"cpu_usage", "team-c/cpu_usage"}
+    assert voices[2] == {"type": "pluck"} and len(voices) == 3
+    assert "Ignoring SonificationProfile team-a/later" in caplog.text
+    assert "Ignoring SonificationProfile team-b/broken: metrics: unknown metric" in caplog.text
+
+    # Listing failed: keep the last profiles
+    assert {e.namespace for e in sonifier.tick()} == {"default", "team-a", "team-c"}
+    assert {e.namespace for e in sonifier.tick()} == {"default"}
+    assert sonifier.state.active_alarms == {"cpu_usage"}
+
+    # Only the Kubernetes source, directly or as a fallback, has profiles
+    resources.append([profile("team-a", "checkout")])
+    assert statsd.sonification_profiles() == [profile("team-a", "checkout")]
+    assert main.MetricSource().sonification_profiles() is None
+
+    crd = main.profile_crd()
+    assert crd["metadata"]["name"] == "sonificationprofiles.sonify.k8s.io"
+    assert crd["spec"]["versions"][0]["name"] == main.PROFILE_VERSION
+    spec = crd["spec"]["versions"][0]["schema"]["openAPIV3Schema"]["properties"]["spec"]
+    priority = spec["properties"]["sound"]["properties"]["priority"]
+    assert priority["anyOf"] == [
+        {"type": "integer", "minimum": -9, "maximum": 9},
+        {"type": "string", "enum": ["low", "normal", "high"]},
+    ]
+
+
+def test_sonifier_sounds_only_changes(monkeypatch):
+    values = iter([10.0, 11.0, 40.0, 41.0, 48.0, 95.0, 95.0])
+    played = []
+    monkeypatch.setattr(main.k8s_client, "initialize", lambda: True)
+    monkeypatch.setattr(main, "get_k8s_data", lambda m, namespace="default": (next(values), {}))
+    monkeypatch.setattr(main, "play_note", lambda f, d, volume=1.0, voice=None: played.append(f))
+    sonifier = main.Sonifier(
+        main.KubernetesSource(), ["cpu_usage"], on_change_only=True, change_delta=5
+    )
+    events = [sonifier.tick()[0] for _ in range(7)]
+    assert len(events) == 7
+    assert played == [events[i].frequency for i in (0, 2, 4, 5)]
+
+
+def test_sonifier_escalates_until_acknowledged(monkeypatch, caplog):
+    clock = [0.0]
+    played = []
+    monkeypatch.setattr(main.time, "time", lambda: clock[0])
+    monkeypatch.setattr(main.k8s_client, "initialize", lambda: True)
+    monkeypatch.setattr(main, "get_k8s_data", lambda m, namespace="default": (95.0, {}))
+    monkeypatch.setattr(
+        main, "play_note", lambda f, d, volume=1.0, voice=None: played.append((1, volume))
+    )
+    monkeypatch.setattr(
+        main, "play_arpeggio",
+        lambda freqs, d, volume=1.0, voice=None: played.append((len(freqs), volume)),
+    )
+    sonifier = main.Sonifier(
+        main.KubernetesSource(), ["cpu_usage"], escalate_after=60, reminder_volume=0.2
+    )
+    sonifier.state.volume = 0.5
+    for now in (0, 30, 60, 200):
+        clock[0] = now
+        sonifier.tick()
+    sonifier.state.acknowledge()
+    clock[0] = 260
+    sonifier.tick()
+    assert played == [
+        (1, 0.5), (1, 0.5), (2, pytest.approx(2 / 3)), (4, 1.0), (1, pytest.approx(0.1)),
+    ]
+    assert "Escalating CPU Usage alarm to level 3: unacknowledged for 180s" in caplog.text
+
+
+def test_sonifier_escalates_between_polls(monkeypatch):
+    clock = [0.0]
+    played = []
+    monkeypatch.setattr(main.time, "time", lambda: clock[0])
+    monkeypatch.setattr(
+        main.time, "sleep", lambda seconds: clock.__setitem__(0, clock[0] + seconds)
+    )
+    monkeypatch.setattr(main, "POLL_INTERVAL", 150)
+    monkeypatch.setattr(main.k8s_client, "initialize", lambda: True)
+    monkeypatch.setattr(main, "get_k8s_data", lambda m, namespace="default": (95.0, {}))
+    monkeypatch.setattr(
+        main, "play_note", lambda f, d, volume=1.0, voice=None: played.append((1, volume))
+    )
+    monkeypatch.setattr(
+        main, "play_arpeggio",
+        lambda freqs, d, volume=1.0, voice=None: played.append((len(freqs), volume)),
+    )
+    sonifier = main.Sonifier(main.KubernetesSource(), ["cpu_usage"], escalate_after=60)
+    sonifier.state.volume = 0.5
+    sonifier.tick()
+    # One poll's wait escalates the alarm twice, on time
+    main.wait_for_next_poll(check=sonifier.escalate_alarms)
+    assert played == [(1, 0.5), (2, pytest.approx(2 / 3)), (3, pytest.approx(5 / 6))]
+    sonifier.state.acknowledge()
+    sonifier.escalate_alarms()
+    assert len(played) == 3 and sonifier.alarm_replays == {}
+
+
+def test_sonifier_ducks_routine_metrics_after_alarm_earcon(monkeypatch):
+    clock = [0.0]
+    cpu = iter([95.0, 95.0, 95.0])
+    volumes = []
+    monkeypatch.setattr(main.time, "time", lambda: clock[0])
+    monkeypatch.setattr(main.k8s_client, "initialize", lambda: True)
+    monkeypatch.setattr(main, "get_k8s_data", lambda m, namespace="default": (
+        next(cpu) if m == "cpu_usage" else 10.0, {}
+    ))
+    monkeypatch.setattr(
+        main, "play_note", lambda f, d, volume=1.0, voice=None: volumes.append(volume)
+    )
+    monkeypatch.setattr(main, "play_earcon", lambda name, volume=1.0, a4=440.0: None)
+    sonifier = main.Sonifier(
+        main.KubernetesSource(), ["cpu_usage", "memory_usage"],
+        alarm_earcons={"fired": "alarm"}, duck_volume=0.4, duck_release=10,
+    )
+    for now in (0, 5, 10):
+        clock[0] = now
+        sonifier.tick()
+    assert volumes == [1.0, pytest.approx(0.4), 1.0, pytest.approx(0.7), 1.0, 1.0]
+
+
+def test_sonifier_falls_back_to_bell_and_flash_without_audio(monkeypatch, capsys):
+    class BrokenSink(main.AudioSink):
+        def play(self, frequency, duration, volume=1.0, voice=None):
+            raise OSError("Error opening PCM device")
+
+    cpu = iter([95.0, 95.0, 10.0])
+    monkeypatch.setenv("TEST_MODE", "false")
+    monkeypatch.setenv("AUDIO_ENABLED", "true")
+    monkeypatch.setattr(main, "audio_sink", BrokenSink())
+    monkeypatch.setattr(main.k8s_client, "initialize", lambda: True)
+    monkeypatch.setattr(main, "get_k8s_data", lambda m, namespace="default": (next(cpu), {}))
+    sonifier = main.Sonifier(
+        main.KubernetesSource(), ["cpu_usage"], note_duration=0.01,
+        audio_fallback=["bell", "flash"],
+    )
+    lines = []
+    for _ in range(3):
+        sonifier.tick()
+        lines.append(capsys.readouterr().out)
+    assert lines[0].startswith("\a\033[7m") and lines[0].endswith("\033[0m\n")
+    assert lines[1].startswith("\033[7m")
+    assert lines[2] == ""
+    assert main.audio_failed.is_set()
+
+    main.audio_failed.clear()
+    cfg = main.merge_config(main.DEFAULT_CONFIG, {"audio": {"fallback": ["beep"]}})
+    assert main.validate_config(cfg) == ["audio.fallback: must be a list of bell, flash"]
+
+
+def test_waterfall_scrolls_colored_columns():
+    import io
+
+    stream = io.StringIO()
+    waterfall = main.Waterfall(["cpu_usage", "replicas"], stream=stream, size=lambda: (19, 10))
+    waterfall.emit(make_event(severity="critical", color="#FF0000"))
+    waterfall.emit(make_event(severity="ok", color="#00FF00", namespace="other"))
+    waterfall.flush()
+    for color in ("#0000FF", "#00FF00"):
+        waterfall.emit(make_event(severity="ok", color=color))
+        waterfall.emit(make_event(metric="replicas", severity="ok", color=color))
+        waterfall.flush()
+    cpu, replicas = waterfall.render().split("\n")[1:]
+    # Two cells fit beside the labels: the first tick has scrolled off
+    assert cpu == "CPU Usage        " + "".join(
+        f"\033[48;2;{rgb}m \033[0m" for rgb in ("0;0;255", "0;255;0")
+    )
+    assert replicas.startswith("Replica Count ")
+    waterfall.size = lambda: (20, 3)
+    cpu, replicas = waterfall.render().split("\n")[1:]
+    assert cpu.startswith("CPU Usage        \033[48;2;255;0;0m \033[0m")
+    assert replicas.startswith("Replica Count    ·\033[48;2;0;0;255m")
+    waterfall.size = lambda: (20, 2)
+    assert len(waterfall.render().split("\n")) == 2
+    assert stream.getvalue().startswith("\033[?1049h")
+    waterfall.close()
+    assert stream.getvalue().endswith("\033[?1049l")
+
+
+def test_sonifier_disables_forbidden_metrics(monkeypatch, caplog):
+    calls = []
+
+    def fake_get_k8s_data(metric, namespace="default"):
+        calls.append(metric)
+        if metric == "node_pressure":
+            raise main.MissingPermission("nodes")
+        if metric == "pod_status" and namespace == "team-a":
+            raise main.MissingPermission("pods", namespace)
+        return 10.0, {}
+
+    earcons = []
+    monkeypatch.setattr(main.k8s_client, "initialize", lambda: True)
+    monkeypatch.setattr(main.k8s_client, "list_namespaces", lambda: ["default", "team-a"])
+    monkeypatch.setattr(main, "get_k8s_data", fake_get_k8s_data)
+    monkeypatch.setattr(main, "play_note", lambda *a, **kw: None)
+    monkeypatch.setattr(main, "play_earcon", lambda name, **kw: earcons.append(name))
+    sonifier = main.Sonifier(
+        main.KubernetesSource(), ["node_pressure", "cpu_usage"],
+        permission_earcon="capability-missing",
+    )
+    assert [e.metric for e in sonifier.tick()] == ["cpu_usage"]
+    assert [e.metric for e in sonifier.tick()] == ["cpu_usage"]
+    assert calls == ["node_pressure", "cpu_usage", "cpu_usage"]
+    assert earcons == ["capability-missing"]
+
+    # Another resource, in another namespace, is heard about too
+    sonifier.metrics = ["pod_status"]
+    sonifier.namespace_include = ["^team-"]
+    assert [e.namespace for e in sonifier.tick()] == ["default"]
+    assert [e.namespace for e in sonifier.tick()] == ["default"]
+    assert earcons == ["capability-missing"] * 2
+    assert "Disabling Node Pressure: the service account may not list nodes" in caplog.text
+    assert sonifier.health == "ok"
+
+
+def test_cluster_health_weights_known_components(monkeypatch):
+    monkeypatch.setitem(main.SOUND_MAP, "cluster_health", dict(
+        main.SOUND_MAP["cluster_health"],
+        weights={"readiness": 0.5, "node_pressure": 0.25, "restarts": 0.25, "errors": 0},
+    ))
+    components = {"readiness": 0.5, "node_pressure": 1.0, "restarts": 0.0, "errors": 0.0}
+    assert main.cluster_health(components) == 50.0
+    assert main.cluster_health(dict(components, node_pressure=None)) == pytest.approx(100 / 3)
+    assert "sound_map.cluster_health.weights" in " ".join(main.validate_config(
+        main.merge_config(main.DEFAULT_CONFIG, {"sound_map": {"cluster_health": {
+            "weights": {"uptime": 1.0},
+        }}})
+    ))
+
+
+def make_pod(name="pod", phase="Running", node_name=None, restarts=0, requests=None,
+             limits=None, owner=None, minutes=0, host_network=False, **context):
+    created = datetime.fromtimestamp(time.time() - minutes * 60, timezone.utc)
+    owners = [types.SimpleNamespace(kind="ReplicaSet", name=owner)] if owner else None
+    container = types.SimpleNamespace(
+        ports=None,
+        resources=types.SimpleNamespace(requests=requests, limits=limits),
+        security_context=types.SimpleNamespace(**dict(
+            dict.fromkeys(["privileged", "allow_privilege_escalation", "run_as_non_root",
+                           "seccomp_profile", "capabilities"]), **context
+        )),
+    )
+    return types.SimpleNamespace(
+        metadata=types.SimpleNamespace(
+            name=name, uid=name, creation_timestamp=created, owner_references=owners
+        ),
+        status=types.SimpleNamespace(
+            phase=phase, container_statuses=[types.SimpleNamespace(restart_count=restarts)]
+        ),
+        spec=types.SimpleNamespace(
+            node_name=node_name, host_network=host_network, host_pid=None, host_ipc=None,
+            volumes=None, init_containers=None, containers=[container],
+            security_context=types.SimpleNamespace(
+                run_as_non_root=True, seccomp_profile=types.SimpleNamespace(type="RuntimeDefault"),
+            ),
+        ),
+    )
+
+
+def make_node(name="node", allocatable=None, pressure=False, unschedulable=None):
+    return types.SimpleNamespace(
+        metadata=types.SimpleNamespace(name=name),
+        spec=types.SimpleNamespace(unschedulable=unschedulable),
+        status=types.SimpleNamespace(
+            allocatable=allocatable,
+            conditions=[types.SimpleNamespace(type="MemoryPressure", status=str(pressure))],
+        ),
+    )
+
+
+def make_k8s_event(name="pod", message="", reason="", count=1, age=60, kind="Pod"):
+    return types.SimpleNamespace(
+        involved_object=types.SimpleNamespace(kind=kind, name=name),
+        message=message,
+        reason=reason,
+        count=count,
+        last_timestamp=datetime.fromtimestamp(time.time() - age, timezone.utc),
+        event_time=None,
+    )
+
+
+def fake_deployment(name, updated, available, deadline_exceeded=False, generation=2,
+                    replicas=3, ready=None):
+    reason = "ProgressDeadlineExceeded" if deadline_exceeded else "NewReplicaSetAvailable"
+    return types.SimpleNamespace(
+        metadata=types.SimpleNamespace(name=name, generation=generation),
+        spec=types.SimpleNamespace(replicas=replicas),
+        status=types.SimpleNamespace(
+            observed_generation=generation, replicas=replicas, updated_replicas=updated,
+            available_replicas=available, ready_replicas=ready,
+            conditions=[types.SimpleNamespace(type="Progressing", reason=reason)],
+        ),
+    )
+
+
+def test_top_offenders_ranks_pods_by_restarts_and_warnings(monkeypatch):
+    pods = [
+        make_pod("api", restarts=2, requests={"cpu": "500m"}),
+        make_pod("web", requests={"cpu": "1"}),
+        make_pod("db", restarts=1, requests={"cpu": "2"}),
+        make_pod("idle", requests={"cpu": "4"}),
+    ]
+    events = [
+        make_k8s_event("web", count=4), make_k8s_event("db", age=30),
+        make_k8s_event("idle", count=5, age=3600),
+    ]
+    client = main.K8sClient()
+    client.v1_core = types.SimpleNamespace(
+        list_namespaced_pod=lambda namespace, **kw: types.SimpleNamespace(items=pods),
+        list_namespaced_event=lambda namespace, **kw: types.SimpleNamespace(items=events),
+    )
+    score, extra = client.get_top_offenders("default", count=2)
+    assert score == 4
+    assert extra["values"] == [4, 2]
+    assert [(o["pod"], o["restarts"], o["warnings"]) for o in extra["offenders"]] == [
+        ("web", 0, 4), ("db", 1, 1),
+    ]
+    assert extra["offenders"][1]["cpu_request"] == 2.0
+
+
+def test_gpu_usage_from_allocations_or_dcgm(monkeypatch):
+    client = main.K8sClient()
+    client.v1_core = types.SimpleNamespace(
+        list_namespaced_pod=lambda namespace, label_selector: types.SimpleNamespace(items=[
+            make_pod(limits={"nvidia.com/gpu": "2"}), make_pod(limits={"nvidia.com/gpu": "1"}),
+            make_pod(phase="Pending", limits={"nvidia.com/gpu": "4"}),
+        ]),
+        list_node=lambda: types.SimpleNamespace(items=[
+            make_node(allocatable={"nvidia.com/gpu": "8"}),
+            make_node(allocatable={"nvidia.com/gpu": "4"}), make_node(),
+        ]),
+    )
+    assert client.get_gpu_usage("ml") == (25.0, {
+        "allocated": 3, "allocatable": 12, "source": "allocation",
+    })
+
+    requests = []
+
+    class FakeResponse(main.io.BytesIO):
+        def __init__(self, result):
+            super().__init__(main.json.dumps({"data": {"result": result}}).encode())
+
+    results = [[{"metric": {}, "value": [1721815200, "7.5"]}], []]
+    monkeypatch.setattr(main.urllib.request, "urlopen", lambda r, timeout=None: (
+        requests.append(r) or FakeResponse(results.pop(0))
+    ))
+    client.prometheus_url, client.prometheus_token = "http://prometheus:9090/", "t"
+    assert client.get_gpu_usage("ml") == (7.5, {"allocated": 3, "source": "dcgm"})
+    assert requests[0].full_url == "http://prometheus:9090/api/v1/query?" + (
+        main.urllib.parse.urlencode({"query": 'avg(DCGM_FI_DEV_GPU_UTIL{namespace="ml"})'})
+    )
+    assert requests[0].get_header("Authorization") == "Bearer t"
+    # No DCGM series: fall back to the allocation
+    assert client.get_gpu_usage("ml")[1]["source"] == "allocation"
+
+    cfg = main.merge_config(main.DEFAULT_CONFIG, {"prometheus": {"url": "prometheus:9090"}})
+    assert main.validate_config(cfg) == [
+        "prometheus.url: must be an http:// or https:// URL, not 'prometheus:9090'"
+    ]
+
+    # Any Sonifier built from a configuration queries its Prometheus
+    monkeypatch.setattr(main, "k8s_client", main.K8sClient())
+    cfg["prometheus"].update(url="http://prometheus:9090", token="t", gpu_query="up")
+    main.SonifierBuilder().with_config(cfg).build()
+    assert (main.k8s_client.prometheus_url, main.k8s_client.prometheus_token) == (
+        "http://prometheus:9090", "t"
+    )
+    assert main.k8s_client.gpu_query == "up"
+
+
+def test_critical_cves_from_running_workloads():
+    def report(kind, name, *vulnerabilities):
+        return {
+            "metadata": {"labels": {
+                "trivy-operator.resource.kind": kind, "trivy-operator.resource.name": name,
+            }},
+            "report": {"vulnerabilities": [
+                {"vulnerabilityID": cve, "severity": severity} for cve, severity in vulnerabilities
+            ]},
+        }
+
+    listed = []
+    client = main.K8sClient()
+    client.v1_core = types.SimpleNamespace(
+        list_namespaced_pod=lambda namespace, label_selector: types.SimpleNamespace(items=[
+            make_pod("web-1", owner="web-new"), make_pod("debug"),
+            make_pod("job-1", phase="Failed", owner="job"),
+        ]),
+    )
+    client.custom_objects = types.SimpleNamespace(
+        list_namespaced_custom_object=lambda *args: listed.append(args) or {"items": [
+            report("ReplicaSet", "web-new", ("CVE-1", "CRITICAL"), ("CVE-2", "HIGH")),
+            report("Pod", "debug", ("CVE-1", "CRITICAL"), ("CVE-3", "CRITICAL"), ("CVE-4", "LOW")),
+            report("ReplicaSet", "web-old", ("CVE-5", "CRITICAL")),
+            report("ReplicaSet", "job", ("CVE-6", "CRITICAL")),
+        ]},
+    )
+    assert client.get_vulnerabilities("shop") == (2, {
+        "critical": 2, "high": 1, "workloads": ["pod/debug", "replicaset/web-new"],
+    })
+    assert listed == [("aquasecurity.github.io", "v1alpha1", "shop", "vulnerabilityreports")]
+    assert main.get_severity("critical_cves", 0) == "ok"
+    assert main.get_severity("critical_cves", 5) == "critical"
+
+
+def test_cost_per_hour_from_opencost(monkeypatch):
+    requests = []
+    data = {"code": 200, "data": [{
+        "shop": {"totalCost": 6.0, "minutes": 30.0},
+        "__idle__": {"totalCost": 40.0, "minutes": 30.0},
+    }]}
+    monkeypatch.setattr(main.urllib.request, "urlopen", lambda r, timeout=None: (
+        requests.append(r) or main.io.BytesIO(main.json.dumps(data).encode())
+    ))
+    client = main.K8sClient()
+    client.opencost_url, client.opencost_window = "http://opencost:9003/", "30m"
+    assert client.get_cost_rate("shop") == (12.0, {"cost": 6.0, "window": "30m"})
+    assert requests[0].full_url == (
+        "http://opencost:9003/allocation/compute?window=30m&aggregate=namespace&accumulate=true"
+    )
+    assert client.get_cost_rate("idle-team") == (0.0, {"cost": 0.0, "window": "30m"})
+
+    gentle, hot = main.map_metric("cost_per_hour", 2.0), main.map_metric("cost_per_hour", 19.0)
+    assert gentle.velocity < hot.velocity and gentle.duration > hot.duration
+    assert main.get_severity("cost_per_hour", 12.0) == "warning"
+
+    cfg = main.merge_config(main.DEFAULT_CONFIG, {
+        "metrics": {"enabled": ["cost_per_hour"]}, "opencost": {"window": "an hour"},
+    })
+    assert main.validate_config(cfg) == [
+        "metrics.enabled: cost_per_hour requires opencost.url",
+        "opencost.window: must be a duration such as 1h, 30m or 1d",
+    ]
+
+    # Any Sonifier built from a configuration queries its OpenCost
+    monkeypatch.setattr(main, "k8s_client", main.K8sClient())
+    cfg["opencost"].update(url="http://opencost:9003", window="1d")
+    main.SonifierBuilder().with_config(cfg).build()
+    assert (main.k8s_client.opencost_url, main.k8s_client.opencost_window) == (
+        "http://opencost:9003", "1d"
+    )
+
+
+def test_evictions_split_memory_from_disk(monkeypatch):
+    evicted = [
+        make_k8s_event("web-1", "The node was low on resource: memory. Threshold quantity: 100Mi"),
+        make_k8s_event("web-2", "The node was low on resource: memory.", count=2),
+        make_k8s_event("cache-1", "The node was low on resource: ephemeral-storage."),
+        make_k8s_event("old-1", "The node was low on resource: memory.", age=3600),
+    ]
+    thresholds = [
+        make_k8s_event("worker-a", "Attempting to reclaim memory", kind="Node"),
+        make_k8s_event("worker-b", "Attempting to reclaim ephemeral-storage", kind="Node"),
+    ]
+    client = main.K8sClient()
+    client.v1_core = types.SimpleNamespace(
+        list_namespaced_event=lambda namespace, field_selector: types.SimpleNamespace(
+            items=evicted
+        ),
+        list_event_for_all_namespaces=lambda field_selector: types.SimpleNamespace(
+            items=thresholds
+        ),
+    )
+    assert client.get_evictions("default", "memory") == (3, {
+        "pods": ["web-1", "web-2"], "evictions": 4, "reclaiming": ["worker-a"],
+    })
+    count, disk = client.get_evictions("default", "disk")
+    assert (count, disk["pods"], disk["reclaiming"]) == (1, ["cache-1"], ["worker-b"])
+    assert main.map_metric("memory_evictions", 3).frequency < 262 < 523 <= (
+        main.map_metric("disk_evictions", 1).frequency
+    )
+
+    before = {"pods": [], "evictions": 0, "reclaiming": []}
+    assert main.detect_cluster_events("disk_evictions", before, disk) == [
+        "eviction_threshold", "disk_eviction",
+    ]
+    assert main.detect_cluster_events("disk_evictions", disk, disk) == []
+
+
+def test_admission_failures_from_controller_events():
+    selectors = []
+    client = main.K8sClient()
+    client.v1_core = types.SimpleNamespace(
+        list_namespaced_event=lambda namespace, field_selector: selectors.append(field_selector)
+        or types.SimpleNamespace(items=[
+            make_k8s_event(message='Error creating: admission webhook "validate.kyverno.svc"'
+                           " denied the request: require-labels", count=3),
+            make_k8s_event(message='Error creating: Internal error occurred: failed calling'
+                           ' webhook "mutate.istio.io": context deadline exceeded'),
+            make_k8s_event(message='Error creating: admission webhook "old.example.com" denied'
+                           " the request", age=3600),
+            make_k8s_event(message="Back-off restarting failed container"),
+        ]),
+    )
+    value, extra = client.get_admission_failures("shop")
+    assert selectors == ["type=Warning"]
+    assert (value, extra) == (4, {
+        "denied": 3, "failed": 1, "webhooks": ["mutate.istio.io", "validate.kyverno.svc"],
+        "rejections": 5,
+    })
+    before = dict(extra, rejections=4)
+    assert main.detect_cluster_events("admission_failures", before, extra) == ["admission_failure"]
+    assert main.detect_cluster_events("admission_failures", extra, extra) == []
+
+
+def test_pod_security_rejections_and_violating_pods():
+    restricted = dict(
+        allow_privilege_escalation=False,
+        capabilities=types.SimpleNamespace(add=None, drop=["ALL"]),
+    )
+    client = main.K8sClient()
+    client.v1_core = types.SimpleNamespace(
+        read_namespace=lambda namespace: types.SimpleNamespace(metadata=types.SimpleNamespace(
+            labels={"pod-security.kubernetes.io/enforce": "baseline",
+                    "pod-security.kubernetes.io/warn": "restricted"},
+        )),
+        list_namespaced_event=lambda namespace, field_selector: types.SimpleNamespace(items=[
+            make_k8s_event(
+                message='Error creating: pods "web-x" is forbidden: violates PodSecurity'
+                ' "baseline:latest": privileged (container "web" must not set privileged=true)',
+                count=2,
+            ),
+        ]),
+        list_namespaced_pod=lambda namespace, label_selector: types.SimpleNamespace(items=[
+            make_pod("ok", **restricted),
+            make_pod("legacy", host_network=True, run_as_non_root=False, **restricted),
+            make_pod("loose"),
+            make_pod("done", phase="Succeeded"),
+        ]),
+    )
+    assert client.get_pod_security("shop") == (4, {
+        "rejected": 2, "warned": 2, "level": "restricted", "rejections": 2,
+        "pods": {
+            "legacy": ["hostNetwork", "runAsNonRoot"],
+            "loose": ["capabilities", "allowPrivilegeEscalation"],
+        },
+    })
+
+    def forbidden(namespace):
+        error = main.ApiException()
+        error.status = 403
+        raise error
+
+    client.v1_core.read_namespace = forbidden
+    assert client.get_pod_security("shop") == (2, {
+        "rejected": 2, "warned": 0, "level": "unknown", "pods": {}, "rejections": 2,
+    })
+    assert main.pod_security_violations(make_pod("loose"), "baseline") == []
+    root = make_pod("root", privileged=True)
+    assert main.pod_security_violations(root, "baseline") == ["privileged"]
+    before = {"rejected": 2, "warned": 1, "rejections": 1}
+    assert main.detect_cluster_events("pod_security", before, {"warned": 2, "rejections": 2}) == [
+        "pod_security_violation",
+    ]
+
+
+def test_pending_volumes_with_provisioning_failures(monkeypatch):
+    def claim(name, phase, minutes):
+        created = datetime.fromtimestamp(time.time() - minutes * 60, timezone.utc)
+        return types.SimpleNamespace(
+            metadata=types.SimpleNamespace(name=name, creation_timestamp=created),
+            spec=types.SimpleNamespace(storage_class_name="fast-ssd"),
+            status=types.SimpleNamespace(phase=phase),
+        )
+
+    client = types.SimpleNamespace(
+        list_namespaced_persistent_volume_claim=lambda namespace: types.SimpleNamespace(items=[
+            claim("data-0", "Pending", 3), claim("data-1", "Pending", 12),
+            claim("bound", "Bound", 60), claim("waiting", "Pending", 20),
+        ]),
+        list_namespaced_event=lambda namespace, field_selector: types.SimpleNamespace(items=[
+            make_k8s_event("data-0", "failed to provision volume: quota exceeded",
+                           "ProvisioningFailed", count=2, kind="PersistentVolumeClaim"),
+            make_k8s_event("data-1", "storageclass.storage.k8s.io not found",
+                           "ProvisioningFailed", kind="PersistentVolumeClaim"),
+            make_k8s_event("waiting", "waiting for first consumer", "WaitForFirstConsumer",
+                           kind="PersistentVolumeClaim"),
+        ]),
+    )
+    monkeypatch.setattr(main.k8s_client, "v1_core", client)
+    monkeypatch.setattr(main.k8s_client, "initialized", True)
+    count, extra = main.get_k8s_data("pending_volumes", "db")
+    age, _ = main.get_k8s_data("pending_volume_age", "db")
+    assert (count, extra["failures"], age) == (2, 3, 12.0)
+    assert [(c["name"], c["storage_class"], c["error"]) for c in extra["claims"]] == [
+        ("data-1", "fast-ssd", "storageclass.storage.k8s.io not found"),
+        ("data-0", "fast-ssd", "failed to provision volume: quota exceeded"),
+    ]
+    assert main.get_severity("pending_volume_age", age) == "warning"
+    before = {"claims": [], "failures": 2}
+    assert main.detect_cluster_events("pending_volumes", before, extra) == ["provisioning_failed"]
+
+
+def test_scheduling_headroom_of_scarcer_resource():
+    selectors = []
+    client = main.K8sClient()
+    client.v1_core = types.SimpleNamespace(
+        list_node=lambda: types.SimpleNamespace(items=[
+            make_node("a", {"cpu": "4", "memory": "16Gi"}),
+            make_node("b", {"cpu": "3500m", "memory": "8Gi"}),
+            make_node("cordoned", {"cpu": "8", "memory": "32Gi"}, unschedulable=True),
+        ]),
+        list_pod_for_all_namespaces=lambda field_selector: selectors.append(field_selector)
+        or types.SimpleNamespace(items=[
+            make_pod(node_name="a", requests={"cpu": "2", "memory": "12Gi"}),
+            make_pod(node_name="b", requests={"cpu": "500m", "memory": "6144Mi"}),
+            make_pod(node_name="cordoned", requests={"cpu": "8"}),
+            make_pod(requests={"cpu": "16"}),
+        ]),
+    )
+    assert client.get_scheduling_headroom() == (25.0, {
+        "cpu_requested": 33.3, "memory_requested": 75.0, "scarcer": "memory", "nodes": 2,
+    })
+    assert selectors == ["status.phase!=Succeeded,status.phase!=Failed"]
+    assert main.parse_memory("1Gi") == main.parse_memory("1024Mi") == 2**30
+    assert main.parse_memory("1G") == 10**9
+    assert main.get_severity("scheduling_headroom", 9.0) == "critical"
+
+
+def test_pod_churn_and_median_age(monkeypatch):
+    listings = [
+        [make_pod("a", minutes=90), make_pod("b", minutes=30), make_pod("c", minutes=2)],
+        [make_pod("a", minutes=91), make_pod("c", minutes=3), make_pod("d", minutes=1),
+         make_pod("e", minutes=0.5)],
+    ]
+    clock = iter([100.0, 130.0])
+    monkeypatch.setattr(main.time, "monotonic", lambda: next(clock))
+    client = main.K8sClient()
+    client.v1_core = types.SimpleNamespace(
+        list_namespaced_pod=lambda namespace, label_selector: types.SimpleNamespace(
+            items=listings.pop(0)
+        ),
+    )
+    assert client.get_pod_churn("jobs") == (0.0, {
+        "created": 0, "deleted": 0, "pods": 3, "median_age_minutes": 30.0,
+    })
+    # One deleted and two created in half a minute
+    assert client.get_pod_churn("jobs") == (6.0, {
+        "created": 2, "deleted": 1, "pods": 4, "median_age_minutes": 2.0,
+    })
+    assert main.get_severity("pod_churn", 6.0) == "warning"
+
+
+def test_node_melody_plays_pressured_nodes_sour(monkeypatch):
+    client = main.K8sClient()
+    client.v1_core = types.SimpleNamespace(
+        list_node=lambda **kw: types.SimpleNamespace(
+            items=[make_node("worker-b", {"cpu": "2"}, pressure=True),
+                   make_node("worker-a", {"cpu": "4"})]
+        ),
+        list_pod_for_all_namespaces=lambda **kw: types.SimpleNamespace(
+            items=[make_pod(node_name="worker-a", requests={"cpu": "1"}),
+                   make_pod(node_name="worker-a", requests={"cpu": "500m"}),
+                   make_pod(node_name="worker-b", requests={"cpu": "2"})]
+        ),
+    )
+    load, extra = client.get_node_loads()
+    assert load == 100.0
+    assert extra == {
+        "nodes": ["worker-a", "worker-b"], "values": [37.5, 100.0], "sour": [False, True],
+    }
+
+    played = []
+    monkeypatch.setattr(main.k8s_client, "initialize", lambda: True)
+    monkeypatch.setattr(main, "get_k8s_data", lambda m, namespace="default": (load, extra))
+    monkeypatch.setattr(main, "play_arpeggio", lambda freqs, *a, **kw: played.extend(freqs))
+    main.Sonifier(main.KubernetesSource(), ["node_melody"]).tick()
+    clean = [main.map_metric("node_melody", v)[1] for v in extra["values"]]
+    assert played == [clean[0], pytest.approx(clean[1] * main.SOUR_INTERVAL)]
+
+
+def test_deployment_readiness_gives_each_deployment_a_row_and_voice(monkeypatch, capsys):
+    client = main.K8sClient()
+    client.v1_apps = types.SimpleNamespace(
+        list_namespaced_deployment=lambda namespace, label_selector: types.SimpleNamespace(items=[
+            fake_deployment("web", 4, 4, replicas=4, ready=4),
+            fake_deployment("checkout", 0, 0, replicas=3),
+            fake_deployment("batch", 0, 0, replicas=0),
+        ]),
+    )
+    readiness, extra = client.get_deployment_readiness("shop")
+    assert (readiness, extra) == (0.0, {
+        "rows": ["batch 0/0", "checkout 0/3", "web 4/4"],
+        "values": [100.0, 0.0, 100.0],
+        "sour": [False, True, False],
+    })
+
+    played = []
+    monkeypatch.setattr(main.k8s_client, "initialize", lambda: True)
+    monkeypatch.setattr(main, "get_k8s_data", lambda m, namespace="default": (readiness, extra))
+    monkeypatch.setattr(
+        main, "play_note", lambda f, d, volume=1.0, voice=None: played.append((f, voice))
+    )
+    main.Sonifier(main.KubernetesSource(), ["deployment_readiness"], use_color=True).tick()
+    assert [(voice["type"], voice["pan"]) for _, voice in played] == [
+        ("sine", -1.0), ("pluck", 0.0), ("fm", 1.0),
+    ]
+    assert played[1][0] == pytest.approx(
+        main.map_metric("deployment_readiness", 0.0)[1] * main.SOUR_INTERVAL
+    )
+    out = capsys.readouterr().out
+    assert "  checkout 0/3: 0%" in out and "  web 4/4: 100%" in out
+
+
+def test_sonifier_plays_accompaniment_under_lead(monkeypatch):
+    volumes = {}
+    monkeypatch.setattr(main.k8s_client, "initialize", lambda: True)
+    monkeypatch.setattr(main, "get_k8s_data", lambda m, namespace="default": (50.0, {}))
+    monkeypatch.setattr(
+        main, "play_note", lambda f, d, volume=1.0, voice=None: volumes.setdefault(f, volume)
+    )
+    cfg = main.merge_config(main.DEFAULT_CONFIG, {"metrics": {
+        "enabled": ["cpu_usage", "cluster_health"],
+        "lead": "cluster_health",
+        "accompaniment_volume": 0.25,
+    }})
+    assert main.validate_config(cfg) == []
+    sonifier = main.SonifierBuilder().with_config(cfg).build()
+    events = sonifier.tick()
+    assert [e.metric for e in events] == ["cluster_health", "cpu_usage"]
+    assert [volumes[e.frequency] for e in events] == [1.0, 0.25]
+
+
+def test_parse_expression_rejects_code():
+    tree = main.parse_expression("errors_per_second / max(replicas, 1)")
+    assert main.expression_metrics(tree) == ["errors_per_second", "replicas"]
+    assert main.evaluate_expression(tree, {"errors_per_second": 6, "replicas": 3}.get) == 2.0
+    assert main.evaluate_expression(tree, {"errors_per_second": 6}.get) is None
+    assert main.evaluate_expression(main.parse_expression("1 / cpu_usage"), lambda m: 0) is None
+    assert main.evaluate_expression(main.parse_expression("9 ** 9 ** 9"), lambda m: 0) is None
+    assert main.parse_expression("cpu_usage ** 2") is main.parse_expression("cpu_usage ** 2")
+    for source in ("__import__('os')", "cpu_usage.real", "'a' * 3", "max(a, key=b)"):
+        with pytest.raises(ValueError):
+            main.parse_expression(source)
+
+
+def test_sonifier_maps_derived_metrics(monkeypatch):
+    monkeypatch.setattr(main, "SOUND_MAP", copy.deepcopy(main.SOUND_MAP))
+    monkeypatch.setattr(main.k8s_client, "initialize", lambda: True)
+    monkeypatch.setattr(main, "get_k8s_data", lambda m, namespace="default": (
+        {"cpu_usage": 80.0, "errors_per_second": 6.0, "replicas": 0.0}[m], {}
+    ))
+    monkeypatch.setattr(main, "play_note", lambda *a, **kw: None)
+    entry = {"range": [0, 100], "thresholds": [20, 10], "notes": ["C4", "C5"],
+             "colors": ["#FF0000", "#00FF00"]}
+    cfg = main.merge_config(main.DEFAULT_CONFIG, {
+        "metrics": {"enabled": ["cpu_usage", "headroom", "error_ratio"]},
+        "sound_map": {
+            "headroom": dict(entry, expression="100 - cpu_usage"),
+            "error_ratio": dict(entry, expression="errors_per_second / max(replicas, 1)",
+                                thresholds=[5, 10]),
+        },
+    })
+    assert main.validate_config(cfg) == []
+    events = main.SonifierBuilder().with_config(cfg).build().tick()
+    assert [(e.metric, e.value, e.severity) for e in events] == [
+        ("cpu_usage", 80.0, "warning"), ("headroom", 20.0, "warning"),
+        ("error_ratio", 6.0, "warning"),
+    ]
+
+    cfg["sound_map"]["headroom"]["expression"] = "100 - latency"
+    assert main.validate_config(cfg) == [
+        "sound_map.headroom.expression: unknown metric 'latency'"
+    ]
+
+
+def test_sonifier_fuses_metrics_into_one_voice(monkeypatch):
+    monkeypatch.setattr(main, "SOUND_MAP", copy.deepcopy(main.SOUND_MAP))
+    monkeypatch.setattr(main.k8s_client, "initialize", lambda: True)
+    monkeypatch.setattr(main, "get_k8s_data", lambda m, namespace="default": (
+        {"http_latency": 250.0, "errors_per_second": 5.0, "cpu_usage": 75.0}[m], {}
+    ))
+    voices = []
+    monkeypatch.setattr(
+        main, "play_note", lambda f, d, volume=1.0, voice=None: voices.append((f, voice))
+    )
+    cfg = main.merge_config(main.DEFAULT_CONFIG, {
+        "metrics": {"enabled": ["service"]},
+        "sound_map": {"service": {"fuse": {
+            "pitch": "http_latency", "brightness": "errors_per_second", "pan": "cpu_usage",
+        }}},
+    })
+    assert main.validate_config(cfg) == []
+    events = main.SonifierBuilder().with_config(cfg).build().tick()
+    assert [(e.metric, e.value, e.extra) for e in events] == [
+        ("service", 250.0, {"fuse": cfg["sound_map"]["service"]["fuse"]}),
+    ]
+    frequency, voice = voices[0]
+    assert frequency == main.map_metric("http_latency", 250.0).frequency
+    assert voice["type"] == "additive"
+    assert voice["brightness_level"] == main.metric_level("errors_per_second", 5.0)
+    assert voice["pan"] == 0.5
+
+    sonifier = main.Sonifier(main.KubernetesSource(), ["service"], namespace="default")
+    sonifier.discovered = ["team-a", "team-b"]
+    assert [sonifier.pan(ns, "namespace") for ns in ("default", "team-a", "team-b")] == [
+        -1.0, 0.0, 1.0,
+    ]
+    cfg["sound_map"]["service"]["fuse"]["pan"] = "cluster"
+    assert main.validate_config(cfg) == ["sound_map.service.fuse.pan: unknown metric 'cluster'"]
+
+
+def test_sonifier_maps_rate_of_change(monkeypatch):
+    monkeypatch.setattr(main, "SOUND_MAP", copy.deepcopy(main.SOUND_MAP))
+    replicas = [2.0, 5.0, 5.0]
+    clock = iter([100.0, 110.0, 120.0])
+    monkeypatch.setattr(main.k8s_client, "initialize", lambda: True)
+    monkeypatch.setattr(main, "get_k8s_data", lambda m, namespace="default": (replicas.pop(0), {}))
+    monkeypatch.setattr(main, "play_note", lambda *a, **kw: None)
+    monkeypatch.setattr(main, "time", types.SimpleNamespace(time=lambda: next(clock)))
+    cfg = main.merge_config(main.DEFAULT_CONFIG, {
+        "metrics": {"enabled": ["replicas.rate"]},
+        "sound_map": {"replicas.rate": {"range": [-0.5, 0.5], "thresholds": [0.1, 0.25]}},
+    })
+    assert main.validate_config(cfg) == []
+    sonifier = main.SonifierBuilder().with_config(cfg).build()
+
+    assert sonifier.tick() == []
+    [event] = sonifier.tick()
+    assert (event.metric, event.value, event.severity) == ("replicas.rate", 0.3, "critical")
+    assert event.note == main.map_metric("replicas.rate", 0.3)[2]
+    assert main.SOUND_MAP["replicas.rate"]["unit"] == "Count/s"
+    [event] = sonifier.tick()
+    assert (event.value, event.severity) == (0.0, "ok")
+    assert main.SOUND_MAP["replicas.rate"]["metric_name"] == "Replica Count Rate"
+
+
+def test_sonifier_stop_ends_run(monkeypatch):
+    monkeypatch.setattr(main.k8s_client, "initialize", lambda: True)
+    monkeypatch.setattr(main, "get_k8s_data", lambda m, namespace="default": (50.0, {}))
+    monkeypatch.setattr(main, "play_note", lambda *a, **kw: None)
+    monkeypatch.setattr(main, "time", types.SimpleNamespace(sleep=lambda _: sonifier.stop()))
+    sonifier = main.SonifierBuilder().with_metrics(["cpu_usage"]).build()
+    events = []
+    sonifier.on_event(events.append)
+    sonifier.run()
+    assert len(events) == 1
+
+
+def test_sonifier_run_queued_processes_polls_in_order(tmp_path, monkeypatch):
+    path = tmp_path / "metrics.json"
+    path.write_text('[{"timestamp": 1, "metric": "cpu_usage", "value": 0},'
+                    ' {"timestamp": 2, "metric": "cpu_usage", "value": 50},'
+                    ' {"timestamp": 3, "metric": "cpu_usage", "value": 100}]')
+    monkeypatch.setattr(main, "time", types.SimpleNamespace(sleep=lambda _: None))
+    sonifier = main.Sonifier(
+        main.FileMetricSource(str(path)), ["cpu_usage"], audio=False, sample_queue=3
+    )
+    events = []
+    sonifier.on_event(events.append)
+    sonifier.run()
+    assert [e.value for e in events] == [0, 50, 100]
+    assert sonifier.state.active_alarms == {"cpu_usage"}
+
+
+def test_sonifier_collect_leaves_alarms_to_playback(monkeypatch):
+    monkeypatch.setattr(main.k8s_client, "initialize", lambda: True)
+    monkeypatch.setattr(main, "get_k8s_data", lambda m, namespace="default": (95.0, {}))
+    sonifier = main.Sonifier(main.KubernetesSource(), ["cpu_usage"], audio=False)
+    sonifier.tick()
+    sonifier.state.namespace = "prod"
+
+    samples = sonifier.collect()
+    assert [s.namespace for s in samples] == ["prod"]
+    assert sonifier.state.active_alarms == {"cpu_usage"}
+    sonifier.apply_changes(sonifier.take_changes())
+    assert sonifier.state.active_alarms == set()
+    assert sonifier.take_changes() == []
+
+
+def test_sonifier_run_queued_plays_heartbeat_on_playback_thread(tmp_path, monkeypatch):
+    path = tmp_path / "metrics.json"
+    path.write_text('[{"timestamp": 1, "metric": "cpu_usage", "value": 0},'
+                    ' {"timestamp": 2, "metric": "cpu_usage", "value": 0}]')
+    threads = []
+    monkeypatch.setattr(main, "POLL_INTERVAL", 2)
+    monkeypatch.setattr(main, "time", types.SimpleNamespace(
+        sleep=lambda _: None, monotonic=main.time.monotonic, time=main.time.time,
+    ))
+    monkeypatch.setattr(
+        main, "play_heartbeat", lambda severity: threads.append(main.threading.current_thread())
+    )
+    sonifier = main.Sonifier(
+        main.FileMetricSource(str(path)), ["cpu_usage"], audio=False, sample_queue=8,
+        heartbeat=True,
+    )
+    sonifier.run(max_ticks=2)
+    assert threads and set(threads) == {main.threading.current_thread()}
+
+
+def test_queued_output_drops_oldest_and_drains_on_close():
+    class BlockingOutput(main.MetricOutput):
+        def __init__(self):
+            self.values, self.closed = [], False
+            self.started, self.release = main.threading.Event(), main.threading.Event()
+
+        def emit(self, event):
+            self.started.set()
+            self.release.wait(5)
+            self.values.append(event.value)
+
+        def close(self):
+            self.closed = True
+
+    inner = BlockingOutput()
+    output = main.QueuedOutput(inner, max_items=2)
+    output.emit(make_event(value=1))
+    assert inner.started.wait(5)
+    for value in (2, 3, 4):
+        output.emit(make_event(value=value))
+    assert output.dropped == 1
+    inner.release.set()
+    output.close()
+    assert inner.values == [1, 3, 4]
+    assert inner.closed
+
+
+def test_sonifier_shutdown_fades_out_and_prints_summary(monkeypatch, capsys):
+    values = [40.0, 95.0, 60.0]
+    fades = []
+
+    class FadingSink(main.AudioSink):
+        def play(self, frequency, duration, volume=1.0, voice=None):
+            pass
+
+        def fade_out(self, duration):
+            fades.append(duration)
+
+    monkeypatch.setattr(main, "audio_sink", main.audio_sink)
+    monkeypatch.setattr(main.k8s_client, "initialize", lambda: True)
+    monkeypatch.setattr(main, "get_k8s_data", lambda m, namespace="default": (values.pop(0), {}))
+    monkeypatch.setattr(main, "time", types.SimpleNamespace(sleep=lambda _: None))
+    sonifier = (
+        main.SonifierBuilder()
+        .with_metrics(["cpu_usage"])
+        .with_audio_sink(FadingSink())
+        .with_summary()
+        .build()
+    )
+    sonifier.run(max_ticks=3)
+
+    assert fades == [main.SHUTDOWN_FADE]
+    out = capsys.readouterr().out
+    assert "raised 1 alarm(s)" in out
+    assert "CPU Usage: worst 95.00 % (critical) in default" in out
+
+
+def test_capture_sink_records_sonified_notes(tmp_path, monkeypatch):
+    path = tmp_path / "metrics.json"
+    path.write_text('[{"timestamp": 1, "metric": "cpu_usage", "value": 0},'
+                    ' {"timestamp": 1, "metric": "replicas", "value": 3},'
+                    ' {"timestamp": 2, "metric": "cpu_usage", "value": 100}]')
+    monkeypatch.setenv("TEST_MODE", "false")
+    monkeypatch.setenv("AUDIO_ENABLED", "true")
+    monkeypatch.setattr(main, "audio_sink", main.audio_sink)
+    clock = iter(range(100))
+    sink = main.CaptureSink(clock=lambda: next(clock))
+    sonifier = (
+        main.SonifierBuilder()
+        .with_source(main.FileMetricSource(str(path)))
+        .with_metrics(["cpu_usage", "replicas"])
+        .with_note_duration(0.1)
+        .with_audio_sink(sink)
+        .build()
+    )
+    sonifier.tick()
+    sonifier.tick()
+    sonifier.shutdown()
+
+    assert sink.frequencies == [
+        main.map_metric("cpu_usage", 0)[1],
+        main.map_metric("replicas", 3)[1],
+        main.map_metric("cpu_usage", 100)[1],
+    ]
+    assert [note.timestamp for note in sink.notes] == [0, 1, 2]
+    assert all(len(note.samples) == main.SAMPLE_RATE // 10 for note in sink.notes)
+    assert sink.fades == [main.SHUTDOWN_FADE] and sink.closed
+
+
+def test_note_scheduler_fade_out_ramps_to_silence():
+    scheduler = main.NoteScheduler(0.001, sample_rate=100)
+    scheduler.schedule(main.np.ones(50))
+    scheduler.render(10)
+    assert scheduler.fade_out(0.1) == 20
+    assert list(scheduler.render(12)) == [1.0, 0.9, 0.8, 0.7, 0.6, 0.5, 0.4, 0.3, 0.2, 0.1, 0, 0]
+    assert scheduler.notes == []
+
+
+def test_render_tone_envelope():
+    tone = main.render_tone(440, 0.5, volume=0.5)
+    assert len(tone) == main.SAMPLE_RATE // 2
+    assert tone[0] == 0
+    assert abs(tone).max() <= 0.5
+
+
+def test_webaudio_sink_pushes_notes():
+    sink = main.WebAudioSink(port=0)
+    try:
+        base = f"http://127.0.0.1:{sink.server.server_port}"
+        with main.urllib.request.urlopen(base + "/", timeout=5) as page:
+            assert b"AudioContext" in page.read()
+        with main.urllib.request.urlopen(base + "/events", timeout=5) as events:
+            while not sink.clients:
+                time.sleep(0.01)
+            sink.play(440, 0.0, volume=0.5)
+            line = events.readline().decode()
+        assert main.json.loads(line[len("data: "):]) == {
+            "frequency": 440, "duration": 0.0, "volume": 0.5, "voice": None,
+        }
+    finally:
+        sink.close()
+
+
+def fake_mido(monkeypatch):
+    """Installs a fake mido module and returns the ports it opens."""
+    ports = []
+
+    class FakePort:
+        def __init__(self, name, virtual):
+            self.name, self.virtual, self.sent, self.closed = name, virtual, [], False
+            ports.append(self)
+
+        def send(self, message):
+            self.sent.append(message)
+
+        def close(self):
+            self.closed = True
+
+    mido = types.SimpleNamespace(
+        open_output=lambda name=None, virtual=False: FakePort(name, virtual),
+        Message=lambda kind, **fields: (kind, fields),
+    )
+    monkeypatch.setitem(main.sys.modules, "mido", mido)
+    return ports
+
+
+def test_midi_sink_creates_virtual_port(monkeypatch):
+    ports = fake_mido(monkeypatch)
+    cfg = main.merge_config(
+        main.DEFAULT_CONFIG, {"audio": {"backend": "midi", "midi_virtual": True}}
+    )
+    sink = main.build_audio_sink(cfg)
+    sink.play(440.0, 0.0, volume=0.5)
+    sink.close()
+
+    assert [(p.name, p.virtual) for p in ports] == [("sonify-k8s", True)]
+    assert ports[0].sent == [
+        ("note_on", {"channel": 0, "note": 69, "velocity": 64}),
+        ("note_off", {"channel": 0, "note": 69, "velocity": 64}),
+    ]
+    assert ports[0].closed
+
+
+def test_install_audio_sink_plays_through_the_configured_backend(monkeypatch):
+    ports = fake_mido(monkeypatch)
+    monkeypatch.setattr(main, "audio_sink", main.audio_sink)
+    cfg = main.merge_config(main.DEFAULT_CONFIG, {"audio": {"backend": "midi"}})
+    sink = main.install_audio_sink(cfg)
+    assert isinstance(sink, main.MidiSink) and main.audio_sink is sink
+    main.play_note(440.0, 0.0)
+    sink.close()
+    assert ports[0].sent[0] == ("note_on", {"channel": 0, "note": 69, "velocity": 127})
+
+
+def test_midi_sink_routes_voice_to_channel_and_program(monkeypatch):
+    ports = fake_mido(monkeypatch)
+    monkeypatch.setattr(main, "time", types.SimpleNamespace(sleep=lambda _: None))
+    sink = main.MidiSink()
+    voice = {"type": "fm", "midi_channel": 3, "midi_program": 20}
+    sink.play(440.0, 0.0, voice=voice)
+    sink.play(440.0, 0.0, voice=voice)
+
+    note = {"channel": 2, "note": 69, "velocity": 127}
+    assert ports[0].sent == [
+        ("program_change", {"channel": 2, "program": 19}),
+        ("note_on", note), ("note_off", note),
+        ("note_on", note), ("note_off", note),
+    ]
+
+
+def test_midi_sink_ramps_control_change_between_notes(monkeypatch):
+    ports = fake_mido(monkeypatch)
+    monkeypatch.setattr(main, "time", types.SimpleNamespace(sleep=lambda _: None))
+    monkeypatch.setattr(main, "MIDI_CC_STEPS", 4)
+    sink = main.MidiSink()
+    sink.play(440.0, 0.0, voice={"midi_cc": 11, "cc_level": 0.0})
+    sink.play(440.0, 0.0, voice={"midi_cc": 11, "cc_level": 1.0})
+
+    ramp = [m[1]["value"] for m in ports[0].sent if m[0] == "control_change"]
+    assert ramp == [0, 31, 63, 95, 127]
+
+
+def test_midi_sink_fades_out_interrupted_notes(monkeypatch):
+    ports = fake_mido(monkeypatch)
+    monkeypatch.setattr(main, "MIDI_CC_STEPS", 2)
+
+    def interrupt(_):
+        raise KeyboardInterrupt
+
+    monkeypatch.setattr(main, "time", types.SimpleNamespace(sleep=interrupt))
+    sink = main.MidiSink()
+    with pytest.raises(KeyboardInterrupt):
+        sink.play(440.0, 1.0)
+    monkeypatch.setattr(main, "time", types.SimpleNamespace(sleep=lambda _: None))
+    sink.fade_out(1.0)
+
+    volume = {"channel": 0, "control": 7}
+    assert ports[0].sent[1:] == [
+        ("control_change", dict(volume, value=50)),
+        ("control_change", dict(volume, value=0)),
+        ("note_off", {"channel": 0, "note": 69}),
+        ("control_change", dict(volume, value=100)),
+    ]
+    assert not sink.sounding
+
+
+def test_metric_voice_cc_follows_cc_metric(monkeypatch):
+    monkeypatch.setitem(main.SOUND_MAP, "cpu_usage", dict(
+        main.SOUND_MAP["cpu_usage"], voice={"midi_cc": 1, "cc_metric": "memory_usage"}
+    ))
+    voice = main.metric_voice("cpu_usage", 90, {"memory_usage": 25}.get)
+    assert (voice["level"], voice["cc_level"]) == (0.9, 0.25)
+
+
+def test_cluster_events_play_drums_on_channel_10(monkeypatch):
+    restarts = [0, 0, 2]
+
+    def fake_get_k8s_data(metric, namespace="default"):
+        return 3, {"status": "Running", "restarts": restarts.pop(0)}
+
+    ports = fake_mido(monkeypatch)
+    monkeypatch.setenv("TEST_MODE", "false")
+    monkeypatch.setenv("AUDIO_ENABLED", "true")
+    monkeypatch.setattr(main, "audio_sink", main.audio_sink)
+    monkeypatch.setattr(main.k8s_client, "initialize", lambda: True)
+    monkeypatch.setattr(main, "get_k8s_data", fake_get_k8s_data)
+    monkeypatch.setattr(main, "play_note", lambda *a, **kw: None)
+    monkeypatch.setattr(main, "time", types.SimpleNamespace(sleep=lambda _: None))
+    events = []
+    sonifier = (
+        main.SonifierBuilder()
+        .with_metrics(["pod_status"])
+        .with_audio_sink(main.MidiSink(drum_map={"pod_restart": 40}))
+        .build()
+    )
+    sonifier.on_event(events.append)
+    sonifier.run(max_ticks=3)
+
+    hit = {"channel": 9, "note": 40, "velocity": 127}
+    assert ports[0].sent == [("note_on", hit), ("note_off", hit)]
+    assert [e.extra.get("cluster_events") for e in events] == [None, None, ["pod_restart"]]
+
+
+def test_desktop_notification_on_critical_alarm(monkeypatch):
+    commands = []
+    monkeypatch.setattr(main.subprocess, "run", lambda command, **kw: commands.append(command))
+    monkeypatch.setattr(main.sys, "platform", "linux")
+    output = main.DesktopNotificationOutput()
+    output.alarm(make_event(), "cleared")
+    output.alarm(make_event(), "fired")
+    assert len(commands) == 1
+    assert commands[0][-2:] == ["CPU Usage is critical", "95.00 % in my ns"]
+
+    assert main.notification_command('Say "hi"', "x", platform="darwin") == [
+        "osascript", "-e", 'display notification "x" with title "Say \\"hi\\""',
+    ]
+    assert main.notification_command("t", "b", platform="win32") is None
+
+
+def test_pipe_sink_writes_pcm_with_silence_between_notes(monkeypatch, tmp_path):
+    import io
+
+    monkeypatch.setattr(main, "PIPE_BLOCK", 0.01)
+    stream = io.BytesIO()
+    stream.close = lambda: None
+    sink = main.PipeSink(stream, fade=0)
+    # Silence is fed before any note is played
+    time.sleep(0.05)
+    assert len(stream.getvalue()) >= 2 * 441 and not any(stream.getvalue())
+    sink.play(440, 0.01)
+    time.sleep(0.05)
+    sink.close()
+    pcm = main.np.frombuffer(stream.getvalue(), dtype="<i2")
+    played = main.np.flatnonzero(pcm)
+    assert 0 < len(played) <= 441 and len(pcm) > played[-1] + 441
+
+    path = tmp_path / "sonify.pcm"
+    cfg = main.merge_config(
+        main.DEFAULT_CONFIG, {"audio": {"backend": "pipe", "pipe_path": str(path)}}
+    )
+    assert main.validate_config(cfg) == []
+    sink = main.build_audio_sink(cfg)
+    sink.play(440, 0.01)
+    sink.close()
+    assert path.stat().st_size >= 2 * 441
+
+
+def test_recording_sink_encodes_tagged_session(monkeypatch, tmp_path):
+    encoded = []
+
+    class FakeSoundFile:
+        def __init__(self, path, mode, samplerate, channels, subtype, format):
+            encoded.append({"path": path, "format": (format, subtype), "frames": 0})
+
+        def __enter__(self):
+            return self
+
+        def __exit__(self, *exc):
+            return False
+
+        def __setattr__(self, name, value):
+            encoded[-1][name] = value
+
+        def write(self, data):
+            encoded[-1]["frames"] += len(data)
+
+    monkeypatch.setitem(
+        main.sys.modules, "soundfile", types.SimpleNamespace(SoundFile=FakeSoundFile)
+    )
+    clock = [1721815200.0]
+    played = main.CaptureSink(render=False)
+    path = str(tmp_path / "session.ogg")
+    sink = main.RecordingSink(
+        played, path, {"cluster": "prod", "namespace": "web"}, fade=0, clock=lambda: clock[0]
+    )
+    sink.play(440, 0.01)
+    clock[0] += 0.03
+    sink.play(660, 0.01)
+    sink.close()
+    assert played.frequencies == [440, 660] and played.closed
+    [ogg] = encoded
+    assert ogg["format"] == ("OGG", "VORBIS") and ogg["frames"] == pytest.approx(4 * 441, abs=1)
+    assert ogg["title"] == "Sonify K8s: prod/web"
+    assert ogg["comment"] == (
+        "cluster=prod, namespace=web, start=2024-07-24T10:00:00+00:00,"
+        " end=2024-07-24T10:00:00+00:00"
+    )
+    assert not main.os.path.exists(sink.wav_path)
+
+    monkeypatch.delitem(main.sys.modules, "soundfile")
+    monkeypatch.setattr(main, "encode_recording", lambda *a: 1 / 0)
+    sink = main.RecordingSink(main.CaptureSink(render=False), str(tmp_path / "session.flac"))
+    assert main.os.path.dirname(sink.wav_path) == str(tmp_path)
+    sink.play(440, 0.01)
+    sink.close()
+    assert (tmp_path / "session.wav").exists()
+
+    cfg = main.merge_config(main.DEFAULT_CONFIG, {"audio": {"record_path": "session.mp3"}})
+    assert main.validate_config(cfg) == [
+        "audio.record_path: must end in one of .wav, .ogg, .flac"
+    ]
+
+
+def test_icecast_sink_streams_notes_and_silence(monkeypatch):
+    import io
+
+    class FakeEncoder:
+        def __init__(self, command, stdin, stdout):
+            self.command = command
+            self.stdin = io.BytesIO()
+            self.stdin.close = lambda: None
+            self.stdout = io.BytesIO(b"OggS stream")
+            encoders.append(self)
+
+        def wait(self, timeout=None):
+            return 0
+
+    class FakeServer:
+        def __init__(self, status):
+            self.status = status
+            self.sent = b""
+            self.closed = False
+
+        def __call__(self, address, timeout):
+            self.address = address
+            return self
+
+        def sendall(self, data):
+            self.sent += data
+
+        def recv(self, size):
+            return self.status
+
+        def settimeout(self, timeout):
+            pass
+
+        def close(self):
+            self.closed = True
+
+    encoders = []
+    monkeypatch.setattr(main, "ICECAST_BLOCK", 0.01)
+    icecast = main.merge_config(main.DEFAULT_CONFIG["outputs"]["icecast"], {
+        "url": "http://radio.internal/prod.ogg", "name": "Radio Production\nCluster",
+    })
+    command = main.icecast_command(icecast)
+    assert not any("p@ss" in arg or "icecast://" in arg for arg in command)
+    assert command[command.index("-c:a") + 1] == "libvorbis"
+    assert command[-3:] == ["-f", "ogg", "-"]
+    address, request = main.icecast_request(icecast, "p@ss")
+    assert address == ("radio.internal", 8000)
+    assert request.startswith(b"PUT /prod.ogg HTTP/1.1\r\n")
+    assert b"Authorization: Basic c291cmNlOnBAc3M=\r\n" in request
+    assert b"Ice-Name: Radio Production Cluster\r\n" in request
+    with pytest.raises(ValueError, match="http://"):
+        main.icecast_request(dict(icecast, url="https://radio.internal/prod.ogg"), "p@ss")
+
+    played = main.CaptureSink(render=False)
+    server = FakeServer(b"HTTP/1.1 100 Continue\r\n\r\n")
+    sink = main.IcecastSink(
+        played, command, address, request, fade=0, popen=FakeEncoder, connect=server
+    )
+    sink.play(440, 0.01)
+    time.sleep(0.1)
+    sink.close()
+    [encoder] = encoders
+    pcm = main.np.frombuffer(encoder.stdin.getvalue(), dtype=main.np.int16)
+    assert played.frequencies == [440] and played.closed
+    assert len(pcm) > 441 and any(pcm)
+    assert encoder.command == command
+    assert server.sent == request + b"OggS stream" and server.closed
+
+    refused = FakeServer(b"HTTP/1.0 401 Unauthorized\r\n\r\n")
+    with pytest.raises(OSError, match="401 Unauthorized"):
+        main.IcecastSink(played, command, address, request, popen=FakeEncoder, connect=refused)
+    assert refused.closed and len(encoders) == 1
+
+    cfg = main.merge_config(main.DEFAULT_CONFIG, {"outputs": {"icecast": {
+        "enabled": True, "url": "radio", "format": "aac",
+    }}})
+    assert main.validate_config(cfg) == [
+        "outputs.icecast.url: must be the mountpoint's http:// URL",
+        "outputs.icecast.format: must be one of ogg, mp3",
+    ]
+    cfg["outputs"]["icecast"].update(url="https://radio.internal/prod.ogg", format="ogg")
+    assert main.validate_config(cfg) == [
+        "outputs.icecast.url: must be the mountpoint's http:// URL",
+    ]
+
+
+def test_haptic_output_rumbles_by_severity(monkeypatch):
+    rumbles = []
+
+    class FakeJoystick:
+        def __init__(self, index):
+            self.index = index
+
+        def init(self):
+            pass
+
+        def rumble(self, low, high, duration):
+            rumbles.append((self.index, low, high, duration))
+            return True
+
+        def stop_rumble(self):
+            rumbles.append("stop")
+
+    joystick = types.SimpleNamespace(init=lambda: None, get_count=lambda: 2, Joystick=FakeJoystick)
+    monkeypatch.setitem(main.sys.modules, "pygame", types.SimpleNamespace(joystick=joystick))
+    cfg = main.merge_config(main.DEFAULT_CONFIG, {"outputs": {"haptic": {
+        "enabled": True, "controller": 1, "warning": 0.4, "duration": 0.25,
+    }}})
+    assert main.validate_config(cfg) == []
+    [output] = main.build_outputs(cfg)
+    for severities in (["ok"], ["ok", "warning"], ["warning", "critical"]):
+        for severity in severities:
+            output.emit(make_event(severity=severity))
+        output.flush()
+    output.close()
+    assert rumbles == [(1, 0.4, 0.0, 250), (1, 1.0, 1.0, 250), "stop"]
+
+    missing = main.HapticOutput(controller=2)
+    missing.emit(make_event())
+    missing.flush()
+    assert not missing.available and len(rumbles) == 3
+
+
+def test_rollout_events(monkeypatch):
+    def rollouts(*deployments):
+        return {"rollouts": {d.metadata.name: main.rollout_state(d) for d in deployments}}
+
+    before = rollouts(fake_deployment("web", 1, 3), fake_deployment("api", 3, 2))
+    after = rollouts(fake_deployment("web", 3, 3), fake_deployment("api", 3, 2, True))
+    assert main.detect_cluster_events("replicas", before, after) == [
+        "rollout_succeeded", "rollout_failed",
+    ]
+    assert main.detect_cluster_events("replicas", after, after) == []
+
+    def generations(*deployments):
+        return dict(
+            rollouts(*deployments),
+            rollout_generations={d.metadata.name: d.metadata.generation for d in deployments},
+        )
+
+    # Rolled out and completed between two polls: only the generation tells
+    before = generations(fake_deployment("web", 3, 3), fake_deployment("api", 3, 2, True))
+    after = generations(
+        fake_deployment("web", 3, 3, generation=3), fake_deployment("api", 3, 2, True, 3)
+    )
+    assert main.detect_cluster_events("replicas", before, after) == [
+        "rollout_succeeded", "rollout_failed",
+    ]
+    assert main.detect_cluster_events("replicas", after, after) == []
+
+    played = []
+    monkeypatch.setattr(main, "play_note", lambda f, d, **kw: played.append(f))
+    extras = [before, after]
+    monkeypatch.setattr(main.k8s_client, "initialize", lambda: True)
+    monkeypatch.setattr(main, "get_k8s_data", lambda m, namespace="default": (3, extras.pop(0)))
+    sonifier = main.Sonifier(
+        main.KubernetesSource(), ["replicas"], event_earcons={"rollout_failed": "failure"}
+    )
+    sonifier.tick()
+    sonifier.tick()
+    assert played[-4:] == [440, 349.23, 293.66, main.map_metric("replicas", 3)[1]]
+
+
+def test_deprecated_apis_remind_more_often_with_more_offenders(monkeypatch):
+    def applied(kind, name, api_version):
+        return {"metadata": {"name": name, "annotations": {
+            "kubectl.kubernetes.io/last-applied-configuration": main.json.dumps(
+                {"apiVersion": api_version, "kind": kind}
+            ),
+        }}}
+
+    def list_objects(group, version, namespace, plural):
+        if plural == "horizontalpodautoscalers":
+            error = main.ApiException()
+            error.status = 404
+            raise error
+        return {"items": {
+            "deployments": [
+                applied("Deployment", "web", "apps/v1"), {"metadata": {"name": "cli"}},
+                applied("Deployment", "legacy", "extensions/v1beta1"),
+            ],
+            "ingresses": [applied("Ingress", "web", "extensions/v1beta1")],
+            "cronjobs": [applied("CronJob", "report", "batch/v1beta1")],
+        }.get(plural, [])}
+
+    client = main.K8sClient()
+    client.custom_objects = types.SimpleNamespace(list_namespaced_custom_object=list_objects)
+    assert client.get_deprecated_apis("shop") == (3, {"offenders": [
+        "CronJob/report: batch/v1beta1, removed in 1.25",
+        "Deployment/legacy: extensions/v1beta1, removed in 1.16",
+        "Ingress/web: extensions/v1beta1, removed in 1.22",
+    ]})
+
+    offenders = iter([0, 1, 1, 8, 8, 8, 16, 0, 16])
+    earcons = []
+    monkeypatch.setattr(main.k8s_client, "initialize", lambda: True)
+    monkeypatch.setattr(main, "get_k8s_data", lambda m, namespace="default": (
+        float(next(offenders)), {}
+    ))
+    monkeypatch.setattr(main, "play_note", lambda f, d, volume=1.0, voice=None: None)
+    monkeypatch.setattr(
+        main, "play_earcon", lambda name, volume=1.0, a4=440.0: earcons.append(name)
+    )
+    sonifier = main.Sonifier(
+        main.KubernetesSource(), ["deprecated_apis"],
+        event_earcons={"deprecated_api": "deprecation-reminder"},
+    )
+    reminded = [bool(sonifier.tick()[0].extra.get("cluster_events")) for _ in range(9)]
+    # Right away, then every 16 polls for one, every 2 for eight and every poll for 16
+    assert reminded == [False, True, False, True, False, True, True, False, True]
+    assert earcons == ["deprecation-reminder"] * 5
+
+
+def test_node_lifecycle_events(monkeypatch):
+    nodes = [
+        ["worker-a", "worker-b"], ["worker-a", "worker-b", "worker-c"], ["worker-a", "worker-d"],
+    ]
+    events = [
+        [make_k8s_event(reason="ScaleDown")],
+        [make_k8s_event(reason="ScaledUpGroup", count=2), make_k8s_event(reason="ScaleDown")],
+        [make_k8s_event(reason="ScaledUpGroup", count=2),
+         make_k8s_event(reason="ScaleDown", count=2), make_k8s_event(reason="NoScaleUp", count=5)],
+    ]
+    client = main.K8sClient()
+    client.v1_core = types.SimpleNamespace(
+        list_node=lambda: types.SimpleNamespace(items=[make_node(n) for n in nodes.pop(0)]),
+        list_event_for_all_namespaces=lambda field_selector: types.SimpleNamespace(
+            items=events.pop(0)
+        ),
+    )
+    extras = [client.get_node_pressure()[1] for _ in range(3)]
+    assert extras[1] == {
+        "pressure": "False", "nodes": 3, "joined": 1, "lost": 0, "scale_ups": 2, "scale_downs": 1,
+    }
+    assert main.detect_cluster_events("node_pressure", extras[0], extras[1]) == [
+        "node_join", "scale_up",
+    ]
+    # worker-d replaced worker-b and worker-c
+    assert (extras[2]["nodes"], extras[2]["joined"], extras[2]["lost"]) == (2, 2, 2)
+    assert main.detect_cluster_events("node_pressure", extras[1], extras[2]) == [
+        "node_join", "node_lost", "scale_down",
+    ]
+    assert main.detect_cluster_events("node_pressure", {"nodes": 3}, {"nodes": 2}) == ["node_lost"]
+
+    played = []
+    monkeypatch.setattr(main, "play_note", lambda f, d, **kw: played.append(f))
+    cfg = main.merge_config(main.DEFAULT_CONFIG, {"metrics": {"enabled": ["node_pressure"]}})
+    monkeypatch.setattr(main.k8s_client, "initialize", lambda: True)
+    monkeypatch.setattr(main, "get_k8s_data", lambda m, namespace="default": (0, extras.pop(0)))
+    sonifier = main.SonifierBuilder().with_config(cfg).build()
+    sonifier.tick()
+    del played[:]
+    sonifier.tick()
+    node_join = [main.note_frequency(n) for n in main.EARCONS["node-join"]["notes"]]
+    scale_up = [main.note_frequency(n) for n in main.EARCONS["scale-up"]["notes"]]
+    assert played[:-1] == node_join + scale_up
+
+
+def test_earcon_library_from_config(monkeypatch):
+    import copy
+
+    monkeypatch.setattr(main, "EARCONS", copy.deepcopy(main.EARCONS))
+    played = []
+    monkeypatch.setattr(main, "play_note", lambda f, d, volume, voice: played.append((f, d, volume)))
+    cfg = main.merge_config(main.DEFAULT_CONFIG, {"earcons": {
+        "library": {"page": {"notes": ["A4", 880], "volume": 0.5}},
+        "alarms": {"fired": "page"},
+    }})
+    assert main.validate_config(cfg) == []
+    main.apply_earcons(cfg["earcons"]["library"])
+    main.play_earcon("page", volume=0.5)
+    assert played == [(440.0, 0.12, 0.25), (880, 0.12, 0.25)]
+
+    cfg["earcons"]["events"]["deploy"] = "missing"
+    assert main.validate_config(cfg) == ["earcons.events.deploy: unknown earcon 'missing'"]
+
+
+def test_rollout_settings_shape_the_rollout_earcons(monkeypatch):
+    import copy
+
+    monkeypatch.setattr(main, "EARCONS", copy.deepcopy(main.EARCONS))
+    cfg = main.merge_config(
+        main.DEFAULT_CONFIG, {"rollouts": {"success": ["C4", "G4"], "step": 0.2}}
+    )
+    assert main.validate_config(cfg) == []
+    sonifier = main.SonifierBuilder().with_config(cfg).build()
+    assert main.EARCONS["fanfare"] == {"notes": ["C4", "G4"], "step": 0.2}
+    assert sonifier.event_earcons["rollout_succeeded"] == "fanfare"
+
+    cfg["rollouts"]["enabled"] = False
+    sonifier = main.SonifierBuilder().with_config(cfg).build()
+    assert "rollout_succeeded" not in sonifier.event_earcons
+    assert sonifier.event_earcons["node_join"] == "node-join"
+
+    cfg["rollouts"]["failure"] = ["H9"]
+    assert main.validate_config(cfg) == [
+        'rollouts.failure: must be a list of note names, e.g. ["C5", "E5"]'
+    ]
+
+
+def test_validate_config_rejects_unknown_audio_backend():
+    cfg = main.merge_config(main.DEFAULT_CONFIG, {"audio": {"backend": "alsa"}})
+    assert main.validate_config(cfg) == [
+        "audio.backend: must be one of simpleaudio, sounddevice, webaudio, midi, pipe"
+    ]
+
+
+def fake_sounddevice(monkeypatch):
+    """Installs a fake sounddevice module and returns the streams it opens."""
+    streams = []
+
+    class FakeStream:
+        def __init__(self, **kwargs):
+            self.kwargs = kwargs
+            self.written = []
+            streams.append(self)
+
+        def start(self):
+            pass
+
+        def stop(self):
+            pass
+
+        def close(self):
+            pass
+
+        def write(self, samples):
+            self.written.append(samples)
+
+    fake_sd = types.SimpleNamespace(
+        OutputStream=FakeStream,
+        query_hostapis=lambda: [
+            {"name": "ALSA", "default_output_device": 0},
+            {"name": "JACK Audio Connection Kit", "default_output_device": 4},
+        ],
+    )
+    monkeypatch.setitem(main.sys.modules, "sounddevice", fake_sd)
+    return streams
+
+
+def test_sounddevice_sink_selects_jack(monkeypatch):
+    streams = fake_sounddevice(monkeypatch)
+    sink = main.SounddeviceSink(host_api="jack")
+    sink.play(440, 0.1)
+    assert streams[0].kwargs["device"] == 4
+    assert streams[0].kwargs["latency"] == "low"
+    assert streams[0].written[0].dtype == main.np.float32
+    assert len(streams[0].written[0]) == main.SAMPLE_RATE // 10
+
+
+def test_tone_cache_reuses_buffers():
+    cache = main.ToneCache(max_entries=2)
+    renders = []
+
+    def render(value):
+        renders.append(value)
+        return main.np.full(4, value, dtype=float)
+
+    assert cache.get(("a",), lambda: render(1))[0] == 1
+    assert cache.get(("a",), lambda: render(9))[0] == 1
+    cache.get(("b",), lambda: render(2))
+    cache.get(("c",), lambda: render(3))
+    cache.get(("a",), lambda: render(4))
+    assert renders == [1, 2, 3, 4]
+    assert (cache.hits, cache.misses) == (1, 4)
+    assert list(cache.buffers) == [("c",), ("a",)]
+
+
+def test_render_tone_scales_cached_buffer():
+    main.tone_cache.clear()
+    quiet = main.render_tone(440, 0.1, volume=0.25)
+    loud = main.render_tone(440, 0.1, volume=1.0)
+    assert main.tone_cache.hits >= 1
+    assert main.np.allclose(quiet * 4, loud)
+    loud[0] = 5  # Callers get their own copy
+    assert main.render_tone(440, 0.1)[0] == 0
+    noise = {"type": "noise", "noise": "white", "level": 0.5}
+    assert not main.np.array_equal(
+        main.render_tone(440, 0.1, voice=noise), main.render_tone(440, 0.1, voice=noise)
+    )
+
+
+class BlockingSink(main.AudioSink):
+    """Records notes, holding the first one until released."""
+
+    def __init__(self):
+        self.played = []
+        self.started = main.threading.Event()
+        self.release = main.threading.Event()
+
+    def play(self, frequency, duration, volume=1.0, voice=None):
+        self.started.set()
+        self.release.wait(5)
+        self.played.append((frequency, duration))
+
+
+@pytest.mark.parametrize(
+    "policy,expected",
+    [
+        ("drop-oldest", [(100, 1.0), (300, 1.0), (400, 1.0)]),
+        ("drop-newest", [(100, 1.0), (200, 1.0), (300, 1.0)]),
+        ("shorten", [(100, 1.0), (300, 0.5), (400, 1.0)]),
+    ],
+)
+def test_queued_sink_overrun_policy(policy, expected):
+    inner = BlockingSink()
+    sink = main.QueuedSink(inner, max_notes=2, policy=policy)
+    sink.play(100, 1.0)
+    assert inner.started.wait(5)
+    for frequency in (200, 300, 400):
+        sink.play(frequency, 1.0)
+    assert sink.dropped == 1
+    inner.release.set()
+    while len(inner.played) < 3:
+        time.sleep(0.01)
+    sink.close()
+    assert inner.played == expected
+
+
+def test_queued_sink_plays_higher_priority_first_and_preempts():
+    class FadingSink(BlockingSink):
+        can_fade = True
+        fades = 0
+
+        def fade_out(self, duration):
+            self.fades += 1
+
+    inner = FadingSink()
+    sink = main.QueuedSink(inner, max_notes=2, preempt=True)
+    sink.play(100, 1.0)
+    assert inner.started.wait(5)
+    for frequency, priority in ((200, 0), (300, 1), (400, 0)):
+        token = main.note_priority.set(priority)
+        sink.play(frequency, 1.0)
+        main.note_priority.reset(token)
+    # 300 Hz cut 100 Hz short, and the ambient 200 Hz was dropped to make room for 400 Hz
+    assert (inner.fades, sink.preempted, sink.dropped) == (1, 1, 1)
+    inner.release.set()
+    while len(inner.played) < 3:
+        time.sleep(0.01)
+    sink.close()
+    assert [frequency for frequency, _ in inner.played] == [100, 300, 400]
+
+    # A sink that can't cut a note short lets it finish, so nothing is preempted
+    inner = BlockingSink()
+    sink = main.QueuedSink(inner, preempt=True)
+    sink.play(100, 1.0)
+    assert inner.started.wait(5)
+    token = main.note_priority.set(1)
+    sink.play(300, 1.0)
+    main.note_priority.reset(token)
+    inner.release.set()
+    while len(inner.played) < 2:
+        time.sleep(0.01)
+    sink.close()
+    assert sink.preempted == 0
+
+
+def test_sonifier_plays_volatile_metrics_with_vibrato(monkeypatch):
+    monkeypatch.setattr(main, "SOUND_MAP", copy.deepcopy(main.SOUND_MAP))
+    main.SOUND_MAP["cpu_usage"]["volatility"] = "vibrato"
+    values = iter([50.0, 50.0, 50.0, 40.0, 60.0, 40.0])
+    voices = []
+    monkeypatch.setattr(main.k8s_client, "initialize", lambda: True)
+    monkeypatch.setattr(main, "get_k8s_data", lambda m, namespace="default": (next(values), {}))
+    monkeypatch.setattr(
+        main, "play_note", lambda f, d, volume=1.0, voice=None: voices.append(voice)
+    )
+    sonifier = main.Sonifier(main.KubernetesSource(), ["cpu_usage"])
+    for _ in range(6):
+        sonifier.tick()
+    assert voices[:3] == [None, None, None]
+    assert [voice["vibrato"] for voice in voices[3:]] == [0.4, 0.6, 0.7]
+
+    steady = main.render_tone(440, 0.2)
+    nervous = main.render_tone(440, 0.2, voice={"type": "sine", "vibrato": 1.0})
+    trembling = main.render_tone(440, 0.2, voice={"type": "sine", "tremolo": 1.0})
+    assert not main.np.allclose(steady, nervous)
+    assert abs(trembling).max() <= abs(steady).max() + 1e-9
+
+
+def test_continuous_mapping_glides_between_notes(monkeypatch):
+    monkeypatch.setitem(main.SOUND_MAP, "http_latency", dict(
+        main.SOUND_MAP["http_latency"], mapping="continuous", frequency_range=(220, 880)
+    ))
+    low, high = main.SOUND_MAP["http_latency"]["range"]
+    middle = (low + high) / 2
+    assert main.map_metric("http_latency", low)[1:3] == (220, "A3")
+    assert main.map_metric("http_latency", middle)[1:3] == (440, "A4")
+    assert main.map_metric("http_latency", high * 2)[1] == 880
+    nudged = main.map_metric("http_latency", middle + (high - low) / 100)
+    assert 440 < nudged[1] < 466 and nudged[2] == "A4+24c"
+    assert main.frequency_name(262) == "C4+2c"
+    monkeypatch.setattr(main, "SOUND_MAP", copy.deepcopy(main.SOUND_MAP))
+    main.apply_sound_map(
+        {"http_latency": {"mapping": "continuous", "notes": ["A3", "A5"]}}, {"a4": 432.0}
+    )
+    # 440 Hz, still the middle of the frequency_range, is sharp of a 432 Hz A4
+    assert main.map_metric("http_latency", middle)[1:3] == (440, "A4+32c")
+    assert "sound_map.http_latency.frequency_range" in " ".join(main.validate_config(
+        main.merge_config(main.DEFAULT_CONFIG, {"sound_map": {"http_latency": {
+            "mapping": "continuous", "frequency_range": [880, 220],
+        }}})
+    ))
+
+
+def test_apply_fades_silences_edges():
+    samples = main.apply_fades(main.np.ones(100), 10 / main.SAMPLE_RATE)
+    assert samples[0] == 0 and samples[-1] == 0
+    assert samples[50] == 1
+
+
+def test_crossfade_mixes_tail_into_head():
+    mixed = main.crossfade(main.np.ones(3), main.np.zeros(5))
+    assert list(mixed) == [1.0, 0.5, 0.0, 0.0, 0.0]
+
+
+def test_sounddevice_sink_crossfades_consecutive_notes(monkeypatch):
+    streams = fake_sounddevice(monkeypatch)
+    timers = []
+
+    class IdleTimer:
+        def __init__(self, delay, function):
+            timers.append(delay)
+
+        def start(self):
+            pass
+
+        def cancel(self):
+            pass
+
+    monkeypatch.setattr(main.threading, "Timer", IdleTimer)
+    sink = main.SounddeviceSink(crossfade=0.01)
+    sink.play(440, 0.1)
+    sink.play(880, 0.1)
+    sink.close()
+    # The held-back 441 samples of each note go into the next note or out on close
+    written = [len(samples) for samples in streams[0].written]
+    assert written == [3969, 3969, 441]
+    assert timers == [0.01, 0.01]
+
+
+def test_soft_limit_keeps_quiet_samples_and_bounds_loud_ones():
+    limited = main.soft_limit(main.np.array([0.5, -0.5, 1.5, -3.0]), threshold=0.8)
+    assert list(limited[:2]) == [0.5, -0.5]
+    assert 0.8 < limited[2] < 1.0
+    assert -1.0 < limited[3] < -0.99
+
+
+def test_loudness_gain_evens_out_perceived_level():
+    assert abs(main.a_weighting(1000)) < 0.1
+    assert main.a_weighting(131) < main.a_weighting(523)
+    assert main.loudness_gain(131) > main.loudness_gain(523)
+    for frequency in (131, 262, 523, 1000, 2500):
+        level = main.a_weighting(frequency) + 20 * main.math.log10(main.loudness_gain(frequency))
+        assert level <= main.LOUDNESS_FLOOR_DB + 1e-9
+
+
+def test_play_note_applies_loudness_compensation(monkeypatch):
+    played = []
+    monkeypatch.setenv("TEST_MODE", "false")
+    monkeypatch.setenv("LOUDNESS_COMPENSATION", "true")
+    sink = types.SimpleNamespace(play=lambda f, d, v, voice=None: played.append(v))
+    monkeypatch.setattr(main, "audio_sink", sink)
+    main.play_note(1000, 0.1, volume=0.5)
+    assert played == [0.5 * main.loudness_gain(1000)]
+
+
+@pytest.mark.parametrize("color", ["white", "pink", "crackle"])
+def test_synthesize_noise_grows_with_level(color):
+    quiet = main.synthesize_noise(color, 0.0, 0.5)
+    loud = main.synthesize_noise(color, 1.0, 0.5)
+    assert len(loud) == main.SAMPLE_RATE // 2
+    assert abs(loud).max() <= 1.0
+    assert abs(loud).sum() > abs(quiet).sum()
+
+
+def test_metric_voice_sets_level(monkeypatch):
+    entry = dict(main.SOUND_MAP["errors_per_second"], voice={"type": "noise", "noise": "crackle"})
+    monkeypatch.setitem(main.SOUND_MAP, "errors_per_second", entry)
+    assert main.metric_voice("errors_per_second", 2.5) == {
+        "type": "noise", "noise": "crackle", "level": 0.25,
+    }
+    assert main.metric_voice("cpu_usage", 50) is None
+
+
+def test_validate_config_checks_voice():
+    cfg = main.merge_config(
+        main.DEFAULT_CONFIG,
+        {"sound_map": {"errors_per_second": {"voice": {"type": "noise", "noise": "brown"}}}},
+    )
+    assert main.validate_config(cfg) == [
+        "sound_map.errors_per_second.voice.noise: must be one of white, pink, crackle"
+    ]
+
+
+def test_synthesize_fm_matches_sine_without_modulation():
+    fm = main.synthesize_fm(440, ratio=2.0, index=0.0, duration=0.05)
+    assert main.np.allclose(fm, main.synthesize_tone(440, 0.05))
+    bright = main.synthesize_fm(440, ratio=2.0, index=5.0, duration=0.05)
+    assert not main.np.allclose(bright, fm)
+    assert abs(bright).max() <= 1.0
+
+
+def test_synthesize_additive_brightness_adds_partials():
+    dull = main.synthesize_additive(220, 8, brightness=0.0, duration=0.05)
+    bright = main.synthesize_additive(220, 8, brightness=1.0, duration=0.05)
+    assert abs(bright).max() <= 1.0
+    # More energy in the upper partials means more sign changes
+    def crossings(samples):
+        samples = list(samples)
+        return sum(1 for a, b in zip(samples, samples[1:]) if a * b < 0)
+
+    assert crossings(bright) > crossings(dull)
+
+
+def test_additive_voice_brightness_follows_secondary_metric(monkeypatch):
+    voice = {"type": "additive", "brightness": "errors_per_second"}
+    entry = dict(main.SOUND_MAP["http_latency"], voice=voice)
+    monkeypatch.setitem(main.SOUND_MAP, "http_latency", entry)
+    voice = main.metric_voice("http_latency", 100, {"errors_per_second": 10}.get)
+    assert (voice["level"], voice["brightness_level"]) == (0.2, 1.0)
+    assert main.metric_voice("http_latency", 100)["brightness_level"] == 0.2
+
+
+def test_synthesize_pluck_decays():
+    pluck = main.synthesize_pluck(220, 0.99, 0.5)
+    assert len(pluck) == main.SAMPLE_RATE // 2
+    quarter = len(pluck) // 4
+    assert abs(pluck[:quarter]).max() <= 1.0
+    assert abs(pluck[-quarter:]).max() < abs(pluck[:quarter]).max() / 2
+
+
+def test_note_scheduler_places_notes_on_grid():
+    scheduler = main.NoteScheduler(grid=4 / main.SAMPLE_RATE)
+    assert scheduler.schedule(main.np.ones(3)) == 0
+    # Back to back notes wait for the previous one, then snap to the grid
+    assert scheduler.schedule(main.np.ones(2) * 2) == 4
+    assert list(scheduler.render(5)) == [1.0, 1.0, 1.0, 0.0, 2.0]
+    # A late tick still lands on the next grid line
+    assert scheduler.schedule(main.np.ones(1) * 3) == 8
+    assert list(scheduler.render(5)) == [2.0, 0.0, 0.0, 3.0, 0.0]
+    assert scheduler.notes == []
+    assert scheduler.wait_until(10, timeout=0)
+
+
+def test_midi_clock_follows_pulses(monkeypatch):
+    now = {"t": 100.0}
+    monkeypatch.setattr(main, "time", types.SimpleNamespace(monotonic=lambda: now["t"]))
+    clock = main.MidiClock(bpm=90)
+    assert clock.bpm() == 90
+    # 24 pulses per quarter note at 150 BPM are 1/60 s apart
+    for _ in range(main.MIDI_CLOCK_PPQN + 1):
+        clock.handle_message(types.SimpleNamespace(type="clock"))
+        now["t"] += 1 / 60
+    assert abs(clock.bpm() - 150) < 1e-6
+    now["t"] += main.MIDI_CLOCK_TIMEOUT + 1
+    assert clock.bpm() == 90
+
+
+def test_build_tempo_clock():
+    cfg = main.merge_config(main.DEFAULT_CONFIG, {"tempo": {"sync": "midi"}})
+    assert isinstance(main.build_tempo_clock(cfg, 100), main.MidiClock)
+    assert main.build_tempo_clock(main.DEFAULT_CONFIG, 100).bpm() == 100